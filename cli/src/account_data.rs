@@ -0,0 +1,210 @@
+//! Account data is read from any of these formats:
+//!
+//! - raw bytes, as written by `solana account <pubkey> --output-file <file>`
+//! - JSON, as printed by `solana account <pubkey> --output json` or as
+//!   returned by the `getAccountInfo` RPC method with base64 encoding
+//! - the hex dump printed by `solana account <pubkey>`
+//! - base64 text
+
+use crate::Result;
+use serde_json::Value;
+use std::{fs, path::Path};
+
+/// Reads account data from given file. If there's no such file, the source
+/// itself is decoded as base64.
+pub fn load(source: &str) -> Result<Vec<u8>> {
+    if Path::new(source).is_file() {
+        parse(fs::read(source)?)
+    } else {
+        base64::decode(source.trim()).map_err(|e| {
+            format!("'{}' is neither a file nor base64: {}", source, e).into()
+        })
+    }
+}
+
+/// Recognizes the format of given contents and returns the account data.
+pub fn parse(contents: Vec<u8>) -> Result<Vec<u8>> {
+    let text = match std::str::from_utf8(&contents) {
+        Ok(text) => text.trim(),
+        Err(_) => return Ok(contents),
+    };
+
+    if text.starts_with('{') {
+        let json: Value = serde_json::from_str(text)?;
+        return from_json(&json)
+            .ok_or_else(|| "JSON contains no base64 account data".into());
+    }
+
+    if let Some(data) = from_hex_dump(text) {
+        return data;
+    }
+
+    // an empty file or zero bytes are valid UTF-8 too
+    Ok(base64::decode(text).unwrap_or(contents))
+}
+
+/// Finds the first "data" property with base64 encoded data.
+fn from_json(json: &Value) -> Option<Vec<u8>> {
+    match json {
+        Value::Object(object) => {
+            let data = match object.get("data") {
+                Some(Value::Array(data)) => match data.as_slice() {
+                    [Value::String(data), Value::String(encoding)]
+                        if encoding == "base64" =>
+                    {
+                        base64::decode(data).ok()
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            data.or_else(|| object.values().find_map(from_json))
+        }
+        Value::Array(values) => values.iter().find_map(from_json),
+        _ => None,
+    }
+}
+
+/// The dump starts with a "Length: <n> (<hex n>) bytes" line, followed by
+/// lines of an offset, up to 16 hex bytes and their ASCII representation.
+/// Other lines, such as the account's pubkey, balance and owner, are skipped.
+fn from_hex_dump(text: &str) -> Option<Result<Vec<u8>>> {
+    let mut lines = text.lines().map(str::trim);
+    let len: usize = lines
+        .find_map(|line| line.strip_prefix("Length:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+
+    let mut data = Vec::with_capacity(len);
+    for line in lines {
+        let bytes = match line.split_once(':') {
+            Some((offset, bytes))
+                if usize::from_str_radix(offset, 16).is_ok() =>
+            {
+                bytes
+            }
+            _ => continue,
+        };
+
+        // the ASCII column could look like hex bytes on the last line, hence
+        // we never read past the length
+        let remaining = len - data.len();
+        for byte in bytes.split_whitespace().take(remaining.min(16)) {
+            match u8::from_str_radix(byte, 16) {
+                Ok(byte) => data.push(byte),
+                Err(e) => {
+                    return Some(Err(format!(
+                        "Invalid hex byte '{}': {}",
+                        byte, e
+                    )
+                    .into()))
+                }
+            }
+        }
+    }
+
+    if data.len() == len {
+        Some(Ok(data))
+    } else {
+        Some(Err(format!(
+            "Hex dump has {} bytes instead of {}",
+            data.len(),
+            len
+        )
+        .into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: [u8; 20] = [
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 0xab, 0xcd,
+        0xef, 0,
+    ];
+
+    #[test]
+    fn it_parses_raw_bytes() {
+        assert_eq!(parse(DATA.to_vec()).unwrap(), DATA);
+        assert_eq!(parse(vec![0; 8]).unwrap(), vec![0; 8]);
+    }
+
+    #[test]
+    fn it_parses_base64() {
+        let encoded = format!("{}\n", base64::encode(DATA));
+
+        assert_eq!(parse(encoded.into_bytes()).unwrap(), DATA);
+    }
+
+    #[test]
+    fn it_parses_cli_json() {
+        let json = format!(
+            r#"{{
+                "pubkey": "11111111111111111111111111111111",
+                "account": {{
+                    "lamports": 1,
+                    "data": ["{}", "base64"],
+                    "owner": "11111111111111111111111111111111",
+                    "executable": false,
+                    "rentEpoch": 0
+                }}
+            }}"#,
+            base64::encode(DATA)
+        );
+
+        assert_eq!(parse(json.into_bytes()).unwrap(), DATA);
+    }
+
+    #[test]
+    fn it_parses_rpc_json() {
+        let json = format!(
+            r#"{{
+                "jsonrpc": "2.0",
+                "result": {{
+                    "context": {{ "slot": 1 }},
+                    "value": {{ "data": ["{}", "base64"], "lamports": 1 }}
+                }},
+                "id": 1
+            }}"#,
+            base64::encode(DATA)
+        );
+
+        assert_eq!(parse(json.into_bytes()).unwrap(), DATA);
+    }
+
+    #[test]
+    fn it_fails_json_without_data() {
+        assert!(
+            parse(br#"{ "data": ["abc", "jsonParsed"] }"#.to_vec()).is_err()
+        );
+    }
+
+    #[test]
+    fn it_parses_hex_dump() {
+        let dump = "
+Public Key: 11111111111111111111111111111111
+Balance: 0.00100224 SOL
+Owner: 11111111111111111111111111111111
+Executable: false
+Rent Epoch: 0
+Length: 20 (0x14) bytes
+0000:   01 02 03 04  05 06 07 08  09 0a 0b 0c  0d 0e 0f 10   ................
+0010:   ab cd ef 00                                          ab..
+";
+
+        assert_eq!(parse(dump.as_bytes().to_vec()).unwrap(), DATA);
+    }
+
+    #[test]
+    fn it_fails_truncated_hex_dump() {
+        let dump = "Length: 20 (0x14) bytes
+0000:   01 02 03 04  05 06 07 08  09 0a 0b 0c  0d 0e 0f 10   ................
+";
+
+        assert!(parse(dump.as_bytes().to_vec()).is_err());
+    }
+}
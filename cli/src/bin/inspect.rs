@@ -0,0 +1,61 @@
+//! Decodes an account of the amm or farming program, see [`cli::describe`].
+//!
+//! ```bash
+//! $ solana account <farmer> --output json > farmer.json
+//! $ solana account <farm> --output-file farm.bin
+//! $ inspect farmer.json --farm farm.bin --slot 150000000 --format table
+//! ```
+
+use clap::{ArgEnum, Parser};
+use cli::describe::Account;
+use cli::{account_data, logs, table, Result};
+
+#[derive(Parser)]
+#[clap(about = "Decodes accounts of the amm and farming programs offline")]
+struct Args {
+    /// File with the account data, or the data as base64. The file is raw
+    /// bytes, base64, JSON of `solana account --output json` or RPC, or the
+    /// hex dump printed by `solana account`.
+    account: String,
+    /// The farm of a farmer account, in the same formats as the account.
+    /// Together with the slot it's used to calculate pending harvest.
+    #[clap(long)]
+    farm: Option<String>,
+    /// Slot at which derived values are calculated. Prices of a weighted pool
+    /// and a farmer's pending harvest need it. Harvest timelines of a farm end
+    /// at this slot, or at the latest snapshot.
+    #[clap(long)]
+    slot: Option<u64>,
+    #[clap(long, arg_enum, default_value = "json")]
+    format: Format,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Format {
+    Json,
+    Table,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    logs::redirect_to_stderr();
+
+    let account = Account::decode(&account_data::load(&args.account)?)?;
+    let farm = match args.farm.as_deref() {
+        Some(source) => match Account::decode(&account_data::load(source)?)? {
+            Account::Farm(farm) => Some(farm),
+            _ => return Err(format!("'{}' is not a farm", source).into()),
+        },
+        None => None,
+    };
+
+    let description = account.describe(args.slot, farm.as_deref());
+    match args.format {
+        Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&description)?)
+        }
+        Format::Table => print!("{}", table::render(&description)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Replays a flow of swaps, deposits and redeems against a pool for each
+//! combination of the given parameters, see [`cli::simulate`].
+//!
+//! ```bash
+//! $ solana account <pool> --output json > pool.json
+//! $ simulate flow.csv --pool pool.json --lp-supply 1000000000 \
+//!     --amplifier 0,10,100 --swap-fee 1000,3000 --format table
+//! $ simulate flow.csv --reserves 1000000000,1000000000 --amplifier 50 \
+//!     --stable-curve-invariant integer
+//! ```
+
+use ::amm::prelude::{
+    Permillion, ProgramConfig, StableCurveInvariantMethod, TokenAmount,
+};
+use clap::{ArgEnum, Parser};
+use cli::describe::Account;
+use cli::simulate::{self, Params, Simulation};
+use cli::{account_data, logs, table, Result};
+use std::fs;
+
+#[derive(Parser)]
+#[clap(about = "Replays trade flow against an amm pool offline")]
+struct Args {
+    /// CSV file with one swap, deposit or redeem per line.
+    ops: String,
+    /// Pool account to start from, in the formats which `inspect` accepts.
+    #[clap(long)]
+    pool: Option<String>,
+    /// LP mint supply of the pool, required with --pool.
+    #[clap(long)]
+    lp_supply: Option<u64>,
+    /// Instead of a pool account, start from a pool with new mints after a
+    /// first deposit of these tokens, one amount per reserve.
+    #[clap(long, use_value_delimiter = true)]
+    reserves: Vec<u64>,
+    /// Amplifiers to simulate. Zero is the constant product curve, others
+    /// the stable curve. Defaults to the pool's curve.
+    #[clap(long, use_value_delimiter = true)]
+    amplifier: Vec<u64>,
+    /// Swap fees in permillion to simulate, 1% is 10000. Defaults to the
+    /// pool's swap fee.
+    #[clap(long, use_value_delimiter = true)]
+    swap_fee: Vec<u64>,
+    /// Slot at which the flow is replayed, needed for weighted pools and
+    /// pending swap fees.
+    #[clap(long)]
+    slot: Option<u64>,
+    /// Program's share of the swap fee in permillion. Defaults to the
+    /// program's default toll.
+    #[clap(long)]
+    program_toll_swap_fee_share: Option<u64>,
+    /// How stable curves recompute their invariant. Defaults to the
+    /// program's default method.
+    #[clap(long, arg_enum)]
+    stable_curve_invariant: Option<InvariantMethod>,
+    #[clap(long, arg_enum, default_value = "json")]
+    format: Format,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Format {
+    Json,
+    Table,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum InvariantMethod {
+    Decimal,
+    Integer,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    logs::redirect_to_stderr();
+
+    // the LP supply of a pool account, or the first deposit of a new pool
+    let (pool, lp_supply) = match (&args.pool, args.lp_supply) {
+        (Some(_), _) if !args.reserves.is_empty() => {
+            return Err("Either --pool or --reserves, not both".into())
+        }
+        (Some(source), Some(lp_supply)) => {
+            match Account::decode(&account_data::load(source)?)? {
+                Account::Pool(pool) => {
+                    (pool, Some(TokenAmount::new(lp_supply)))
+                }
+                _ => return Err(format!("'{}' is not a pool", source).into()),
+            }
+        }
+        (Some(_), None) => return Err("--pool requires --lp-supply".into()),
+        (None, _) if !args.reserves.is_empty() => {
+            (simulate::new_pool(args.reserves.len())?, None)
+        }
+        (None, _) => {
+            return Err("Either --pool or --reserves is required".into())
+        }
+    };
+
+    // as the program config account would set them
+    let mut config = ProgramConfig::default();
+    if let Some(permillion) = args.program_toll_swap_fee_share {
+        config.program_toll_swap_fee_share = Permillion { permillion };
+    }
+    if let Some(method) = args.stable_curve_invariant {
+        config.stable_curve_invariant = match method {
+            InvariantMethod::Decimal => StableCurveInvariantMethod::Decimal,
+            InvariantMethod::Integer => StableCurveInvariantMethod::Integer,
+        };
+    }
+    config.validate()?;
+
+    let ops = simulate::parse_ops(&fs::read_to_string(&args.ops)?, &pool)?;
+
+    let amplifiers = optional(&args.amplifier);
+    let swap_fees = optional(&args.swap_fee);
+
+    let mut reports = Vec::new();
+    for amplifier in &amplifiers {
+        for swap_fee in &swap_fees {
+            let params = Params {
+                amplifier: *amplifier,
+                swap_fee: swap_fee.map(|permillion| Permillion { permillion }),
+            };
+            let pool = simulate::with_params(&pool, params, &config)?;
+
+            let mut sim = match lp_supply {
+                Some(lp_supply) => {
+                    Simulation::new(pool, lp_supply, args.slot, config.clone())
+                }
+                None => Simulation::with_first_deposit(
+                    pool,
+                    &args.reserves,
+                    args.slot,
+                    config.clone(),
+                )?,
+            };
+            sim.run(&ops);
+            reports.push(sim.report());
+        }
+    }
+
+    let output = serde_json::Value::Array(reports);
+    match args.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+        Format::Table => print!("{}", table::render(&output)),
+    }
+
+    Ok(())
+}
+
+/// Without values to sweep, the pool's own parameter is used.
+fn optional(values: &[u64]) -> Vec<Option<u64>> {
+    if values.is_empty() {
+        vec![None]
+    } else {
+        values.iter().copied().map(Some).collect()
+    }
+}
@@ -0,0 +1,516 @@
+//! Decodes accounts of both programs and describes them as JSON, together with
+//! values derived from them:
+//!
+//! - a pool's invariant computed from its reserves, and prices between each
+//!   pair of its reserves. A price is how many buy tokens a trader gets per
+//!   sell token when they sell 0.01% of the sell reserve, fee excluded. Token
+//!   amounts are in the mints' smallest units.
+//! - a farm's snapshots in chronological order, and timelines of the harvest
+//!   periods with the gaps between them filled in.
+//! - a farmer's pending harvest at a given slot, which requires the farm.
+//!
+//! Pubkeys are base58 strings, integers which don't fit into a JSON number are
+//! decimal strings.
+
+use crate::Result;
+use ::amm::math::stable_curve_invariant_int::{self, Rounding, U512};
+use ::amm::prelude::{
+    Curve, Decimal, Discount, DiscountSettings, Pool, PoolStats, ProgramToll,
+    Pubkey, TokenAmount,
+};
+use anchor_lang::{AccountDeserialize, Discriminator};
+use farming::consts::SNAPSHOTS_LEN;
+use farming::models::{Farm, Farmer, Harvest, Slot, Snapshot};
+use serde_json::{json, Value};
+use std::mem;
+
+/// Sell this share of a reserve to quote a price.
+const PRICE_QUOTE_SHARE: u64 = 10_000;
+
+pub enum Account {
+    Pool(Pool),
+    ProgramToll(ProgramToll),
+    DiscountSettings(DiscountSettings),
+    Discount(Discount),
+    PoolStats(PoolStats),
+    /// Boxed because of the snapshot history.
+    Farm(Box<Farm>),
+    Farmer(Farmer),
+}
+
+impl Account {
+    /// Recognizes the account by its discriminator. Pools which haven't been
+    /// migrated to the latest version yet are accepted too.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let discriminator = data
+            .get(..8)
+            .ok_or("Account data is shorter than a discriminator")?;
+        let mut buf = data;
+
+        let account = if discriminator == Pool::discriminator() {
+            Self::Pool(Pool::try_deserialize_any_version(&mut buf)?)
+        } else if discriminator == ProgramToll::discriminator() {
+            Self::ProgramToll(ProgramToll::try_deserialize(&mut buf)?)
+        } else if discriminator == DiscountSettings::discriminator() {
+            Self::DiscountSettings(DiscountSettings::try_deserialize(&mut buf)?)
+        } else if discriminator == Discount::discriminator() {
+            Self::Discount(Discount::try_deserialize(&mut buf)?)
+        } else if discriminator == PoolStats::discriminator() {
+            Self::PoolStats(PoolStats::try_deserialize(&mut buf)?)
+        } else if discriminator == Farm::discriminator() {
+            let farm = data
+                .get(8..8 + mem::size_of::<Farm>())
+                .ok_or("Account data is shorter than a farm")?;
+            Self::Farm(Box::new(bytemuck::pod_read_unaligned(farm)))
+        } else if discriminator == Farmer::discriminator() {
+            Self::Farmer(Farmer::try_deserialize(&mut buf)?)
+        } else {
+            return Err("Unknown account discriminator".into());
+        };
+
+        Ok(account)
+    }
+
+    /// The slot is needed for a farmer's pending harvest, which also needs
+    /// the farmer's farm. See [`describe_farm`] for how it's used otherwise.
+    pub fn describe(&self, slot: Option<u64>, farm: Option<&Farm>) -> Value {
+        match self {
+            Self::Pool(pool) => describe_pool(pool, slot),
+            Self::ProgramToll(toll) => json!({
+                "account": "ProgramToll",
+                "authority": toll.authority.to_string(),
+            }),
+            Self::DiscountSettings(settings) => json!({
+                "account": "DiscountSettings",
+                "authority": settings.authority.to_string(),
+            }),
+            Self::Discount(discount) => json!({
+                "account": "Discount",
+                "amount_permillion": discount.amount.permillion,
+                "valid_until": discount.valid_until.slot,
+            }),
+            Self::PoolStats(stats) => describe_pool_stats(stats),
+            Self::Farm(farm) => describe_farm(farm, slot),
+            Self::Farmer(farmer) => describe_farmer(farmer, farm.zip(slot)),
+        }
+    }
+}
+
+/// Prices of a weighted pool depend on the slot, there are none without it.
+pub fn describe_pool(pool: &Pool, slot: Option<u64>) -> Value {
+    let curve = match pool.curve {
+        Curve::ConstProd => json!({ "kind": "const_prod" }),
+        Curve::Stable {
+            amplifier,
+            invariant,
+        } => json!({
+            "kind": "stable",
+            "amplifier": amplifier,
+            "invariant": Decimal::from(invariant).to_string(),
+        }),
+        Curve::Weighted {
+            start_weight,
+            end_weight,
+            start,
+            end,
+        } => json!({
+            "kind": "weighted",
+            "start_weight_permillion": start_weight.permillion,
+            "end_weight_permillion": end_weight.permillion,
+            "start": start.slot,
+            "end": end.slot,
+        }),
+    };
+
+    json!({
+        "account": "Pool",
+        "version": pool.version,
+        "admin": pool.admin.to_string(),
+        "signer": pool.signer.to_string(),
+        "lp_mint": pool.mint.to_string(),
+        "program_toll_wallet": pool.program_toll_wallet.to_string(),
+        "swap_fee_permillion": pool.swap_fee.permillion,
+        "pending_swap_fee": pool.pending_swap_fee.map(|pending| json!({
+            "fee_permillion": pending.fee.permillion,
+            "effective_at": pending.effective_at.slot,
+        })),
+        "access_policy": pool.access_policy.map(|policy| policy.to_string()),
+        "curve": curve,
+        "reserves": pool
+            .reserves()
+            .iter()
+            .map(|reserve| json!({
+                "mint": reserve.mint.to_string(),
+                "vault": reserve.vault.to_string(),
+                "tokens": reserve.tokens.amount,
+            }))
+            .collect::<Vec<_>>(),
+        "invariant_from_reserves": invariant_from_reserves(pool),
+        "prices": prices(pool, slot),
+    })
+}
+
+/// The counters are `u128`, hence decimal strings. Entries of reserves which
+/// the stats haven't tracked yet are omitted.
+pub fn describe_pool_stats(stats: &PoolStats) -> Value {
+    json!({
+        "account": "PoolStats",
+        "pool": stats.pool.to_string(),
+        "reserves": stats
+            .reserves
+            .iter()
+            .filter(|reserve| reserve.mint != Pubkey::default())
+            .map(|reserve| json!({
+                "mint": reserve.mint.to_string(),
+                "swap_volume_in": reserve.swap_volume_in.to_string(),
+                "swap_volume_out": reserve.swap_volume_out.to_string(),
+                "swap_fees": reserve.swap_fees.to_string(),
+                "deposited": reserve.deposited.to_string(),
+                "redeemed": reserve.redeemed.to_string(),
+            }))
+            .collect::<Vec<_>>(),
+        "toll_lp_minted": stats.toll_lp_minted.to_string(),
+        "swap_count": stats.swap_count,
+        "last_swap_slot": stats.last_swap_slot.slot,
+    })
+}
+
+/// The constant product invariant is the product of reserves, the stable
+/// curve invariant is rounded down. There's none for weighted pools.
+fn invariant_from_reserves(pool: &Pool) -> Value {
+    let reserves: Vec<_> = pool.reserves().iter().map(|r| r.tokens).collect();
+
+    match pool.curve {
+        Curve::ConstProd => {
+            let product = reserves
+                .iter()
+                .fold(U512::one(), |product, r| product * U512::from(r.amount));
+            Value::String(product.to_string())
+        }
+        Curve::Stable { amplifier, .. } => stable_curve_invariant_int::compute(
+            amplifier,
+            &reserves,
+            Rounding::Down,
+        )
+        .map(|invariant| Value::String(invariant.to_string()))
+        .unwrap_or(Value::Null),
+        Curve::Weighted { .. } => Value::Null,
+    }
+}
+
+fn prices(pool: &Pool, slot: Option<u64>) -> Vec<Value> {
+    let reserves = pool.reserves();
+
+    let mut prices = Vec::new();
+    for sell in reserves {
+        for buy in reserves.iter().filter(|r| r.mint != sell.mint) {
+            prices.push(json!({
+                "sell_mint": sell.mint.to_string(),
+                "buy_mint": buy.mint.to_string(),
+                "price": quote_price(pool, sell.mint, buy.mint, slot),
+            }));
+        }
+    }
+
+    prices
+}
+
+/// How many buy tokens a trader gets per sell token when they sell 0.01% of
+/// the sell reserve, fee excluded. There's no price if the swap fails, eg.
+/// because a reserve is empty, or if the pool is weighted and there's no slot.
+pub fn quote_price(
+    pool: &Pool,
+    sell_mint: Pubkey,
+    buy_mint: Pubkey,
+    slot: Option<u64>,
+) -> Option<f64> {
+    // only the weighted curve depends on the slot
+    let now = match (pool.curve, slot) {
+        (Curve::Weighted { .. }, None) => return None,
+        (_, slot) => Slot::new(slot.unwrap_or_default()),
+    };
+    let sell = pool.reserves().iter().find(|r| r.mint == sell_mint)?;
+    let sell_tokens = (sell.tokens.amount / PRICE_QUOTE_SHARE).max(1);
+
+    pool.calculate_swap(sell_mint, TokenAmount::new(sell_tokens), buy_mint, now)
+        .ok()
+        .map(|bought| bought.amount as f64 / sell_tokens as f64)
+}
+
+/// Harvest timelines end at given slot, or at the latest snapshot if there's
+/// no slot.
+pub fn describe_farm(farm: &Farm, slot: Option<u64>) -> Value {
+    let latest_snapshot = farm.latest_snapshot();
+    let until = slot.unwrap_or(latest_snapshot.started_at.slot);
+    let harvests = farm.harvests;
+
+    json!({
+        "account": "Farm",
+        "admin": farm.admin.to_string(),
+        "stake_mint": farm.stake_mint.to_string(),
+        "stake_vault": farm.stake_vault.to_string(),
+        "min_snapshot_window_slots": farm.min_snapshot_window_slots,
+        "harvests": harvests
+            .iter()
+            .filter(|harvest| harvest.mint != Default::default())
+            .map(|harvest| describe_harvest(harvest, until))
+            .collect::<Vec<_>>(),
+        "ring_buffer_tip": farm.snapshots.ring_buffer_tip,
+        "snapshots": chronological_snapshots(farm)
+            .into_iter()
+            .map(|snapshot| json!({
+                "started_at": snapshot.started_at.slot,
+                "staked": snapshot.staked.amount,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn describe_harvest(harvest: &Harvest, until: u64) -> Value {
+    let mut periods: Vec<_> = harvest
+        .periods
+        .iter()
+        .filter(|period| period.ends_at.slot != 0)
+        .copied()
+        .collect();
+    periods.sort_by_key(|period| period.starts_at);
+
+    json!({
+        "mint": harvest.mint.to_string(),
+        "vault": harvest.vault.to_string(),
+        "periods": periods
+            .iter()
+            .map(|period| {
+                let slots = period.ends_at.slot + 1 - period.starts_at.slot;
+                json!({
+                    "starts_at": period.starts_at.slot,
+                    "ends_at": period.ends_at.slot,
+                    "tps": period.tps.amount,
+                    "total_tokens": (period.tps.amount as u128 * slots as u128)
+                        .to_string(),
+                })
+            })
+            .collect::<Vec<_>>(),
+        "timeline": harvest
+            .tps_history(Slot::new(until))
+            .into_iter()
+            .map(|(slots, tps)| json!({
+                "from": slots.start().slot,
+                "to": slots.end().slot,
+                "tps": tps.amount,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Until the ring buffer rotates for the first time, the oldest snapshot is
+/// at the beginning of the buffer. After that it follows the tip.
+pub fn chronological_snapshots(farm: &Farm) -> Vec<Snapshot> {
+    let tip = farm.snapshots.ring_buffer_tip as usize;
+    let ring_buffer = farm.snapshots.ring_buffer;
+
+    let rotated = ring_buffer[SNAPSHOTS_LEN - 1].started_at.slot != 0;
+    let (oldest, len) = if rotated {
+        ((tip + 1) % SNAPSHOTS_LEN, SNAPSHOTS_LEN)
+    } else {
+        (0, tip + 1)
+    };
+
+    (0..len)
+        .map(|offset| ring_buffer[(oldest + offset) % SNAPSHOTS_LEN])
+        .collect()
+}
+
+/// The pending harvest is calculated with the program's logic, as if the
+/// farmer's harvest was updated at given slot.
+pub fn describe_farmer(
+    farmer: &Farmer,
+    farm_at: Option<(&Farm, u64)>,
+) -> Value {
+    let pending_harvest = farm_at.map(|(farm, slot)| {
+        let mut farmer = farmer.clone();
+        match farmer
+            .check_vested_period_and_update_harvest(farm, Slot::new(slot))
+        {
+            Ok(()) => json!({
+                "slot": slot,
+                "staked": farmer.staked.amount,
+                "vested": farmer.vested.amount,
+                "harvests": farmer_harvests(&farmer),
+            }),
+            Err(e) => json!({ "slot": slot, "error": e.to_string() }),
+        }
+    });
+
+    json!({
+        "account": "Farmer",
+        "authority": farmer.authority.to_string(),
+        "farm": farmer.farm.to_string(),
+        "staked": farmer.staked.amount,
+        "vested": farmer.vested.amount,
+        "vested_at": farmer.vested_at.slot,
+        "calculate_next_harvest_from": farmer.calculate_next_harvest_from.slot,
+        "harvests": farmer_harvests(farmer),
+        "pending_harvest": pending_harvest,
+    })
+}
+
+fn farmer_harvests(farmer: &Farmer) -> Vec<Value> {
+    farmer
+        .harvests
+        .iter()
+        .filter(|harvest| harvest.mint != Default::default())
+        .map(|harvest| {
+            json!({
+                "mint": harvest.mint.to_string(),
+                "tokens": harvest.tokens.amount,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::amm::prelude::{AccountSerialize, Reserve};
+
+    #[test]
+    fn it_decodes_pool_with_derived_values() {
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+        let mut pool = Pool {
+            dimension: 2,
+            version: Pool::VERSION,
+            ..Default::default()
+        };
+        pool.reserves[0] = Reserve {
+            tokens: TokenAmount::new(1_000_000),
+            mint: mint_a,
+            vault: Pubkey::new_unique(),
+        };
+        pool.reserves[1] = Reserve {
+            tokens: TokenAmount::new(2_000_000),
+            mint: mint_b,
+            vault: Pubkey::new_unique(),
+        };
+        let mut data = Vec::new();
+        pool.try_serialize(&mut data).unwrap();
+        data.resize(Pool::space(), 0);
+
+        let description = match Account::decode(&data).unwrap() {
+            Account::Pool(decoded) => {
+                assert_eq!(decoded, pool);
+                describe_pool(&decoded, None)
+            }
+            _ => panic!("Expected a pool"),
+        };
+
+        assert_eq!(description["curve"]["kind"], "const_prod");
+        assert_eq!(description["invariant_from_reserves"], "2000000000000");
+        assert_eq!(description["prices"][0]["sell_mint"], mint_a.to_string());
+        // selling 100 tokens of A gives 199 tokens of B
+        assert_eq!(description["prices"][0]["price"], 1.99);
+        assert_eq!(description["prices"][1]["sell_mint"], mint_b.to_string());
+    }
+
+    #[test]
+    fn it_decodes_pool_stats() {
+        let mint = Pubkey::new_unique();
+        let mut stats = PoolStats {
+            pool: Pubkey::new_unique(),
+            swap_count: 3,
+            ..Default::default()
+        };
+        stats.reserves[0].mint = mint;
+        stats.reserves[0].swap_volume_in = u128::MAX;
+        let mut data = Vec::new();
+        stats.try_serialize(&mut data).unwrap();
+
+        let description = Account::decode(&data).unwrap().describe(None, None);
+
+        assert_eq!(description["account"], "PoolStats");
+        assert_eq!(description["swap_count"], 3);
+        assert_eq!(description["reserves"].as_array().unwrap().len(), 1);
+        assert_eq!(description["reserves"][0]["mint"], mint.to_string());
+        assert_eq!(
+            description["reserves"][0]["swap_volume_in"],
+            u128::MAX.to_string()
+        );
+    }
+
+    #[test]
+    fn it_fails_unknown_discriminator() {
+        assert!(Account::decode(&[0; 100]).is_err());
+        assert!(Account::decode(&[0; 4]).is_err());
+    }
+
+    #[test]
+    fn it_orders_snapshots_chronologically() {
+        let mut farm = Farm::default();
+        assert_eq!(chronological_snapshots(&farm).len(), 1);
+
+        let mut ring_buffer = farm.snapshots.ring_buffer;
+        for (index, snapshot) in ring_buffer.iter_mut().enumerate().take(3) {
+            snapshot.started_at = Slot::new(index as u64 * 10);
+        }
+        farm.snapshots.ring_buffer = ring_buffer;
+        farm.snapshots.ring_buffer_tip = 2;
+        let snapshots = chronological_snapshots(&farm);
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[2].started_at, Slot::new(20));
+
+        // rotated, the tip overwrote the oldest snapshots
+        for (index, snapshot) in ring_buffer.iter_mut().enumerate() {
+            snapshot.started_at = Slot::new(if index <= 2 {
+                (SNAPSHOTS_LEN + index) as u64
+            } else {
+                index as u64
+            });
+        }
+        farm.snapshots.ring_buffer = ring_buffer;
+        let snapshots = chronological_snapshots(&farm);
+        assert_eq!(snapshots.len(), SNAPSHOTS_LEN);
+        assert_eq!(snapshots[0].started_at, Slot::new(3));
+        assert!(snapshots
+            .windows(2)
+            .all(|pair| pair[0].started_at < pair[1].started_at));
+    }
+
+    #[test]
+    fn it_calculates_farmer_pending_harvest() {
+        let mint = Pubkey::new_unique();
+        let mut farm = Farm::default();
+        let mut harvests = farm.harvests;
+        harvests[0].mint = mint;
+        harvests[0].periods[0] = farming::models::HarvestPeriod {
+            tps: TokenAmount::new(10),
+            starts_at: Slot::new(0),
+            ends_at: Slot::new(100),
+        };
+        farm.harvests = harvests;
+        let mut ring_buffer = farm.snapshots.ring_buffer;
+        ring_buffer[0] = Snapshot {
+            staked: TokenAmount::new(100),
+            started_at: Slot::new(1),
+        };
+        farm.snapshots.ring_buffer = ring_buffer;
+
+        let farmer = Farmer {
+            staked: TokenAmount::new(50),
+            calculate_next_harvest_from: Slot::new(1),
+            ..Default::default()
+        };
+
+        let description = describe_farmer(&farmer, Some((&farm, 10)));
+
+        // half of the stake for slots 1 to 10
+        let pending = &description["pending_harvest"];
+        assert_eq!(pending["harvests"][0]["mint"], mint.to_string());
+        assert_eq!(pending["harvests"][0]["tokens"], 50);
+        assert_eq!(description["harvests"], json!([]));
+        assert_eq!(
+            describe_farmer(&farmer, None)["pending_harvest"],
+            Value::Null
+        );
+    }
+}
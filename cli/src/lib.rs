@@ -0,0 +1,15 @@
+//! Offline tools for accounts of the amm and farming programs. They read
+//! account data dumped by `solana account` or RPC and need neither Node nor a
+//! connection to a cluster.
+//!
+//! - `inspect` decodes an account into JSON or a table, see [`describe`]
+//! - `simulate` replays trade flow against a pool with other parameters, see
+//!   [`simulate`]
+
+pub mod account_data;
+pub mod describe;
+pub mod logs;
+pub mod simulate;
+pub mod table;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
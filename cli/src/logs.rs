@@ -0,0 +1,19 @@
+//! Program logs are written to stdout by default. Offline, stdout is reserved
+//! for the output, hence we stub the log syscall.
+
+use anchor_lang::solana_program::program_stubs::{
+    set_syscall_stubs, SyscallStubs,
+};
+
+struct LogStub;
+
+impl SyscallStubs for LogStub {
+    fn sol_log(&self, message: &str) {
+        eprintln!("[LOG] {}", message);
+    }
+}
+
+/// From now on program logs are written to stderr.
+pub fn redirect_to_stderr() {
+    set_syscall_stubs(Box::new(LogStub));
+}
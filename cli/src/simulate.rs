@@ -0,0 +1,747 @@
+//! Replays a flow of swaps, deposits and redeems against a pool with the
+//! program's own logic, to see how LPs would have fared with other curve or
+//! swap fee parameters.
+//!
+//! The flow is CSV with one operation per line. Reserves are referred to by
+//! their index in the pool or by their mint. Token amounts are in the mints'
+//! smallest units. Empty lines, lines starting with `#` and a header line
+//! starting with `kind` are skipped.
+//!
+//! ```text
+//! kind,arg1,arg2,arg3
+//! swap,<sell reserve>,<buy reserve>,<sell tokens, swap fee included>
+//! deposit,<max tokens of reserve 0>,<max tokens of reserve 1>,...
+//! redeem,<lp tokens to burn>
+//! ```
+//!
+//! Operations are applied with the same [`Pool`] methods as the endpoints
+//! apply them, with the given [`ProgramConfig`], except that there's no
+//! discount, no referrer and no slippage limit. An operation which fails
+//! leaves the pool as it was, like a failed transaction, and is counted in the
+//! report.
+//!
+//! Values are in the smallest units of the pool's first reserve. Reserves are
+//! valued with [`quote_price`] against the first reserve.
+
+use crate::describe::quote_price;
+use crate::Result;
+use ::amm::consts::MAX_RESERVES;
+use ::amm::prelude::{
+    err, error, AmmError, Curve, Permillion, Pool, ProgramConfig, Pubkey,
+    Reserve, SDecimal, Slot, SwapResult, TokenAmount,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    /// Sells tokens of a reserve for tokens of another, swap fee included.
+    Swap {
+        sell: usize,
+        buy: usize,
+        tokens: u64,
+    },
+    /// Max tokens to deposit, one amount per reserve.
+    Deposit(Vec<u64>),
+    /// LP tokens to burn.
+    Redeem(u64),
+}
+
+/// Overrides of the pool's parameters. The parameters which are [`None`] are
+/// taken from the pool.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Params {
+    /// Zero for [`Curve::ConstProd`], otherwise [`Curve::Stable`].
+    pub amplifier: Option<u64>,
+    pub swap_fee: Option<Permillion>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ReserveStats {
+    sold: u128,
+    bought: u128,
+    deposited: u128,
+    redeemed: u128,
+    swap_fees: u128,
+}
+
+pub struct Simulation {
+    pool: Pool,
+    lp_supply: TokenAmount,
+    /// Weighted pools and pending swap fees need the slot, other flows are
+    /// replayed without it.
+    slot: Option<u64>,
+    /// The program toll and the stable curve invariant method.
+    config: ProgramConfig,
+    start: (Pool, TokenAmount),
+    reserves: Vec<ReserveStats>,
+    toll_lp_tokens: u128,
+    swaps: u64,
+    deposits: u64,
+    redeems: u64,
+    /// Per swap, how much less the trader got than at the spot price.
+    slippage_bps: Vec<f64>,
+    /// Error messages of failed operations and how often they occurred.
+    errors: BTreeMap<String, u64>,
+}
+
+/// Parses the flow, see the module docs for the format.
+pub fn parse_ops(csv: &str, pool: &Pool) -> Result<Vec<Op>> {
+    let mut ops = Vec::new();
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        let is_header = index == 0 && line.starts_with("kind");
+        if line.is_empty() || line.starts_with('#') || is_header {
+            continue;
+        }
+
+        let op = parse_op(line, pool)
+            .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        ops.push(op);
+    }
+
+    Ok(ops)
+}
+
+fn parse_op(line: &str, pool: &Pool) -> Result<Op> {
+    let mut columns: Vec<&str> = line.split(',').map(str::trim).collect();
+    // spreadsheets export the same number of columns on every line
+    while columns.last() == Some(&"") {
+        columns.pop();
+    }
+
+    let op = match columns.as_slice() {
+        ["swap", sell, buy, tokens] => Op::Swap {
+            sell: reserve_index(pool, sell)?,
+            buy: reserve_index(pool, buy)?,
+            tokens: tokens.parse()?,
+        },
+        ["deposit", max_tokens @ ..]
+            if max_tokens.len() == pool.reserves().len() =>
+        {
+            Op::Deposit(
+                max_tokens
+                    .iter()
+                    .map(|tokens| tokens.parse())
+                    .collect::<std::result::Result<_, _>>()?,
+            )
+        }
+        ["redeem", lp_tokens] => Op::Redeem(lp_tokens.parse()?),
+        _ => return Err(format!("Unrecognized operation '{}'", line).into()),
+    };
+
+    Ok(op)
+}
+
+fn reserve_index(pool: &Pool, reserve: &str) -> Result<usize> {
+    let reserves = pool.reserves();
+
+    if let Ok(index) = reserve.parse::<usize>() {
+        return if index < reserves.len() {
+            Ok(index)
+        } else {
+            Err(format!("Pool has no reserve {}", index).into())
+        };
+    }
+
+    let mint = Pubkey::from_str(reserve)
+        .map_err(|_| format!("'{}' is neither an index nor a mint", reserve))?;
+    reserves
+        .iter()
+        .position(|r| r.mint == mint)
+        .ok_or_else(|| format!("Pool has no reserve of mint {}", mint).into())
+}
+
+/// An empty constant product pool with a new mint per reserve, to be started
+/// with [`Simulation::with_first_deposit`].
+pub fn new_pool(dimension: usize) -> Result<Pool> {
+    if !(2..=MAX_RESERVES).contains(&dimension) {
+        return Err(
+            format!("A pool has from 2 to {} reserves", MAX_RESERVES).into()
+        );
+    }
+
+    let mut reserves = [Reserve::default(); MAX_RESERVES];
+    for reserve in reserves.iter_mut().take(dimension) {
+        reserve.mint = Pubkey::new_unique();
+        reserve.vault = Pubkey::new_unique();
+    }
+
+    Ok(Pool {
+        version: Pool::VERSION,
+        dimension: dimension as u64,
+        reserves,
+        curve: Curve::ConstProd,
+        ..Default::default()
+    })
+}
+
+/// The pool with given parameters. The invariant of a stable curve is
+/// computed from the reserves with the configured method. Overriding the swap
+/// fee drops a pending one.
+pub fn with_params(
+    pool: &Pool,
+    params: Params,
+    config: &ProgramConfig,
+) -> Result<Pool> {
+    let mut pool = pool.clone();
+
+    if let Some(amplifier) = params.amplifier {
+        pool.curve = if amplifier == 0 {
+            Curve::ConstProd
+        } else {
+            Curve::Stable {
+                amplifier,
+                invariant: SDecimal::default(),
+            }
+        };
+        pool.update_curve_invariant_with(config.stable_curve_invariant)?;
+    }
+
+    if let Some(swap_fee) = params.swap_fee {
+        pool.swap_fee = swap_fee;
+        pool.pending_swap_fee = None;
+    }
+
+    Ok(pool)
+}
+
+impl Simulation {
+    pub fn new(
+        pool: Pool,
+        lp_supply: TokenAmount,
+        slot: Option<u64>,
+        config: ProgramConfig,
+    ) -> Self {
+        let dimension = pool.reserves().len();
+
+        Self {
+            start: (pool.clone(), lp_supply),
+            pool,
+            lp_supply,
+            slot,
+            config,
+            reserves: vec![ReserveStats::default(); dimension],
+            toll_lp_tokens: 0,
+            swaps: 0,
+            deposits: 0,
+            redeems: 0,
+            slippage_bps: Vec::new(),
+            errors: BTreeMap::new(),
+        }
+    }
+
+    /// Starts after the first deposit of given tokens, one amount per
+    /// reserve, into a pool without liquidity.
+    pub fn with_first_deposit(
+        pool: Pool,
+        tokens: &[u64],
+        slot: Option<u64>,
+        config: ProgramConfig,
+    ) -> Result<Self> {
+        let mut sim = Self::new(pool, TokenAmount::new(0), slot, config);
+        sim.deposit(tokens)
+            .map_err(|e| format!("First deposit failed: {}", e))?;
+
+        Ok(Self::new(sim.pool, sim.lp_supply, slot, sim.config))
+    }
+
+    pub fn run(&mut self, ops: &[Op]) {
+        for op in ops {
+            self.apply(op);
+        }
+    }
+
+    pub fn apply(&mut self, op: &Op) {
+        let before = (self.pool.clone(), self.lp_supply);
+
+        let result = match op {
+            Op::Swap { sell, buy, tokens } => {
+                self.swap(*sell, TokenAmount::new(*tokens), *buy)
+            }
+            Op::Deposit(max_tokens) => self.deposit(max_tokens),
+            Op::Redeem(lp_tokens) => self.redeem(TokenAmount::new(*lp_tokens)),
+        };
+
+        if let Err(e) = result {
+            // the endpoint would revert the transaction
+            self.pool = before.0;
+            self.lp_supply = before.1;
+            *self.errors.entry(e.to_string()).or_default() += 1;
+        }
+    }
+
+    /// As in [`amm::endpoints::swap::handle`].
+    fn swap(
+        &mut self,
+        sell: usize,
+        sell_tokens: TokenAmount,
+        buy: usize,
+    ) -> anchor_lang::Result<()> {
+        let needs_slot = self.pool.pending_swap_fee.is_some()
+            || matches!(self.pool.curve, Curve::Weighted { .. });
+        let now = if needs_slot {
+            self.now()?
+        } else {
+            Slot::default()
+        };
+        self.pool.apply_pending_swap_fee(now);
+
+        let sell_mint = self.pool.reserves[sell].mint;
+        let buy_mint = self.pool.reserves[buy].mint;
+        let spot_price =
+            quote_price(&self.pool, sell_mint, buy_mint, self.slot);
+
+        // there's no discount
+        let SwapResult {
+            bought,
+            swap_fee,
+            toll_in_lp_tokens,
+        } = self.pool.swap_with_fee(
+            sell_mint,
+            sell_tokens,
+            buy_mint,
+            self.pool.swap_fee.into(),
+            self.lp_supply,
+            &self.config,
+            now,
+        )?;
+        if let Some(toll) = toll_in_lp_tokens {
+            self.lp_supply.amount = self
+                .lp_supply
+                .amount
+                .checked_add(toll.amount)
+                .ok_or(AmmError::MathOverflow)?;
+            self.toll_lp_tokens += toll.amount as u128;
+        }
+
+        self.swaps += 1;
+        self.reserves[sell].sold += sell_tokens.amount as u128;
+        self.reserves[sell].swap_fees += swap_fee.amount as u128;
+        self.reserves[buy].bought += bought.amount as u128;
+        if let Some(spot_price) = spot_price {
+            let tokens_to_swap = sell_tokens.amount - swap_fee.amount;
+            let at_spot_price = tokens_to_swap as f64 * spot_price;
+            if at_spot_price > 0.0 {
+                self.slippage_bps.push(
+                    (1.0 - bought.amount as f64 / at_spot_price) * 10_000.0,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// As in [`amm::endpoints::deposit_liquidity::handle`].
+    fn deposit(&mut self, max_tokens: &[u64]) -> anchor_lang::Result<()> {
+        self.check_not_bootstrapping()?;
+
+        let max_tokens = self
+            .pool
+            .reserves()
+            .iter()
+            .zip(max_tokens)
+            .map(|(r, tokens)| (r.mint, TokenAmount::new(*tokens)))
+            .collect();
+
+        let (lp_tokens_to_distribute, tokens_to_deposit) = self
+            .pool
+            .deposit_liquidity(max_tokens, self.lp_supply, &self.config)?;
+
+        self.lp_supply.amount = self
+            .lp_supply
+            .amount
+            .checked_add(lp_tokens_to_distribute.amount)
+            .ok_or(AmmError::MathOverflow)?;
+        self.deposits += 1;
+        for (stats, reserve) in
+            self.reserves.iter_mut().zip(self.pool.reserves())
+        {
+            stats.deposited += tokens_to_deposit
+                .get(&reserve.mint)
+                .map_or(0, |t| t.amount) as u128;
+        }
+
+        Ok(())
+    }
+
+    /// As in [`amm::endpoints::redeem_liquidity::handle`].
+    fn redeem(&mut self, lp_tokens: TokenAmount) -> anchor_lang::Result<()> {
+        self.check_not_bootstrapping()?;
+
+        let min_tokens = self
+            .pool
+            .reserves()
+            .iter()
+            .map(|r| (r.mint, TokenAmount::new(0)))
+            .collect();
+        let tokens_to_redeem = self.pool.redeem_liquidity(
+            min_tokens,
+            lp_tokens,
+            self.lp_supply,
+            &self.config,
+        )?;
+
+        self.lp_supply.amount -= lp_tokens.amount;
+        self.redeems += 1;
+        for (stats, reserve) in
+            self.reserves.iter_mut().zip(self.pool.reserves())
+        {
+            stats.redeemed += tokens_to_redeem
+                .get(&reserve.mint)
+                .map_or(0, |t| t.amount) as u128;
+        }
+
+        Ok(())
+    }
+
+    /// While a weighted pool bootstraps only its admin provides liquidity,
+    /// and the flow isn't the admin's.
+    fn check_not_bootstrapping(&self) -> anchor_lang::Result<()> {
+        if matches!(self.pool.curve, Curve::Weighted { .. })
+            && self.pool.is_bootstrapping(self.now()?)
+        {
+            return Err(error!(AmmError::PoolBootstrapping));
+        }
+
+        Ok(())
+    }
+
+    fn now(&self) -> anchor_lang::Result<Slot> {
+        self.slot.map(Slot::new).ok_or_else(|| {
+            error!(err::arg("Weighted pools and pending swap fees need a slot"))
+        })
+    }
+
+    /// Describes the outcome of the operations applied so far.
+    ///
+    /// An LP token's value is the value of the reserves divided by the LP
+    /// supply. Holding is valued as the tokens which backed an LP token at
+    /// the start, at the end prices. Swap fees are valued at the end prices,
+    /// and the toll's share of them is the value of the LP tokens minted as
+    /// toll.
+    pub fn report(&self) -> Value {
+        let (start_pool, start_lp_supply) = &self.start;
+        let start_prices = prices(start_pool, self.slot);
+        let end_prices = prices(&self.pool, self.slot);
+
+        let start_tokens = tokens(start_pool);
+        let end_tokens = tokens(&self.pool);
+        let start_value = value(&start_tokens, &start_prices);
+        let end_value = value(&end_tokens, &end_prices);
+        let hold_value = value(&start_tokens, &end_prices);
+
+        let per_lp_token = |value: Option<f64>, lp_supply: TokenAmount| {
+            value
+                .filter(|_| lp_supply.amount > 0)
+                .map(|value| value / lp_supply.amount as f64)
+        };
+        let lp_token_start = per_lp_token(start_value, *start_lp_supply);
+        let lp_token_end = per_lp_token(end_value, self.lp_supply);
+        let lp_token_hold = per_lp_token(hold_value, *start_lp_supply);
+
+        let swap_fees: Vec<f64> =
+            self.reserves.iter().map(|r| r.swap_fees as f64).collect();
+        let swap_fees_value = value(&swap_fees, &end_prices);
+        let toll_value =
+            lp_token_end.map(|value| value * self.toll_lp_tokens as f64);
+
+        let failed: u64 = self.errors.values().sum();
+
+        json!({
+            "curve": curve_name(&self.pool.curve),
+            "amplifier": match self.pool.curve {
+                Curve::Stable { amplifier, .. } => Some(amplifier),
+                _ => None,
+            },
+            "swap_fee_permillion": self.pool.swap_fee.permillion,
+            "swaps": self.swaps,
+            "deposits": self.deposits,
+            "redeems": self.redeems,
+            "failed": failed,
+            "lp_supply_start": start_lp_supply.amount,
+            "lp_supply_end": self.lp_supply.amount,
+            "toll_lp_tokens": self.toll_lp_tokens.to_string(),
+            "swap_fees_value": swap_fees_value,
+            "toll_value": toll_value,
+            "lp_fees_value": swap_fees_value
+                .zip(toll_value)
+                .map(|(fees, toll)| fees - toll),
+            "lp_token_value_start": lp_token_start,
+            "lp_token_value_end": lp_token_end,
+            "lp_token_value_hold": lp_token_hold,
+            "lp_vs_hold_percent": lp_token_end
+                .zip(lp_token_hold)
+                .filter(|(_, hold)| *hold > 0.0)
+                .map(|(end, hold)| (end / hold - 1.0) * 100.0),
+            "slippage_bps": slippage(&self.slippage_bps),
+            "reserves": start_pool
+                .reserves()
+                .iter()
+                .zip(self.pool.reserves())
+                .zip(&self.reserves)
+                .map(|((start, end), stats)| json!({
+                    "mint": end.mint.to_string(),
+                    "start": start.tokens.amount,
+                    "end": end.tokens.amount,
+                    "sold": stats.sold.to_string(),
+                    "bought": stats.bought.to_string(),
+                    "deposited": stats.deposited.to_string(),
+                    "redeemed": stats.redeemed.to_string(),
+                    "swap_fees": stats.swap_fees.to_string(),
+                }))
+                .collect::<Vec<_>>(),
+            "errors": self
+                .errors
+                .iter()
+                .map(|(error, count)| json!({
+                    "error": error,
+                    "count": count,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn curve_name(curve: &Curve) -> &'static str {
+    match curve {
+        Curve::ConstProd => "const_prod",
+        Curve::Stable { .. } => "stable",
+        Curve::Weighted { .. } => "weighted",
+    }
+}
+
+fn tokens(pool: &Pool) -> Vec<f64> {
+    pool.reserves()
+        .iter()
+        .map(|r| r.tokens.amount as f64)
+        .collect()
+}
+
+/// Per reserve, the price in tokens of the first reserve.
+fn prices(pool: &Pool, slot: Option<u64>) -> Vec<Option<f64>> {
+    let reserves = pool.reserves();
+
+    reserves
+        .iter()
+        .enumerate()
+        .map(|(index, reserve)| {
+            if index == 0 {
+                Some(1.0)
+            } else {
+                quote_price(pool, reserve.mint, reserves[0].mint, slot)
+            }
+        })
+        .collect()
+}
+
+fn value(tokens: &[f64], prices: &[Option<f64>]) -> Option<f64> {
+    tokens
+        .iter()
+        .zip(prices)
+        .map(|(tokens, price)| price.map(|price| tokens * price))
+        .sum()
+}
+
+/// Nearest rank percentiles.
+fn slippage(samples_bps: &[f64]) -> Value {
+    if samples_bps.is_empty() {
+        return Value::Null;
+    }
+
+    let mut sorted = samples_bps.to_vec();
+    // there are no NaNs as the spot value is positive
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.max(1) - 1]
+    };
+
+    json!({
+        "mean": sorted.iter().sum::<f64>() / sorted.len() as f64,
+        "p50": percentile(50.0),
+        "p90": percentile(90.0),
+        "p99": percentile(99.0),
+        "max": sorted[sorted.len() - 1],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::amm::prelude::StableCurveInvariantMethod;
+
+    /// Reserves are large enough for quoted prices not to be distorted by
+    /// rounding.
+    const PRECISION: u64 = 1_000_000_000_000;
+
+    fn pool() -> Pool {
+        new_pool(2).unwrap()
+    }
+
+    #[test]
+    fn it_parses_ops() {
+        let pool = pool();
+        let mint = pool.reserves[1].mint;
+        let csv = format!(
+            "kind,arg1,arg2,arg3
+# a comment
+
+swap,0,{},1000
+deposit, 10, 20,
+redeem,5,,
+",
+            mint
+        );
+
+        assert_eq!(
+            parse_ops(&csv, &pool).unwrap(),
+            vec![
+                Op::Swap {
+                    sell: 0,
+                    buy: 1,
+                    tokens: 1000
+                },
+                Op::Deposit(vec![10, 20]),
+                Op::Redeem(5),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_fails_invalid_ops() {
+        let pool = pool();
+
+        for csv in [
+            "swap,0,2,1000",
+            "swap,0,1",
+            "deposit,10",
+            "redeem,-5",
+            "withdraw,5",
+        ] {
+            let error = parse_ops(csv, &pool).unwrap_err();
+            assert!(error.to_string().starts_with("Line 1:"), "{}", csv);
+        }
+    }
+
+    #[test]
+    fn it_applies_params() {
+        let pool = with_params(
+            &pool(),
+            Params {
+                amplifier: Some(10),
+                swap_fee: Some(Permillion::from_percent(1)),
+            },
+            &ProgramConfig::default(),
+        )
+        .unwrap();
+
+        assert!(matches!(pool.curve, Curve::Stable { amplifier: 10, .. }));
+        assert_eq!(pool.swap_fee, Permillion::from_percent(1));
+
+        let pool =
+            with_params(&pool, Params::default(), &ProgramConfig::default())
+                .unwrap();
+        assert!(matches!(pool.curve, Curve::Stable { amplifier: 10, .. }));
+    }
+
+    #[test]
+    fn it_simulates_flow() {
+        let pool = with_params(
+            &pool(),
+            Params {
+                amplifier: None,
+                swap_fee: Some(Permillion::from_percent(1)),
+            },
+            &ProgramConfig::default(),
+        )
+        .unwrap();
+        let mut sim = Simulation::with_first_deposit(
+            pool,
+            &[PRECISION, PRECISION],
+            None,
+            ProgramConfig::default(),
+        )
+        .unwrap();
+
+        sim.run(&[
+            Op::Swap {
+                sell: 0,
+                buy: 1,
+                tokens: PRECISION / 100,
+            },
+            Op::Swap {
+                sell: 1,
+                buy: 0,
+                tokens: PRECISION / 100,
+            },
+            // more than the supply
+            Op::Redeem(u64::MAX),
+            Op::Deposit(vec![1_000_000, 1_000_000]),
+        ]);
+        let report = sim.report();
+
+        assert_eq!(report["swaps"], 2);
+        assert_eq!(report["deposits"], 1);
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["reserves"][0]["swap_fees"], "100000000");
+        assert_eq!(report["reserves"][1]["swap_fees"], "100000000");
+        assert_eq!(report["errors"][0]["count"], 1);
+        // the fees stay in the pool
+        let start = report["lp_token_value_start"].as_f64().unwrap();
+        let end = report["lp_token_value_end"].as_f64().unwrap();
+        assert!(end > start);
+        assert!(report["lp_vs_hold_percent"].as_f64().unwrap() > 0.0);
+        let slippage = &report["slippage_bps"];
+        assert!(slippage["p50"].as_f64().unwrap() > 0.0);
+        assert!(slippage["max"].as_f64() >= slippage["p50"].as_f64());
+    }
+
+    #[test]
+    fn it_simulates_with_program_config() {
+        let pool = with_params(
+            &pool(),
+            Params {
+                amplifier: Some(10),
+                swap_fee: Some(Permillion::from_percent(1)),
+            },
+            &ProgramConfig::default(),
+        )
+        .unwrap();
+        let swap = Op::Swap {
+            sell: 0,
+            buy: 1,
+            tokens: PRECISION / 100,
+        };
+
+        let simulate = |config: ProgramConfig| {
+            let mut sim = Simulation::with_first_deposit(
+                pool.clone(),
+                &[PRECISION, PRECISION],
+                None,
+                config,
+            )
+            .unwrap();
+            sim.run(&[swap.clone()]);
+            assert_eq!(sim.report()["swaps"], 1);
+            sim
+        };
+
+        assert!(simulate(ProgramConfig::default()).toll_lp_tokens > 0);
+        let sim = simulate(ProgramConfig {
+            program_toll_swap_fee_share: Permillion { permillion: 0 },
+            ..Default::default()
+        });
+        assert_eq!(sim.toll_lp_tokens, 0);
+
+        let integer = StableCurveInvariantMethod::Integer;
+        let sim = simulate(ProgramConfig {
+            stable_curve_invariant: integer,
+            ..Default::default()
+        });
+        let mut expected = sim.pool.clone();
+        expected.update_curve_invariant_with(integer).unwrap();
+        assert_eq!(sim.pool.curve, expected.curve);
+    }
+}
@@ -0,0 +1,158 @@
+//! Renders JSON for humans. Objects become indented "key: value" lines, arrays
+//! of flat objects with the same keys become tables with a column per key, and
+//! other arrays become lists.
+
+use serde_json::Value;
+use std::fmt::Write;
+
+const INDENT: usize = 2;
+
+pub fn render(value: &Value) -> String {
+    let mut out = String::new();
+    render_into(&mut out, value, 0);
+
+    out
+}
+
+fn render_into(out: &mut String, value: &Value, indent: usize) {
+    let pad = " ".repeat(indent);
+
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                if is_scalar(value) {
+                    writeln!(out, "{}{}: {}", pad, key, scalar(value)).unwrap();
+                } else {
+                    writeln!(out, "{}{}:", pad, key).unwrap();
+                    render_into(out, value, indent + INDENT);
+                }
+            }
+        }
+        Value::Array(values) if !values.is_empty() => {
+            if let Some(columns) = table_columns(values) {
+                render_table(out, &columns, values, &pad);
+                return;
+            }
+
+            for value in values {
+                if is_scalar(value) {
+                    writeln!(out, "{}- {}", pad, scalar(value)).unwrap();
+                } else {
+                    writeln!(out, "{}-", pad).unwrap();
+                    render_into(out, value, indent + INDENT);
+                }
+            }
+        }
+        value => writeln!(out, "{}{}", pad, scalar(value)).unwrap(),
+    }
+}
+
+/// Keys of the objects if they all have the same keys and scalar values.
+fn table_columns(values: &[Value]) -> Option<Vec<&str>> {
+    let columns: Vec<&str> = values
+        .first()?
+        .as_object()?
+        .keys()
+        .map(String::as_str)
+        .collect();
+
+    let is_row = |value: &Value| {
+        value.as_object().map_or(false, |object| {
+            object.len() == columns.len()
+                && columns.iter().zip(object).all(|(column, (key, value))| {
+                    *column == key && is_scalar(value)
+                })
+        })
+    };
+
+    values.iter().all(is_row).then(|| columns)
+}
+
+fn render_table(out: &mut String, columns: &[&str], rows: &[Value], pad: &str) {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|column| scalar(&row[*column])).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            cells
+                .iter()
+                .map(|row| row[index].len())
+                .chain([column.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let header: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+    for row in [header].iter().chain(&cells) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+            .collect();
+        writeln!(out, "{}{}", pad, line.join("  ").trim_end()).unwrap();
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    match value {
+        Value::Object(object) => object.is_empty(),
+        Value::Array(values) => values.is_empty(),
+        _ => true,
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(_) => "{}".to_string(),
+        Value::Array(_) => "[]".to_string(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_renders_objects_lists_and_tables() {
+        let value = json!({
+            "account": "Farm",
+            "pending": null,
+            "harvests": [{
+                "mint": "abc",
+                "periods": [],
+                "timeline": [
+                    { "from": 1, "to": 10, "tps": 100 },
+                    { "from": 11, "to": 2000, "tps": 0 },
+                ],
+            }],
+            "tags": ["a", "b"],
+        });
+
+        assert_eq!(
+            render(&value),
+            "\
+account: Farm
+pending: -
+harvests:
+  -
+    mint: abc
+    periods: []
+    timeline:
+      from    to  tps
+         1    10  100
+        11  2000    0
+tags:
+  - a
+  - b
+"
+        );
+    }
+}
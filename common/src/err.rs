@@ -0,0 +1,22 @@
+//! Each program has its own error enum, hence these helpers return the error
+//! they're given. The programs' `err::acc` and `err::arg` wrap them with their
+//! own error variants.
+
+use anchor_lang::prelude::msg;
+use std::fmt::Display;
+
+/// Logs why an account given to an endpoint is invalid and returns the
+/// program's invalid account input error.
+pub fn acc<E>(error: E, msg: impl Display) -> E {
+    msg!("[InvalidAccountInput] {}", msg);
+
+    error
+}
+
+/// Logs why an argument given to an endpoint is invalid and returns the
+/// program's invalid argument error.
+pub fn arg<E>(error: E, msg: impl Display) -> E {
+    msg!("[InvalidArg] {}", msg);
+
+    error
+}
@@ -0,0 +1,19 @@
+//! Types and helpers shared by the amm and farming programs. Because both
+//! programs use these types, a [`TokenAmount`] of one program is a
+//! [`TokenAmount`] of the other, eg. when the amm program stakes LP tokens in
+//! a farm by CPI.
+//!
+//! The programs re-export the types from their `models` modules. The layouts
+//! are the same as when each program defined the types itself.
+//!
+//! With the `test-utils` feature, [`test_utils`] has helpers for the
+//! programs' unit tests.
+
+pub mod err;
+pub mod models;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
+pub use models::*;
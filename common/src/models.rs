@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+use decimal::Decimal;
+
+#[derive(
+    AnchorDeserialize,
+    AnchorSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct TokenAmount {
+    pub amount: u64,
+}
+
+#[derive(
+    AnchorDeserialize,
+    AnchorSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct Slot {
+    pub slot: u64,
+}
+
+#[derive(
+    AnchorDeserialize,
+    AnchorSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct Permillion {
+    /// 1% = 10_000
+    pub permillion: u64,
+}
+
+impl TokenAmount {
+    pub fn new(amount: u64) -> Self {
+        Self { amount }
+    }
+
+    pub fn max_value() -> Self {
+        Self {
+            amount: std::u64::MAX,
+        }
+    }
+
+    /// Returns [`None`] on overflow, which the caller turns into their
+    /// program's math overflow error.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.amount.checked_add(other.amount).map(Self::new)
+    }
+
+    /// Returns [`None`] on underflow.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.amount.checked_sub(other.amount).map(Self::new)
+    }
+}
+
+impl Slot {
+    pub fn new(slot: u64) -> Self {
+        Self { slot }
+    }
+
+    pub fn current() -> Result<Self> {
+        Ok(Self {
+            slot: Clock::get()?.slot,
+        })
+    }
+
+    /// The slot given number of slots after this one, or [`None`] on
+    /// overflow.
+    pub fn checked_add(self, slots: u64) -> Option<Self> {
+        self.slot.checked_add(slots).map(Self::new)
+    }
+}
+
+impl Permillion {
+    pub fn from_percent(percent: u64) -> Self {
+        Self {
+            permillion: percent.checked_mul(10_000).unwrap(),
+        }
+    }
+}
+
+impl From<TokenAmount> for Decimal {
+    fn from(tokens: TokenAmount) -> Self {
+        Decimal::from(tokens.amount)
+    }
+}
+
+impl From<Permillion> for Decimal {
+    fn from(permillion: Permillion) -> Self {
+        Decimal::from_permillion(permillion.permillion)
+    }
+}
+
+impl From<u64> for TokenAmount {
+    fn from(amount: u64) -> Self {
+        Self { amount }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use decimal::TryDiv;
+
+    #[test]
+    fn it_adds_and_subtracts_token_amounts() {
+        let tokens = TokenAmount::new(10);
+
+        assert_eq!(
+            tokens.checked_add(TokenAmount::new(5)),
+            Some(TokenAmount::new(15))
+        );
+        assert_eq!(tokens.checked_add(TokenAmount::max_value()), None);
+        assert_eq!(
+            tokens.checked_sub(TokenAmount::new(10)),
+            Some(TokenAmount::new(0))
+        );
+        assert_eq!(tokens.checked_sub(TokenAmount::new(11)), None);
+    }
+
+    #[test]
+    fn it_adds_slots() {
+        assert_eq!(Slot::new(10).checked_add(5), Some(Slot::new(15)));
+        assert_eq!(Slot::new(u64::MAX).checked_add(1), None);
+    }
+
+    #[test]
+    fn it_converts_to_decimal() {
+        assert_eq!(Decimal::from(TokenAmount::new(3)), Decimal::from(3u64));
+        assert_eq!(
+            Decimal::from(Permillion::from_percent(50)),
+            Decimal::one().try_div(Decimal::from(2u64)).unwrap()
+        );
+    }
+}
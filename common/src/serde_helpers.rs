@@ -0,0 +1,129 @@
+//! With the `serde` feature, the models of both programs derive serde traits.
+//! These modules are for fields whose serde representation isn't the one we
+//! want, to be used with `#[serde(with)]`. The programs re-export this module,
+//! so that their models refer to it as `crate::serde_helpers`.
+
+/// Pubkeys as base58 strings instead of byte arrays.
+pub mod pubkey {
+    use anchor_lang::prelude::Pubkey;
+    use serde_crate::de::Error;
+    use serde_crate::{Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(
+        pubkey: &Pubkey,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Pubkey, D::Error> {
+        let base58 = String::deserialize(deserializer)?;
+        Pubkey::from_str(&base58).map_err(D::Error::custom)
+    }
+}
+
+/// Same as [`pubkey`] for optional pubkeys.
+pub mod option_pubkey {
+    use anchor_lang::prelude::Pubkey;
+    use serde_crate::de::Error;
+    use serde_crate::{Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(
+        pubkey: &Option<Pubkey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match pubkey {
+            Some(pubkey) => serializer.serialize_some(&pubkey.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Pubkey>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|base58| Pubkey::from_str(&base58).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// Serde implements its traits only for arrays of up to 32 elements.
+pub mod array {
+    use serde_crate::de::Error;
+    use serde_crate::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::convert::TryInto;
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+        array: &[T; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(array)
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(
+        deserializer: D,
+    ) -> Result<[T; N], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        let elements = Vec::<T>::deserialize(deserializer)?;
+        let len = elements.len();
+
+        elements.try_into().map_err(|_| {
+            D::Error::invalid_length(len, &format!("{} elements", N).as_str())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::Pubkey;
+    use serde_crate::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Model {
+        #[serde(with = "super::pubkey")]
+        key: Pubkey,
+        #[serde(with = "super::option_pubkey")]
+        policy: Option<Pubkey>,
+        #[serde(with = "super::array")]
+        history: [u64; 40],
+    }
+
+    #[test]
+    fn it_serializes_pubkeys_as_base58_and_long_arrays() {
+        let model = Model {
+            key: Pubkey::new_unique(),
+            policy: Some(Pubkey::new_unique()),
+            history: [7; 40],
+        };
+
+        let value = serde_json::to_value(&model).unwrap();
+
+        assert_eq!(value["key"], json!(model.key.to_string()));
+        assert_eq!(value["policy"], json!(model.policy.unwrap().to_string()));
+        assert_eq!(value["history"], json!(vec![7; 40]));
+        assert_eq!(serde_json::from_value::<Model>(value).unwrap(), model);
+
+        let value = json!({ "key": "invalid", "policy": null, "history": [] });
+        assert!(serde_json::from_value::<Model>(value).is_err());
+    }
+
+    #[test]
+    fn it_fails_array_of_wrong_len() {
+        let value = json!({
+            "key": Pubkey::default().to_string(),
+            "policy": null,
+            "history": vec![7; 39],
+        });
+
+        assert!(serde_json::from_value::<Model>(value).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Helpers for the programs' unit tests.
+
+use crate::Slot;
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{
+    set_syscall_stubs, SyscallStubs,
+};
+use anchor_lang::solana_program::rent::Rent;
+
+/// Sets the global clock to given slot. Tests which depend on the clock must
+/// run serially, eg. with `#[serial]`.
+///
+/// The rent sysvar is [`Rent::default`]. The stub is meant for code which
+/// doesn't invoke other programs, and panics on any CPI.
+pub fn set_clock(slot: Slot) {
+    set_syscall_stubs(Box::new(ClockStub { slot }));
+}
+
+struct ClockStub {
+    slot: Slot,
+}
+
+impl SyscallStubs for ClockStub {
+    fn sol_log(&self, message: &str) {
+        println!("[LOG] {}", message);
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            slot: self.slot.slot,
+            ..Default::default()
+        };
+        // the program's memory for the sysvar isn't necessarily aligned
+        unsafe {
+            std::ptr::write_unaligned(var_addr as *mut Clock, clock);
+        }
+
+        0 // success
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            std::ptr::write_unaligned(var_addr as *mut Rent, Rent::default());
+        }
+
+        0 // success
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        panic!("No instructions expected, got {:#?}", instruction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_sets_clock_beyond_u8_slots_and_default_rent() {
+        use anchor_lang::solana_program::sysvar::Sysvar;
+
+        set_clock(Slot::new(1_000_000));
+
+        assert_eq!(Slot::current().unwrap(), Slot::new(1_000_000));
+        assert_eq!(Rent::get().unwrap(), Rent::default());
+    }
+}
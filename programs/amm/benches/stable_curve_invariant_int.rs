@@ -0,0 +1,61 @@
+use amm::math::stable_curve_invariant::compute as compute_decimal;
+use amm::math::stable_curve_invariant_int::{compute, Rounding};
+use amm::prelude::TokenAmount;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn bench_reserves(c: &mut Criterion, name: &str, reserves: &[TokenAmount]) {
+    let amp = 4;
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function("amm::math::stable_curve_invariant::compute", |b| {
+        b.iter(|| {
+            compute_decimal(black_box(amp), black_box(reserves)).unwrap();
+        })
+    });
+    group.bench_function(
+        "amm::math::stable_curve_invariant_int::compute",
+        |b| {
+            b.iter(|| {
+                compute(black_box(amp), black_box(reserves), Rounding::Down)
+                    .unwrap();
+            })
+        },
+    );
+    group.finish();
+}
+
+fn bench_two_reserves(c: &mut Criterion) {
+    let reserves =
+        vec![8_374_498_120_012_u64.into(), 8_329_984_930_238_u64.into()];
+
+    bench_reserves(c, "two_reserves", &reserves);
+}
+
+fn bench_three_reserves(c: &mut Criterion) {
+    let reserves = vec![
+        8_374_498_120_012_u64.into(),
+        8_329_984_930_238_u64.into(),
+        8_338_259_921_130_u64.into(),
+    ];
+
+    bench_reserves(c, "three_reserves", &reserves);
+}
+
+fn bench_four_reserves(c: &mut Criterion) {
+    let reserves = vec![
+        8_374_498_120_012_u64.into(),
+        8_329_984_930_238_u64.into(),
+        8_338_259_921_130_u64.into(),
+        8_401_773_002_917_u64.into(),
+    ];
+
+    bench_reserves(c, "four_reserves", &reserves);
+}
+
+criterion_group!(
+    benches,
+    bench_two_reserves,
+    bench_three_reserves,
+    bench_four_reserves
+);
+criterion_main!(benches);
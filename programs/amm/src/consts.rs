@@ -8,14 +8,66 @@ pub const MAX_RESERVES: usize = 4;
 
 /// The pool's admin can change the swap fee with
 /// [`crate::endpoints::set_pool_swap_fee`] endpoint. However, we limit this
-/// update to a maximum fee given by this constant.
+/// update to a maximum fee given by this constant, unless the
+/// [`crate::models::ProgramConfig`] says otherwise.
 pub const MAX_SWAP_FEE: Permillion = Permillion {
     // 1%
     permillion: 1_0000,
 };
 
-/// The program owner gets a share of the swap fee defined by this value.
+/// The [`crate::models::ProgramConfig`] can raise the maximum swap fee at most
+/// to this value.
+pub const MAX_SWAP_FEE_UPPER_BOUND: Permillion = Permillion {
+    // 10%
+    permillion: 10_0000,
+};
+
+/// A swap fee change proposed by the pool's admin with
+/// [`crate::endpoints::propose_swap_fee`] can become effective at the earliest
+/// after this many slots. This gives traders time to react to the change.
+///
+/// There are ~2 slots per second, ie. this is ~1 day.
+pub const MIN_SWAP_FEE_CHANGE_DELAY_SLOTS: u64 = 2 * 3600 * 24;
+
+/// The initial deposit of a reserve added to a pool with
+/// [`crate::endpoints::add_reserve`] must be worth each of the existing
+/// reserves within this tolerance, see [`crate::models::Pool::seed_tokens`].
+pub const MAX_SEED_VALUE_DEVIATION: Permillion = Permillion {
+    // 1%
+    permillion: 1_0000,
+};
+
+/// The program owner gets a share of the swap fee defined by this value,
+/// unless the [`crate::models::ProgramConfig`] says otherwise.
 pub const PROGRAM_TOLL_SWAP_FEE_SHARE: Permillion = Permillion {
     // 1/3
     permillion: 33_3333,
 };
+
+/// The [`crate::models::ProgramConfig`] can set the program toll share at most
+/// to this value. Together with [`MAX_REFERRER_SWAP_FEE_SHARE`] this makes sure
+/// that the liquidity providers always keep the majority of the swap fee.
+pub const MAX_PROGRAM_TOLL_SWAP_FEE_SHARE: Permillion = Permillion {
+    // 1/3
+    permillion: 33_3333,
+};
+
+/// A [`crate::models::Referrer`] gets a share of the swap fee, on top of the
+/// program toll, which can be at most this value. This way the liquidity
+/// providers always keep the majority of the swap fee.
+pub const MAX_REFERRER_SWAP_FEE_SHARE: Permillion = Permillion {
+    // 1/6
+    permillion: 16_6666,
+};
+
+/// Bounds for the weights of the reserves of a pool with
+/// [`crate::models::Curve::Weighted`]. Extreme weights make the price too
+/// sensitive to the reserves.
+pub const MIN_RESERVE_WEIGHT: Permillion = Permillion {
+    // 1%
+    permillion: 1_0000,
+};
+pub const MAX_RESERVE_WEIGHT: Permillion = Permillion {
+    // 99%
+    permillion: 99_0000,
+};
@@ -1,17 +1,67 @@
+pub mod add_reserve;
+pub mod cancel_swap_fee;
+pub mod check_pool_integrity;
+pub mod close_pool;
+pub mod convert_to_const_prod;
 pub mod create_discount_settings;
 pub mod create_pool;
+pub mod create_pool_stats;
 pub mod create_program_toll;
+pub mod create_weighted_pool;
+pub mod deposit_and_farm;
 pub mod deposit_liquidity;
+pub mod deposit_liquidity_v2;
+pub mod execute_twamm;
+pub mod migrate_pool;
+pub mod place_twamm_order;
+pub mod propose_swap_fee;
 pub mod put_discount;
+pub mod put_pool_access;
+pub mod put_program_config;
 pub mod redeem_liquidity;
+pub mod register_referrer;
+pub mod remove_reserve;
+pub mod set_lp_metadata;
+pub mod set_pool_access_policy;
 pub mod set_pool_swap_fee;
+pub mod skim;
 pub mod swap;
+pub mod swap_v2;
+pub mod sync_pool;
+pub mod unfarm_and_redeem;
+pub mod withdraw_twamm_proceeds;
+pub mod zap_in;
 
+pub use add_reserve::*;
+pub use cancel_swap_fee::*;
+pub use check_pool_integrity::*;
+pub use close_pool::*;
+pub use convert_to_const_prod::*;
 pub use create_discount_settings::*;
 pub use create_pool::*;
+pub use create_pool_stats::*;
 pub use create_program_toll::*;
+pub use create_weighted_pool::*;
+pub use deposit_and_farm::*;
 pub use deposit_liquidity::*;
+pub use deposit_liquidity_v2::*;
+pub use execute_twamm::*;
+pub use migrate_pool::*;
+pub use place_twamm_order::*;
+pub use propose_swap_fee::*;
 pub use put_discount::*;
+pub use put_pool_access::*;
+pub use put_program_config::*;
 pub use redeem_liquidity::*;
+pub use register_referrer::*;
+pub use remove_reserve::*;
+pub use set_lp_metadata::*;
+pub use set_pool_access_policy::*;
 pub use set_pool_swap_fee::*;
+pub use skim::*;
 pub use swap::*;
+pub use swap_v2::*;
+pub use sync_pool::*;
+pub use unfarm_and_redeem::*;
+pub use withdraw_twamm_proceeds::*;
+pub use zap_in::*;
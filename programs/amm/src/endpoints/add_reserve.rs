@@ -0,0 +1,205 @@
+//! Admin of a [`Pool`] can add a new reserve to an existing pool without
+//! migrating the liquidity providers.
+//!
+//! If the pool already has liquidity, the admin seeds the new reserve with an
+//! initial deposit. The size of the deposit is given by a [`ReservePrice`],
+//! which states how many tokens of the new mint are worth some amount of tokens
+//! of an existing reserve. In return for the deposit, the admin receives LP
+//! tokens. The deposit must be worth as much as each of the existing reserves,
+//! see [`Pool::seed_tokens`] for how that's checked. Then the new reserve adds
+//! `1 / dimension` of the worth, and therefore the admin receives
+//! `lp_supply / dimension` LP tokens.
+//!
+//! The price of the new mint in a constant product pool cannot be verified on
+//! chain. The liquidity providers trust the admin to give a fair price, as a
+//! mispriced reserve is arbitraged at their expense.
+//!
+//! If the pool has no liquidity yet, the new reserve is empty and no tokens are
+//! transferred, same as in [`crate::endpoints::create_pool`].
+//!
+//! The vault is validated the same way as in
+//! [`crate::endpoints::create_pool`].
+
+use crate::endpoints::create_pool::check_vault;
+use crate::misc::print_lp_supply;
+use crate::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use std::collections::BTreeMap;
+
+#[derive(Accounts)]
+pub struct AddReserve<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = pool.admin == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [Pool::SIGNER_PDA_PREFIX, pool.key().as_ref()],
+        bump
+    )]
+    pub pool_signer: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = lp_mint.key() == pool.mint
+            @ err::acc("LP mint must match pool's mint"),
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+    /// Receives LP tokens for the initial deposit.
+    #[account(
+        mut,
+        constraint = admin_lp_wallet.mint == pool.mint
+            @ err::acc("LP wallet must be of the same mint as pool's mint"),
+    )]
+    pub admin_lp_wallet: Box<Account<'info, TokenAccount>>,
+    /// Vault of the new reserve.
+    #[account(mut)]
+    pub vault: Box<Account<'info, TokenAccount>>,
+    /// Initial deposit is transferred FROM this wallet.
+    #[account(
+        mut,
+        constraint = admin_wallet.mint == vault.mint
+            @ err::acc("Admin wallet mint must match vault mint"),
+    )]
+    pub admin_wallet: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: The pool stats might not be created, in which case they're not
+    /// updated, see [`PoolStats::update_if_created`].
+    #[account(
+        mut,
+        seeds = [PoolStats::PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_stats: AccountInfo<'info>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+}
+
+/// 1. Validates the vault of the new reserve.
+///
+/// 2. Calculates the initial deposit and the LP tokens for the admin.
+///
+/// 3. Transfers the initial deposit into the vault.
+///
+/// 4. Mints LP tokens to the admin.
+///
+/// 5. Records the initial deposit in the pool stats, if they exist.
+///
+/// 6. Updates the curve invariant.
+pub fn handle(ctx: Context<AddReserve>, price: ReservePrice) -> Result<()> {
+    let accs = ctx.accounts;
+
+    //
+    // 1.
+    //
+    check_vault(&accs.vault, accs.pool_signer.key())?;
+    if accs.vault.amount != 0 {
+        return Err(error!(err::acc("Vault of the new reserve must be empty")));
+    }
+
+    //
+    // 2.
+    //
+    let lp_supply = TokenAmount::new(accs.lp_mint.supply);
+    let (seed, lp_tokens_to_mint) = if lp_supply.amount == 0 {
+        (TokenAmount::new(0), TokenAmount::new(0))
+    } else {
+        let seed = accs.pool.seed_tokens(price)?;
+        let lp_tokens_to_mint =
+            TokenAmount::new(lp_supply.amount / accs.pool.dimension);
+
+        (seed, lp_tokens_to_mint)
+    };
+
+    accs.pool
+        .add_reserve(accs.vault.mint, accs.vault.key(), seed)?;
+
+    let signer_seeds = &[
+        Pool::SIGNER_PDA_PREFIX,
+        &accs.pool.key().to_bytes()[..],
+        &[*ctx.bumps.get("pool_signer").unwrap()],
+    ];
+
+    //
+    // 3.
+    //
+    if seed.amount > 0 {
+        if seed.amount > accs.admin_wallet.amount {
+            msg!(
+                "Initial deposit of {} tokens is needed, \
+                but admin wallet has only {}",
+                seed.amount,
+                accs.admin_wallet.amount
+            );
+            return Err(error!(AmmError::InvalidArg));
+        }
+
+        token::transfer(accs.as_transfer_seed_to_vault_ctx(), seed.amount)?;
+    }
+
+    //
+    // 4.
+    //
+    if lp_tokens_to_mint.amount > 0 {
+        token::mint_to(
+            accs.as_mint_lp_tokens_to_admin_ctx()
+                .with_signer(&[&signer_seeds[..]]),
+            lp_tokens_to_mint.amount,
+        )?;
+    }
+
+    //
+    // 5.
+    //
+    if seed.amount > 0 {
+        let mint = accs.vault.mint;
+        PoolStats::update_if_created(&accs.pool_stats, |stats| {
+            stats.record_deposit(&accs.pool, &BTreeMap::from([(mint, seed)]))
+        })?;
+    }
+
+    //
+    // 6.
+    //
+    // no-op if const prod
+    let config = ProgramConfig::load(&accs.program_config)?;
+    accs.pool
+        .update_curve_invariant_with(config.stable_curve_invariant)?;
+
+    print_lp_supply(&mut accs.lp_mint)?;
+
+    Ok(())
+}
+
+impl<'info> AddReserve<'info> {
+    fn as_transfer_seed_to_vault_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.admin_wallet.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.admin.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    fn as_mint_lp_tokens_to_admin_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::MintTo<'info>> {
+        let cpi_accounts = token::MintTo {
+            mint: self.lp_mint.to_account_info(),
+            to: self.admin_lp_wallet.to_account_info(),
+            authority: self.pool_signer.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -0,0 +1,25 @@
+//! Admin of a pool cancels a swap fee change scheduled with
+//! [`crate::endpoints::propose_swap_fee`].
+
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct CancelSwapFee<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = pool.admin.key() == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn handle(ctx: Context<CancelSwapFee>) -> Result<()> {
+    let accs = ctx.accounts;
+
+    if accs.pool.pending_swap_fee.take().is_none() {
+        return Err(error!(err::acc("There's no pending swap fee change")));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! A read only endpoint which asserts that each reserve's vault holds at least
+//! as many tokens as the [`Reserve`] tracks. A surplus is fine, see
+//! [`crate::endpoints::skim`], but a deficit means there's a bug and the
+//! endpoint fails with [`AmmError::InvariantViolation`].
+//!
+//! The remaining accounts must be the pool's vaults, one for each reserve. The
+//! order of the accounts does not matter.
+
+use crate::misc::load_reserve_vaults;
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct CheckPoolIntegrity<'info> {
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, CheckPoolIntegrity<'info>>,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let vaults = load_reserve_vaults(&accs.pool, ctx.remaining_accounts)?;
+    for vault in vaults {
+        let reserve = accs
+            .pool
+            .reserves()
+            .iter()
+            .find(|r| r.mint == vault.mint)
+            // checked when loading vaults
+            .ok_or(AmmError::InvariantViolation)?;
+
+        if reserve.surplus(TokenAmount::new(vault.amount)).is_none() {
+            msg!(
+                "Vault of mint {} holds {} tokens but reserve tracks {}",
+                vault.mint,
+                vault.amount,
+                reserve.tokens.amount
+            );
+            return Err(error!(AmmError::InvariantViolation));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Admin of a [`Pool`] can retire it once all liquidity has been redeemed, ie.
+//! when the LP mint supply is zero and every reserve vault is empty. The vaults
+//! are closed via the pool signer and the rent of both the vaults and the pool
+//! account is returned to the admin.
+//!
+//! The remaining accounts must be the pool's vaults, one for each reserve. The
+//! order of the accounts does not matter.
+//!
+//! # Note
+//! The token program doesn't support closing mints, therefore the LP mint is
+//! left behind with zero supply.
+
+use crate::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use std::collections::BTreeSet;
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    /// Receives the rent of the pool account and of all the vaults.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        close = admin,
+        constraint = pool.admin == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Account<'info, Pool>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [Pool::SIGNER_PDA_PREFIX, pool.key().as_ref()],
+        bump
+    )]
+    pub pool_signer: AccountInfo<'info>,
+    #[account(
+        constraint = lp_mint.key() == pool.mint
+            @ err::acc("LP mint must match pool's mint"),
+        constraint = lp_mint.supply == 0
+            @ err::acc("All LP tokens must be redeemed before closing the pool"),
+    )]
+    pub lp_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Record of a pool closure which can be parsed from the tx logs.
+#[event]
+pub struct PoolClosed {
+    pub pool: Pubkey,
+    pub admin: Pubkey,
+    pub lp_mint: Pubkey,
+    pub slot: Slot,
+}
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, ClosePool<'info>>,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let pool_signer_bump_seed = *ctx.bumps.get("pool_signer").unwrap();
+
+    let expected_rem_accs_len = accs.pool.dimension as usize;
+    if ctx.remaining_accounts.len() != expected_rem_accs_len {
+        return Err(error!(err::acc(format!(
+            "The remaining accs must be of length {}",
+            expected_rem_accs_len
+        ))));
+    }
+
+    let signer_seeds = &[
+        Pool::SIGNER_PDA_PREFIX,
+        &accs.pool.key().to_bytes()[..],
+        &[pool_signer_bump_seed],
+    ];
+
+    let mut closed_vaults = BTreeSet::new();
+    for vault_info in ctx.remaining_accounts {
+        let vault = Account::<TokenAccount>::try_from(vault_info)?;
+
+        if !accs.pool.reserves().iter().any(|r| r.vault == vault.key()) {
+            return Err(error!(err::acc(
+                "At least one of the vaults in remaining account \
+                does not correspond to any vault in the pool reserves",
+            )));
+        }
+        if !closed_vaults.insert(vault.key()) {
+            return Err(error!(err::acc("Duplicate vault")));
+        }
+        if vault.amount != 0 {
+            return Err(error!(err::acc(format!(
+                "Vault '{}' must be empty",
+                vault.key()
+            ))));
+        }
+
+        token::close_account(
+            accs.as_close_vault_ctx(vault_info)
+                .with_signer(&[&signer_seeds[..]]),
+        )?;
+    }
+
+    emit!(PoolClosed {
+        pool: accs.pool.key(),
+        admin: accs.admin.key(),
+        lp_mint: accs.lp_mint.key(),
+        slot: Slot::current()?,
+    });
+
+    Ok(())
+}
+
+impl<'info> ClosePool<'info> {
+    fn as_close_vault_ctx(
+        &self,
+        vault: &AccountInfo<'info>,
+    ) -> CpiContext<'_, '_, '_, 'info, token::CloseAccount<'info>> {
+        let cpi_accounts = token::CloseAccount {
+            account: vault.clone(),
+            destination: self.admin.to_account_info(),
+            authority: self.pool_signer.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -0,0 +1,44 @@
+//! Once a pool with [`Curve::Weighted`] is done bootstrapping, its admin can
+//! convert it to a regular constant product pool.
+//!
+//! The constant product curve prices the reserves as if they had equal
+//! weights. Therefore only a pool which ends with equal weights can be
+//! converted, otherwise the price would jump. A pool with different end
+//! weights can be drained by the admin with
+//! [`crate::endpoints::redeem_liquidity`] instead.
+
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct ConvertToConstProd<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = pool.admin.key() == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn handle(ctx: Context<ConvertToConstProd>) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let end_weight = match accs.pool.curve {
+        Curve::Weighted { end_weight, .. } => end_weight,
+        _ => return Err(error!(err::acc("Pool curve must be weighted"))),
+    };
+
+    if accs.pool.is_bootstrapping(Slot::current()?) {
+        return Err(error!(AmmError::PoolBootstrapping));
+    }
+
+    if end_weight != Permillion::from_percent(50) {
+        return Err(error!(err::acc(
+            "Only a pool which ends with equal weights can be converted"
+        )));
+    }
+
+    accs.pool.curve = Curve::ConstProd;
+
+    Ok(())
+}
@@ -58,6 +58,7 @@ pub struct CreatePool<'info> {
 pub fn handle(ctx: Context<CreatePool>, amplifier: u64) -> Result<()> {
     let accs = ctx.accounts;
 
+    accs.pool.version = Pool::VERSION;
     accs.pool.mint = accs.lp_mint.key();
     accs.pool.admin = accs.admin.key();
     accs.pool.signer = accs.pool_signer.key();
@@ -71,7 +72,7 @@ pub fn handle(ctx: Context<CreatePool>, amplifier: u64) -> Result<()> {
     };
 
     if ctx.remaining_accounts.len() > consts::MAX_RESERVES {
-        return Err(error!(err::acc("Too many reserves")));
+        return Err(error!(AmmError::TooManyReserves));
     }
 
     let is_lp_mint_without_supply = accs.lp_mint.supply == 0;
@@ -94,22 +95,9 @@ pub fn handle(ctx: Context<CreatePool>, amplifier: u64) -> Result<()> {
         }
 
         if mints.contains(&vault.mint) {
-            return Err(error!(err::acc("Duplicate reserve mint")));
-        }
-        if vault.close_authority.is_some() {
-            return Err(error!(err::acc(
-                "Vault mustn't have a close authority"
-            )));
-        }
-        if vault.delegate.is_some() {
-            return Err(error!(err::acc("Vault mustn't have a delegate")));
-        }
-        if vault.owner != accs.pool_signer.key() {
-            return Err(error!(err::acc("Vault owner must be pool signer")));
-        }
-        if vault.is_frozen() {
-            return Err(error!(err::acc("Vault mustn't be frozen")));
+            return Err(error!(AmmError::DuplicateReserve));
         }
+        check_vault(&vault, accs.pool_signer.key())?;
 
         mints.insert(vault.mint);
         accs.pool.reserves[index] = Reserve {
@@ -120,7 +108,7 @@ pub fn handle(ctx: Context<CreatePool>, amplifier: u64) -> Result<()> {
     }
 
     if mints.len() < 2 {
-        return Err(error!(err::acc("At least 2 vaults must be provided")));
+        return Err(error!(AmmError::TooFewReserves));
     }
 
     accs.pool.dimension = mints.len() as u64;
@@ -128,3 +116,22 @@ pub fn handle(ctx: Context<CreatePool>, amplifier: u64) -> Result<()> {
 
     Ok(())
 }
+
+/// Checks that the token account can serve as a reserve vault of a pool with
+/// given signer. Used whenever a new reserve is added to a pool.
+pub fn check_vault(vault: &TokenAccount, pool_signer: Pubkey) -> Result<()> {
+    if vault.close_authority.is_some() {
+        return Err(error!(AmmError::VaultHasCloseAuthority));
+    }
+    if vault.delegate.is_some() {
+        return Err(error!(AmmError::VaultHasDelegate));
+    }
+    if vault.owner != pool_signer {
+        return Err(error!(AmmError::VaultOwnerNotPoolSigner));
+    }
+    if vault.is_frozen() {
+        return Err(error!(AmmError::VaultFrozen));
+    }
+
+    Ok(())
+}
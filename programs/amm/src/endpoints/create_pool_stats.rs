@@ -0,0 +1,38 @@
+//! Anyone can create the [`PoolStats`] account of a pool, paying its rent.
+//! From then on, swaps, deposits and redemptions update the stats.
+//!
+//! See the [`crate::models::pool_stats`] module for more info.
+
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct CreatePoolStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = payer,
+        space = PoolStats::space(),
+        seeds = [PoolStats::PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_stats: Account<'info, PoolStats>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle(ctx: Context<CreatePoolStats>) -> Result<()> {
+    let accs = ctx.accounts;
+
+    accs.pool_stats.pool = accs.pool.key();
+    for (stats, reserve) in accs
+        .pool_stats
+        .reserves
+        .iter_mut()
+        .zip(accs.pool.reserves())
+    {
+        stats.mint = reserve.mint;
+    }
+
+    Ok(())
+}
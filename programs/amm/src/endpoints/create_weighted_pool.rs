@@ -0,0 +1,67 @@
+//! Creates a new [`Pool`] with [`Curve::Weighted`] for liquidity bootstrapping.
+//! The accounts are the same as in [`crate::endpoints::create_pool`], but there
+//! must be exactly two vaults in the remaining accounts. The first vault is the
+//! first reserve, ie. the one whose weight is given by the arguments.
+//!
+//! Typically the admin creates the pool with a high start weight of the token
+//! being launched, which sets a high initial price. The weight then decreases
+//! over time and buyers bid the price back up, which discourages sniping the
+//! opening slot.
+//!
+//! Until the `end` slot nobody redeems, not even the admin. Once the `end` slot
+//! is reached, the admin either converts the pool to a regular one with
+//! [`crate::endpoints::convert_to_const_prod`], or drains it with
+//! [`crate::endpoints::redeem_liquidity`].
+
+use crate::endpoints::create_pool::{self, CreatePool};
+use crate::prelude::*;
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, CreatePool<'info>>,
+    start_weight: Permillion,
+    end_weight: Permillion,
+    start: Slot,
+    end: Slot,
+) -> Result<()> {
+    if ctx.remaining_accounts.len() != 2 {
+        return Err(error!(err::acc(
+            "Weighted pool must have exactly 2 vaults"
+        )));
+    }
+    for weight in [start_weight, end_weight] {
+        if weight < consts::MIN_RESERVE_WEIGHT
+            || weight > consts::MAX_RESERVE_WEIGHT
+        {
+            return Err(error!(err::arg(format!(
+                "Weights must be between {} and {} permillion",
+                consts::MIN_RESERVE_WEIGHT.permillion,
+                consts::MAX_RESERVE_WEIGHT.permillion
+            ))));
+        }
+    }
+    if start >= end {
+        return Err(error!(err::arg("Start slot must be before end slot")));
+    }
+
+    let Context {
+        program_id,
+        accounts,
+        remaining_accounts,
+        bumps,
+    } = ctx;
+
+    create_pool::handle(
+        Context::new(program_id, &mut *accounts, remaining_accounts, bumps),
+        0,
+    )?;
+
+    // the order of the reserves is the order of the remaining accounts
+    accounts.pool.curve = Curve::Weighted {
+        start_weight,
+        end_weight,
+        start,
+        end,
+    };
+
+    Ok(())
+}
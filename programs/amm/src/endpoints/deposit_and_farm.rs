@@ -0,0 +1,84 @@
+//! Deposits liquidity into a [`Pool`] and in the same instruction stakes all
+//! the minted LP tokens in a farm of the farming program.
+//!
+//! The accounts for the deposit are the same as in
+//! [`crate::endpoints::deposit_liquidity`], including the remaining accounts
+//! with vault and wallet pairs. The user must already have a
+//! [`farming::models::Farmer`] account for the given farm, and the farm's
+//! stake mint must be the pool's LP mint, otherwise the farming program
+//! rejects the stake.
+
+use crate::endpoints::deposit_liquidity::{
+    deposit_with_min_lp_tokens_out, DepositLiquidity,
+};
+use crate::prelude::*;
+use farming::program::Farming;
+
+#[derive(Accounts)]
+pub struct DepositAndFarm<'info> {
+    pub deposit: DepositLiquidity<'info>,
+    /// CHECK: validated by the farming program
+    #[account(mut)]
+    pub farmer: AccountInfo<'info>,
+    /// CHECK: validated by the farming program
+    pub farm: AccountInfo<'info>,
+    /// CHECK: validated by the farming program
+    #[account(mut)]
+    pub farm_stake_vault: AccountInfo<'info>,
+    pub farming: Program<'info, Farming>,
+}
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositAndFarm<'info>>,
+    max_amount_tokens: Vec<TokenLimit>,
+    min_lp_tokens_out: TokenAmount,
+) -> Result<()> {
+    let Context {
+        program_id,
+        accounts,
+        remaining_accounts,
+        bumps,
+    } = ctx;
+
+    let lp_tokens = deposit_with_min_lp_tokens_out(
+        Context::new(
+            program_id,
+            &mut accounts.deposit,
+            remaining_accounts,
+            bumps,
+        ),
+        max_amount_tokens,
+        min_lp_tokens_out,
+    )?;
+
+    farming::cpi::start_farming(
+        accounts.as_start_farming_context(),
+        lp_tokens,
+    )?;
+
+    Ok(())
+}
+
+impl<'info> DepositAndFarm<'info> {
+    fn as_start_farming_context(
+        &self,
+    ) -> CpiContext<
+        '_,
+        '_,
+        '_,
+        'info,
+        farming::cpi::accounts::StartFarming<'info>,
+    > {
+        let cpi_accounts = farming::cpi::accounts::StartFarming {
+            wallet_authority: self.deposit.user.to_account_info(),
+            farmer: self.farmer.to_account_info(),
+            stake_wallet: self.deposit.lp_token_wallet.to_account_info(),
+            farm: self.farm.to_account_info(),
+            stake_vault: self.farm_stake_vault.to_account_info(),
+            token_program: self.deposit.token_program.to_account_info(),
+        };
+        let cpi_program = self.farming.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -5,6 +5,12 @@
 //! well as the amount of LP tokens to be minted, accordingly.
 //! When a [`Pool`] is created by an admin, the amount of LP tokens to be
 //! minted corresponds to the minimum value of tokens deposited.
+//!
+//! If the pool has an access policy, the user's [`PoolAccess`] must follow the
+//! vaults and wallets as the last remaining account.
+//!
+//! See also [`crate::endpoints::deposit_liquidity_v2`] which lets the user
+//! set the minimum amount of LP tokens to receive.
 
 use crate::misc::print_lp_supply;
 use crate::prelude::*;
@@ -38,6 +44,21 @@ pub struct DepositLiquidity<'info> {
     )]
     pub lp_token_wallet: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
+    /// CHECK: The pool stats might not be created, in which case they're not
+    /// updated, see [`PoolStats::update_if_created`].
+    #[account(
+        mut,
+        seeds = [PoolStats::PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_stats: AccountInfo<'info>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
 }
 
 /// The deposit_liquidity endpoint logic can be segmented as follows:
@@ -59,18 +80,51 @@ pub struct DepositLiquidity<'info> {
 /// user lp token wallet
 /// 7. Update the pool curve invariant value
 /// (only in the case the curve is stable)
+/// 8. Update the pool's stats, if created
 pub fn handle<'info>(
     ctx: Context<'_, '_, '_, 'info, DepositLiquidity<'info>>,
     max_amount_tokens: Vec<TokenLimit>,
 ) -> Result<()> {
+    deposit_with_min_lp_tokens_out(
+        ctx,
+        max_amount_tokens,
+        TokenAmount::new(0),
+    )?;
+
+    Ok(())
+}
+
+/// Same as [`handle`], but fails if the user would receive fewer than
+/// `min_lp_tokens_out` LP tokens.
+///
+/// Returns how many LP tokens were minted to the user.
+pub(crate) fn deposit_with_min_lp_tokens_out<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositLiquidity<'info>>,
+    max_amount_tokens: Vec<TokenLimit>,
+    min_lp_tokens_out: TokenAmount,
+) -> Result<TokenAmount> {
     let accs = ctx.accounts;
 
+    // while bootstrapping, the admin alone provides the liquidity
+    if matches!(accs.pool.curve, Curve::Weighted { .. })
+        && accs.pool.is_bootstrapping(Slot::current()?)
+        && accs.user.key() != accs.pool.admin
+    {
+        return Err(error!(AmmError::PoolBootstrapping));
+    }
+
     let pool_signer_bump_seed = *ctx.bumps.get("pool_signer_pda").unwrap();
-    let token_vaults_wallets: Vec<Account<'_, TokenAccount>> = ctx
-        .remaining_accounts
-        .iter()
-        .map(Account::try_from)
-        .collect::<Result<_>>()?;
+    let remaining_accounts = check_pool_access(
+        &accs.pool,
+        accs.user.key(),
+        ctx.remaining_accounts,
+        false,
+    )?;
+    let token_vaults_wallets: Vec<Account<'_, TokenAccount>> =
+        remaining_accounts
+            .iter()
+            .map(Account::try_from)
+            .collect::<Result<_>>()?;
     // prevents a scenario where the user provides vault-token acc pairs of the
     // same mint multiple times
     let unique_mints_in_rem_accounts = token_vaults_wallets
@@ -107,17 +161,24 @@ pub fn handle<'info>(
 
     // Get amount of lp tokens to be minted and transferred to user lp token
     // wallet and the amount of tokens that user should deposit on the pool.
-    let DepositResult {
-        lp_tokens_to_distribute,
-        tokens_to_deposit,
-    } = accs.pool.deposit_tokens(
-        max_amount_tokens,
-        TokenAmount::new(accs.lp_mint.supply),
-    )?;
-    let lp_tokens_to_distribute = lp_tokens_to_distribute.ok_or_else(|| {
-        msg!("Provided liquidity is too small to be represented");
-        AmmError::InvalidArg
-    })?;
+    //
+    // This mutates the state of the pool, adding the amounts returned, and
+    // updates the curve invariant.
+    let config = ProgramConfig::load(&accs.program_config)?;
+    let (lp_tokens_to_distribute, tokens_to_deposit) =
+        accs.pool.deposit_liquidity(
+            max_amount_tokens,
+            TokenAmount::new(accs.lp_mint.supply),
+            &config,
+        )?;
+    if min_lp_tokens_out > lp_tokens_to_distribute {
+        msg!(
+            "Deposit would mint {} LP tokens, but requested minimum of {}",
+            lp_tokens_to_distribute.amount,
+            min_lp_tokens_out.amount
+        );
+        return Err(error!(AmmError::LpTokensOutBelowMinimum));
+    }
 
     // deposit tokens from pool reserves
     for vault_wallet in token_vaults_wallets.chunks(2) {
@@ -182,11 +243,13 @@ pub fn handle<'info>(
         lp_tokens_to_distribute.amount,
     )?;
 
-    accs.pool.update_curve_invariant()?;
+    PoolStats::update_if_created(&accs.pool_stats, |stats| {
+        stats.record_deposit(&accs.pool, &tokens_to_deposit)
+    })?;
 
     print_lp_supply(&mut accs.lp_mint)?;
 
-    Ok(())
+    Ok(lp_tokens_to_distribute)
 }
 
 impl<'info> DepositLiquidity<'info> {
@@ -0,0 +1,21 @@
+//! Same as [`crate::endpoints::deposit_liquidity`], but protects the user from
+//! the pool moving between signing and execution of the transaction. If the
+//! deposit would mint fewer than `min_lp_tokens_out` LP tokens, it fails with
+//! [`AmmError::LpTokensOutBelowMinimum`].
+//!
+//! A deposit which is too small to be represented in LP tokens always fails.
+
+use crate::endpoints::deposit_liquidity::{
+    deposit_with_min_lp_tokens_out, DepositLiquidity,
+};
+use crate::prelude::*;
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositLiquidity<'info>>,
+    max_amount_tokens: Vec<TokenLimit>,
+    min_lp_tokens_out: TokenAmount,
+) -> Result<()> {
+    deposit_with_min_lp_tokens_out(ctx, max_amount_tokens, min_lp_tokens_out)?;
+
+    Ok(())
+}
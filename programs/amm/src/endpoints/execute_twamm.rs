@@ -0,0 +1,219 @@
+//! Permissionless crank which trades the order flow of a [`TwammOrder`]
+//! accumulated since the last execution against the pool. See
+//! [`TwammOrder::execute`] for how much is traded.
+//!
+//! The order pays the pool's full swap fee, there are no discounts. A fraction
+//! of the swap fee is sent to the program owner's wallet in LP tokens, same as
+//! on a regular swap.
+//!
+//! Unless the order has a maximum price, the order trades at whatever price
+//! the pool offers at the time of the execution. Frequent executions keep the
+//! traded amounts small. With a maximum price, an execution at a worse price
+//! fails, see [`TwammOrder::execute`].
+
+use crate::endpoints::swap::calculate_toll_in_lp_tokens;
+use crate::misc::print_lp_supply;
+use crate::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct ExecuteTwamm<'info> {
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [Pool::SIGNER_PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_signer: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = order.pool == pool.key()
+            @ err::acc("Order trades against a different pool"),
+    )]
+    pub order: Box<Account<'info, TwammOrder>>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [TwammOrder::SIGNER_PDA_PREFIX, order.key().as_ref()],
+        bump,
+    )]
+    pub order_signer: AccountInfo<'info>,
+    /// Tokens to SELL flow FROM this account.
+    #[account(
+        mut,
+        constraint = order_sell_vault.key() == order.sell_vault
+            @ err::acc("Sell vault must match order's sell vault"),
+    )]
+    pub order_sell_vault: Box<Account<'info, TokenAccount>>,
+    /// Tokens to BUY flow INTO this account.
+    #[account(
+        mut,
+        constraint = order_buy_vault.key() == order.buy_vault
+            @ err::acc("Buy vault must match order's buy vault"),
+    )]
+    pub order_buy_vault: Box<Account<'info, TokenAccount>>,
+    /// Tokens to SELL flow INTO this account.
+    #[account(
+        mut,
+        constraint = pool.reserve_vault(order.sell_mint) == Some(pool_sell_vault.key())
+            @ err::acc("Sell vault is not reserve's vault"),
+    )]
+    pub pool_sell_vault: Box<Account<'info, TokenAccount>>,
+    /// Tokens to BUY flow FROM this account.
+    #[account(
+        mut,
+        constraint = pool.reserve_vault(order.buy_mint) == Some(pool_buy_vault.key())
+            @ err::acc("Buy vault is not reserve's vault"),
+    )]
+    pub pool_buy_vault: Box<Account<'info, TokenAccount>>,
+    /// We mint LPs into `program_toll_wallet`
+    #[account(
+        mut,
+        constraint = pool.mint == lp_mint.key() @ err::acc("LP mint mismatch"),
+        constraint = lp_mint.supply > 0 @ err::acc("No liquidity provided yet"),
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = pool.program_toll_wallet == program_toll_wallet.key()
+            @ err::acc("Program toll wallet mismatch"),
+    )]
+    pub program_toll_wallet: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+    /// CHECK: The pool stats might not be created, in which case they're not
+    /// updated, see [`PoolStats::update_if_created`].
+    #[account(
+        mut,
+        seeds = [PoolStats::PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_stats: AccountInfo<'info>,
+}
+
+pub fn handle(ctx: Context<ExecuteTwamm>) -> Result<()> {
+    let accs = ctx.accounts;
+    let now = Slot::current()?;
+
+    // a scheduled swap fee change applies from its effective slot on
+    accs.pool.apply_pending_swap_fee(now);
+
+    let TwammExecution {
+        sold,
+        swap_fee,
+        bought,
+    } = match accs.order.execute(&mut accs.pool, now)? {
+        Some(execution) => execution,
+        None => {
+            msg!("Accumulated order flow is too small to be traded");
+            return Ok(());
+        }
+    };
+    msg!("Sold {} for {}", sold.amount, bought.amount);
+
+    let order_seeds = &[
+        TwammOrder::SIGNER_PDA_PREFIX,
+        &accs.order.key().to_bytes()[..],
+        &[*ctx.bumps.get("order_signer").unwrap()],
+    ];
+    token::transfer(
+        accs.as_transfer_sold_tokens_to_pool_ctx()
+            .with_signer(&[&order_seeds[..]]),
+        sold.amount,
+    )?;
+
+    let pool_seeds = &[
+        Pool::SIGNER_PDA_PREFIX,
+        &accs.pool.key().to_bytes()[..],
+        &[*ctx.bumps.get("pool_signer").unwrap()],
+    ];
+    token::transfer(
+        accs.as_transfer_bought_tokens_to_order_ctx()
+            .with_signer(&[&pool_seeds[..]]),
+        bought.amount,
+    )?;
+
+    let config = ProgramConfig::load(&accs.program_config)?;
+    let toll_in_lp_tokens = calculate_toll_in_lp_tokens(
+        &accs.pool,
+        swap_fee,
+        config.program_toll_swap_fee_share,
+        accs.order.sell_mint,
+        accs.lp_mint.supply.into(),
+    )?;
+    if let Some(toll_in_lp_tokens) = toll_in_lp_tokens {
+        token::mint_to(
+            accs.as_pay_toll_ctx().with_signer(&[&pool_seeds[..]]),
+            toll_in_lp_tokens.amount,
+        )?;
+    }
+
+    let (sell_mint, buy_mint) = (accs.order.sell_mint, accs.order.buy_mint);
+    PoolStats::update_if_created(&accs.pool_stats, |stats| {
+        stats.record_swap(
+            &accs.pool,
+            SwapRecord {
+                sell_mint,
+                sold,
+                swap_fee,
+                buy_mint,
+                bought,
+                toll_lp_minted: toll_in_lp_tokens
+                    .unwrap_or_else(|| TokenAmount::new(0)),
+                slot: now,
+            },
+        )
+    })?;
+
+    print_lp_supply(&mut accs.lp_mint)?;
+
+    // no-op if const prod
+    accs.pool
+        .update_curve_invariant_with(config.stable_curve_invariant)?;
+
+    Ok(())
+}
+
+impl<'info> ExecuteTwamm<'info> {
+    fn as_transfer_sold_tokens_to_pool_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            authority: self.order_signer.to_account_info(),
+            from: self.order_sell_vault.to_account_info(),
+            to: self.pool_sell_vault.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    fn as_transfer_bought_tokens_to_order_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            authority: self.pool_signer.to_account_info(),
+            from: self.pool_buy_vault.to_account_info(),
+            to: self.order_buy_vault.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    fn as_pay_toll_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::MintTo<'info>> {
+        let cpi_accounts = token::MintTo {
+            authority: self.pool_signer.to_account_info(),
+            mint: self.lp_mint.to_account_info(),
+            to: self.program_toll_wallet.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
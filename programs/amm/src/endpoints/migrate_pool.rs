@@ -0,0 +1,78 @@
+//! Upgrades a [`Pool`] account of an older layout version to the current one,
+//! see [`Pool::VERSION`]. The account is reallocated in place and the payer
+//! tops up its rent for the additional space.
+//!
+//! Anyone can migrate any pool, because the migration doesn't change the
+//! pool's state, only the way it's stored.
+//!
+//! All other endpoints load the pool as an [`Account`] of the current layout,
+//! which fails on an older layout. Therefore a pool can neither be traded nor
+//! deposited into or redeemed from until it's migrated, and every existing
+//! pool must be migrated right after the program is upgraded.
+
+use crate::prelude::*;
+use anchor_lang::system_program;
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// Pays rent for the additional space.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: the pool is stored in an older layout which [`Account`] cannot
+    /// parse, see [`Pool::try_deserialize_any_version`] which checks the
+    /// discriminator
+    #[account(
+        mut,
+        owner = crate::ID @ err::acc("Pool must be owned by the AMM program"),
+    )]
+    pub pool: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle(ctx: Context<MigratePool>) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let pool = {
+        let data = accs.pool.try_borrow_data()?;
+        Pool::try_deserialize_any_version(&mut &data[..])?
+    };
+    if pool.version >= Pool::VERSION {
+        return Err(error!(err::acc(format!(
+            "Pool is already of version {}",
+            pool.version
+        ))));
+    }
+
+    let space = Pool::space();
+    let rent = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(accs.pool.lamports());
+    if rent > 0 {
+        system_program::transfer(accs.as_pay_rent_context(), rent)?;
+    }
+
+    // the new space is zeroed, hence the new properties start with defaults
+    accs.pool.realloc(space, true)?;
+
+    let pool = Pool {
+        version: Pool::VERSION,
+        ..pool
+    };
+    let mut pool_data = accs.pool.try_borrow_mut_data()?;
+    pool.try_serialize(&mut pool_data.as_mut())?;
+
+    Ok(())
+}
+
+impl<'info> MigratePool<'info> {
+    fn as_pay_rent_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let cpi_accounts = system_program::Transfer {
+            from: self.payer.to_account_info(),
+            to: self.pool.to_account_info(),
+        };
+        let cpi_program = self.system_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -0,0 +1,131 @@
+//! Creates a new [`TwammOrder`] which sells `sell` tokens against the pool
+//! evenly over the next `duration` slots. The tokens are transferred into the
+//! order's sell vault right away.
+//!
+//! The optional `max_price` is the maximum number of sell tokens the order
+//! pays per bought token in any execution, swap fee included.
+//!
+//! Both vaults must be token accounts owned by the order's signer PDA. The
+//! sell vault's mint is what the order sells and the buy vault's mint is what
+//! it buys, and both must be mints of the pool's reserves.
+//!
+//! If the pool has an access policy, the only remaining account is the user's
+//! [`PoolAccess`].
+
+use crate::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct PlaceTwammOrder<'info> {
+    /// Authority over the sell wallet, becomes the order's authority.
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub pool: Box<Account<'info, Pool>>,
+    #[account(
+        init,
+        payer = user,
+        space = TwammOrder::space()
+    )]
+    pub order: Box<Account<'info, TwammOrder>>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [TwammOrder::SIGNER_PDA_PREFIX, order.key().as_ref()],
+        bump
+    )]
+    pub order_signer: AccountInfo<'info>,
+    /// Tokens to SELL flow FROM this account.
+    #[account(
+        mut,
+        constraint = sell_wallet.mint == sell_vault.mint
+            @ err::acc("Sell wallet mint must match sell vault mint"),
+    )]
+    pub sell_wallet: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = sell_vault.owner == order_signer.key()
+            @ err::acc("Sell vault owner must be order signer"),
+        constraint = sell_vault.delegate.is_none()
+            @ err::acc("Sell vault mustn't have a delegate"),
+        constraint = sell_vault.close_authority.is_none()
+            @ err::acc("Sell vault mustn't have a close authority"),
+        constraint = pool.reserve_vault(sell_vault.mint).is_some()
+            @ err::acc("Sell mint is not any reserve's mint"),
+    )]
+    pub sell_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = buy_vault.owner == order_signer.key()
+            @ err::acc("Buy vault owner must be order signer"),
+        constraint = buy_vault.delegate.is_none()
+            @ err::acc("Buy vault mustn't have a delegate"),
+        constraint = buy_vault.close_authority.is_none()
+            @ err::acc("Buy vault mustn't have a close authority"),
+        constraint = buy_vault.mint != sell_vault.mint
+            @ err::acc("Mint to sell mustn't equal the mint to buy"),
+        constraint = pool.reserve_vault(buy_vault.mint).is_some()
+            @ err::acc("Buy mint is not any reserve's mint"),
+    )]
+    pub buy_vault: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, PlaceTwammOrder<'info>>,
+    sell: TokenAmount,
+    duration: u64,
+    max_price: Option<SDecimal>,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    // the order trades against the pool, hence it's subject to the same
+    // access policy as swaps
+    check_pool_access(
+        &accs.pool,
+        accs.user.key(),
+        ctx.remaining_accounts,
+        false,
+    )?;
+
+    if sell.amount == 0 {
+        return Err(error!(AmmError::ZeroTokenAmount));
+    }
+    if duration == 0 {
+        return Err(error!(err::arg("Duration mustn't be zero")));
+    }
+    if max_price.map(Decimal::from) == Some(Decimal::zero()) {
+        return Err(error!(err::arg("Maximum price mustn't be zero")));
+    }
+
+    let start = Slot::current()?;
+    let end = start.checked_add(duration).ok_or(AmmError::MathOverflow)?;
+
+    accs.order.pool = accs.pool.key();
+    accs.order.authority = accs.user.key();
+    accs.order.sell_mint = accs.sell_vault.mint;
+    accs.order.buy_mint = accs.buy_vault.mint;
+    accs.order.sell_vault = accs.sell_vault.key();
+    accs.order.buy_vault = accs.buy_vault.key();
+    accs.order.sell = sell;
+    accs.order.start = start;
+    accs.order.end = end;
+    accs.order.max_price = max_price;
+
+    token::transfer(accs.as_transfer_sell_tokens_to_vault_ctx(), sell.amount)?;
+
+    Ok(())
+}
+
+impl<'info> PlaceTwammOrder<'info> {
+    fn as_transfer_sell_tokens_to_vault_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.sell_wallet.to_account_info(),
+            to: self.sell_vault.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
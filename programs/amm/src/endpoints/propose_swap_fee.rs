@@ -0,0 +1,44 @@
+//! Admin of a pool schedules a swap fee change. The new fee becomes effective
+//! at the earliest [`consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS`] after the
+//! proposal, which prevents the admin from front-running large trades by
+//! raising the fee. Once the effective slot is reached, the next
+//! [`crate::endpoints::swap`] applies the fee.
+//!
+//! A new proposal overwrites a pending one. Proposal can be cancelled with
+//! [`crate::endpoints::cancel_swap_fee`].
+
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct ProposeSwapFee<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = pool.admin.key() == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Account<'info, Pool>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+}
+
+pub fn handle(
+    ctx: Context<ProposeSwapFee>,
+    fee: Permillion,
+    effective_at: Slot,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let config = ProgramConfig::load(&accs.program_config)?;
+    accs.pool.propose_swap_fee(
+        fee,
+        config.max_swap_fee,
+        effective_at,
+        Slot::current()?,
+    )
+}
@@ -0,0 +1,92 @@
+//! Either creates a [`PoolAccess`] model for a user - if it doesn't exist yet -
+//! or updates an existing one. In the former scenario, the authority must be
+//! mutable so that we can transfer rent to the new account.
+//!
+//! Only the pool's access policy authority can grant or revoke the access.
+//!
+//! See the [`crate::models::pool_access`] module for more info.
+
+use crate::prelude::*;
+use anchor_lang::system_program;
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct PutPoolAccess<'info> {
+    #[account(
+        constraint = pool.access_policy == Some(authority.key())
+            @ err::acc("The authority must be the pool's access policy"),
+    )]
+    pub authority: Signer<'info>,
+    pub pool: Account<'info, Pool>,
+    /// CHECK: we create the pool access account if it does not exist yet in
+    /// the [`handle`] fn
+    #[account(
+        mut,
+        seeds = [PoolAccess::PDA_PREFIX, pool.key().as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub pool_access: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle(
+    ctx: Context<PutPoolAccess>,
+    user: Pubkey,
+    is_allowed: bool,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let should_be_created = accs.pool_access.owner == &system_program::ID;
+    if should_be_created {
+        // See the put discount endpoint, the logic is the same.
+
+        // we must transfer rent from authority
+        if !accs.authority.is_writable {
+            return Err(error!(err::acc(
+                "Authority must be writable \
+                because pool access account doesn't exist yet"
+            )));
+        }
+
+        let pool_key = accs.pool.key();
+        let pda_seeds = &[
+            PoolAccess::PDA_PREFIX,
+            pool_key.as_ref(),
+            user.as_ref(),
+            &[*ctx.bumps.get("pool_access").unwrap()],
+        ];
+        let rent = Rent::get()?.minimum_balance(PoolAccess::space());
+        system_program::create_account(
+            accs.as_create_pool_access_account_context()
+                .with_signer(&[&pda_seeds[..]]),
+            rent,
+            PoolAccess::space() as u64,
+            ctx.program_id,
+        )?;
+    }
+
+    // overwrites the data in the pool access account
+    let pool_access = PoolAccess {
+        pool: accs.pool.key(),
+        user,
+        is_allowed,
+    };
+    let mut pool_access_data = accs.pool_access.try_borrow_mut_data()?;
+    pool_access.try_serialize(&mut pool_access_data.as_mut())?;
+
+    Ok(())
+}
+
+impl<'info> PutPoolAccess<'info> {
+    fn as_create_pool_access_account_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::CreateAccount<'info>>
+    {
+        let cpi_accounts = system_program::CreateAccount {
+            from: self.authority.to_account_info(),
+            to: self.pool_access.to_account_info(),
+        };
+        let cpi_program = self.system_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -3,6 +3,14 @@
 //! Moreover, this endpoint computes the necessary amount of tokens that
 //! need to be redeem, given the amount of LP tokens the user wants to burn,
 //! such that the redemption respects the current pool ratio.
+//!
+//! If the pool has an access policy, the user's [`PoolAccess`] must follow the
+//! vaults and wallets as the last remaining account. Users whose access was
+//! revoked can still redeem.
+//!
+//! A pool with [`Curve::Weighted`] can't be redeemed from until it stops
+//! bootstrapping. That applies to everyone including the admin, so that the
+//! admin cannot pull the liquidity while buyers are bidding.
 
 use crate::misc::print_lp_supply;
 use crate::prelude::*;
@@ -36,6 +44,21 @@ pub struct RedeemLiquidity<'info> {
     )]
     pub lp_token_wallet: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
+    /// CHECK: The pool stats might not be created, in which case they're not
+    /// updated, see [`PoolStats::update_if_created`].
+    #[account(
+        mut,
+        seeds = [PoolStats::PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_stats: AccountInfo<'info>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
 }
 
 /// The redeem_liquidity endpoint logic can be segmented as follows:
@@ -55,6 +78,7 @@ pub struct RedeemLiquidity<'info> {
 /// the user token wallet
 /// 6. burn the correct amount of lp tokens from the user lp token wallet
 /// 7. Update the pool curve invariant value if stable curve
+/// 8. Update the pool's stats, if created
 pub fn handle<'info>(
     ctx: Context<'_, '_, '_, 'info, RedeemLiquidity<'info>>,
     lp_tokens_to_burn: TokenAmount,
@@ -62,13 +86,25 @@ pub fn handle<'info>(
 ) -> Result<()> {
     let accs = ctx.accounts;
 
+    if matches!(accs.pool.curve, Curve::Weighted { .. })
+        && accs.pool.is_bootstrapping(Slot::current()?)
+    {
+        return Err(error!(AmmError::PoolBootstrapping));
+    }
+
     let pool_signer_bump_seed = *ctx.bumps.get("pool_signer").unwrap();
 
-    let token_vaults_wallets: Vec<Account<'_, TokenAccount>> = ctx
-        .remaining_accounts
-        .iter()
-        .map(Account::try_from)
-        .collect::<Result<_>>()?;
+    let remaining_accounts = check_pool_access(
+        &accs.pool,
+        accs.user.key(),
+        ctx.remaining_accounts,
+        true,
+    )?;
+    let token_vaults_wallets: Vec<Account<'_, TokenAccount>> =
+        remaining_accounts
+            .iter()
+            .map(Account::try_from)
+            .collect::<Result<_>>()?;
 
     // prevents a scenario where the user provides vault-token acc pairs of the
     // same mint multiple times
@@ -85,10 +121,8 @@ pub fn handle<'info>(
     }
 
     if lp_tokens_to_burn.amount > accs.lp_mint.supply {
-        return Err(error!(err::arg(
-            "The amount of lp tokens to burn cannot \
-            surpass current supply."
-        )));
+        msg!("The amount of lp tokens to burn cannot surpass current supply");
+        return Err(error!(AmmError::InvalidLpTokenAmount));
     }
 
     // if user does not have enough lp tokens we return an error
@@ -120,11 +154,14 @@ pub fn handle<'info>(
     // Get amount of lp tokens to be burned and transferred to user lp token
     // wallet and the amount of tokens that user should deposit on the pool.
     //
-    // This mutates the state of the pool, removing the amounts returned.
-    let tokens_to_redeem = accs.pool.redeem_tokens(
+    // This mutates the state of the pool, removing the amounts returned, and
+    // updates the curve invariant.
+    let config = ProgramConfig::load(&accs.program_config)?;
+    let tokens_to_redeem = accs.pool.redeem_liquidity(
         min_amount_tokens,
         lp_tokens_to_burn,
         TokenAmount::new(accs.lp_mint.supply),
+        &config,
     )?;
 
     // redeem tokens from pool reserves
@@ -185,8 +222,9 @@ pub fn handle<'info>(
         lp_tokens_to_burn.amount,
     )?;
 
-    // no-op if const prod
-    accs.pool.update_curve_invariant()?;
+    PoolStats::update_if_created(&accs.pool_stats, |stats| {
+        stats.record_redeem(&accs.pool, &tokens_to_redeem)
+    })?;
 
     print_lp_supply(&mut accs.lp_mint)?;
 
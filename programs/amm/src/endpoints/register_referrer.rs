@@ -0,0 +1,49 @@
+//! The program toll authority registers a [`Referrer`] which receives a share
+//! of the swap fee on swaps routed through it. The share is capped by
+//! [`consts::MAX_REFERRER_SWAP_FEE_SHARE`].
+//!
+//! See the [`crate::models::referrer`] module for more info.
+
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        mut,
+        constraint = program_toll_authority.key() == program_toll.authority
+            @ err::acc("The signer must be the program toll authority"),
+    )]
+    pub program_toll_authority: Signer<'info>,
+    #[account(
+        seeds = [ProgramToll::PDA_SEED],
+        bump,
+    )]
+    pub program_toll: Account<'info, ProgramToll>,
+    /// CHECK: owner of the LP wallets which receive the referral fees
+    pub referrer_authority: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = program_toll_authority,
+        space = Referrer::space(),
+        seeds = [Referrer::PDA_PREFIX, referrer_authority.key().as_ref()],
+        bump,
+    )]
+    pub referrer: Account<'info, Referrer>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle(ctx: Context<RegisterReferrer>, share: Permillion) -> Result<()> {
+    let accs = ctx.accounts;
+
+    if share > consts::MAX_REFERRER_SWAP_FEE_SHARE {
+        return Err(error!(err::arg(format!(
+            "Maximum referrer share can be {} permillion",
+            consts::MAX_REFERRER_SWAP_FEE_SHARE.permillion
+        ))));
+    }
+
+    accs.referrer.authority = accs.referrer_authority.key();
+    accs.referrer.share = share;
+
+    Ok(())
+}
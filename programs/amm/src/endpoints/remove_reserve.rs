@@ -0,0 +1,80 @@
+//! Admin of a [`Pool`] can retire a reserve once its balance reaches zero. The
+//! reserve's vault is closed via the pool signer and its rent is returned to
+//! the admin.
+//!
+//! A pool must always keep at least two reserves.
+
+use crate::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct RemoveReserve<'info> {
+    /// Receives the rent of the closed vault.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = pool.admin == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [Pool::SIGNER_PDA_PREFIX, pool.key().as_ref()],
+        bump
+    )]
+    pub pool_signer: AccountInfo<'info>,
+    /// Vault of the reserve to remove.
+    #[account(
+        mut,
+        // either the mint is not any reserve's mint, or the vault doesn't match
+        constraint = pool.reserve_vault(vault.mint) == Some(vault.key())
+            @ err::acc("Vault is not reserve's vault"),
+        constraint = vault.amount == 0 @ err::acc("Vault must be empty"),
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+}
+
+pub fn handle(ctx: Context<RemoveReserve>) -> Result<()> {
+    let accs = ctx.accounts;
+
+    accs.pool.remove_reserve(accs.vault.mint)?;
+
+    let signer_seeds = &[
+        Pool::SIGNER_PDA_PREFIX,
+        &accs.pool.key().to_bytes()[..],
+        &[*ctx.bumps.get("pool_signer").unwrap()],
+    ];
+    token::close_account(
+        accs.as_close_vault_ctx().with_signer(&[&signer_seeds[..]]),
+    )?;
+
+    // no-op if const prod
+    let config = ProgramConfig::load(&accs.program_config)?;
+    accs.pool
+        .update_curve_invariant_with(config.stable_curve_invariant)?;
+
+    Ok(())
+}
+
+impl<'info> RemoveReserve<'info> {
+    fn as_close_vault_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::CloseAccount<'info>> {
+        let cpi_accounts = token::CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.admin.to_account_info(),
+            authority: self.pool_signer.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
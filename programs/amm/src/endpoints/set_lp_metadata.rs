@@ -0,0 +1,196 @@
+//! Admin of a pool creates or updates the token metadata of the pool's LP
+//! mint, so that wallets display the LP tokens by name rather than as an
+//! unknown token. The pool signer is both the mint authority and the update
+//! authority of the metadata.
+//!
+//! Unless given, the name and symbol are derived from the symbols of the
+//! reserve mints, e.g. "AMM LP USDC-USDT" and "USDC-USDT". The metadata
+//! accounts of the reserve mints are read from the remaining accounts, in the
+//! order of the pool's reserves. A reserve mint without metadata is
+//! represented by the first characters of its pubkey. If the derived symbol is
+//! too long, [`DEFAULT_LP_SYMBOL`] is used.
+
+use crate::prelude::*;
+use crate::token_metadata::{self, DataV2, TokenMetadata};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program;
+use anchor_spl::token::Mint;
+
+/// Used if the symbol derived from the reserve mints is too long.
+pub const DEFAULT_LP_SYMBOL: &str = "AMM-LP";
+/// Reserve mints without metadata are shown as this many characters of their
+/// pubkey.
+const SHORT_MINT_LEN: usize = 4;
+
+#[derive(Accounts)]
+pub struct SetLpMetadata<'info> {
+    /// Pays for the metadata account if it's being created.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = pool.admin.key() == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Account<'info, Pool>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [Pool::SIGNER_PDA_PREFIX, pool.key().as_ref()],
+        bump
+    )]
+    pub pool_signer: AccountInfo<'info>,
+    #[account(
+        constraint = lp_mint.key() == pool.mint.key()
+            @ err::acc("LP mint must match pool's mint"),
+    )]
+    pub lp_mint: Account<'info, Mint>,
+    /// CHECK: The metadata program checks the account, and we create it if it
+    /// doesn't exist yet in the [`handle`] fn.
+    #[account(
+        mut,
+        constraint = lp_metadata.key() ==
+            token_metadata::metadata_address(pool.mint)
+            @ err::acc("LP metadata must be the metadata PDA of pool's mint"),
+    )]
+    pub lp_metadata: AccountInfo<'info>,
+    pub token_metadata_program: Program<'info, TokenMetadata>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: The metadata program requires the rent sysvar when creating
+    /// the metadata account.
+    #[account(
+        constraint = rent.key() == sysvar::rent::ID
+            @ err::acc("Expected the rent sysvar"),
+    )]
+    pub rent: AccountInfo<'info>,
+}
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, SetLpMetadata<'info>>,
+    name: Option<String>,
+    symbol: Option<String>,
+    uri: String,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let reserve_symbols = if name.is_none() || symbol.is_none() {
+        reserve_symbols(&accs.pool, ctx.remaining_accounts)?
+    } else {
+        vec![]
+    };
+    let data = DataV2::new(
+        name.unwrap_or_else(|| derive_name(&reserve_symbols)),
+        symbol.unwrap_or_else(|| derive_symbol(&reserve_symbols)),
+        uri,
+    );
+    data.validate()?;
+
+    let pool_signer_bump_seed = *ctx.bumps.get("pool_signer").unwrap();
+    let signer_seeds = &[
+        Pool::SIGNER_PDA_PREFIX,
+        &accs.pool.key().to_bytes()[..],
+        &[pool_signer_bump_seed],
+    ];
+
+    let should_be_created = accs.lp_metadata.owner == &system_program::ID;
+    if should_be_created {
+        let ix = token_metadata::create_metadata_account_v2(
+            accs.lp_metadata.key(),
+            accs.lp_mint.key(),
+            accs.pool_signer.key(),
+            accs.admin.key(),
+            accs.pool_signer.key(),
+            data,
+        );
+        invoke_signed(
+            &ix,
+            &[
+                accs.lp_metadata.to_account_info(),
+                accs.lp_mint.to_account_info(),
+                accs.pool_signer.to_account_info(),
+                accs.admin.to_account_info(),
+                accs.system_program.to_account_info(),
+                accs.rent.to_account_info(),
+                accs.token_metadata_program.to_account_info(),
+            ],
+            &[&signer_seeds[..]],
+        )?;
+    } else {
+        let ix = token_metadata::update_metadata_account_v2(
+            accs.lp_metadata.key(),
+            accs.pool_signer.key(),
+            data,
+        );
+        invoke_signed(
+            &ix,
+            &[
+                accs.lp_metadata.to_account_info(),
+                accs.pool_signer.to_account_info(),
+                accs.token_metadata_program.to_account_info(),
+            ],
+            &[&signer_seeds[..]],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns a symbol for each reserve of the pool, in the order of the pool's
+/// reserves.
+fn reserve_symbols(
+    pool: &Pool,
+    metadata_accounts: &[AccountInfo],
+) -> Result<Vec<String>> {
+    if !metadata_accounts.is_empty()
+        && metadata_accounts.len() != pool.dimension as usize
+    {
+        return Err(error!(err::acc(format!(
+            "The remaining accs must be either empty or of length {}",
+            pool.dimension
+        ))));
+    }
+
+    let symbols = pool
+        .reserves()
+        .iter()
+        .enumerate()
+        .map(|(index, reserve)| {
+            metadata_accounts
+                .get(index)
+                .and_then(|metadata| {
+                    token_metadata::read_symbol(metadata, reserve.mint)
+                })
+                .unwrap_or_else(|| {
+                    reserve.mint.to_string()[..SHORT_MINT_LEN].to_string()
+                })
+        })
+        .collect();
+
+    Ok(symbols)
+}
+
+/// E.g. "AMM LP USDC-USDT", truncated to the maximum name length.
+fn derive_name(reserve_symbols: &[String]) -> String {
+    let mut name = format!("AMM LP {}", reserve_symbols.join("-"));
+    truncate_to_char_boundary(&mut name, token_metadata::MAX_NAME_LEN);
+    name
+}
+
+/// E.g. "USDC-USDT", or [`DEFAULT_LP_SYMBOL`] if that's too long.
+fn derive_symbol(reserve_symbols: &[String]) -> String {
+    let symbol = reserve_symbols.join("-");
+    if symbol.len() > token_metadata::MAX_SYMBOL_LEN {
+        DEFAULT_LP_SYMBOL.to_string()
+    } else {
+        symbol
+    }
+}
+
+fn truncate_to_char_boundary(s: &mut String, max_len: usize) {
+    if s.len() > max_len {
+        let mut len = max_len;
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        s.truncate(len);
+    }
+}
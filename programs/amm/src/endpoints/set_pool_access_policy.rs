@@ -0,0 +1,28 @@
+//! Admin of a pool can restrict the pool to allow-listed users by setting an
+//! access policy authority, or open it to everyone again by unsetting it.
+//!
+//! See the [`crate::models::pool_access`] module for more info.
+
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPoolAccessPolicy<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = pool.admin.key() == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn handle(
+    ctx: Context<SetPoolAccessPolicy>,
+    access_policy: Option<Pubkey>,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    accs.pool.access_policy = access_policy;
+
+    Ok(())
+}
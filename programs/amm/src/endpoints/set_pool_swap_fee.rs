@@ -1,5 +1,11 @@
-//! Admin of a pool can change the swap fee to a maximum of
-//! [`consts::MAX_SWAP_FEE`].
+//! Admin of a pool can change the swap fee to a maximum given by the
+//! [`ProgramConfig`], which defaults to [`consts::MAX_SWAP_FEE`].
+//!
+//! Same as with [`crate::endpoints::propose_swap_fee`], the change is scheduled
+//! to become effective at the earliest slot allowed by
+//! [`consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS`], so that the admin cannot
+//! front-run trades with a fee change. The only exception is a pool without
+//! liquidity, whose fee is set right away as there are no trades to front-run.
 
 use crate::prelude::*;
 
@@ -12,19 +18,29 @@ pub struct SetPoolSwapFee<'info> {
             @ err::acc("The signer must match pool's admin"),
     )]
     pub pool: Account<'info, Pool>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
 }
 
 pub fn handle(ctx: Context<SetPoolSwapFee>, fee: Permillion) -> Result<()> {
     let accs = ctx.accounts;
 
-    if fee > consts::MAX_SWAP_FEE {
-        return Err(error!(err::arg(format!(
-            "Maximum fee can be {} permillion",
-            consts::MAX_SWAP_FEE.permillion
-        ),)));
-    }
+    let config = ProgramConfig::load(&accs.program_config)?;
+    let now = Slot::current()?;
+    let effective_at = Pool::earliest_swap_fee_change(now)?;
+    accs.pool
+        .propose_swap_fee(fee, config.max_swap_fee, effective_at, now)?;
 
-    accs.pool.swap_fee = fee;
+    let has_liquidity =
+        accs.pool.reserves().iter().any(|r| r.tokens.amount != 0);
+    if !has_liquidity {
+        accs.pool.apply_pending_swap_fee(effective_at);
+    }
 
     Ok(())
 }
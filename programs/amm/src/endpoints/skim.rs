@@ -0,0 +1,99 @@
+//! Anyone can send tokens in a reserve's vault which are not accounted for by
+//! the [`Reserve`] (airdrops, mistaken transfers) to the program toll. This
+//! does not change the state of the [`Pool`].
+//!
+//! Because the program toll wallet which is set on the pool holds LP tokens,
+//! the surplus is sent to a wallet of the reserve's mint which is owned by
+//! the [`ProgramToll`] authority.
+
+use crate::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct Skim<'info> {
+    pub pool: Account<'info, Pool>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [Pool::SIGNER_PDA_PREFIX, pool.key().as_ref()],
+        bump
+    )]
+    pub pool_signer: AccountInfo<'info>,
+    #[account(
+        mut,
+        // either the mint is not any reserve's mint, or the vault doesn't match
+        constraint = pool.reserve_vault(vault.mint) == Some(vault.key())
+            @ err::acc("Vault is not reserve's vault"),
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [ProgramToll::PDA_SEED],
+        bump,
+    )]
+    pub program_toll: Account<'info, ProgramToll>,
+    /// Surplus is transferred INTO this wallet.
+    #[account(
+        mut,
+        constraint = program_toll_wallet.mint == vault.mint
+            @ err::acc("Toll wallet must be of vault's mint"),
+        constraint = program_toll_wallet.owner == program_toll.authority
+            @ err::acc(
+                "Toll wallet authority must match \
+                program toll authority"
+            ),
+    )]
+    pub program_toll_wallet: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handle(ctx: Context<Skim>) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let reserve = accs
+        .pool
+        .reserves()
+        .iter()
+        .find(|r| r.mint == accs.vault.mint)
+        // checked by the vault constraint
+        .ok_or(AmmError::InvariantViolation)?;
+
+    let surplus = reserve
+        .surplus(TokenAmount::new(accs.vault.amount))
+        .ok_or_else(|| {
+            msg!(
+                "Vault holds {} tokens but reserve tracks {}",
+                accs.vault.amount,
+                reserve.tokens.amount
+            );
+            AmmError::InvariantViolation
+        })?;
+    if surplus.amount == 0 {
+        return Err(error!(err::acc("Vault has no surplus to skim")));
+    }
+
+    let signer_seeds = &[
+        Pool::SIGNER_PDA_PREFIX,
+        &accs.pool.key().to_bytes()[..],
+        &[*ctx.bumps.get("pool_signer").unwrap()],
+    ];
+    token::transfer(
+        accs.as_transfer_surplus_to_toll_ctx()
+            .with_signer(&[&signer_seeds[..]]),
+        surplus.amount,
+    )?;
+
+    Ok(())
+}
+
+impl<'info> Skim<'info> {
+    fn as_transfer_surplus_to_toll_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            from: self.vault.to_account_info(),
+            to: self.program_toll_wallet.to_account_info(),
+            authority: self.pool_signer.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
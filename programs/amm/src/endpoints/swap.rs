@@ -5,11 +5,21 @@
 //! The user pays a fee for the swap, which is scaled down by the [`Discount`]
 //! associated with this user. A fraction of the swap fee is sent to program
 //! owner's wallet in LP tokens.
+//!
+//! Optionally, the swap can be routed through a [`Referrer`]. In such case the
+//! remaining accounts are the referrer's account and the referrer's LP wallet,
+//! in this order. The referrer's share of the swap fee is paid in LP tokens the
+//! same way as the program toll.
+//!
+//! If the pool has an access policy, the user's [`PoolAccess`] must follow as
+//! the last remaining account.
+//!
+//! See also [`crate::endpoints::swap_v2`] which on top of the minimum amount to
+//! receive lets the user bound the slot and the price of the swap.
 
 use crate::misc::print_lp_supply;
 use crate::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
-use std::collections::BTreeMap;
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
@@ -78,6 +88,21 @@ pub struct Swap<'info> {
     )]
     pub program_toll_wallet: Box<Account<'info, TokenAccount>>,
     pub token_program: Program<'info, Token>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+    /// CHECK: The pool stats might not be created, in which case they're not
+    /// updated, see [`PoolStats::update_if_created`].
+    #[account(
+        mut,
+        seeds = [PoolStats::PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_stats: AccountInfo<'info>,
 }
 
 /// 1. Calculates swap fee and how many tokens should the user get in return for
@@ -88,38 +113,64 @@ pub struct Swap<'info> {
 /// 3. Transfers the bought tokens to the user
 ///
 /// 4. Mints LP token to program owner's wallet as a toll for the swap
-pub fn handle(
-    ctx: Context<Swap>,
+///
+/// 5. Mints LP token to referrer's wallet, if any
+///
+/// 6. Updates the pool's stats, if created
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
     sell: TokenAmount,
     min_buy: TokenAmount,
+) -> Result<()> {
+    swap_with_limits(ctx, sell, min_buy, None)
+}
+
+/// Same as [`handle`], but optionally fails the swap if the user would pay more
+/// than `max_price` sell tokens per one bought token, swap fee included.
+pub(crate) fn swap_with_limits<'info>(
+    ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+    sell: TokenAmount,
+    min_buy: TokenAmount,
+    max_price: Option<Decimal>,
 ) -> Result<()> {
     let accs = ctx.accounts;
     let sell_mint = accs.sell_vault.mint;
+    let remaining_accounts = check_pool_access(
+        &accs.pool,
+        accs.user.key(),
+        ctx.remaining_accounts,
+        false,
+    )?;
+    let referrer = load_referrer(&accs.pool, remaining_accounts)?;
 
-    if sell.amount == 0 {
-        return Err(error!(err::arg("Sell amount mustn't be zero")));
-    }
+    let now = Slot::current()?;
+
+    // a scheduled swap fee change applies from its effective slot on
+    accs.pool.apply_pending_swap_fee(now);
 
     //
     // 1.
     //
 
-    let swap_fee =
-        calculate_swap_fee(sell, accs.pool.swap_fee, &accs.discount)?;
-
-    // swap fee is a fraction of the sell amount
-    let tokens_to_swap = TokenAmount::new(sell.amount - swap_fee.amount);
-    // this also updates the reserves' balances
-    let bought =
-        accs.pool
-            .swap(sell_mint, tokens_to_swap, accs.buy_vault.mint)?;
-    // We must explicitly update the pool's state as swap fee was subtracted
-    // from the sell amount. However, the swap fee should still be considered
-    // when depositing or withdrawing.
-    accs.pool
-        .reserve_mut(sell_mint)
-        .unwrap() // the mint is part of the pool as per constraints and swap fn
-        .add_tokens(swap_fee)?;
+    let swap_fee_share = calculate_discounted_swap_fee_share(
+        accs.pool.swap_fee,
+        &accs.discount,
+    )?;
+    let config = ProgramConfig::load(&accs.program_config)?;
+    // this also updates the reserves' balances and the curve invariant
+    let SwapResult {
+        bought,
+        swap_fee,
+        toll_in_lp_tokens,
+    } = accs.pool.swap_with_fee(
+        sell_mint,
+        sell,
+        accs.buy_vault.mint,
+        swap_fee_share,
+        accs.lp_mint.supply.into(),
+        &config,
+        now,
+    )?;
 
     if min_buy > bought {
         msg!(
@@ -131,6 +182,20 @@ pub fn handle(
         return Err(error!(AmmError::SlippageExceeded));
     }
 
+    if let Some(max_price) = max_price {
+        // `sell / bought > max_price` without dividing by zero
+        if Decimal::from(sell) > max_price.try_mul(Decimal::from(bought))? {
+            msg!(
+                "For {} would receive {}, which is more than the maximum \
+                price of {} sell tokens per bought token",
+                sell.amount,
+                bought.amount,
+                max_price
+            );
+            return Err(error!(AmmError::LimitPriceExceeded));
+        }
+    }
+
     let pda_seeds = &[
         Pool::SIGNER_PDA_PREFIX,
         &accs.pool.key().to_bytes()[..],
@@ -154,12 +219,6 @@ pub fn handle(
     //
     // 4.
     //
-    let toll_in_lp_tokens = calculate_toll_in_lp_tokens(
-        &accs.pool,
-        swap_fee,
-        sell_mint,
-        accs.lp_mint.supply.into(),
-    )?;
     if let Some(toll_in_lp_tokens) = toll_in_lp_tokens {
         // this will lower the value of the LP token mint by such an amount
         // which equals to the value of the toll
@@ -169,14 +228,50 @@ pub fn handle(
         )?;
     }
 
-    print_lp_supply(&mut accs.lp_mint)?;
+    //
+    // 5.
+    //
+    if let Some((referrer, referrer_lp_wallet)) = referrer {
+        // same as the toll, the referral is calculated from the current LP
+        // supply, which already includes the toll
+        accs.lp_mint.reload()?;
+        let referral_in_lp_tokens = calculate_referral_in_lp_tokens(
+            &accs.pool,
+            swap_fee,
+            referrer.share,
+            sell_mint,
+            accs.lp_mint.supply.into(),
+        )?;
+        if let Some(referral_in_lp_tokens) = referral_in_lp_tokens {
+            token::mint_to(
+                accs.as_pay_referral_ctx(&referrer_lp_wallet)
+                    .with_signer(&[&pda_seeds[..]]),
+                referral_in_lp_tokens.amount,
+            )?;
+        }
+    }
 
-    // we need to update the invariant because fees have been taken, therefore
-    // even though the invariant applies to swaps, it is changed during each
-    // trade because of the collected fees
     //
-    // no-op if const prod
-    accs.pool.update_curve_invariant()?;
+    // 6.
+    //
+    let buy_mint = accs.buy_vault.mint;
+    PoolStats::update_if_created(&accs.pool_stats, |stats| {
+        stats.record_swap(
+            &accs.pool,
+            SwapRecord {
+                sell_mint,
+                sold: sell,
+                swap_fee,
+                buy_mint,
+                bought,
+                toll_lp_minted: toll_in_lp_tokens
+                    .unwrap_or_else(|| TokenAmount::new(0)),
+                slot: now,
+            },
+        )
+    })?;
+
+    print_lp_supply(&mut accs.lp_mint)?;
 
     Ok(())
 }
@@ -194,6 +289,19 @@ impl<'info> Swap<'info> {
         CpiContext::new(cpi_program, cpi_accounts)
     }
 
+    fn as_pay_referral_ctx(
+        &self,
+        referrer_lp_wallet: &Account<'info, TokenAccount>,
+    ) -> CpiContext<'_, '_, '_, 'info, token::MintTo<'info>> {
+        let cpi_accounts = token::MintTo {
+            authority: self.pool_signer.to_account_info(),
+            mint: self.lp_mint.to_account_info(),
+            to: referrer_lp_wallet.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
     fn as_transfer_sold_tokens_to_vault_ctx(
         &self,
     ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
@@ -225,6 +333,17 @@ pub fn calculate_swap_fee(
     swap_fee_share: Permillion,
     discount: &AccountInfo,
 ) -> Result<TokenAmount> {
+    let swap_fee_share =
+        calculate_discounted_swap_fee_share(swap_fee_share, discount)?;
+
+    Pool::calculate_swap_fee(sell, swap_fee_share)
+}
+
+// `swap_fee_share - swap_fee_share * discount`
+pub fn calculate_discounted_swap_fee_share(
+    swap_fee_share: Permillion,
+    discount: &AccountInfo,
+) -> Result<Decimal> {
     let is_discount_created = discount.owner == &crate::ID;
 
     let swap_fee_share: Decimal = swap_fee_share.into();
@@ -241,71 +360,69 @@ pub fn calculate_swap_fee(
         swap_fee_share
     };
 
-    // total swap fee, ie. liquidity providers fee + toll fee
-    let swap_fee = TokenAmount::new(
-        Decimal::from(sell.amount)
-            .try_mul(swap_fee_share)?
-            .try_ceil()?,
-    );
+    Ok(swap_fee_share)
+}
 
-    Ok(swap_fee)
+/// Parses the optional referrer from the remaining accounts. There are either
+/// none, or exactly the [`Referrer`] account and referrer's LP wallet.
+fn load_referrer<'info>(
+    pool: &Pool,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<Option<(Account<'info, Referrer>, Account<'info, TokenAccount>)>> {
+    match remaining_accounts {
+        [] => Ok(None),
+        [referrer, referrer_lp_wallet] => {
+            // the account is owned by our program and of the correct type,
+            // hence it's been created by the register referrer endpoint
+            let referrer = Account::<Referrer>::try_from(referrer)?;
+            let referrer_lp_wallet =
+                Account::<TokenAccount>::try_from(referrer_lp_wallet)?;
+
+            if referrer_lp_wallet.mint != pool.mint {
+                return Err(error!(err::acc(
+                    "Referrer LP wallet must be of the same mint as pool's mint"
+                )));
+            }
+            if referrer_lp_wallet.owner != referrer.authority {
+                return Err(error!(err::acc(
+                    "Referrer must be authority over the referrer LP wallet"
+                )));
+            }
+
+            Ok(Some((referrer, referrer_lp_wallet)))
+        }
+        _ => Err(error!(err::acc(
+            "The remaining accs must be either empty, \
+            or the referrer and its LP wallet"
+        ))),
+    }
 }
 
-// To find out how many LPs should we mint, we pretend to deposit to the pool.
-// We deposit tokens which are in total worth the `swap_fee * toll_share`.
-// Returns the LP amount.
+// Returns how many LP tokens are worth the `toll_share` of the swap fee, see
+// [`Pool::swap_fee_share_in_lp_tokens`].
 pub fn calculate_toll_in_lp_tokens(
     pool: &Pool,
     swap_fee: TokenAmount,
+    toll_share: Permillion,
     sell_mint: Pubkey,
     lp_supply: TokenAmount,
 ) -> Result<Option<TokenAmount>> {
-    let toll_in_sell_tokens_divided_by_dimension = TokenAmount::new(
-        Decimal::from(swap_fee)
-            .try_mul(Decimal::from(consts::PROGRAM_TOLL_SWAP_FEE_SHARE))?
-            // Since we will pretend to deposit this, we need to divide it by
-            // the number of reserves. The LPs we get from the fake call to
-            // [`Pool::deposit_tokens`] below will return LPs as in all the
-            // reserves were deposited to in the same ratio.
-            .try_div(Decimal::from(pool.dimension))?
-            .try_round()?,
-    );
-
-    if toll_in_sell_tokens_divided_by_dimension.amount == 0 {
-        return Ok(None);
-    }
+    pool.swap_fee_share_in_lp_tokens(swap_fee, toll_share, sell_mint, lp_supply)
+}
 
-    let max_deposits: BTreeMap<_, _> = pool
-        .reserves()
-        .iter()
-        .map(|r| {
-            (
-                r.mint,
-                if r.mint == sell_mint {
-                    // this is going to become the limiting factor
-                    toll_in_sell_tokens_divided_by_dimension
-                } else {
-                    // We don't care about how many tokens of the other reserves
-                    // are deposited, the limiting factor is the sell tokens
-                    // mint. The [`Pool::deposit_tokens`]
-                    // function ensures that ratios
-                    // are preserved.
-                    TokenAmount::max_value()
-                },
-            )
-        })
-        .collect();
-
-    // We make a fake call (by cloning [`Pool`]) to the [`Pool::deposit_tokens`]
-    // which would tell us that if we were to deposit tokens worth the toll,
-    // we would get this many LPs. We don't actually deposit anything
-
-    let toll_in_lp_tokens = pool
-        // IMPORTANT: we don't actually want to deposit these tokens, we are
-        // just wondering how many LPs would they amount to if we deposited them
-        .clone()
-        .deposit_tokens(max_deposits, lp_supply)?
-        .lp_tokens_to_distribute;
-
-    Ok(toll_in_lp_tokens)
+// Same as [`calculate_toll_in_lp_tokens`], but with the referrer's share of the
+// swap fee.
+pub fn calculate_referral_in_lp_tokens(
+    pool: &Pool,
+    swap_fee: TokenAmount,
+    referrer_share: Permillion,
+    sell_mint: Pubkey,
+    lp_supply: TokenAmount,
+) -> Result<Option<TokenAmount>> {
+    pool.swap_fee_share_in_lp_tokens(
+        swap_fee,
+        referrer_share,
+        sell_mint,
+        lp_supply,
+    )
 }
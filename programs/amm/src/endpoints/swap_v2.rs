@@ -0,0 +1,34 @@
+//! Same as [`crate::endpoints::swap`], but a transaction which sits in the
+//! mempool for a long time can no longer execute at a stale price which still
+//! satisfies `min_buy`. The user can optionally set:
+//!
+//! - `valid_until` slot (inclusive) after which the swap fails with
+//!   [`AmmError::DeadlineExceeded`];
+//! - `max_price`, the maximum number of sell tokens the user is willing to pay
+//!   per one bought token, swap fee included. If exceeded, the swap fails with
+//!   [`AmmError::LimitPriceExceeded`].
+
+use crate::endpoints::swap::{swap_with_limits, Swap};
+use crate::prelude::*;
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+    sell: TokenAmount,
+    min_buy: TokenAmount,
+    valid_until: Option<Slot>,
+    max_price: Option<SDecimal>,
+) -> Result<()> {
+    if let Some(valid_until) = valid_until {
+        let current_slot = Slot::current()?;
+        if current_slot > valid_until {
+            msg!(
+                "Swap was valid until slot {}, but current slot is {}",
+                valid_until.slot,
+                current_slot.slot
+            );
+            return Err(error!(AmmError::DeadlineExceeded));
+        }
+    }
+
+    swap_with_limits(ctx, sell, min_buy, max_price.map(Decimal::from))
+}
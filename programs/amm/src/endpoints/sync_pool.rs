@@ -0,0 +1,63 @@
+//! [`Reserve`]'s tokens are tracked separately from the actual balance of its
+//! vault. Tokens sent directly to a vault are not accounted for, and a mismatch
+//! caused by a bug would go unnoticed. With this endpoint, the admin of a
+//! [`Pool`] adopts the vault balances into the reserves.
+//!
+//! The remaining accounts must be the pool's vaults, one for each reserve. The
+//! order of the accounts does not matter.
+//!
+//! See also [`crate::endpoints::skim`] which instead sends the surplus to the
+//! program toll.
+
+use crate::misc::load_reserve_vaults;
+use crate::prelude::*;
+
+#[derive(Accounts)]
+pub struct SyncPool<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        constraint = pool.admin == admin.key()
+            @ err::acc("The signer must match pool's admin"),
+    )]
+    pub pool: Account<'info, Pool>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+}
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, SyncPool<'info>>,
+) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let vaults = load_reserve_vaults(&accs.pool, ctx.remaining_accounts)?;
+    for vault in vaults {
+        let reserve = accs
+            .pool
+            .reserve_mut(vault.mint)
+            // checked when loading vaults
+            .ok_or(AmmError::InvariantViolation)?;
+
+        if reserve.tokens.amount != vault.amount {
+            msg!(
+                "Reserve of mint {} synced from {} to {} tokens",
+                vault.mint,
+                reserve.tokens.amount,
+                vault.amount
+            );
+        }
+        reserve.tokens = TokenAmount::new(vault.amount);
+    }
+
+    // no-op if const prod
+    let config = ProgramConfig::load(&accs.program_config)?;
+    accs.pool
+        .update_curve_invariant_with(config.stable_curve_invariant)?;
+
+    Ok(())
+}
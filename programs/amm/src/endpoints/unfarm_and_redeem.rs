@@ -0,0 +1,88 @@
+//! Unstakes LP tokens from a farm of the farming program and in the same
+//! instruction redeems them for the pool's reserve tokens. This is the
+//! counterpart to [`crate::endpoints::deposit_and_farm`].
+//!
+//! The farming program unstakes at most `unstake_max` LP tokens into the
+//! user's LP wallet. Exactly as many LP tokens as were unstaked are then
+//! redeemed, LP tokens which were already in the wallet are left untouched.
+//! The accounts for the redemption are the same as in
+//! [`crate::endpoints::redeem_liquidity`], including the remaining accounts
+//! with vault and wallet pairs.
+
+use crate::endpoints::redeem_liquidity::{self, RedeemLiquidity};
+use crate::prelude::*;
+use farming::program::Farming;
+
+#[derive(Accounts)]
+pub struct UnfarmAndRedeem<'info> {
+    pub redeem: RedeemLiquidity<'info>,
+    /// CHECK: validated by the farming program
+    #[account(mut)]
+    pub farmer: AccountInfo<'info>,
+    /// CHECK: validated by the farming program
+    pub farm: AccountInfo<'info>,
+    /// CHECK: validated by the farming program
+    pub farm_signer_pda: AccountInfo<'info>,
+    /// CHECK: validated by the farming program
+    #[account(mut)]
+    pub farm_stake_vault: AccountInfo<'info>,
+    pub farming: Program<'info, Farming>,
+}
+
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, UnfarmAndRedeem<'info>>,
+    unstake_max: TokenAmount,
+    min_amount_tokens: Vec<TokenLimit>,
+) -> Result<()> {
+    let Context {
+        program_id,
+        accounts,
+        remaining_accounts,
+        bumps,
+    } = ctx;
+
+    let lp_tokens_before = accounts.redeem.lp_token_wallet.amount;
+    farming::cpi::stop_farming(
+        accounts.as_stop_farming_context(),
+        unstake_max,
+    )?;
+    accounts.redeem.lp_token_wallet.reload()?;
+    let unstaked = accounts
+        .redeem
+        .lp_token_wallet
+        .amount
+        .checked_sub(lp_tokens_before)
+        .ok_or(AmmError::MathOverflow)?;
+    msg!("Unstaked {} LP tokens", unstaked);
+
+    redeem_liquidity::handle(
+        Context::new(
+            program_id,
+            &mut accounts.redeem,
+            remaining_accounts,
+            bumps,
+        ),
+        TokenAmount::new(unstaked),
+        min_amount_tokens,
+    )
+}
+
+impl<'info> UnfarmAndRedeem<'info> {
+    fn as_stop_farming_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, farming::cpi::accounts::StopFarming<'info>>
+    {
+        let cpi_accounts = farming::cpi::accounts::StopFarming {
+            authority: self.redeem.user.to_account_info(),
+            farmer: self.farmer.to_account_info(),
+            stake_wallet: self.redeem.lp_token_wallet.to_account_info(),
+            farm: self.farm.to_account_info(),
+            farm_signer_pda: self.farm_signer_pda.to_account_info(),
+            stake_vault: self.farm_stake_vault.to_account_info(),
+            token_program: self.redeem.token_program.to_account_info(),
+        };
+        let cpi_program = self.farming.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -0,0 +1,120 @@
+//! The authority of a [`TwammOrder`] withdraws the tokens the order has bought
+//! so far. This can be done at any time.
+//!
+//! Once the order has ended, the sell tokens which haven't been sold are
+//! withdrawn too. This happens if the order flow accumulated after the last
+//! execution was too small to be traded, or if the authority withdraws before
+//! the crank executed the rest of the order.
+
+use crate::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount};
+
+#[derive(Accounts)]
+pub struct WithdrawTwammProceeds<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = order.authority == authority.key()
+            @ err::acc("Signer is not the order's authority"),
+    )]
+    pub order: Box<Account<'info, TwammOrder>>,
+    /// CHECK: UNSAFE_CODES.md#signer
+    #[account(
+        seeds = [TwammOrder::SIGNER_PDA_PREFIX, order.key().as_ref()],
+        bump,
+    )]
+    pub order_signer: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = order_sell_vault.key() == order.sell_vault
+            @ err::acc("Sell vault must match order's sell vault"),
+    )]
+    pub order_sell_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = order_buy_vault.key() == order.buy_vault
+            @ err::acc("Buy vault must match order's buy vault"),
+    )]
+    pub order_buy_vault: Box<Account<'info, TokenAccount>>,
+    /// Unsold tokens flow INTO this account.
+    #[account(
+        mut,
+        constraint = sell_wallet.mint == order.sell_mint
+            @ err::acc("Sell wallet mint must match order's sell mint"),
+    )]
+    pub sell_wallet: Box<Account<'info, TokenAccount>>,
+    /// Proceeds flow INTO this account.
+    #[account(
+        mut,
+        constraint = buy_wallet.mint == order.buy_mint
+            @ err::acc("Buy wallet mint must match order's buy mint"),
+    )]
+    pub buy_wallet: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handle(ctx: Context<WithdrawTwammProceeds>) -> Result<()> {
+    let accs = ctx.accounts;
+
+    let proceeds = accs.order.proceeds;
+    let unsold = if Slot::current()? >= accs.order.end {
+        accs.order.unsold()
+    } else {
+        TokenAmount::new(0)
+    };
+
+    accs.order.proceeds = TokenAmount::new(0);
+    // the order's total is lowered to what was actually sold, so that the
+    // crank has nothing left to trade
+    accs.order.sell.amount -= unsold.amount;
+
+    let signer_seeds = &[
+        TwammOrder::SIGNER_PDA_PREFIX,
+        &accs.order.key().to_bytes()[..],
+        &[*ctx.bumps.get("order_signer").unwrap()],
+    ];
+
+    if proceeds.amount > 0 {
+        token::transfer(
+            accs.as_transfer_proceeds_to_wallet_ctx()
+                .with_signer(&[&signer_seeds[..]]),
+            proceeds.amount,
+        )?;
+    }
+
+    if unsold.amount > 0 {
+        token::transfer(
+            accs.as_transfer_unsold_to_wallet_ctx()
+                .with_signer(&[&signer_seeds[..]]),
+            unsold.amount,
+        )?;
+    }
+
+    Ok(())
+}
+
+impl<'info> WithdrawTwammProceeds<'info> {
+    fn as_transfer_proceeds_to_wallet_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            authority: self.order_signer.to_account_info(),
+            from: self.order_buy_vault.to_account_info(),
+            to: self.buy_wallet.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    fn as_transfer_unsold_to_wallet_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            authority: self.order_signer.to_account_info(),
+            from: self.order_sell_vault.to_account_info(),
+            to: self.sell_wallet.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -0,0 +1,370 @@
+//! Users who hold tokens of only one of the pool's mints can deposit them
+//! without swapping part of them manually first. We find how many of the
+//! tokens to swap into the other reserve's mint so that the rest and the
+//! bought tokens are in the ratio of the reserves after the swap, see
+//! [`Pool::calculate_zap_in_swap`]. The swap is performed with the same fees
+//! as [`crate::endpoints::swap`], including the program toll, and then the
+//! tokens are deposited as in [`crate::endpoints::deposit_liquidity`].
+//!
+//! The bought tokens which cannot be deposited due to rounding are returned
+//! to the user's buy wallet. The sell tokens which cannot be deposited are
+//! never transferred from the user's sell wallet.
+//!
+//! Zap is available only for pools with two reserves.
+//!
+//! If the pool has an access policy, the only remaining account is the user's
+//! [`PoolAccess`].
+
+use crate::endpoints::swap::{
+    calculate_discounted_swap_fee_share, calculate_swap_fee,
+    calculate_toll_in_lp_tokens,
+};
+use crate::misc::print_lp_supply;
+use crate::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use std::collections::BTreeMap;
+
+#[derive(Accounts)]
+pub struct ZapIn<'info> {
+    /// Authority over the sell wallet.
+    pub user: Signer<'info>,
+    /// CHECK: The user's discount might not be initialized, and that's fine,
+    /// we are conditionally parsing this account and only if it's valid
+    /// will we consider the discount.
+    #[account(
+        seeds = [Discount::PDA_PREFIX, user.key().as_ref()],
+        bump,
+    )]
+    pub discount: AccountInfo<'info>,
+    #[account(mut)]
+    pub pool: Box<Account<'info, Pool>>,
+    /// CHECK: pda signer
+    #[account(
+        seeds = [Pool::SIGNER_PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_signer: AccountInfo<'info>,
+    /// Zapped tokens flow FROM this account.
+    #[account(
+        mut,
+        constraint = sell_wallet.mint != buy_wallet.mint
+            @ err::acc("Mint to zap mustn't equal the other reserve's mint"),
+        constraint = sell_wallet.mint == sell_vault.mint
+            @ err::acc("Sell wallet mint must match sell vault mint"),
+    )]
+    pub sell_wallet: Box<Account<'info, TokenAccount>>,
+    /// Bought tokens which cannot be deposited flow INTO this account.
+    #[account(
+        mut,
+        constraint = buy_wallet.mint == buy_vault.mint
+            @ err::acc("Buy wallet mint must match buy vault mint"),
+    )]
+    pub buy_wallet: Box<Account<'info, TokenAccount>>,
+    /// Zapped tokens flow INTO this account.
+    #[account(
+        mut,
+        // either the mint is not any reserve's mint, or the vault doesn't match
+        constraint = pool.reserve_vault(sell_vault.mint) == Some(sell_vault.key())
+            @ err::acc("Sell vault is not reserve's vault"),
+    )]
+    pub sell_vault: Box<Account<'info, TokenAccount>>,
+    /// Bought tokens which cannot be deposited flow FROM this account.
+    #[account(
+        mut,
+        // either the mint is not any reserve's mint, or the vault doesn't match
+        constraint = pool.reserve_vault(buy_vault.mint) == Some(buy_vault.key())
+            @ err::acc("Buy vault is not reserve's vault"),
+    )]
+    pub buy_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = pool.mint == lp_mint.key() @ err::acc("LP mint mismatch"),
+        constraint = lp_mint.supply > 0 @ err::acc("No liquidity provided yet"),
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+    /// We mint LPs for the deposit into this account.
+    #[account(
+        mut,
+        constraint = lp_token_wallet.mint == pool.mint
+            @ err::acc("LP wallet must be of the same mint as pool's mint"),
+    )]
+    pub lp_token_wallet: Box<Account<'info, TokenAccount>>,
+    /// Part of the fee is the program owner's cut, and is payed in LPs.
+    #[account(
+        mut,
+        constraint = pool.program_toll_wallet == program_toll_wallet.key()
+            @ err::acc("Program toll wallet mismatch"),
+    )]
+    pub program_toll_wallet: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+    /// CHECK: The pool stats might not be created, in which case they're not
+    /// updated, see [`PoolStats::update_if_created`].
+    #[account(
+        mut,
+        seeds = [PoolStats::PDA_PREFIX, pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_stats: AccountInfo<'info>,
+}
+
+/// 1. Calculates how many of the zapped tokens to swap and swaps them.
+///
+/// 2. Calculates the toll and the deposit of the remaining sell tokens and the
+/// bought tokens.
+///
+/// 3. Transfers the swapped and deposited sell tokens to the vault.
+///
+/// 4. Transfers the bought tokens which cannot be deposited back to the user.
+///
+/// 5. Mints LP tokens to program owner's wallet as a toll for the swap.
+///
+/// 6. Mints LP tokens for the deposit to the user.
+///
+/// 7. Records the swap and the deposit in the pool stats, if they exist.
+pub fn handle<'info>(
+    ctx: Context<'_, '_, '_, 'info, ZapIn<'info>>,
+    tokens: TokenAmount,
+    min_lp_tokens_out: TokenAmount,
+) -> Result<()> {
+    let accs = ctx.accounts;
+    let sell_mint = accs.sell_vault.mint;
+    let buy_mint = accs.buy_vault.mint;
+
+    if tokens.amount == 0 {
+        return Err(error!(AmmError::ZeroTokenAmount));
+    }
+
+    check_pool_access(
+        &accs.pool,
+        accs.user.key(),
+        ctx.remaining_accounts,
+        false,
+    )?;
+
+    // a scheduled swap fee change applies from its effective slot on
+    let now = Slot::current()?;
+    accs.pool.apply_pending_swap_fee(now);
+
+    //
+    // 1.
+    //
+    let swap_fee_share = calculate_discounted_swap_fee_share(
+        accs.pool.swap_fee,
+        &accs.discount,
+    )?;
+    let (zap_buy_mint, tokens_to_sell) = accs.pool.calculate_zap_in_swap(
+        sell_mint,
+        tokens,
+        swap_fee_share,
+        now,
+    )?;
+    if zap_buy_mint != buy_mint {
+        // only possible if the pool is inconsistent with its vaults
+        return Err(error!(AmmError::InvariantViolation));
+    }
+
+    let swap_fee =
+        calculate_swap_fee(tokens_to_sell, accs.pool.swap_fee, &accs.discount)?;
+    let bought = accs.pool.swap(
+        sell_mint,
+        TokenAmount::new(tokens_to_sell.amount - swap_fee.amount),
+        buy_mint,
+        now,
+    )?;
+    // same as in swap, the fee stays in the sell reserve
+    accs.pool
+        .reserve_mut(sell_mint)
+        .unwrap() // the mint is part of the pool as per constraints and swap fn
+        .add_tokens(swap_fee)?;
+
+    //
+    // 2.
+    //
+    let lp_supply = TokenAmount::new(accs.lp_mint.supply);
+    let config = ProgramConfig::load(&accs.program_config)?;
+    let toll_in_lp_tokens = calculate_toll_in_lp_tokens(
+        &accs.pool,
+        swap_fee,
+        config.program_toll_swap_fee_share,
+        sell_mint,
+        lp_supply,
+    )?;
+    // the toll is minted before the deposit and therefore dilutes it
+    let lp_supply_after_toll = TokenAmount::new(
+        lp_supply
+            .amount
+            .checked_add(toll_in_lp_tokens.unwrap_or_default().amount)
+            .ok_or(AmmError::MathOverflow)?,
+    );
+
+    let max_tokens = BTreeMap::from([
+        (
+            sell_mint,
+            TokenAmount::new(tokens.amount - tokens_to_sell.amount),
+        ),
+        (buy_mint, bought),
+    ]);
+    let DepositResult {
+        lp_tokens_to_distribute,
+        tokens_to_deposit,
+    } = accs.pool.deposit_tokens(max_tokens, lp_supply_after_toll)?;
+    let lp_tokens_to_distribute = lp_tokens_to_distribute.ok_or_else(|| {
+        msg!("Provided liquidity is too small to be represented");
+        AmmError::InvalidArg
+    })?;
+    if min_lp_tokens_out > lp_tokens_to_distribute {
+        msg!(
+            "Zap would mint {} LP tokens, but requested minimum of {}",
+            lp_tokens_to_distribute.amount,
+            min_lp_tokens_out.amount
+        );
+        return Err(error!(AmmError::LpTokensOutBelowMinimum));
+    }
+
+    let sell_tokens_to_deposit = tokens_to_deposit
+        .get(&sell_mint)
+        .copied()
+        .ok_or(AmmError::InvariantViolation)?;
+    let buy_tokens_to_deposit = tokens_to_deposit
+        .get(&buy_mint)
+        .copied()
+        .ok_or(AmmError::InvariantViolation)?;
+    let buy_dust = TokenAmount::new(
+        bought
+            .amount
+            .checked_sub(buy_tokens_to_deposit.amount)
+            .ok_or(AmmError::InvariantViolation)?,
+    );
+
+    let pda_seeds = &[
+        Pool::SIGNER_PDA_PREFIX,
+        &accs.pool.key().to_bytes()[..],
+        &[*ctx.bumps.get("pool_signer").unwrap()],
+    ];
+
+    //
+    // 3.
+    //
+    token::transfer(
+        accs.as_transfer_sell_tokens_to_vault_ctx(),
+        tokens_to_sell
+            .amount
+            .checked_add(sell_tokens_to_deposit.amount)
+            .ok_or(AmmError::MathOverflow)?,
+    )?;
+
+    //
+    // 4.
+    //
+    if buy_dust.amount > 0 {
+        token::transfer(
+            accs.as_transfer_buy_dust_to_wallet_ctx()
+                .with_signer(&[&pda_seeds[..]]),
+            buy_dust.amount,
+        )?;
+    }
+
+    //
+    // 5.
+    //
+    if let Some(toll_in_lp_tokens) = toll_in_lp_tokens {
+        token::mint_to(
+            accs.as_pay_toll_ctx().with_signer(&[&pda_seeds[..]]),
+            toll_in_lp_tokens.amount,
+        )?;
+    }
+
+    //
+    // 6.
+    //
+    token::mint_to(
+        accs.as_mint_lp_tokens_to_user_ctx()
+            .with_signer(&[&pda_seeds[..]]),
+        lp_tokens_to_distribute.amount,
+    )?;
+
+    //
+    // 7.
+    //
+    PoolStats::update_if_created(&accs.pool_stats, |stats| {
+        stats.record_swap(
+            &accs.pool,
+            SwapRecord {
+                sell_mint,
+                sold: tokens_to_sell,
+                swap_fee,
+                buy_mint,
+                bought,
+                toll_lp_minted: toll_in_lp_tokens
+                    .unwrap_or_else(|| TokenAmount::new(0)),
+                slot: now,
+            },
+        )?;
+        stats.record_deposit(&accs.pool, &tokens_to_deposit)
+    })?;
+
+    print_lp_supply(&mut accs.lp_mint)?;
+
+    // no-op if const prod
+    accs.pool
+        .update_curve_invariant_with(config.stable_curve_invariant)?;
+
+    Ok(())
+}
+
+impl<'info> ZapIn<'info> {
+    fn as_transfer_sell_tokens_to_vault_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            authority: self.user.to_account_info(),
+            from: self.sell_wallet.to_account_info(),
+            to: self.sell_vault.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    fn as_transfer_buy_dust_to_wallet_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let cpi_accounts = token::Transfer {
+            authority: self.pool_signer.to_account_info(),
+            from: self.buy_vault.to_account_info(),
+            to: self.buy_wallet.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    fn as_pay_toll_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::MintTo<'info>> {
+        let cpi_accounts = token::MintTo {
+            authority: self.pool_signer.to_account_info(),
+            mint: self.lp_mint.to_account_info(),
+            to: self.program_toll_wallet.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    fn as_mint_lp_tokens_to_user_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::MintTo<'info>> {
+        let cpi_accounts = token::MintTo {
+            authority: self.pool_signer.to_account_info(),
+            mint: self.lp_mint.to_account_info(),
+            to: self.lp_token_wallet.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
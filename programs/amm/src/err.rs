@@ -28,16 +28,72 @@ pub enum AmmError {
     InvalidTokenMints,
     #[msg("Invalid lp token amount to burn")]
     InvalidLpTokenAmount,
+    #[msg("The transaction was executed after the requested deadline slot")]
+    DeadlineExceeded,
+    #[msg(
+        "Given amount of tokens to swap would result in \
+        a higher price than the maximum requested price"
+    )]
+    LimitPriceExceeded,
+    #[msg(
+        "Given amount of tokens to deposit would result in \
+        less than minimum requested LP tokens to receive"
+    )]
+    LpTokensOutBelowMinimum,
+    #[msg(
+        "Until the end of the bootstrapping only the admin can deposit \
+        and nobody can redeem"
+    )]
+    PoolBootstrapping,
+    #[msg("The user is not allowed to swap or deposit in this pool")]
+    PoolAccessDenied,
+    #[msg("At least one of the pool's reserves has no tokens")]
+    ZeroReserve,
+    #[msg("The amount of tokens to swap must be less than the sell reserve")]
+    SwapExceedsReserve,
+    #[msg(
+        "The trade would result in an extreme imbalance \
+        of the stable curve pool's reserves"
+    )]
+    StableCurveImbalance,
+    #[msg("The number of token limits doesn't match the pool's dimension")]
+    DimensionMismatch,
+    #[msg("Not all of the pool's reserve mints are in the token limits")]
+    MissingMintInLimits,
+    #[msg("Each token amount must be positive")]
+    ZeroTokenAmount,
+    #[msg("The pool already has a reserve of the mint")]
+    DuplicateReserve,
+    #[msg("The pool cannot have more reserves")]
+    TooManyReserves,
+    #[msg("The pool must have at least 2 reserves")]
+    TooFewReserves,
+    #[msg("Only a reserve with zero balance can be removed")]
+    ReserveNotEmpty,
+    #[msg("Reserve vault mustn't have a delegate")]
+    VaultHasDelegate,
+    #[msg("Reserve vault mustn't have a close authority")]
+    VaultHasCloseAuthority,
+    #[msg("Reserve vault must be owned by the pool signer")]
+    VaultOwnerNotPoolSigner,
+    #[msg("Reserve vault mustn't be frozen")]
+    VaultFrozen,
+    #[msg(
+        "Given amount of LP tokens to burn would result in \
+        less than minimum requested tokens to receive"
+    )]
+    RedeemBelowMinimum,
+    #[msg(
+        "The initial deposit of the new reserve must be worth \
+        each of the pool's existing reserves"
+    )]
+    SeedValueOutOfBounds,
 }
 
 pub fn acc(msg: impl Display) -> AmmError {
-    msg!("[InvalidAccountInput] {}", msg);
-
-    AmmError::InvalidAccountInput
+    common::err::acc(AmmError::InvalidAccountInput, msg)
 }
 
 pub fn arg(msg: impl Display) -> AmmError {
-    msg!("[InvalidArg] {}", msg);
-
-    AmmError::InvalidArg
+    common::err::arg(AmmError::InvalidArg, msg)
 }
@@ -7,6 +7,10 @@ pub mod math;
 pub mod misc;
 pub mod models;
 pub mod prelude;
+pub mod token_metadata;
+
+#[cfg(feature = "serde")]
+pub use common::serde_helpers;
 
 use crate::endpoints::*;
 use crate::prelude::*;
@@ -25,6 +29,23 @@ pub mod amm {
         endpoints::create_program_toll::handle(ctx)
     }
 
+    /// # Important
+    /// This endpoint requires different accounts based on whether the program
+    /// is compiled with the "dev" feature.
+    pub fn put_program_config(
+        ctx: Context<PutProgramConfig>,
+        max_swap_fee: Permillion,
+        program_toll_swap_fee_share: Permillion,
+        stable_curve_invariant: StableCurveInvariantMethod,
+    ) -> Result<()> {
+        endpoints::put_program_config::handle(
+            ctx,
+            max_swap_fee,
+            program_toll_swap_fee_share,
+            stable_curve_invariant,
+        )
+    }
+
     /// # Important
     /// This endpoint requires different accounts based on whether the program
     /// is compiled with the "dev" feature.
@@ -38,6 +59,62 @@ pub mod amm {
         endpoints::create_pool::handle(ctx, amplifier)
     }
 
+    pub fn create_weighted_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreatePool<'info>>,
+        start_weight: Permillion,
+        end_weight: Permillion,
+        start: Slot,
+        end: Slot,
+    ) -> Result<()> {
+        endpoints::create_weighted_pool::handle(
+            ctx,
+            start_weight,
+            end_weight,
+            start,
+            end,
+        )
+    }
+
+    pub fn create_pool_stats(ctx: Context<CreatePoolStats>) -> Result<()> {
+        endpoints::create_pool_stats::handle(ctx)
+    }
+
+    pub fn convert_to_const_prod(
+        ctx: Context<ConvertToConstProd>,
+    ) -> Result<()> {
+        endpoints::convert_to_const_prod::handle(ctx)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        endpoints::migrate_pool::handle(ctx)
+    }
+
+    pub fn set_lp_metadata<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetLpMetadata<'info>>,
+        name: Option<String>,
+        symbol: Option<String>,
+        uri: String,
+    ) -> Result<()> {
+        endpoints::set_lp_metadata::handle(ctx, name, symbol, uri)
+    }
+
+    pub fn close_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClosePool<'info>>,
+    ) -> Result<()> {
+        endpoints::close_pool::handle(ctx)
+    }
+
+    pub fn add_reserve(
+        ctx: Context<AddReserve>,
+        price: ReservePrice,
+    ) -> Result<()> {
+        endpoints::add_reserve::handle(ctx, price)
+    }
+
+    pub fn remove_reserve(ctx: Context<RemoveReserve>) -> Result<()> {
+        endpoints::remove_reserve::handle(ctx)
+    }
+
     pub fn put_discount(
         ctx: Context<PutDiscount>,
         user: Pubkey,
@@ -47,6 +124,28 @@ pub mod amm {
         endpoints::put_discount::handle(ctx, user, discount_amount, valid_until)
     }
 
+    pub fn set_pool_access_policy(
+        ctx: Context<SetPoolAccessPolicy>,
+        access_policy: Option<Pubkey>,
+    ) -> Result<()> {
+        endpoints::set_pool_access_policy::handle(ctx, access_policy)
+    }
+
+    pub fn put_pool_access(
+        ctx: Context<PutPoolAccess>,
+        user: Pubkey,
+        is_allowed: bool,
+    ) -> Result<()> {
+        endpoints::put_pool_access::handle(ctx, user, is_allowed)
+    }
+
+    pub fn register_referrer(
+        ctx: Context<RegisterReferrer>,
+        share: Permillion,
+    ) -> Result<()> {
+        endpoints::register_referrer::handle(ctx, share)
+    }
+
     pub fn set_pool_swap_fee(
         ctx: Context<SetPoolSwapFee>,
         fee: Permillion,
@@ -54,6 +153,18 @@ pub mod amm {
         endpoints::set_pool_swap_fee::handle(ctx, fee)
     }
 
+    pub fn propose_swap_fee(
+        ctx: Context<ProposeSwapFee>,
+        fee: Permillion,
+        effective_at: Slot,
+    ) -> Result<()> {
+        endpoints::propose_swap_fee::handle(ctx, fee, effective_at)
+    }
+
+    pub fn cancel_swap_fee(ctx: Context<CancelSwapFee>) -> Result<()> {
+        endpoints::cancel_swap_fee::handle(ctx)
+    }
+
     pub fn deposit_liquidity<'info>(
         ctx: Context<'_, '_, '_, 'info, DepositLiquidity<'info>>,
         max_amount_tokens: Vec<TokenLimit>,
@@ -61,6 +172,18 @@ pub mod amm {
         endpoints::deposit_liquidity::handle(ctx, max_amount_tokens)
     }
 
+    pub fn deposit_liquidity_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositLiquidity<'info>>,
+        max_amount_tokens: Vec<TokenLimit>,
+        min_lp_tokens_out: TokenAmount,
+    ) -> Result<()> {
+        endpoints::deposit_liquidity_v2::handle(
+            ctx,
+            max_amount_tokens,
+            min_lp_tokens_out,
+        )
+    }
+
     pub fn redeem_liquidity<'info>(
         ctx: Context<'_, '_, '_, 'info, RedeemLiquidity<'info>>,
         lp_tokens_to_burn: TokenAmount,
@@ -73,6 +196,30 @@ pub mod amm {
         )
     }
 
+    pub fn deposit_and_farm<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositAndFarm<'info>>,
+        max_amount_tokens: Vec<TokenLimit>,
+        min_lp_tokens_out: TokenAmount,
+    ) -> Result<()> {
+        endpoints::deposit_and_farm::handle(
+            ctx,
+            max_amount_tokens,
+            min_lp_tokens_out,
+        )
+    }
+
+    pub fn unfarm_and_redeem<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnfarmAndRedeem<'info>>,
+        unstake_max: TokenAmount,
+        min_amount_tokens: Vec<TokenLimit>,
+    ) -> Result<()> {
+        endpoints::unfarm_and_redeem::handle(
+            ctx,
+            unstake_max,
+            min_amount_tokens,
+        )
+    }
+
     pub fn swap<'info>(
         ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
         sell: TokenAmount,
@@ -80,4 +227,57 @@ pub mod amm {
     ) -> Result<()> {
         endpoints::swap::handle(ctx, sell, min_buy)
     }
+
+    pub fn swap_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+        sell: TokenAmount,
+        min_buy: TokenAmount,
+        valid_until: Option<Slot>,
+        max_price: Option<SDecimal>,
+    ) -> Result<()> {
+        endpoints::swap_v2::handle(ctx, sell, min_buy, valid_until, max_price)
+    }
+
+    pub fn zap_in<'info>(
+        ctx: Context<'_, '_, '_, 'info, ZapIn<'info>>,
+        tokens: TokenAmount,
+        min_lp_tokens_out: TokenAmount,
+    ) -> Result<()> {
+        endpoints::zap_in::handle(ctx, tokens, min_lp_tokens_out)
+    }
+
+    pub fn place_twamm_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, PlaceTwammOrder<'info>>,
+        sell: TokenAmount,
+        duration: u64,
+        max_price: Option<SDecimal>,
+    ) -> Result<()> {
+        endpoints::place_twamm_order::handle(ctx, sell, duration, max_price)
+    }
+
+    pub fn execute_twamm(ctx: Context<ExecuteTwamm>) -> Result<()> {
+        endpoints::execute_twamm::handle(ctx)
+    }
+
+    pub fn withdraw_twamm_proceeds(
+        ctx: Context<WithdrawTwammProceeds>,
+    ) -> Result<()> {
+        endpoints::withdraw_twamm_proceeds::handle(ctx)
+    }
+
+    pub fn sync_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncPool<'info>>,
+    ) -> Result<()> {
+        endpoints::sync_pool::handle(ctx)
+    }
+
+    pub fn skim(ctx: Context<Skim>) -> Result<()> {
+        endpoints::skim::handle(ctx)
+    }
+
+    pub fn check_pool_integrity<'info>(
+        ctx: Context<'_, '_, '_, 'info, CheckPoolIntegrity<'info>>,
+    ) -> Result<()> {
+        endpoints::check_pool_integrity::handle(ctx)
+    }
 }
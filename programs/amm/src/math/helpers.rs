@@ -116,6 +116,42 @@ pub fn try_mul_div(a: Decimal, b: Decimal, c: Decimal) -> Result<Decimal> {
     }
 }
 
+/// How many binary digits of the fractional part of an exponent are
+/// considered by [`try_pow_fraction`]. Each digit costs a square root.
+pub const FRACTIONAL_POW_PRECISION_BITS: usize = 40;
+
+/// Raises `base` to a non-negative `exponent` which doesn't have to be an
+/// integer.
+///
+/// The integer part of the exponent is applied with [`TryPow`]. The fractional
+/// part is expanded in binary, and for each set digit `k` the result is
+/// multiplied by `base^(1 / 2^k)`, which we get by repeated square roots. Only
+/// the first [`FRACTIONAL_POW_PRECISION_BITS`] digits are considered. For
+/// `base` in `(0, 1)` truncating the exponent makes the result larger, by a
+/// relative error of at most `|ln(base)| * 2^-40` on top of the rounding of
+/// the decimal operations.
+pub fn try_pow_fraction(base: Decimal, exponent: Decimal) -> Result<Decimal> {
+    let integer = exponent.try_floor()?;
+    let mut result = base.try_pow(integer)?;
+
+    let mut fraction = exponent.try_sub(Decimal::from(integer))?;
+    let mut root = base;
+    for _ in 0..FRACTIONAL_POW_PRECISION_BITS {
+        if fraction == Decimal::zero() {
+            break;
+        }
+
+        root = root.try_sqrt()?;
+        fraction = fraction.try_mul(Decimal::from(2u64))?;
+        if fraction >= Decimal::one() {
+            result = result.try_mul(root)?;
+            fraction = fraction.try_sub(Decimal::one())?;
+        }
+    }
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,6 +321,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn it_computes_try_pow_fraction() -> Result<()> {
+        let quarter = Decimal::one().try_div(Decimal::from(4u64))?;
+        let half = Decimal::one().try_div(Decimal::from(2u64))?;
+
+        assert_eq!(try_pow_fraction(quarter, half)?, half);
+        assert_eq!(
+            try_pow_fraction(quarter, Decimal::from(2u64))?,
+            quarter.try_pow(2)?
+        );
+        assert_eq!(try_pow_fraction(quarter, Decimal::zero())?, Decimal::one());
+        assert_eq!(try_pow_fraction(Decimal::one(), half)?, Decimal::one());
+
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn try_pow_fraction_is_close_to_float_pow(
+            base_permillion in 1_000..1_000_000_u64,
+            exponent_permillion in 0..20_000_000_u64,
+        ) {
+            let base = Decimal::from_permillion(base_permillion);
+            let exponent = Decimal::from_permillion(exponent_permillion);
+            let actual: f64 = try_pow_fraction(base, exponent)
+                .unwrap()
+                .to_string()
+                .parse()
+                .unwrap();
+
+            let expected = (base_permillion as f64 / 1_000_000.0)
+                .powf(exponent_permillion as f64 / 1_000_000.0);
+            prop_assert!(
+                (actual - expected).abs() <= expected * 1e-9 + 1e-15,
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+    }
+
     proptest! {
         #[test]
         fn successfully_returns_positive_exponent(
@@ -1,6 +1,7 @@
 pub mod helpers;
 mod sdecimal;
 pub mod stable_curve_invariant;
+pub mod stable_curve_invariant_int;
 pub mod swap_equation;
 
 pub use decimal::{
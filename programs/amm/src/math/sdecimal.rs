@@ -25,6 +25,9 @@ use decimal::U192;
 /// }
 /// ```
 ///
+/// With the `serde` feature, the number is represented as a decimal string,
+/// such as `"0.001000000000000000"`.
+///
 /// [web3-bn]: https://web3js.readthedocs.io/en/v1.5.2/web3-utils.html#bn
 #[derive(
     AnchorSerialize,
@@ -36,12 +39,6 @@ use decimal::U192;
     PartialEq,
     Eq,
 )]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde_crate::Serialize, serde_crate::Deserialize),
-    serde(crate = "serde_crate")
-)]
-#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct SDecimal {
     u192: [u64; 3],
 }
@@ -83,6 +80,48 @@ impl SDecimal {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde_crate::Serialize for SDecimal {
+    fn serialize<S: serde_crate::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.to_dec())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde_crate::Deserialize<'de> for SDecimal {
+    fn deserialize<D: serde_crate::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        use serde_crate::de::Error;
+
+        // the inner value is the number times 10^18
+        const SCALE: usize = 18;
+
+        let number = String::deserialize(deserializer)?;
+        let (integer, fraction) =
+            number.split_once('.').unwrap_or((number.as_str(), ""));
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty()
+            || !is_digits(integer)
+            || !is_digits(fraction)
+            || fraction.len() > SCALE
+        {
+            return Err(D::Error::custom(format!(
+                "'{}' is not a decimal with up to {} decimal places",
+                number, SCALE
+            )));
+        }
+
+        let scaled = format!("{}{:0<scale$}", integer, fraction, scale = SCALE);
+        U192::from_dec_str(&scaled)
+            .map(|u192| Self { u192: u192.0 })
+            .map_err(|_| D::Error::custom(format!("'{}' is too large", number)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,4 +154,26 @@ mod tests {
         };
         assert_eq!(dec.to_dec().to_string(), "0.001000000000000000");
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_as_decimal_string() {
+        let dec = SDecimal::from(
+            Decimal::from(3u64).try_div(Decimal::from(2u64)).unwrap(),
+        );
+
+        let json = serde_json::to_string(&dec).unwrap();
+        assert_eq!(json, r#""1.500000000000000000""#);
+        assert_eq!(serde_json::from_str::<SDecimal>(&json).unwrap(), dec);
+        assert_eq!(serde_json::from_str::<SDecimal>(r#""1.5""#).unwrap(), dec);
+        assert_eq!(
+            serde_json::from_str::<SDecimal>(r#""17890""#).unwrap(),
+            SDecimal::from(17_890)
+        );
+
+        for invalid in ["", ".5", "-1", "1e5", "0.0000000000000000001"] {
+            let json = format!(r#""{}""#, invalid);
+            assert!(serde_json::from_str::<SDecimal>(&json).is_err());
+        }
+    }
 }
@@ -6,20 +6,17 @@
 use crate::prelude::*;
 use helpers::scale_down_value;
 
-// The method should converge within few iterations, due to the fact
-// we are approximating positive root from a well positioned first
-// initial guess.
-// We use the same max that was used in the old AMM version.
+// The method converges within few iterations on balanced reserves, due to the
+// fact we are approximating positive root from a well positioned first
+// initial guess. The more imbalanced the reserves, the longer it first
+// decreases linearly, but the most imbalanced reserves which we accept need
+// fewer than 50 iterations.
 //
 // This is one of two bounding conditions for the newtons method. The other is
-// the admissible error. We're pretty much guaranteed that the admissible error
-// bounding condition is going to be the breaking point for the newton's method
-// loop. We use this constant as a sanity check. We don't lower the constant to
-// be more in line with the admissible error, because we want to have only one
-// condition to reason about. It's better to know that the method will always
-// break on surpassing the admissible error, than to reason about whether it
-// was max iterations or the error.
-const MAX_ITERATIONS: usize = 32;
+// the admissible error. The method is expected to always break on the
+// admissible error. If it doesn't within this many iterations, we fail rather
+// than return an approximation which is far from the root.
+const MAX_ITERATIONS: usize = 64;
 
 pub fn compute(
     amp: u64,
@@ -39,23 +36,25 @@ pub fn compute(
     StableCurveInvariant::new(amp, token_reserves_amount)?.compute()
 }
 
-struct StableCurveInvariant {
-    // number of reserves
-    exponent: u64,
-    // initial guess for Newton's Method
+/// Fails with [`AmmError::StableCurveImbalance`] if the reserves are too
+/// imbalanced for [`compute`]. The integer implementation in
+/// [`super::stable_curve_invariant_int`] rejects the same reserves.
+pub fn check_balance(token_reserves_amount: &[TokenAmount]) -> Result<()> {
+    ScaledDownReserves::new(token_reserves_amount).map(|_| ())
+}
+
+struct ScaledDownReserves {
+    sum: Decimal,
+    // scaled down sum, our initial guess for Newton's method
     initial_guess: Decimal,
     // scale down exponent
     scl_down_coef: Decimal,
-    // amplifier * n - 1
-    first_order_coeff: Decimal,
-    // amplifier * n * sum
-    polynomial_third_term: Decimal,
+    // product of scaled down reserves
+    product: Decimal,
 }
 
-impl StableCurveInvariant {
-    fn new(amp: u64, token_reserves_amount: &[TokenAmount]) -> Result<Self> {
-        let amp = Decimal::from(amp);
-
+impl ScaledDownReserves {
+    fn new(token_reserves_amount: &[TokenAmount]) -> Result<Self> {
         let sum = token_reserves_amount
             .iter()
             .try_fold(Decimal::zero(), |acc, el| {
@@ -79,13 +78,42 @@ impl StableCurveInvariant {
         // we don't allow trades in which the product is infinitesimally close
         // to zero, as this means extreme imbalance on a stable swap pool
         if product < Decimal::from_scaled_val(1_000_000) {
-            msg!(
-                "invalid trade, it creates extreme imbalance \
-                on current stable pool"
-            );
-            return Err(error!(AmmError::MathOverflow));
+            return Err(error!(AmmError::StableCurveImbalance));
         }
 
+        Ok(Self {
+            sum,
+            initial_guess,
+            scl_down_coef,
+            product,
+        })
+    }
+}
+
+struct StableCurveInvariant {
+    // number of reserves
+    exponent: u64,
+    // initial guess for Newton's Method
+    initial_guess: Decimal,
+    // scale down exponent
+    scl_down_coef: Decimal,
+    // amplifier * n - 1
+    first_order_coeff: Decimal,
+    // amplifier * n * sum
+    polynomial_third_term: Decimal,
+}
+
+impl StableCurveInvariant {
+    fn new(amp: u64, token_reserves_amount: &[TokenAmount]) -> Result<Self> {
+        let amp = Decimal::from(amp);
+
+        let ScaledDownReserves {
+            sum,
+            initial_guess,
+            scl_down_coef,
+            product,
+        } = ScaledDownReserves::new(token_reserves_amount)?;
+
         let exponent = token_reserves_amount.len() as u64;
         let base: Decimal = exponent.into();
         let n: Decimal = base.try_pow(exponent)?;
@@ -111,9 +139,8 @@ impl StableCurveInvariant {
 
     fn compute(self) -> Result<Decimal> {
         // acts as a threshold for the difference between successive
-        // approximations
-        let admissible_error: Decimal =
-            Decimal::from(1u64).try_div(Decimal::from(2u64)).unwrap();
+        // approximations, which is a single token once scaled back up
+        let admissible_error = Decimal::one().try_div(self.scl_down_coef)?;
 
         // our initial guess is the scaled down sum of token reserve balances
         let mut prev_val = self.initial_guess;
@@ -123,7 +150,12 @@ impl StableCurveInvariant {
 
         for _ in 0..MAX_ITERATIONS {
             prev_val = new_val;
-            new_val = self.newton_method_single_iteration(&prev_val)?;
+            new_val = match self.newton_method_single_iteration(&prev_val)? {
+                Some(new_val) => new_val,
+                // the decimal rounding got us just below the root, which is as
+                // close to it as we can get
+                None => return prev_val.try_mul(self.scl_down_coef),
+            };
             // We proved by algebraic manipulations that given a first initial
             // guess coinciding with the sum of token reserve
             // balances, then sum(x_i) >= positive_zero where
@@ -138,13 +170,10 @@ impl StableCurveInvariant {
             // applying Newton method to it will result in getting x again,
             // and the reciprocal statement holds true, so it is an equivalence.
             // Thus, the following checks are sufficient to guarantee
-            // full logic coverage.
+            // full logic coverage. The decimal rounding makes the method stall
+            // within a rounding error from the root, which we accept as well.
             if prev_val <= new_val {
-                let is_val_root_stable_poly = self
-                    .get_stable_swap_polynomial(&prev_val)?
-                    == Decimal::zero();
-
-                if is_val_root_stable_poly {
+                if new_val.try_sub(prev_val)? <= admissible_error {
                     return prev_val.try_mul(self.scl_down_coef);
                 } else {
                     // in this case, prev_val is not a root of the polynomial,
@@ -162,36 +191,50 @@ impl StableCurveInvariant {
             // assuming that prev_val >= new_val, we just need to check that
             // prev_val - new_val <= adm_error
             if prev_val.try_sub(new_val)? <= admissible_error {
-                break;
+                return new_val.try_mul(self.scl_down_coef);
             }
         }
 
-        new_val.try_mul(self.scl_down_coef)
+        msg!("Newton's method did not converge");
+        Err(error!(AmmError::InvariantViolation))
     }
 
+    /// Returns [`None`] if the value is below the root of the stable swap
+    /// polynomial.
     fn newton_method_single_iteration(
         &self,
         initial_guess: &Decimal,
-    ) -> Result<Decimal> {
+    ) -> Result<Option<Decimal>> {
         let stable_swap_poly =
-            self.get_stable_swap_polynomial(initial_guess)?;
+            match self.get_stable_swap_polynomial(initial_guess)? {
+                Some(stable_swap_poly) => stable_swap_poly,
+                None => return Ok(None),
+            };
         let derivative_stable_swap_poly =
             self.get_derivate_stable_swap_polynomial(initial_guess)?;
 
         initial_guess
             .try_sub(stable_swap_poly.try_div(derivative_stable_swap_poly)?)
+            .map(Some)
     }
 
-    // Stable swap polynomial to be found in README.md under AMM - Equations
-    fn get_stable_swap_polynomial(&self, val: &Decimal) -> Result<Decimal> {
+    // Stable swap polynomial to be found in README.md under AMM - Equations.
+    // It's negative below the root, in which case we return [`None`].
+    fn get_stable_swap_polynomial(
+        &self,
+        val: &Decimal,
+    ) -> Result<Option<Decimal>> {
         // D^(n+1) + D(An^n -1)\prod_i x_i n^n + A(n^n)^2\sum_i x_i \prod_i x_i
         let first_term = val.try_pow(self.exponent + 1)?;
 
         let second_term = val.try_mul(self.first_order_coeff)?;
 
-        first_term
-            .try_add(second_term)?
-            .try_sub(self.polynomial_third_term)
+        let positive_terms = first_term.try_add(second_term)?;
+        if positive_terms < self.polynomial_third_term {
+            Ok(None)
+        } else {
+            positive_terms.try_sub(self.polynomial_third_term).map(Some)
+        }
     }
 
     // Derivative of stable swap polynomial to be found in README.md under AMM -
@@ -234,10 +277,22 @@ mod tests {
         let state =
             StableCurveInvariant::new(amp, &token_reserves_amount).unwrap();
 
-        let val: Decimal = 1u64.into();
+        let val = Decimal::from_scaled_val(u128::MAX);
         assert!(state.get_stable_swap_polynomial(&val).is_err());
     }
 
+    #[test]
+    fn stable_swap_polynomial_is_none_below_root() {
+        let amp = 2u64;
+        let token_reserves_amount: Vec<TokenAmount> =
+            vec![2u64.into(), 2u64.into(), 2u64.into()];
+        let state =
+            StableCurveInvariant::new(amp, &token_reserves_amount).unwrap();
+
+        let val: Decimal = 1u64.into();
+        assert_eq!(state.get_stable_swap_polynomial(&val).unwrap(), None);
+    }
+
     #[test]
     fn derivate_stable_swap_polynomial_fails_with_overflow() {
         let amp = 2u64.into();
@@ -402,7 +457,10 @@ mod tests {
         let val: Decimal = (110u64).into();
         let result = state.get_stable_swap_polynomial(&val).unwrap();
 
-        assert_eq!(result, Decimal::from_scaled_val(891000000000000000000000));
+        assert_eq!(
+            result,
+            Some(Decimal::from_scaled_val(891000000000000000000000))
+        );
     }
 
     #[test]
@@ -432,7 +490,7 @@ mod tests {
 
         assert_eq!(
             result,
-            Decimal::from_scaled_val(14366160000000000000000000000)
+            Some(Decimal::from_scaled_val(14366160000000000000000000000))
         );
     }
 
@@ -476,7 +534,10 @@ mod tests {
         let val: Decimal = (110u64).into();
         let result = state.newton_method_single_iteration(&val).unwrap();
 
-        assert_eq!(result, Decimal::from_scaled_val(105366614664586583464));
+        assert_eq!(
+            result,
+            Some(Decimal::from_scaled_val(105366614664586583464))
+        );
     }
 
     #[test]
@@ -490,7 +551,10 @@ mod tests {
         let val: Decimal = (360u64).into();
         let result = state.newton_method_single_iteration(&val).unwrap();
 
-        assert_eq!(result, Decimal::from_scaled_val(352825436207222027454));
+        assert_eq!(
+            result,
+            Some(Decimal::from_scaled_val(352825436207222027454))
+        );
     }
 
     #[test]
@@ -575,6 +639,108 @@ mod tests {
 
             assert!(compute(amp, &token_reserves_amount).is_ok());
         }
+
+        #[test]
+        fn integer_implementation_fails_on_same_reserves(
+            amp in 0..200u64,
+            reserves in collection::vec(
+                1..10_000_000_000_000_000u64,
+                2..=consts::MAX_RESERVES,
+            ),
+        ) {
+            let token_reserves_amount: Vec<_> =
+                reserves.iter().copied().map(TokenAmount::new).collect();
+
+            let decimal = compute(amp, &token_reserves_amount);
+            let down = stable_curve_invariant_int::compute(
+                amp,
+                &token_reserves_amount,
+                stable_curve_invariant_int::Rounding::Down,
+            );
+            let up = stable_curve_invariant_int::compute(
+                amp,
+                &token_reserves_amount,
+                stable_curve_invariant_int::Rounding::Up,
+            );
+
+            match (decimal, down, up) {
+                (Ok(decimal), Ok(down), Ok(up)) => {
+                    prop_assert!(down <= up && up - down <= 1);
+
+                    let decimal: u64 = decimal.try_floor().unwrap();
+                    let decimal = decimal as u128;
+                    let max = *reserves.iter().max().unwrap();
+                    let min = *reserves.iter().min().unwrap();
+                    if max <= min * 10 {
+                        prop_assert!(
+                            decimal <= down + 1 && down <= decimal + 1,
+                            "decimal {} vs integer {}", decimal, down
+                        );
+                    } else {
+                        // the scaled down product of an imbalanced pool has
+                        // few significant digits in decimal, which makes the
+                        // decimal invariant lower, but never higher
+                        prop_assert!(
+                            decimal <= down + 1,
+                            "decimal {} vs integer {}", decimal, down
+                        );
+                    }
+                }
+                (Err(decimal), Err(down), Err(up)) => {
+                    let decimal = error_variant(&decimal);
+                    prop_assert!(decimal.is_some());
+                    prop_assert_eq!(decimal, error_variant(&down));
+                    prop_assert_eq!(decimal, error_variant(&up));
+                }
+                (decimal, down, up) => prop_assert!(
+                    false,
+                    "decimal {:?} vs integer {:?} and {:?}", decimal, down, up
+                ),
+            }
+        }
+
+        #[test]
+        fn integer_implementation_agrees_on_balanced_reserves(
+            amp in 1..200u64,
+            base in 1_000..5_000_000_000_000_000u64,
+            permille in collection::vec(0..1_000u64, 2..=consts::MAX_RESERVES),
+        ) {
+            let token_reserves_amount: Vec<_> = permille
+                .into_iter()
+                .map(|p| TokenAmount::new(base + base / 1_000 * p))
+                .collect();
+
+            let decimal: u64 = compute(amp, &token_reserves_amount)
+                .unwrap()
+                .try_floor()
+                .unwrap();
+            let decimal = decimal as u128;
+            let down = stable_curve_invariant_int::compute(
+                amp,
+                &token_reserves_amount,
+                stable_curve_invariant_int::Rounding::Down,
+            )
+            .unwrap();
+
+            prop_assert!(
+                decimal <= down + 1 && down <= decimal + 1,
+                "decimal {} vs integer {}", decimal, down
+            );
+        }
+    }
+
+    /// Both implementations should fail with one of these.
+    fn error_variant(err: &Error) -> Option<&'static str> {
+        let err = err.to_string();
+        [
+            "InvalidArg",
+            "TooFewReserves",
+            "StableCurveImbalance",
+            "MathOverflow",
+            "InvariantViolation",
+        ]
+        .into_iter()
+        .find(|variant| err.contains(variant))
     }
 
     #[test]
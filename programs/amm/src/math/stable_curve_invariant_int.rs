@@ -0,0 +1,318 @@
+//! Computes the same invariant as [`super::stable_curve_invariant`], but on
+//! plain integers instead of [`Decimal`]. There's no scaling down of the
+//! reserves and no fractional powers, hence the Newton-Raphson method costs
+//! fewer compute units, especially on pools with four reserves.
+//!
+//! The invariant of the stable curve is rarely an integer. The caller decides
+//! with [`Rounding`] whether the result is the largest integer below the exact
+//! invariant or the smallest integer above it. We always round against the
+//! user, i.e. in favour of the pool.
+//!
+//! Each Newton-Raphson iteration is written in terms of
+//!
+//! ```text
+//! D_P = D^(n+1) / (n^n prod_i x_i)
+//! D' = (A n^n sum_i x_i + n D_P) D / ((A n^n - 1) D + (n + 1) D_P)
+//! ```
+//!
+//! which is the stable swap polynomial from README.md under AMM - Equations
+//! divided by `n^n prod_i x_i`.
+
+use crate::prelude::*;
+use std::cmp::Ordering;
+
+pub use uints::U512;
+
+/// Starting from the sum of reserves, the method first decreases by a factor
+/// of about `n / (n + 1)` per iteration and then converges quadratically. The
+/// most imbalanced reserves which [`super::stable_curve_invariant`] accepts
+/// need fewer than 50 iterations.
+const MAX_ITERATIONS: usize = 64;
+
+/// Direction in which the invariant is rounded to an integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// The largest integer which is less than or equal to the invariant.
+    Down,
+    /// The smallest integer which is greater than or equal to the invariant.
+    Up,
+}
+
+/// Given at least two reserves, fails on the same reserves as
+/// [`super::stable_curve_invariant::compute`].
+pub fn compute(
+    amp: u64,
+    token_reserves_amount: &[TokenAmount],
+    rounding: Rounding,
+) -> Result<u128> {
+    if amp == 0 {
+        msg!("Input value of amplifier is zero, reduces to constant product curve case");
+        return Err(error!(AmmError::InvalidArg));
+    }
+
+    if token_reserves_amount.len() < 2 {
+        return Err(error!(AmmError::TooFewReserves));
+    }
+
+    // the product of reserves would be zero, which is the ultimate imbalance
+    if token_reserves_amount.iter().any(|r| r.amount == 0) {
+        return Err(error!(AmmError::StableCurveImbalance));
+    }
+
+    super::stable_curve_invariant::check_balance(token_reserves_amount)?;
+
+    let state = StableCurveInvariant::new(amp, token_reserves_amount)?;
+    let approximation = state.newton_method()?;
+    let invariant = state.round(approximation, rounding)?;
+
+    if invariant > U512::from(u128::MAX) {
+        return Err(error!(AmmError::MathOverflow));
+    }
+
+    Ok(invariant.low_u128())
+}
+
+/// All values fit into 512 bits with four reserves of `u64::MAX` tokens, as
+/// the invariant is at most the sum of reserves.
+struct StableCurveInvariant {
+    // number of reserves
+    n: U512,
+    // amplifier * n^n
+    ann: U512,
+    // sum of reserves, also the initial guess for Newton's method
+    sum: U512,
+    // n^n * prod_i x_i
+    n_pow_n_product: U512,
+}
+
+impl StableCurveInvariant {
+    fn new(amp: u64, reserves: &[TokenAmount]) -> Result<Self> {
+        let n = U512::from(reserves.len());
+        let n_pow_n = n.checked_pow(n).ok_or(AmmError::MathOverflow)?;
+        let ann = U512::from(amp)
+            .checked_mul(n_pow_n)
+            .ok_or(AmmError::MathOverflow)?;
+        let sum = reserves.iter().try_fold(U512::zero(), |acc, r| {
+            acc.checked_add(U512::from(r.amount))
+                .ok_or(AmmError::MathOverflow)
+        })?;
+        let n_pow_n_product = reserves.iter().try_fold(n_pow_n, |acc, r| {
+            acc.checked_mul(U512::from(r.amount))
+                .ok_or(AmmError::MathOverflow)
+        })?;
+
+        Ok(Self {
+            n,
+            ann,
+            sum,
+            n_pow_n_product,
+        })
+    }
+
+    /// Approximates the invariant from above, up to a unit or two. The
+    /// direction of the error is not known, see [`Self::round`].
+    fn newton_method(&self) -> Result<U512> {
+        let mut d = self.sum;
+
+        for _ in 0..MAX_ITERATIONS {
+            let prev_d = d;
+            d = self.newton_method_single_iteration(d)?;
+
+            let diff = if d > prev_d { d - prev_d } else { prev_d - d };
+            if diff <= U512::one() {
+                return Ok(d);
+            }
+        }
+
+        msg!("Newton's method did not converge");
+        Err(error!(AmmError::InvariantViolation))
+    }
+
+    fn newton_method_single_iteration(&self, d: U512) -> Result<U512> {
+        let d_p = self.d_p(d)?;
+
+        // (A n^n sum_i x_i + n D_P) D
+        let numerator = self
+            .ann
+            .checked_mul(self.sum)
+            .and_then(|ann_sum| ann_sum.checked_add(self.n.checked_mul(d_p)?))
+            .and_then(|v| v.checked_mul(d))
+            .ok_or(AmmError::MathOverflow)?;
+        // (A n^n - 1) D + (n + 1) D_P
+        let denominator = (self.ann - U512::one())
+            .checked_mul(d)
+            .and_then(|v| {
+                v.checked_add((self.n + U512::one()).checked_mul(d_p)?)
+            })
+            .ok_or(AmmError::MathOverflow)?;
+
+        numerator
+            .checked_div(denominator)
+            .ok_or_else(|| error!(AmmError::MathOverflow))
+    }
+
+    /// `D^(n+1) / (n^n prod_i x_i)` rounded down. Dividing once keeps the
+    /// method precise even if the pool is imbalanced.
+    fn d_p(&self, d: U512) -> Result<U512> {
+        d.checked_pow(self.n + U512::one())
+            .and_then(|d_pow| d_pow.checked_div(self.n_pow_n_product))
+            .ok_or_else(|| error!(AmmError::MathOverflow))
+    }
+
+    /// Moves the approximation to the nearest integer on the requested side
+    /// of the exact invariant.
+    fn round(&self, mut d: U512, rounding: Rounding) -> Result<U512> {
+        // the approximation is off by few units at most, so each loop makes
+        // just a couple of steps
+        let mut steps = 0;
+        let mut step = || {
+            steps += 1;
+            if steps > MAX_ITERATIONS {
+                msg!("Cannot round the stable curve invariant");
+                Err(error!(AmmError::InvariantViolation))
+            } else {
+                Ok(())
+            }
+        };
+
+        match rounding {
+            Rounding::Down => {
+                while self.cmp_to_invariant(d)? == Ordering::Greater {
+                    step()?;
+                    d -= U512::one();
+                }
+                while self.cmp_to_invariant(d + U512::one())?
+                    != Ordering::Greater
+                {
+                    step()?;
+                    d += U512::one();
+                }
+            }
+            Rounding::Up => {
+                while self.cmp_to_invariant(d)? == Ordering::Less {
+                    step()?;
+                    d += U512::one();
+                }
+                while !d.is_zero()
+                    && self.cmp_to_invariant(d - U512::one())? != Ordering::Less
+                {
+                    step()?;
+                    d -= U512::one();
+                }
+            }
+        }
+
+        Ok(d)
+    }
+
+    /// Compares `d` to the exact invariant without any rounding.
+    ///
+    /// The stable swap polynomial is increasing for positive `d`, therefore
+    /// its sign tells us on which side of its positive root `d` is. We
+    /// evaluate
+    ///
+    /// `D^(n+1) + (A n^n - 1) D n^n prod_i x_i - A n^n sum_i x_i n^n prod_i
+    /// x_i`
+    fn cmp_to_invariant(&self, d: U512) -> Result<Ordering> {
+        let lhs = d
+            .checked_pow(self.n + U512::one())
+            .and_then(|d_pow| {
+                let linear = (self.ann - U512::one())
+                    .checked_mul(d)?
+                    .checked_mul(self.n_pow_n_product)?;
+                d_pow.checked_add(linear)
+            })
+            .ok_or(AmmError::MathOverflow)?;
+        let rhs = self
+            .ann
+            .checked_mul(self.sum)
+            .and_then(|v| v.checked_mul(self.n_pow_n_product))
+            .ok_or(AmmError::MathOverflow)?;
+
+        Ok(lhs.cmp(&rhs))
+    }
+}
+
+#[allow(
+    clippy::assign_op_pattern,
+    clippy::ptr_offset_with_cast,
+    clippy::manual_range_contains
+)]
+mod uints {
+    uint::construct_uint! {
+        pub struct U512(8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fails_if_amplifier_is_zero() {
+        let token_reserves_amount = [100u64.into(), 10u64.into()];
+
+        assert!(compute(0, &token_reserves_amount, Rounding::Down)
+            .unwrap_err()
+            .to_string()
+            .contains("InvalidArg"));
+    }
+
+    #[test]
+    fn fails_if_reserve_is_zero() {
+        let token_reserves_amount = [100u64.into(), 0u64.into()];
+
+        assert!(compute(10, &token_reserves_amount, Rounding::Down)
+            .unwrap_err()
+            .to_string()
+            .contains("StableCurveImbalance"));
+    }
+
+    #[test]
+    fn it_equals_sum_of_balanced_reserves() -> Result<()> {
+        for n in 2..=consts::MAX_RESERVES {
+            for amount in [1u64, 1_000, 8_374_498_120_012, u64::MAX / 4] {
+                let token_reserves_amount = vec![TokenAmount::new(amount); n];
+                let sum = amount as u128 * n as u128;
+
+                for rounding in [Rounding::Down, Rounding::Up] {
+                    assert_eq!(
+                        compute(100, &token_reserves_amount, rounding)?,
+                        sum
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rounds_in_requested_direction() -> Result<()> {
+        let token_reserves_amount = [
+            TokenAmount::new(323_937_059_261_502),
+            TokenAmount::new(307_818_470_989_694),
+            TokenAmount::new(409_053_424_216_126),
+        ];
+
+        let down = compute(36, &token_reserves_amount, Rounding::Down)?;
+        let up = compute(36, &token_reserves_amount, Rounding::Up)?;
+        assert_eq!(up, down + 1);
+
+        let state = StableCurveInvariant::new(36, &token_reserves_amount)?;
+        assert_eq!(state.cmp_to_invariant(U512::from(down))?, Ordering::Less);
+        assert_eq!(state.cmp_to_invariant(U512::from(up))?, Ordering::Greater);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_works_for_large_numbers_with_four_reserves() -> Result<()> {
+        let token_reserves_amount = [TokenAmount::new(u64::MAX); 4];
+
+        let invariant = compute(1_000, &token_reserves_amount, Rounding::Down)?;
+        assert_eq!(invariant, u64::MAX as u128 * 4);
+
+        Ok(())
+    }
+}
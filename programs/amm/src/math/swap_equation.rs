@@ -47,11 +47,7 @@ pub fn get_buy_reserve_balance_after_swap(
     // we don't allow trades in which the product is infinitesimally close
     // to zero, as this means extreme imbalance on a stable swap pool
     if product < Decimal::from_scaled_val(1_000_000) {
-        msg!(
-            "invalid trade, it creates extreme imbalance \
-            on current stable pool"
-        );
-        return Err(error!(AmmError::MathOverflow));
+        return Err(error!(AmmError::StableCurveImbalance));
     }
 
     let sum = sum.try_div(Decimal::from(1000u64.pow(exp)))?;
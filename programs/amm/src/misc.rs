@@ -1,5 +1,6 @@
 use crate::prelude::*;
-use anchor_spl::token::Mint;
+use anchor_spl::token::{Mint, TokenAccount};
+use std::collections::BTreeSet;
 
 /// Reloads the mint, gets supply and prints it in a predictable way which
 /// can then be parsed from tx logs.
@@ -11,3 +12,38 @@ pub fn print_lp_supply(lp_mint: &mut Account<Mint>) -> Result<()> {
 
     Ok(())
 }
+
+/// Deserializes the pool's vaults from given accounts. There must be exactly
+/// one vault for each reserve, in any order.
+pub fn load_reserve_vaults<'info>(
+    pool: &Pool,
+    accounts: &[AccountInfo<'info>],
+) -> Result<Vec<Account<'info, TokenAccount>>> {
+    let expected_len = pool.dimension as usize;
+    if accounts.len() != expected_len {
+        return Err(error!(err::acc(format!(
+            "The remaining accs must be of length {}",
+            expected_len
+        ))));
+    }
+
+    let mut vault_keys = BTreeSet::new();
+    accounts
+        .iter()
+        .map(|vault_info| {
+            let vault = Account::<TokenAccount>::try_from(vault_info)?;
+
+            if pool.reserve_vault(vault.mint) != Some(vault.key()) {
+                return Err(error!(err::acc(format!(
+                    "Vault '{}' is not reserve's vault",
+                    vault.key()
+                ))));
+            }
+            if !vault_keys.insert(vault.key()) {
+                return Err(error!(err::acc("Duplicate vault")));
+            }
+
+            Ok(vault)
+        })
+        .collect()
+}
@@ -1,108 +1,46 @@
 pub mod discount;
 pub mod pool;
+pub mod pool_access;
+#[cfg(test)]
+mod pool_simulation;
+pub mod pool_stats;
+pub mod pool_v1;
+pub mod program_config;
 pub mod program_toll;
+pub mod referrer;
+pub mod twamm_order;
 
+pub use common::{Permillion, Slot, TokenAmount};
 pub use discount::*;
 pub use pool::*;
+pub use pool_access::*;
+pub use pool_stats::*;
+pub use pool_v1::*;
+pub use program_config::*;
 pub use program_toll::*;
+pub use referrer::*;
+pub use twamm_order::*;
 
-use crate::prelude::*;
+// Anchor's IDL parser reads only this crate's sources, hence it wouldn't find
+// the layouts of the shared types re-exported above. The parser doesn't
+// evaluate `cfg` attributes, so it reads these copies, which are never
+// compiled. They must match the definitions in the `common` crate.
 
-#[derive(
-    AnchorDeserialize,
-    AnchorSerialize,
-    Clone,
-    Copy,
-    Debug,
-    Default,
-    Eq,
-    PartialEq,
-    Ord,
-    PartialOrd,
-)]
+#[cfg(any())]
+#[derive(AnchorDeserialize, AnchorSerialize)]
 pub struct TokenAmount {
     pub amount: u64,
 }
 
-#[derive(
-    AnchorDeserialize,
-    AnchorSerialize,
-    Clone,
-    Copy,
-    Debug,
-    Default,
-    Eq,
-    PartialEq,
-    Ord,
-    PartialOrd,
-)]
+#[cfg(any())]
+#[derive(AnchorDeserialize, AnchorSerialize)]
 pub struct Slot {
     pub slot: u64,
 }
 
-#[derive(
-    AnchorDeserialize,
-    AnchorSerialize,
-    Clone,
-    Copy,
-    Debug,
-    Default,
-    Eq,
-    PartialEq,
-    Ord,
-    PartialOrd,
-)]
+#[cfg(any())]
+#[derive(AnchorDeserialize, AnchorSerialize)]
 pub struct Permillion {
     /// 1% = 10_000
     pub permillion: u64,
 }
-
-impl TokenAmount {
-    pub fn new(amount: u64) -> Self {
-        Self { amount }
-    }
-
-    pub fn max_value() -> Self {
-        Self {
-            amount: std::u64::MAX,
-        }
-    }
-}
-
-impl Slot {
-    pub fn new(slot: u64) -> Self {
-        Self { slot }
-    }
-
-    pub fn current() -> Result<Self> {
-        Ok(Self {
-            slot: Clock::get()?.slot,
-        })
-    }
-}
-
-impl Permillion {
-    pub fn from_percent(percent: u64) -> Self {
-        Self {
-            permillion: percent.checked_mul(10_000).unwrap(),
-        }
-    }
-}
-
-impl From<TokenAmount> for Decimal {
-    fn from(tokens: TokenAmount) -> Self {
-        Decimal::from(tokens.amount)
-    }
-}
-
-impl From<Permillion> for Decimal {
-    fn from(permillion: Permillion) -> Self {
-        Decimal::from_permillion(permillion.permillion)
-    }
-}
-
-impl From<u64> for TokenAmount {
-    fn from(amount: u64) -> Self {
-        Self { amount }
-    }
-}
@@ -10,9 +10,18 @@ use crate::prelude::*;
 /// A singleton discount settings model enables us to configure some parts of
 /// the discounts feature.
 #[account]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct DiscountSettings {
     /// This signer is can call the [`crate::endpoints::put_discount`]
     /// endpoint.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub authority: Pubkey,
 }
 
@@ -22,6 +31,11 @@ pub struct DiscountSettings {
 /// account in which we store this structure.
 #[account]
 #[derive(Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Discount {
     /// What's the discount the user is eligible for. The discount applies to
     /// the fee, ie. if the amount is `Permillion { permillion: 500_000 }`,
@@ -6,17 +6,41 @@ use crate::prelude::*;
 use std::collections::BTreeMap;
 use std::mem;
 
+/// See [`Pool::calculate_zap_in_swap`].
+const MAX_ZAP_IN_BISECTIONS: usize = 16;
+
 #[derive(Default, Debug, Eq, PartialEq)]
 #[account]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Pool {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub admin: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub signer: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub mint: Pubkey,
     /// The swap fee is divided into a part that goes to the program's owner
     /// and part that goes to liquidity providers via increase in LP token
     /// worth.
     ///
     /// The destination for the former is defined by this key.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub program_toll_wallet: Pubkey,
     /// How many reserves in the `reserves` array are initialized (ie. not
     /// having mint of [`Pubkey::default`]).
@@ -31,14 +55,100 @@ pub struct Pool {
     pub reserves: [Reserve; 4],
     pub curve: Curve,
     pub swap_fee: Permillion,
+    /// Layout version of the account, see [`Pool::VERSION`]. Pools created
+    /// before the layout was versioned are of version 1, see [`PoolV1`].
+    pub version: u8,
+    /// The admin schedules swap fee changes with
+    /// [`crate::endpoints::propose_swap_fee`]. Once the effective slot is
+    /// reached, the next swap applies the new fee.
+    pub pending_swap_fee: Option<PendingSwapFee>,
+    /// If set, only users who were granted a [`PoolAccess`] by this authority
+    /// can swap and deposit liquidity. See the
+    /// [`crate::models::pool_access`] module.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::option_pubkey")
+    )]
+    pub access_policy: Option<Pubkey>,
+    /// New properties take their space from the end of this array, so that
+    /// adding them doesn't require reallocating the pool accounts. Bump
+    /// [`Pool::VERSION`] when doing so.
+    pub reserved: [u64; 16],
 }
 
 #[derive(
     AnchorDeserialize, AnchorSerialize, Copy, Clone, Debug, Eq, PartialEq,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub enum Curve {
     ConstProd,
-    Stable { amplifier: u64, invariant: SDecimal },
+    Stable {
+        amplifier: u64,
+        invariant: SDecimal,
+    },
+    /// Weighted product of two reserves, used for liquidity bootstrapping.
+    /// The weight of the first reserve moves linearly from `start_weight` to
+    /// `end_weight` between the `start` and `end` slots, and the weight of the
+    /// second reserve is the complement. See [`Curve::first_reserve_weight`].
+    ///
+    /// Until the `end` slot, only the pool's admin can deposit liquidity and
+    /// nobody can redeem it.
+    Weighted {
+        start_weight: Permillion,
+        end_weight: Permillion,
+        start: Slot,
+        end: Slot,
+    },
+}
+
+/// How [`Pool::update_curve_invariant_with`] computes the invariant of
+/// [`Curve::Stable`]. Both methods fail on the same reserves.
+#[derive(
+    AnchorDeserialize, AnchorSerialize, Copy, Clone, Debug, Eq, PartialEq,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum StableCurveInvariantMethod {
+    /// [`math::stable_curve_invariant::compute`], which approximates the
+    /// invariant from above.
+    Decimal,
+    /// [`math::stable_curve_invariant_int::compute`] rounded up, which costs
+    /// fewer compute units.
+    Integer,
+}
+
+impl Default for StableCurveInvariantMethod {
+    fn default() -> Self {
+        Self::Decimal
+    }
+}
+
+#[derive(
+    AnchorDeserialize,
+    AnchorSerialize,
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Default,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct PendingSwapFee {
+    pub fee: Permillion,
+    /// From this slot on (inclusive) the fee applies.
+    pub effective_at: Slot,
 }
 
 #[derive(
@@ -51,9 +161,22 @@ pub enum Curve {
     PartialEq,
     Default,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Reserve {
     pub tokens: TokenAmount,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub mint: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub vault: Pubkey,
 }
 
@@ -67,11 +190,49 @@ pub struct Reserve {
     Eq,
     PartialEq,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct TokenLimit {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub mint: Pubkey,
     pub tokens: TokenAmount,
 }
 
+/// The admin sets the price of a new reserve's mint by stating how many of its
+/// tokens are worth given amount of tokens of an existing reserve.
+#[derive(
+    AnchorDeserialize,
+    AnchorSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct ReservePrice {
+    /// Mint of an existing reserve.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub reference_mint: Pubkey,
+    pub reference_tokens: TokenAmount,
+    /// Tokens of the new reserve's mint which are worth `reference_tokens`.
+    pub tokens: TokenAmount,
+}
+
 #[derive(Debug, Eq, PartialEq, Default)]
 pub struct DepositResult {
     /// # Important
@@ -87,6 +248,17 @@ pub struct RedeemResult {
     pub tokens_to_redeem: BTreeMap<Pubkey, TokenAmount>,
 }
 
+/// See [`Pool::swap_with_fee`].
+#[derive(Debug, Eq, PartialEq, Default)]
+pub struct SwapResult {
+    pub bought: TokenAmount,
+    /// Part of the sold tokens which stayed in the sell reserve.
+    pub swap_fee: TokenAmount,
+    /// LP tokens to mint to the program toll wallet. [`None`] if the toll is
+    /// too small to be represented in LP tokens.
+    pub toll_in_lp_tokens: Option<TokenAmount>,
+}
+
 impl Default for Curve {
     fn default() -> Self {
         Curve::ConstProd
@@ -95,46 +267,81 @@ impl Default for Curve {
 
 impl Reserve {
     pub fn add_tokens(&mut self, tokens: TokenAmount) -> Result<()> {
-        self.tokens = TokenAmount::new(
-            self.tokens
-                .amount
-                .checked_add(tokens.amount)
-                .ok_or_else(|| {
-                    msg!("Reserves cannot hold more than u64 amount of tokens");
-                    AmmError::MathOverflow
-                })?,
-        );
+        self.tokens = self.tokens.checked_add(tokens).ok_or_else(|| {
+            msg!("Reserves cannot hold more than u64 amount of tokens");
+            AmmError::MathOverflow
+        })?;
 
         Ok(())
     }
 
     pub fn remove_tokens(&mut self, tokens: TokenAmount) -> Result<()> {
-        self.tokens = TokenAmount::new(
-            self.tokens
-                .amount
-                .checked_sub(tokens.amount)
-                .ok_or_else(|| {
-                    msg!("Reserves cannot less than 0 tokens");
-                    AmmError::MathOverflow
-                })?,
-        );
+        self.tokens = self.tokens.checked_sub(tokens).ok_or_else(|| {
+            msg!("Reserves cannot less than 0 tokens");
+            AmmError::MathOverflow
+        })?;
 
         Ok(())
     }
+
+    /// How many tokens are in the reserve's vault on top of what the reserve
+    /// tracks. Returns [`None`] if the vault holds fewer tokens than the
+    /// reserve, ie. there's a deficit.
+    pub fn surplus(&self, vault_amount: TokenAmount) -> Option<TokenAmount> {
+        vault_amount.checked_sub(self.tokens)
+    }
 }
 
 impl Curve {
     pub fn invariant(&self) -> Option<Decimal> {
         match self {
-            Curve::ConstProd => None,
+            Curve::ConstProd | Curve::Weighted { .. } => None,
             Curve::Stable { invariant, .. } => Some(Decimal::from(*invariant)),
         }
     }
+
+    /// For [`Curve::Weighted`] returns the weight of the first reserve at
+    /// given slot. Before the `start` slot it's the start weight, after the
+    /// `end` slot it's the end weight, and in between it's interpolated
+    /// linearly.
+    pub fn first_reserve_weight(&self, now: Slot) -> Option<Permillion> {
+        match *self {
+            Curve::Weighted {
+                start_weight,
+                end_weight,
+                start,
+                end,
+            } => {
+                let permillion = if now <= start {
+                    start_weight.permillion
+                } else if now >= end {
+                    end_weight.permillion
+                } else {
+                    let elapsed = (now.slot - start.slot) as i128;
+                    let duration = (end.slot - start.slot) as i128;
+                    let change = end_weight.permillion as i128
+                        - start_weight.permillion as i128;
+
+                    // lies between the start and end weights, hence fits u64
+                    (start_weight.permillion as i128
+                        + change * elapsed / duration)
+                        as u64
+                };
+
+                Some(Permillion { permillion })
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Pool {
     pub const SIGNER_PDA_PREFIX: &'static [u8; 6] = b"signer";
 
+    /// Pools of older versions are upgraded to this version with
+    /// [`crate::endpoints::migrate_pool`].
+    pub const VERSION: u8 = 2;
+
     pub fn space() -> usize {
         let discriminant = 8;
         let initializer = 32;
@@ -145,6 +352,10 @@ impl Pool {
         let reserves = mem::size_of::<Reserve>() * 4;
         let curve = mem::size_of::<Curve>();
         let fee = mem::size_of::<Permillion>();
+        let version = 1;
+        let pending_fee = 1 + mem::size_of::<PendingSwapFee>();
+        let access_policy = 1 + 32;
+        let reserved = mem::size_of::<[u64; 16]>();
 
         discriminant
             + initializer
@@ -155,6 +366,80 @@ impl Pool {
             + reserves
             + curve
             + fee
+            + version
+            + pending_fee
+            + access_policy
+            + reserved
+    }
+
+    /// Unlike [`Pool::try_deserialize`], which expects the current layout,
+    /// this method also accepts pools of older versions which haven't been
+    /// migrated yet. Those are recognized by the size of the account data.
+    ///
+    /// The returned pool keeps the version it's been stored with.
+    pub fn try_deserialize_any_version(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() == PoolV1::space() {
+            PoolV1::try_deserialize(buf).map(Self::from)
+        } else {
+            Self::try_deserialize(buf)
+        }
+    }
+
+    /// The earliest slot at which a swap fee change proposed at the current
+    /// slot can become effective.
+    pub fn earliest_swap_fee_change(current_slot: Slot) -> Result<Slot> {
+        let slot = current_slot
+            .slot
+            .checked_add(consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS)
+            .ok_or(AmmError::MathOverflow)?;
+
+        Ok(Slot::new(slot))
+    }
+
+    /// Schedules a swap fee change which is applied by
+    /// [`Pool::apply_pending_swap_fee`] once effective. A new proposal
+    /// overwrites a pending one.
+    pub fn propose_swap_fee(
+        &mut self,
+        fee: Permillion,
+        max_fee: Permillion,
+        effective_at: Slot,
+        current_slot: Slot,
+    ) -> Result<()> {
+        if fee > max_fee {
+            return Err(error!(err::arg(format!(
+                "Maximum fee can be {} permillion",
+                max_fee.permillion
+            ))));
+        }
+
+        let earliest_effective_at =
+            Self::earliest_swap_fee_change(current_slot)?;
+        if effective_at < earliest_effective_at {
+            return Err(error!(err::arg(format!(
+                "Fee change can be effective at the earliest at slot {}",
+                earliest_effective_at.slot
+            ))));
+        }
+
+        self.pending_swap_fee = Some(PendingSwapFee { fee, effective_at });
+
+        Ok(())
+    }
+
+    /// If there's a scheduled swap fee change which is due, the new fee is
+    /// applied and the schedule is cleared. Returns whether the fee changed.
+    pub fn apply_pending_swap_fee(&mut self, current_slot: Slot) -> bool {
+        match self.pending_swap_fee {
+            Some(PendingSwapFee { fee, effective_at })
+                if effective_at <= current_slot =>
+            {
+                self.swap_fee = fee;
+                self.pending_swap_fee = None;
+                true
+            }
+            _ => false,
+        }
     }
 
     /// Returns only reserves which are initialized, ie. this would return
@@ -186,6 +471,139 @@ impl Pool {
         reserves.iter().map(|r| (r.mint, r.tokens)).collect()
     }
 
+    /// Appends a new reserve to the pool. The reserve starts with given amount
+    /// of tokens.
+    pub fn add_reserve(
+        &mut self,
+        mint: Pubkey,
+        vault: Pubkey,
+        tokens: TokenAmount,
+    ) -> Result<()> {
+        if self.dimension as usize >= consts::MAX_RESERVES {
+            return Err(error!(AmmError::TooManyReserves));
+        }
+        if matches!(self.curve, Curve::Weighted { .. }) {
+            return Err(error!(err::acc(
+                "Weighted pool must have exactly 2 reserves"
+            )));
+        }
+        if self.reserves().iter().any(|r| r.mint == mint) {
+            return Err(error!(AmmError::DuplicateReserve));
+        }
+
+        self.reserves[self.dimension as usize] = Reserve {
+            tokens,
+            mint,
+            vault,
+        };
+        self.dimension += 1;
+
+        Ok(())
+    }
+
+    /// Removes the reserve of given mint from the pool. Only a reserve with
+    /// zero balance can be removed. The reserves after it are shifted so that
+    /// the initialized reserves stay at the beginning of the array.
+    pub fn remove_reserve(&mut self, mint: Pubkey) -> Result<Reserve> {
+        let index = self
+            .reserves()
+            .iter()
+            .position(|r| r.mint == mint)
+            .ok_or(AmmError::InvalidTokenMints)?;
+
+        if self.dimension <= 2 {
+            return Err(error!(AmmError::TooFewReserves));
+        }
+
+        let reserve = self.reserves[index];
+        if reserve.tokens.amount != 0 {
+            return Err(error!(AmmError::ReserveNotEmpty));
+        }
+
+        self.reserves_mut()[index..].rotate_left(1);
+        self.dimension -= 1;
+        self.reserves[self.dimension as usize] = Reserve::default();
+
+        Ok(reserve)
+    }
+
+    /// How many tokens of a new reserve's mint must be deposited so that the
+    /// new reserve is worth as much as each of the existing reserves. That's
+    /// what entitles the admin to `lp_supply / dimension` LP tokens. We ceil
+    /// the result as the admin is the one paying.
+    ///
+    /// In a constant product pool each reserve is worth the same at the pool's
+    /// price, so the seed is the reference reserve converted with the admin's
+    /// price. The price of a new mint cannot be verified on chain, but the
+    /// seed after rounding must be worth the reference reserve within
+    /// [`consts::MAX_SEED_VALUE_DEVIATION`]. Otherwise an extreme price would
+    /// round a dust seed up to a single token.
+    ///
+    /// In a stable pool all tokens are worth the same, so the seed is the mean
+    /// of the reserves, and the admin's price must be within the deviation
+    /// from parity.
+    pub fn seed_tokens(&self, price: ReservePrice) -> Result<TokenAmount> {
+        if price.reference_tokens.amount == 0 || price.tokens.amount == 0 {
+            return Err(error!(AmmError::ZeroTokenAmount));
+        }
+
+        let reference = self
+            .reserves()
+            .iter()
+            .find(|r| r.mint == price.reference_mint)
+            .ok_or(AmmError::InvalidTokenMints)?;
+
+        let max_deviation = Decimal::from(consts::MAX_SEED_VALUE_DEVIATION);
+        let tokens = match self.curve {
+            Curve::ConstProd => {
+                let tokens = try_mul_div(
+                    Decimal::from(reference.tokens),
+                    Decimal::from(price.tokens),
+                    Decimal::from(price.reference_tokens),
+                )?
+                .try_ceil()?;
+
+                // how many reference tokens is the rounded seed worth
+                let seed_value = try_mul_div(
+                    Decimal::from(tokens),
+                    Decimal::from(price.reference_tokens),
+                    Decimal::from(price.tokens),
+                )?;
+                let max_value = Decimal::from(reference.tokens)
+                    .try_mul(Decimal::one().try_add(max_deviation)?)?;
+                if seed_value > max_value {
+                    return Err(error!(AmmError::SeedValueOutOfBounds));
+                }
+
+                tokens
+            }
+            Curve::Stable { .. } => {
+                let parity = Decimal::from(price.tokens)
+                    .try_div(Decimal::from(price.reference_tokens))?;
+                let min_parity = Decimal::one().try_sub(max_deviation)?;
+                let max_parity = Decimal::one().try_add(max_deviation)?;
+                if parity < min_parity || parity > max_parity {
+                    return Err(error!(AmmError::SeedValueOutOfBounds));
+                }
+
+                let total = self.reserves().iter().try_fold(
+                    Decimal::zero(),
+                    |total, reserve| {
+                        total.try_add(Decimal::from(reserve.tokens))
+                    },
+                )?;
+                total.try_div(Decimal::from(self.dimension))?.try_ceil()?
+            }
+            Curve::Weighted { .. } => {
+                return Err(error!(err::acc(
+                    "Weighted pool must have exactly 2 reserves"
+                )));
+            }
+        };
+
+        Ok(TokenAmount::new(tokens))
+    }
+
     /// This method calculates the tokens to deposit out of a [`BTreeMap`] of
     /// max tokens available to deposit by the user. When the supply of lp
     /// tokens is zero, in other words, on the first deposit, the tokens to
@@ -200,15 +618,11 @@ impl Pool {
         lp_mint_supply: TokenAmount,
     ) -> Result<DepositResult> {
         if max_tokens.values().any(|v| v.amount == 0) {
-            return Err(error!(err::arg(
-                "Must deposit positive amount of tokens for each mint"
-            )));
+            return Err(error!(AmmError::ZeroTokenAmount));
         }
 
         if max_tokens.len() != self.dimension as usize {
-            return Err(error!(err::arg(
-                "Max tokens map does not match pool dimension"
-            )));
+            return Err(error!(AmmError::DimensionMismatch));
         }
 
         if self
@@ -216,9 +630,7 @@ impl Pool {
             .iter()
             .any(|r| !max_tokens.contains_key(&r.mint))
         {
-            return Err(error!(err::arg(
-                "Not all reserve mints are represented in the max tokens map"
-            )));
+            return Err(error!(AmmError::MissingMintInLimits));
         }
 
         let is_first_deposit = lp_mint_supply.amount == 0;
@@ -279,6 +691,34 @@ impl Pool {
         })
     }
 
+    /// Deposits as [`crate::endpoints::deposit_liquidity`] does, ie. on top of
+    /// [`Pool::deposit_tokens`] fails if the deposit is too small to be
+    /// represented in LP tokens and updates the curve invariant with the
+    /// configured method.
+    ///
+    /// Returns the LP tokens to mint and the tokens to deposit.
+    pub fn deposit_liquidity(
+        &mut self,
+        max_tokens: BTreeMap<Pubkey, TokenAmount>,
+        lp_mint_supply: TokenAmount,
+        config: &ProgramConfig,
+    ) -> Result<(TokenAmount, BTreeMap<Pubkey, TokenAmount>)> {
+        let DepositResult {
+            lp_tokens_to_distribute,
+            tokens_to_deposit,
+        } = self.deposit_tokens(max_tokens, lp_mint_supply)?;
+        let lp_tokens_to_distribute =
+            lp_tokens_to_distribute.ok_or_else(|| {
+                msg!("Provided liquidity is too small to be represented");
+                AmmError::InvalidArg
+            })?;
+
+        // no-op if const prod
+        self.update_curve_invariant_with(config.stable_curve_invariant)?;
+
+        Ok((lp_tokens_to_distribute, tokens_to_deposit))
+    }
+
     /// This method calculates the tokens to redeem out of a given amount of lp
     /// tokens the user is relinquishing back to the pool, to be burned. The
     /// user will also provide a [`BTreeMap`] of min tokens, which serves as a
@@ -294,22 +734,19 @@ impl Pool {
         lp_mint_supply: TokenAmount,
     ) -> Result<BTreeMap<Pubkey, TokenAmount>> {
         if lp_mint_supply.amount == 0 {
-            return Err(error!(err::arg(
-                "There are no lp tokens currently in supply."
-            )));
+            msg!("There are no lp tokens currently in supply");
+            return Err(error!(AmmError::InvalidLpTokenAmount));
         }
 
         if lp_tokens_to_burn > lp_mint_supply {
-            return Err(error!(err::arg(
-                "The amount of lp tokens to burn cannot \
-                surpass current supply."
-            )));
+            msg!(
+                "The amount of lp tokens to burn cannot surpass current supply"
+            );
+            return Err(error!(AmmError::InvalidLpTokenAmount));
         }
 
         if min_tokens.len() != self.dimension as usize {
-            return Err(error!(err::arg(
-                "Length of min tokens map does not match pool dimension"
-            )));
+            return Err(error!(AmmError::DimensionMismatch));
         }
 
         if self
@@ -317,9 +754,7 @@ impl Pool {
             .iter()
             .any(|r| !min_tokens.contains_key(&r.mint))
         {
-            return Err(error!(err::arg(
-                "Not all reserve mints are represented in the min tokens map"
-            )));
+            return Err(error!(AmmError::MissingMintInLimits));
         }
 
         let weight = Decimal::from(lp_tokens_to_burn.amount)
@@ -356,10 +791,7 @@ impl Pool {
             });
 
         if is_any_redeem_token_below_min_threshold {
-            return Err(error!(err::arg(
-                "The amount of tokens to be redeemed is below \
-                the min_tokens parameter for at least one of the reserves."
-            )));
+            return Err(error!(AmmError::RedeemBelowMinimum));
         }
 
         // mutate the Pool reserve balances
@@ -376,6 +808,25 @@ impl Pool {
         Ok(tokens_to_redeem)
     }
 
+    /// Redeems as [`crate::endpoints::redeem_liquidity`] does, ie. on top of
+    /// [`Pool::redeem_tokens`] updates the curve invariant with the configured
+    /// method.
+    pub fn redeem_liquidity(
+        &mut self,
+        min_tokens: BTreeMap<Pubkey, TokenAmount>,
+        lp_tokens_to_burn: TokenAmount,
+        lp_mint_supply: TokenAmount,
+        config: &ProgramConfig,
+    ) -> Result<BTreeMap<Pubkey, TokenAmount>> {
+        let tokens_to_redeem =
+            self.redeem_tokens(min_tokens, lp_tokens_to_burn, lp_mint_supply)?;
+
+        // no-op if const prod
+        self.update_curve_invariant_with(config.stable_curve_invariant)?;
+
+        Ok(tokens_to_redeem)
+    }
+
     /// Returns the ratio by which all token reserves need to be multiplied or
     /// divided, depending if the ratio is inverted or not, to arrive to the
     /// token deposit amounts.
@@ -487,10 +938,45 @@ impl Pool {
         })
     }
 
-    /// This is called after a deposit or redemption.
+    /// A pool with [`Curve::Weighted`] is bootstrapping until its `end` slot.
+    /// Meanwhile only the admin can deposit liquidity and nobody can redeem.
+    pub fn is_bootstrapping(&self, now: Slot) -> bool {
+        matches!(self.curve, Curve::Weighted { end, .. } if now < end)
+    }
+
+    /// For [`Curve::Weighted`] returns the weight of the reserve with given
+    /// mint at given slot.
+    pub fn reserve_weight(
+        &self,
+        mint: Pubkey,
+        now: Slot,
+    ) -> Option<Permillion> {
+        let first_reserve_weight = self.curve.first_reserve_weight(now)?;
+        let index = self.reserves().iter().position(|r| r.mint == mint)?;
+
+        Some(match index {
+            0 => first_reserve_weight,
+            _ => Permillion {
+                permillion: 1_000_000 - first_reserve_weight.permillion,
+            },
+        })
+    }
+
+    /// Same as [`Pool::update_curve_invariant_with`] with the default method.
+    /// Endpoints use the method from [`ProgramConfig`] instead, so that the
+    /// stored invariant doesn't depend on which endpoint touched the pool last.
     pub fn update_curve_invariant(&mut self) -> Result<()> {
+        self.update_curve_invariant_with(StableCurveInvariantMethod::Decimal)
+    }
+
+    /// This is called after every change to the reserves, i.e. swaps,
+    /// deposits, redemptions and changes to the set of reserves.
+    pub fn update_curve_invariant_with(
+        &mut self,
+        method: StableCurveInvariantMethod,
+    ) -> Result<()> {
         match self.curve {
-            Curve::ConstProd => (),
+            Curve::ConstProd | Curve::Weighted { .. } => (),
             Curve::Stable { amplifier, .. } => {
                 // need to recompute curve invariant, using Newton-Raphson
                 // approximation method
@@ -504,10 +990,27 @@ impl Pool {
                     // this can happen on redeem, when all tokens are withdrawn
                     Decimal::zero().into()
                 } else {
-                    math::stable_curve_invariant::compute(
-                        amplifier,
-                        &token_reserves_amount,
-                    )?
+                    match method {
+                        StableCurveInvariantMethod::Decimal => {
+                            math::stable_curve_invariant::compute(
+                                amplifier,
+                                &token_reserves_amount,
+                            )?
+                        }
+                        StableCurveInvariantMethod::Integer => {
+                            use math::stable_curve_invariant_int::{
+                                compute, Rounding,
+                            };
+
+                            // rounding up is in favour of the pool, because a
+                            // larger invariant leaves more in the buy reserve
+                            Decimal::from(compute(
+                                amplifier,
+                                &token_reserves_amount,
+                                Rounding::Up,
+                            )?)
+                        }
+                    }
                     .into()
                 };
 
@@ -554,15 +1057,18 @@ impl Pool {
     /// by the trader. The method then deducts the base amount and adds the
     /// quote amount from & to the reserves.
     ///
+    /// The weighted curve swaps with the weights at the slot `now`.
+    ///
     /// Returns how many tokens were given for the input `tokens_to_swap`.
     pub fn swap(
         &mut self,
         sell_mint: Pubkey,
         tokens_to_swap: TokenAmount,
         buy_mint: Pubkey,
+        now: Slot,
     ) -> Result<TokenAmount> {
         let receive_tokens =
-            self.calculate_swap(sell_mint, tokens_to_swap, buy_mint)?;
+            self.calculate_swap(sell_mint, tokens_to_swap, buy_mint, now)?;
 
         self.reserve_mut(sell_mint)
             .ok_or(AmmError::InvariantViolation)?
@@ -574,30 +1080,166 @@ impl Pool {
         Ok(receive_tokens)
     }
 
+    /// Swaps as [`crate::endpoints::swap`] does. The swap fee is the
+    /// `swap_fee_share` of the `sell` tokens, which is already discounted for
+    /// the user. It's not swapped but it stays in the sell reserve, and the
+    /// program toll is the configured share of it in LP tokens, see
+    /// [`Pool::swap_fee_share_in_lp_tokens`]. Then the curve invariant is
+    /// updated with the configured method.
+    ///
+    /// The caller mints the toll, ie. the `lp_mint_supply` doesn't include it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap_with_fee(
+        &mut self,
+        sell_mint: Pubkey,
+        sell: TokenAmount,
+        buy_mint: Pubkey,
+        swap_fee_share: Decimal,
+        lp_mint_supply: TokenAmount,
+        config: &ProgramConfig,
+        now: Slot,
+    ) -> Result<SwapResult> {
+        if sell.amount == 0 {
+            return Err(error!(AmmError::ZeroTokenAmount));
+        }
+
+        let swap_fee = Self::calculate_swap_fee(sell, swap_fee_share)?;
+
+        // swap fee is a fraction of the sell amount
+        let tokens_to_swap = TokenAmount::new(sell.amount - swap_fee.amount);
+        // this also updates the reserves' balances
+        let bought = self.swap(sell_mint, tokens_to_swap, buy_mint, now)?;
+        // We must explicitly update the pool's state as swap fee was subtracted
+        // from the sell amount. However, the swap fee should still be considered
+        // when depositing or withdrawing.
+        self.reserve_mut(sell_mint)
+            .ok_or(AmmError::InvariantViolation)? // checked by swap
+            .add_tokens(swap_fee)?;
+
+        let toll_in_lp_tokens = self.swap_fee_share_in_lp_tokens(
+            swap_fee,
+            config.program_toll_swap_fee_share,
+            sell_mint,
+            lp_mint_supply,
+        )?;
+
+        // we need to update the invariant because fees have been taken,
+        // therefore even though the invariant applies to swaps, it is changed
+        // during each trade because of the collected fees
+        //
+        // no-op if const prod
+        self.update_curve_invariant_with(config.stable_curve_invariant)?;
+
+        Ok(SwapResult {
+            bought,
+            swap_fee,
+            toll_in_lp_tokens,
+        })
+    }
+
+    /// `swap_fee = sell * swap_fee_share`, rounded up in favour of the pool.
+    /// This is the total swap fee, ie. liquidity providers fee + toll fee.
+    pub fn calculate_swap_fee(
+        sell: TokenAmount,
+        swap_fee_share: Decimal,
+    ) -> Result<TokenAmount> {
+        Ok(TokenAmount::new(
+            Decimal::from(sell.amount)
+                .try_mul(swap_fee_share)?
+                .try_ceil()?,
+        ))
+    }
+
+    /// How many LP tokens are worth the `share` of the `swap_fee`, which is in
+    /// tokens of the `sell_mint`. [`None`] if they're too few to be
+    /// represented.
+    ///
+    /// To find out how many LPs should we mint, we pretend to deposit to the
+    /// pool tokens which are in total worth the `swap_fee * share`.
+    pub fn swap_fee_share_in_lp_tokens(
+        &self,
+        swap_fee: TokenAmount,
+        share: Permillion,
+        sell_mint: Pubkey,
+        lp_mint_supply: TokenAmount,
+    ) -> Result<Option<TokenAmount>> {
+        let share_in_sell_tokens_divided_by_dimension = TokenAmount::new(
+            Decimal::from(swap_fee)
+                .try_mul(Decimal::from(share))?
+                // Since we will pretend to deposit this, we need to divide it
+                // by the number of reserves. The LPs we get from the fake call
+                // to [`Pool::deposit_tokens`] below will return LPs as in all
+                // the reserves were deposited to in the same ratio.
+                .try_div(Decimal::from(self.dimension))?
+                .try_round()?,
+        );
+
+        if share_in_sell_tokens_divided_by_dimension.amount == 0 {
+            return Ok(None);
+        }
+
+        let max_deposits: BTreeMap<_, _> = self
+            .reserves()
+            .iter()
+            .map(|r| {
+                (
+                    r.mint,
+                    if r.mint == sell_mint {
+                        // this is going to become the limiting factor
+                        share_in_sell_tokens_divided_by_dimension
+                    } else {
+                        // We don't care about how many tokens of the other
+                        // reserves are deposited, the limiting factor is the
+                        // sell tokens mint. The [`Pool::deposit_tokens`]
+                        // function ensures that ratios are preserved.
+                        TokenAmount::max_value()
+                    },
+                )
+            })
+            .collect();
+
+        // We make a fake call (by cloning [`Pool`]) to the
+        // [`Pool::deposit_tokens`] which would tell us that if we were to
+        // deposit tokens worth the share, we would get this many LPs. We don't
+        // actually deposit anything
+
+        let share_in_lp_tokens = self
+            // IMPORTANT: we don't actually want to deposit these tokens, we are
+            // just wondering how many LPs would they amount to if we deposited
+            // them
+            .clone()
+            .deposit_tokens(max_deposits, lp_mint_supply)?
+            .lp_tokens_to_distribute;
+
+        Ok(share_in_lp_tokens)
+    }
+
     /// Given the current state of the pool, how many buy tokens does the
     /// trader receive out of the quote tokens he/she is selling in the swap.
-    fn calculate_swap(
+    ///
+    /// Unlike [`Pool::swap`], this method doesn't change the reserves.
+    pub fn calculate_swap(
         &self,
         sell_mint: Pubkey,
         tokens_to_swap: TokenAmount,
         buy_mint: Pubkey,
+        now: Slot,
     ) -> Result<TokenAmount> {
         let reserves: BTreeMap<_, _> =
             self.reserves().iter().map(|r| (r.mint, r.tokens)).collect();
 
         if reserves.values().any(|v| v.amount == 0) {
-            msg!("Need to provide positive token reserves deposits");
-            return Err(error!(AmmError::InvalidArg));
+            return Err(error!(AmmError::ZeroReserve));
         }
 
         if !reserves.contains_key(&buy_mint) {
             msg!("Provided long (buy) token mint is invalid");
-            return Err(error!(AmmError::InvalidArg));
+            return Err(error!(AmmError::InvalidTokenMints));
         }
 
         if !reserves.contains_key(&sell_mint) {
             msg!("Provided short (sell) token mint is invalid");
-            return Err(error!(AmmError::InvalidArg));
+            return Err(error!(AmmError::InvalidTokenMints));
         }
 
         // checks if amount of short (sell) token to be swapped fits within
@@ -608,11 +1250,7 @@ impl Pool {
         // tokens amount is bigger than the amount in the pool that would
         // result in a buy tokens amount > to the amount of tokens in the pool
         if tokens_to_swap >= *reserves.get(&sell_mint).unwrap() {
-            msg!(
-                "The user tries to swap the total amount of a single
-                 token reserve within the pool"
-            );
-            return Err(error!(AmmError::InvalidArg));
+            return Err(error!(AmmError::SwapExceedsReserve));
         }
 
         let sold_or_neutral_token_balances_after_swap: Vec<Decimal> = reserves
@@ -664,6 +1302,33 @@ impl Pool {
                     product,
                 )?
             }
+            Curve::Weighted { .. } => {
+                // Balancer's weighted product, with sell reserve `a`, buy
+                // reserve `b`, their weights `w_a` and `w_b` and `x` tokens to
+                // swap, the buy reserve after the swap is
+                //
+                // b * (a / (a + x)) ^ (w_a / w_b)
+                let sell_weight: Decimal = self
+                    .reserve_weight(sell_mint, now)
+                    .ok_or(AmmError::InvariantViolation)?
+                    .into();
+                let buy_weight: Decimal = self
+                    .reserve_weight(buy_mint, now)
+                    .ok_or(AmmError::InvariantViolation)?
+                    .into();
+
+                let sell_reserve = Decimal::from(reserves[&sell_mint]);
+                let ratio = sell_reserve
+                    .try_div(sell_reserve.try_add(tokens_to_swap.into())?)?;
+                // the power is approximated, therefore we nudge it up so that
+                // the user rather receives a little less than more
+                let factor =
+                    try_pow_fraction(ratio, sell_weight.try_div(buy_weight)?)?
+                        .try_add(weighted_curve_pow_tolerance())?
+                        .min(Decimal::one());
+
+                Decimal::from(reserves[&buy_mint]).try_mul(factor)?
+            }
         };
 
         let tokens_to_receive = compute_delta_withdraw_token_amount(
@@ -675,37 +1340,205 @@ impl Pool {
 
         Ok(tokens_to_receive.into())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use proptest::*;
 
-    /// This method will return a [`BTreeMap`] with all the reserve token prices
-    /// measured in parity (all with the same denominator/quote). We chose the
-    /// token in the pool that has the lowest price to be the quote price for
-    /// all the tokens. As an example, if we have x1, x2, x3 and x3 is
-    /// the token with the biggest reserve, then this means x3 is the cheapest
-    /// token from the perspective of the pool prices. Therefore we calculate
-    /// x1 and x2 prices based on x3 as quote.
+    /// When depositing tokens of a single mint into a pool with two reserves,
+    /// part of them must first be swapped into the other reserve's mint. This
+    /// method finds how many of the `tokens` to sell so that the remaining sell
+    /// tokens and the bought tokens are in the same ratio as the reserves after
+    /// the swap, ie. so that as little as possible is left over after the
+    /// deposit.
     ///
-    /// # Important
-    /// This function mustn't be called when any reserve's balance is 0.
-    fn get_reserve_parity_prices(
-        pool: &Pool,
-    ) -> Result<BTreeMap<Pubkey, Decimal>> {
-        debug_assert!(pool.dimension >= 2);
-        let lowest_priced_token: Decimal = pool
+    /// The `fee_share` is the fraction of the sold tokens which is taken as the
+    /// swap fee. The fee stays in the sell reserve.
+    ///
+    /// Returns the buy mint and how many tokens to sell, swap fee included.
+    pub fn calculate_zap_in_swap(
+        &self,
+        sell_mint: Pubkey,
+        tokens: TokenAmount,
+        fee_share: Decimal,
+        now: Slot,
+    ) -> Result<(Pubkey, TokenAmount)> {
+        if self.dimension != 2 {
+            return Err(error!(err::arg(
+                "Zap is only available for pools with two reserves"
+            )));
+        }
+
+        let sell_reserve = self
             .reserves()
             .iter()
-            .map(|r| r.tokens.amount)
-            .max()
-            // there always have to be at least two reserves in the pool
-            .ok_or(AmmError::InvariantViolation)?
-            .into();
-
-        // pick the token with the lowest pool price and
+            .find(|r| r.mint == sell_mint)
+            .copied()
+            .ok_or(AmmError::InvalidTokenMints)?;
+        let buy_reserve = self
+            .reserves()
+            .iter()
+            .find(|r| r.mint != sell_mint)
+            .copied()
+            .ok_or(AmmError::InvariantViolation)?;
+
+        if sell_reserve.tokens.amount == 0 || buy_reserve.tokens.amount == 0 {
+            return Err(error!(AmmError::ZeroReserve));
+        }
+
+        let tokens_to_sell = match self.curve {
+            Curve::ConstProd => {
+                // With sell reserve `a`, buy reserve `b`, zapped tokens `A`
+                // and `r = 1 - fee_share`, we look for `s` such that
+                //
+                // (A - s) / (a + s) = out / (b - out)
+                //
+                // where `out = b - a * b / (a + r * s)`. The right hand side
+                // simplifies to `r * s / a`, which gives a quadratic equation
+                //
+                // r * s^2 + a * (1 + r) * s - a * A = 0
+                //
+                // with the positive root
+                //
+                // s = a * (sqrt((1 + r)^2 + 4 * r * A / a) - (1 + r)) / (2 * r)
+                let a = Decimal::from(sell_reserve.tokens);
+                let r = Decimal::one().try_sub(fee_share)?;
+                let one_plus_r = Decimal::one().try_add(r)?;
+
+                let discriminant = one_plus_r.try_pow(2)?.try_add(
+                    Decimal::from(4u64)
+                        .try_mul(r)?
+                        .try_mul(Decimal::from(tokens))?
+                        .try_div(a)?,
+                )?;
+
+                a.try_mul(discriminant.try_sqrt()?.try_sub(one_plus_r)?)?
+                    .try_div(Decimal::from(2u64).try_mul(r)?)?
+                    .try_floor()?
+            }
+            Curve::Weighted { .. } => {
+                return Err(error!(err::arg(
+                    "Zap is not available for weighted pools"
+                )));
+            }
+            Curve::Stable { .. } => {
+                // There's no closed-form solution for the stable curve, but
+                // the more we sell, the fewer sell tokens remain and the more
+                // bought tokens there are to deposit. Therefore we can bisect
+                // between selling nothing and selling everything.
+                //
+                // Each step calculates a swap, hence we take at most
+                // [`MAX_ZAP_IN_BISECTIONS`] steps. That leaves the sold tokens
+                // short by less than a 65536th of the zapped tokens, which
+                // stay in the user's wallet.
+                let mut too_little = 0;
+                let mut too_much = tokens.amount;
+                for _ in 0..MAX_ZAP_IN_BISECTIONS {
+                    if too_much - too_little <= 1 {
+                        break;
+                    }
+
+                    let mid = too_little + (too_much - too_little) / 2;
+                    if self.is_zap_in_swap_too_little(
+                        sell_reserve,
+                        buy_reserve,
+                        tokens,
+                        TokenAmount::new(mid),
+                        fee_share,
+                        now,
+                    )? {
+                        too_little = mid;
+                    } else {
+                        too_much = mid;
+                    }
+                }
+
+                too_little
+            }
+        };
+
+        if tokens_to_sell == 0 || tokens_to_sell >= tokens.amount {
+            msg!("Zapped tokens are too few to be split into a deposit");
+            return Err(error!(AmmError::InvalidArg));
+        }
+
+        Ok((buy_reserve.mint, TokenAmount::new(tokens_to_sell)))
+    }
+
+    /// Whether after selling `tokens_to_sell` out of the zapped `tokens` there
+    /// are more sell tokens to deposit relative to the sell reserve than there
+    /// are bought tokens relative to the buy reserve.
+    fn is_zap_in_swap_too_little(
+        &self,
+        sell_reserve: Reserve,
+        buy_reserve: Reserve,
+        tokens: TokenAmount,
+        tokens_to_sell: TokenAmount,
+        fee_share: Decimal,
+        now: Slot,
+    ) -> Result<bool> {
+        let fee = Decimal::from(tokens_to_sell)
+            .try_mul(fee_share)?
+            .try_ceil()?;
+        let tokens_to_swap = tokens_to_sell.amount.saturating_sub(fee);
+        if tokens_to_swap >= sell_reserve.tokens.amount {
+            // selling the whole reserve is surely too much
+            return Ok(false);
+        }
+
+        let bought = self.calculate_swap(
+            sell_reserve.mint,
+            TokenAmount::new(tokens_to_swap),
+            buy_reserve.mint,
+            now,
+        )?;
+
+        // (A - s) / (a + s) > out / (b - out)
+        let sell_side = Decimal::from(tokens.amount - tokens_to_sell.amount)
+            .try_mul(Decimal::from(
+                buy_reserve.tokens.amount - bought.amount,
+            ))?;
+        let buy_side = Decimal::from(bought).try_mul(
+            Decimal::from(sell_reserve.tokens)
+                .try_add(Decimal::from(tokens_to_sell))?,
+        )?;
+
+        Ok(sell_side > buy_side)
+    }
+}
+
+/// [`try_pow_fraction`] approximates the power, therefore on a swap on the
+/// [`Curve::Weighted`] we add this value, 10^-12, to it to round against the
+/// user.
+fn weighted_curve_pow_tolerance() -> Decimal {
+    Decimal::from_scaled_val(1_000_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::*;
+
+    /// This method will return a [`BTreeMap`] with all the reserve token prices
+    /// measured in parity (all with the same denominator/quote). We chose the
+    /// token in the pool that has the lowest price to be the quote price for
+    /// all the tokens. As an example, if we have x1, x2, x3 and x3 is
+    /// the token with the biggest reserve, then this means x3 is the cheapest
+    /// token from the perspective of the pool prices. Therefore we calculate
+    /// x1 and x2 prices based on x3 as quote.
+    ///
+    /// # Important
+    /// This function mustn't be called when any reserve's balance is 0.
+    fn get_reserve_parity_prices(
+        pool: &Pool,
+    ) -> Result<BTreeMap<Pubkey, Decimal>> {
+        debug_assert!(pool.dimension >= 2);
+        let lowest_priced_token: Decimal = pool
+            .reserves()
+            .iter()
+            .map(|r| r.tokens.amount)
+            .max()
+            // there always have to be at least two reserves in the pool
+            .ok_or(AmmError::InvariantViolation)?
+            .into();
+
+        // pick the token with the lowest pool price and
         // price all other tokens with that denominator
         pool.reserves()
             .iter()
@@ -1011,7 +1844,7 @@ mod tests {
 
         let invariant_before_swap = pool.curve.invariant().unwrap();
 
-        pool.swap(sell_mint, TokenAmount::new(10_000), buy_mint)?;
+        pool.swap(sell_mint, TokenAmount::new(10_000), buy_mint, Slot::new(0))?;
 
         pool.update_curve_invariant()?;
         let invariant_after_swap = pool.curve.invariant().unwrap();
@@ -1720,14 +2553,25 @@ mod tests {
         pool.update_curve_invariant().unwrap();
 
         let invariant = match pool.curve {
-            Curve::ConstProd => panic!("unexpected constant product curve"),
             Curve::Stable { invariant, .. } => invariant,
+            _ => panic!("unexpected non stable curve"),
         };
 
         assert_eq!(
             Decimal::from(invariant),
             Decimal::from_scaled_val(352805602632122973013)
         );
+
+        pool.update_curve_invariant_with(StableCurveInvariantMethod::Integer)
+            .unwrap();
+
+        let invariant = match pool.curve {
+            Curve::Stable { invariant, .. } => invariant,
+            _ => panic!("unexpected non stable curve"),
+        };
+
+        // rounded up to the next integer
+        assert_eq!(Decimal::from(invariant), Decimal::from(353_u64));
     }
 
     #[test]
@@ -1862,10 +2706,15 @@ mod tests {
         let tokens_to_swap = TokenAmount::new(50);
 
         assert!(pool
-            .calculate_swap(sell_mint, tokens_to_swap, Pubkey::new_unique(),)
+            .calculate_swap(
+                sell_mint,
+                tokens_to_swap,
+                Pubkey::new_unique(),
+                Slot::new(0)
+            )
             .unwrap_err()
             .to_string()
-            .contains("InvalidArg"));
+            .contains("InvalidTokenMints"));
     }
 
     #[test]
@@ -1894,10 +2743,15 @@ mod tests {
         let tokens_to_swap = TokenAmount::new(50);
 
         assert!(pool
-            .calculate_swap(Pubkey::new_unique(), tokens_to_swap, buy_mint)
+            .calculate_swap(
+                Pubkey::new_unique(),
+                tokens_to_swap,
+                buy_mint,
+                Slot::new(0)
+            )
             .unwrap_err()
             .to_string()
-            .contains("InvalidArg"));
+            .contains("InvalidTokenMints"));
     }
 
     #[test]
@@ -1926,10 +2780,10 @@ mod tests {
         let tokens_to_swap = TokenAmount::new(50);
 
         assert!(pool
-            .calculate_swap(sell_mint, tokens_to_swap, buy_mint)
+            .calculate_swap(sell_mint, tokens_to_swap, buy_mint, Slot::new(0))
             .unwrap_err()
             .to_string()
-            .contains("InvalidArg"));
+            .contains("ZeroReserve"));
     }
 
     #[test]
@@ -1958,10 +2812,10 @@ mod tests {
         let tokens_to_swap = TokenAmount::new(100);
 
         assert!(pool
-            .calculate_swap(sell_mint, tokens_to_swap, buy_mint)
+            .calculate_swap(sell_mint, tokens_to_swap, buy_mint, Slot::new(0))
             .unwrap_err()
             .to_string()
-            .contains("InvalidArg"));
+            .contains("SwapExceedsReserve"));
     }
 
     #[test]
@@ -1991,8 +2845,13 @@ mod tests {
         let tokens_to_swap = TokenAmount::new(50);
 
         assert_eq!(
-            pool.calculate_swap(deposit_mint, tokens_to_swap, withdraw_mint)
-                .unwrap(),
+            pool.calculate_swap(
+                deposit_mint,
+                tokens_to_swap,
+                withdraw_mint,
+                Slot::new(0)
+            )
+            .unwrap(),
             // (x - Δx) . y = K <=> Δx = x - (K / y)
             // (10 - Δx) . 100 = 1_000 <=> Δx = 10 - (1_000 / 150)
             // We floor this amount so instead of 3.33 it will be 3
@@ -2000,6 +2859,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_swaps_with_fee_and_toll() -> Result<()> {
+        let sell_mint = Pubkey::new_unique();
+        let buy_mint = Pubkey::new_unique();
+        let reserve = |mint| Reserve {
+            tokens: TokenAmount::new(1_000_000),
+            mint,
+            vault: Pubkey::new_unique(),
+        };
+        let pool = Pool {
+            dimension: 2,
+            reserves: [
+                reserve(sell_mint),
+                reserve(buy_mint),
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        };
+        let lp_supply = TokenAmount::new(1_000_000);
+        let sell = TokenAmount::new(10_000);
+        let swap_fee_share = Permillion::from_percent(1).into();
+
+        let mut swapped = pool.clone();
+        let result = swapped.swap_with_fee(
+            sell_mint,
+            sell,
+            buy_mint,
+            swap_fee_share,
+            lp_supply,
+            &ProgramConfig::default(),
+            Slot::new(0),
+        )?;
+
+        assert_eq!(result.swap_fee, TokenAmount::new(100));
+        assert_eq!(
+            result.bought,
+            pool.calculate_swap(
+                sell_mint,
+                TokenAmount::new(9_900),
+                buy_mint,
+                Slot::new(0)
+            )?
+        );
+        // the swap fee stays in the sell reserve
+        assert_eq!(swapped.reserves[0].tokens, TokenAmount::new(1_010_000));
+        assert_eq!(
+            result.toll_in_lp_tokens,
+            swapped.swap_fee_share_in_lp_tokens(
+                result.swap_fee,
+                consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+                sell_mint,
+                lp_supply,
+            )?
+        );
+        assert!(result.toll_in_lp_tokens.is_some());
+
+        let without_toll = ProgramConfig {
+            program_toll_swap_fee_share: Permillion { permillion: 0 },
+            ..Default::default()
+        };
+        let result = pool.clone().swap_with_fee(
+            sell_mint,
+            sell,
+            buy_mint,
+            swap_fee_share,
+            lp_supply,
+            &without_toll,
+            Slot::new(0),
+        )?;
+        assert_eq!(result.toll_in_lp_tokens, None);
+
+        Ok(())
+    }
+
     #[test]
     fn works_if_constant_product_curve_with_three_reserves() {
         let deposit_mint = Pubkey::new_unique();
@@ -2032,8 +2966,13 @@ mod tests {
         let tokens_to_swap = TokenAmount::new(50);
 
         assert_eq!(
-            pool.calculate_swap(deposit_mint, tokens_to_swap, withdraw_mint)
-                .unwrap(),
+            pool.calculate_swap(
+                deposit_mint,
+                tokens_to_swap,
+                withdraw_mint,
+                Slot::new(0)
+            )
+            .unwrap(),
             // (x - Δx) . y = K <=> Δx = x - (K / y)
             // (10 - Δx) . 100 = 1_000 <=> Δx = 10 - (1_000 / 150)
             // We floor this amount so instead of 3.33 it will be 3
@@ -2073,8 +3012,13 @@ mod tests {
         let tokens_to_swap = TokenAmount::new(50);
 
         assert_eq!(
-            pool.calculate_swap(deposit_mint, tokens_to_swap, withdraw_mint)
-                .unwrap(),
+            pool.calculate_swap(
+                deposit_mint,
+                tokens_to_swap,
+                withdraw_mint,
+                Slot::new(0)
+            )
+            .unwrap(),
             // The result for the root polynomial is x = 1.007841346549156426
             // Where x is the token balance of the token bought by the trader
             // after the swap. This means that floor(10 - 1.007841346549156426)
@@ -2123,8 +3067,13 @@ mod tests {
                 ],
                 ..Default::default()
             };
-            pool.calculate_swap(sell_mint, tokens_to_swap, buy_mint)
-                .unwrap();
+            pool.calculate_swap(
+                sell_mint,
+                tokens_to_swap,
+                buy_mint,
+                Slot::new(0),
+            )
+            .unwrap();
         }
     }
 
@@ -2316,6 +3265,307 @@ mod tests {
         };
     }
 
+    #[test]
+    fn it_proposes_swap_fee_after_delay() -> Result<()> {
+        let mut pool = Pool::default();
+        let now = Slot::new(100);
+        let earliest = Pool::earliest_swap_fee_change(now)?;
+        assert_eq!(
+            earliest.slot,
+            100 + consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS
+        );
+
+        let fee = Permillion::from_percent(1);
+        assert!(pool
+            .propose_swap_fee(fee, fee, Slot::new(earliest.slot - 1), now)
+            .is_err());
+        assert!(pool
+            .propose_swap_fee(Permillion::from_percent(2), fee, earliest, now)
+            .is_err());
+        assert_eq!(pool.pending_swap_fee, None);
+
+        pool.propose_swap_fee(fee, fee, earliest, now)?;
+        assert_eq!(
+            pool.pending_swap_fee,
+            Some(PendingSwapFee {
+                fee,
+                effective_at: earliest,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_applies_pending_swap_fee_once_effective() {
+        let mut pool = Pool {
+            swap_fee: Permillion::from_percent(1),
+            pending_swap_fee: Some(PendingSwapFee {
+                fee: Permillion { permillion: 5_000 },
+                effective_at: Slot::new(100),
+            }),
+            ..Default::default()
+        };
+
+        assert!(!pool.apply_pending_swap_fee(Slot::new(99)));
+        assert_eq!(pool.swap_fee, Permillion::from_percent(1));
+        assert!(pool.pending_swap_fee.is_some());
+
+        assert!(pool.apply_pending_swap_fee(Slot::new(100)));
+        assert_eq!(pool.swap_fee, Permillion { permillion: 5_000 });
+        assert_eq!(pool.pending_swap_fee, None);
+
+        assert!(!pool.apply_pending_swap_fee(Slot::new(101)));
+        assert_eq!(pool.swap_fee, Permillion { permillion: 5_000 });
+    }
+
+    #[test]
+    fn it_calculates_reserve_surplus() {
+        let reserve = Reserve {
+            tokens: TokenAmount::new(100),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            reserve.surplus(TokenAmount::new(150)),
+            Some(TokenAmount::new(50))
+        );
+        assert_eq!(
+            reserve.surplus(TokenAmount::new(100)),
+            Some(TokenAmount::new(0))
+        );
+        assert_eq!(reserve.surplus(TokenAmount::new(99)), None);
+    }
+
+    #[test]
+    fn it_adds_reserve() -> Result<()> {
+        let mint1 = Pubkey::new_unique();
+        let mint2 = Pubkey::new_unique();
+        let mint3 = Pubkey::new_unique();
+        let vault3 = Pubkey::new_unique();
+
+        let mut pool = Pool {
+            dimension: 2,
+            reserves: [
+                Reserve {
+                    tokens: TokenAmount::new(100),
+                    mint: mint1,
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve {
+                    tokens: TokenAmount::new(200),
+                    mint: mint2,
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        };
+
+        pool.add_reserve(mint3, vault3, TokenAmount::new(50))?;
+
+        assert_eq!(pool.dimension, 3);
+        assert_eq!(
+            pool.reserves[2],
+            Reserve {
+                tokens: TokenAmount::new(50),
+                mint: mint3,
+                vault: vault3,
+            }
+        );
+        assert_eq!(pool.reserves[3], Reserve::default());
+
+        assert!(pool
+            .add_reserve(mint3, Pubkey::new_unique(), TokenAmount::new(1))
+            .is_err());
+
+        pool.add_reserve(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            TokenAmount::new(1),
+        )?;
+        assert!(pool
+            .add_reserve(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                TokenAmount::new(1)
+            )
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_removes_reserve_and_keeps_reserves_contiguous() -> Result<()> {
+        let reserves: Vec<_> = (0..3)
+            .map(|i| Reserve {
+                tokens: TokenAmount::new(if i == 0 { 0 } else { 10 }),
+                mint: Pubkey::new_unique(),
+                vault: Pubkey::new_unique(),
+            })
+            .collect();
+
+        let mut pool = Pool {
+            dimension: 3,
+            reserves: [
+                reserves[0],
+                reserves[1],
+                reserves[2],
+                Reserve::default(),
+            ],
+            ..Default::default()
+        };
+
+        // only an empty reserve can be removed
+        assert!(pool.remove_reserve(reserves[1].mint).is_err());
+        assert!(pool.remove_reserve(Pubkey::new_unique()).is_err());
+
+        let removed = pool.remove_reserve(reserves[0].mint)?;
+
+        assert_eq!(removed, reserves[0]);
+        assert_eq!(pool.dimension, 2);
+        assert_eq!(pool.reserves(), &reserves[1..]);
+        assert_eq!(pool.reserves[2], Reserve::default());
+        assert_eq!(pool.reserves[3], Reserve::default());
+
+        // at least two reserves must remain
+        pool.reserves[0].tokens = TokenAmount::new(0);
+        assert!(pool.remove_reserve(reserves[1].mint).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_calculates_seed_tokens_from_reference_reserve() -> Result<()> {
+        let mint1 = Pubkey::new_unique();
+        let mint2 = Pubkey::new_unique();
+
+        let pool = Pool {
+            dimension: 2,
+            reserves: [
+                Reserve {
+                    tokens: TokenAmount::new(1_000),
+                    mint: mint1,
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve {
+                    tokens: TokenAmount::new(3_000),
+                    mint: mint2,
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        };
+
+        // 3 new tokens are worth 2 tokens of mint1
+        let seed = pool.seed_tokens(ReservePrice {
+            reference_mint: mint1,
+            reference_tokens: TokenAmount::new(2),
+            tokens: TokenAmount::new(3),
+        })?;
+        assert_eq!(seed, TokenAmount::new(1_500));
+
+        // rounds up
+        let seed = pool.seed_tokens(ReservePrice {
+            reference_mint: mint2,
+            reference_tokens: TokenAmount::new(7),
+            tokens: TokenAmount::new(1),
+        })?;
+        assert_eq!(seed, TokenAmount::new(429));
+
+        assert!(pool
+            .seed_tokens(ReservePrice {
+                reference_mint: Pubkey::new_unique(),
+                reference_tokens: TokenAmount::new(1),
+                tokens: TokenAmount::new(1),
+            })
+            .is_err());
+        assert!(pool
+            .seed_tokens(ReservePrice {
+                reference_mint: mint1,
+                reference_tokens: TokenAmount::new(0),
+                tokens: TokenAmount::new(1),
+            })
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_seed_tokens_of_adversarial_price() -> Result<()> {
+        let mint1 = Pubkey::new_unique();
+        let mint2 = Pubkey::new_unique();
+
+        let mut pool = Pool {
+            dimension: 2,
+            reserves: [
+                Reserve {
+                    tokens: TokenAmount::new(1_000),
+                    mint: mint1,
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve {
+                    tokens: TokenAmount::new(3_000),
+                    mint: mint2,
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        };
+
+        // a single new token is claimed to be worth a thousand times the
+        // reference reserve, the exact seed would be a fraction of a token
+        assert!(pool
+            .seed_tokens(ReservePrice {
+                reference_mint: mint1,
+                reference_tokens: TokenAmount::new(1_000_000),
+                tokens: TokenAmount::new(1),
+            })
+            .unwrap_err()
+            .to_string()
+            .contains("SeedValueOutOfBounds"));
+
+        // rounding a seed of 4.5 tokens up is more than the tolerance
+        assert!(pool
+            .seed_tokens(ReservePrice {
+                reference_mint: mint1,
+                reference_tokens: TokenAmount::new(2_000),
+                tokens: TokenAmount::new(9),
+            })
+            .is_err());
+
+        pool.curve = Curve::Stable {
+            amplifier: 2,
+            invariant: Default::default(),
+        };
+
+        // in a stable pool the seed is the mean of the reserves
+        let seed = pool.seed_tokens(ReservePrice {
+            reference_mint: mint1,
+            reference_tokens: TokenAmount::new(1_000),
+            tokens: TokenAmount::new(1_005),
+        })?;
+        assert_eq!(seed, TokenAmount::new(2_000));
+
+        // and the price must be close to parity
+        assert!(pool
+            .seed_tokens(ReservePrice {
+                reference_mint: mint1,
+                reference_tokens: TokenAmount::new(1),
+                tokens: TokenAmount::new(1_000),
+            })
+            .unwrap_err()
+            .to_string()
+            .contains("SeedValueOutOfBounds"));
+
+        Ok(())
+    }
+
     proptest! {
         #[test]
         fn deposits_tokens_using_deposit_ratio_stress_token_reserves(
@@ -2650,4 +3900,359 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn it_calculates_zap_in_swap_for_const_prod_in_closed_form() -> Result<()> {
+        let pool = zap_in_pool(Curve::ConstProd, 10_000, 10_000);
+        let sell_mint = pool.reserves[0].mint;
+
+        // 10_000 * (sqrt(2^2 + 4 * 1_000 / 10_000) - 2) / 2 = 488.09
+        let (buy_mint, tokens_to_sell) = pool.calculate_zap_in_swap(
+            sell_mint,
+            TokenAmount::new(1_000),
+            Decimal::zero(),
+            Slot::new(0),
+        )?;
+        assert_eq!(buy_mint, pool.reserves[1].mint);
+        assert_eq!(tokens_to_sell, TokenAmount::new(488));
+
+        // r = 0.99
+        // 10_000 * (sqrt((1 + r)^2 + 4 * r * 1_000 / 10_000) - (1 + r))
+        //  / (2 * r) = 490.54
+        let (_, tokens_to_sell) = pool.calculate_zap_in_swap(
+            sell_mint,
+            TokenAmount::new(1_000),
+            Permillion::from_percent(1).into(),
+            Slot::new(0),
+        )?;
+        assert_eq!(tokens_to_sell, TokenAmount::new(490));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_little_dust_after_zap_in_into_const_prod() -> Result<()> {
+        let pool = zap_in_pool(Curve::ConstProd, 10_000, 10_000);
+
+        let (sell_dust, buy_dust) = zap_in_dust(
+            &pool,
+            TokenAmount::new(1_000),
+            Permillion::from_percent(1).into(),
+        )?;
+        assert!(sell_dust.amount <= 1);
+        assert!(buy_dust.amount <= 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_little_dust_after_zap_in_into_stable_curve() -> Result<()> {
+        let curve = Curve::Stable {
+            amplifier: 10,
+            invariant: 0_u64.into(),
+        };
+        let mut pool = zap_in_pool(curve, 10_000_000, 9_000_000);
+        pool.update_curve_invariant()?;
+
+        let (sell_dust, buy_dust) = zap_in_dust(
+            &pool,
+            TokenAmount::new(100_000),
+            Permillion::from_percent(1).into(),
+        )?;
+        // less than 0.1% of the zapped tokens
+        assert!(sell_dust.amount + buy_dust.amount < 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_leaves_little_dust_after_large_zap_in_into_stable() -> Result<()> {
+        let curve = Curve::Stable {
+            amplifier: 10,
+            invariant: 0_u64.into(),
+        };
+        let mut pool =
+            zap_in_pool(curve, 10_000_000_000_000, 9_000_000_000_000);
+        pool.update_curve_invariant()?;
+
+        // the bisection stops before the bracket narrows down to a token
+        let (sell_dust, buy_dust) = zap_in_dust(
+            &pool,
+            TokenAmount::new(1_000_000_000_000),
+            Permillion::from_percent(1).into(),
+        )?;
+        // less than 0.01% of the zapped tokens
+        assert!(sell_dust.amount + buy_dust.amount < 100_000_000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_errs_zap_in_if_pool_has_more_than_two_reserves() {
+        let mut pool = zap_in_pool(Curve::ConstProd, 10_000, 10_000);
+        pool.dimension = 3;
+        pool.reserves[2] = Reserve {
+            tokens: TokenAmount::new(10_000),
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        };
+
+        assert!(pool
+            .calculate_zap_in_swap(
+                pool.reserves[0].mint,
+                TokenAmount::new(1_000),
+                Decimal::zero(),
+                Slot::new(0),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn it_errs_zap_in_if_tokens_are_too_few_to_split() {
+        let pool = zap_in_pool(Curve::ConstProd, 10_000, 10_000);
+
+        assert!(pool
+            .calculate_zap_in_swap(
+                pool.reserves[0].mint,
+                TokenAmount::new(1),
+                Decimal::zero(),
+                Slot::new(0),
+            )
+            .is_err());
+    }
+
+    fn zap_in_pool(curve: Curve, sell_tokens: u64, buy_tokens: u64) -> Pool {
+        Pool {
+            curve,
+            dimension: 2,
+            reserves: [
+                Reserve {
+                    tokens: TokenAmount::new(sell_tokens),
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve {
+                    tokens: TokenAmount::new(buy_tokens),
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// Zaps tokens of the first reserve into the pool the same way the
+    /// endpoint does and returns how many sell and buy tokens are left over.
+    fn zap_in_dust(
+        pool: &Pool,
+        tokens: TokenAmount,
+        fee_share: Decimal,
+    ) -> Result<(TokenAmount, TokenAmount)> {
+        let mut pool = pool.clone();
+        let sell_mint = pool.reserves[0].mint;
+
+        let (buy_mint, tokens_to_sell) = pool.calculate_zap_in_swap(
+            sell_mint,
+            tokens,
+            fee_share,
+            Slot::new(0),
+        )?;
+        let fee = TokenAmount::new(
+            Decimal::from(tokens_to_sell)
+                .try_mul(fee_share)?
+                .try_ceil()?,
+        );
+        let bought = pool.swap(
+            sell_mint,
+            TokenAmount::new(tokens_to_sell.amount - fee.amount),
+            buy_mint,
+            Slot::new(0),
+        )?;
+        pool.reserve_mut(sell_mint).unwrap().add_tokens(fee)?;
+
+        let max_tokens = BTreeMap::from([
+            (
+                sell_mint,
+                TokenAmount::new(tokens.amount - tokens_to_sell.amount),
+            ),
+            (buy_mint, bought),
+        ]);
+        let DepositResult {
+            tokens_to_deposit, ..
+        } =
+            pool.deposit_tokens(max_tokens.clone(), TokenAmount::new(10_000))?;
+
+        Ok((
+            TokenAmount::new(
+                max_tokens[&sell_mint].amount
+                    - tokens_to_deposit[&sell_mint].amount,
+            ),
+            TokenAmount::new(
+                max_tokens[&buy_mint].amount
+                    - tokens_to_deposit[&buy_mint].amount,
+            ),
+        ))
+    }
+
+    #[test]
+    fn it_interpolates_weights_linearly() {
+        let curve = weighted_curve(90, 50, 100, 200);
+
+        let weight = |slot| curve.first_reserve_weight(Slot::new(slot));
+        assert_eq!(weight(0), Some(Permillion::from_percent(90)));
+        assert_eq!(weight(100), Some(Permillion::from_percent(90)));
+        assert_eq!(weight(125), Some(Permillion::from_percent(80)));
+        assert_eq!(weight(150), Some(Permillion::from_percent(70)));
+        assert_eq!(weight(200), Some(Permillion::from_percent(50)));
+        assert_eq!(weight(1_000), Some(Permillion::from_percent(50)));
+
+        assert_eq!(Curve::ConstProd.first_reserve_weight(Slot::new(150)), None);
+    }
+
+    #[test]
+    fn it_is_bootstrapping_until_end_slot() {
+        let pool = weighted_pool(weighted_curve(90, 50, 100, 200));
+
+        assert!(pool.is_bootstrapping(Slot::new(0)));
+        assert!(pool.is_bootstrapping(Slot::new(199)));
+        assert!(!pool.is_bootstrapping(Slot::new(200)));
+        assert!(!Pool::default().is_bootstrapping(Slot::new(0)));
+    }
+
+    #[test]
+    fn it_swaps_on_weighted_curve_with_equal_weights_as_const_prod() {
+        let now = Slot::new(150);
+        let mut weighted = weighted_pool(weighted_curve(50, 50, 100, 200));
+        let mut const_prod = weighted_pool(Curve::ConstProd);
+        let sell_mint = weighted.reserves[0].mint;
+        let buy_mint = weighted.reserves[1].mint;
+
+        for tokens in [1, 1_000, 100_000, 999_999] {
+            assert_eq!(
+                weighted
+                    .calculate_swap(
+                        sell_mint,
+                        TokenAmount::new(tokens),
+                        buy_mint,
+                        now,
+                    )
+                    .unwrap(),
+                const_prod
+                    .calculate_swap(
+                        sell_mint,
+                        TokenAmount::new(tokens),
+                        buy_mint,
+                        now,
+                    )
+                    .unwrap(),
+            );
+        }
+
+        weighted
+            .swap(sell_mint, TokenAmount::new(1_000), buy_mint, now)
+            .unwrap();
+        const_prod
+            .swap(sell_mint, TokenAmount::new(1_000), buy_mint, now)
+            .unwrap();
+        assert_eq!(weighted.reserves, const_prod.reserves);
+    }
+
+    #[test]
+    fn it_swaps_on_weighted_curve_with_current_weights() {
+        let pool = weighted_pool(weighted_curve(90, 40, 100, 200));
+        let first_mint = pool.reserves[0].mint;
+        let second_mint = pool.reserves[1].mint;
+        let swap = |sell_mint, buy_mint, slot| {
+            pool.calculate_swap(
+                sell_mint,
+                TokenAmount::new(1_000),
+                buy_mint,
+                Slot::new(slot),
+            )
+            .unwrap()
+            .amount
+        };
+
+        // weights are 80% and 20%
+        assert_eq!(swap(first_mint, second_mint, 120), 3_990);
+        assert_eq!(swap(second_mint, first_mint, 120), 249);
+
+        // weights are 40% and 60%
+        assert_eq!(swap(first_mint, second_mint, 300), 666);
+    }
+
+    fn weighted_curve(
+        start_weight_percent: u64,
+        end_weight_percent: u64,
+        start: u64,
+        end: u64,
+    ) -> Curve {
+        Curve::Weighted {
+            start_weight: Permillion::from_percent(start_weight_percent),
+            end_weight: Permillion::from_percent(end_weight_percent),
+            start: Slot::new(start),
+            end: Slot::new(end),
+        }
+    }
+
+    fn weighted_pool(curve: Curve) -> Pool {
+        Pool {
+            dimension: 2,
+            curve,
+            reserves: [
+                Reserve {
+                    tokens: TokenAmount::new(1_000_000),
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve {
+                    tokens: TokenAmount::new(1_000_000),
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_pool_with_base58_pubkeys() {
+        let mut pool = Pool {
+            admin: Pubkey::new_unique(),
+            dimension: 2,
+            curve: Curve::Stable {
+                amplifier: 10,
+                invariant: SDecimal::from(2_000_000),
+            },
+            swap_fee: Permillion { permillion: 3_000 },
+            access_policy: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        pool.reserves[0].mint = Pubkey::new_unique();
+        pool.reserves[0].tokens = TokenAmount::new(1_000_000);
+
+        let value = serde_json::to_value(&pool).unwrap();
+
+        assert_eq!(value["admin"], pool.admin.to_string());
+        assert_eq!(
+            value["access_policy"],
+            pool.access_policy.unwrap().to_string()
+        );
+        assert_eq!(
+            value["reserves"][0]["mint"],
+            pool.reserves[0].mint.to_string()
+        );
+        assert_eq!(value["reserves"][0]["tokens"], 1_000_000);
+        assert_eq!(value["swap_fee"], 3_000);
+        assert_eq!(
+            value["curve"]["Stable"]["invariant"],
+            "2000000.000000000000000000"
+        );
+        assert_eq!(serde_json::from_value::<Pool>(value).unwrap(), pool);
+    }
 }
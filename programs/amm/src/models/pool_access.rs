@@ -0,0 +1,87 @@
+//! Pools with an access policy are open only to allow-listed users. The
+//! policy authority, see [`Pool::access_policy`], grants and revokes access
+//! with the [`crate::endpoints::put_pool_access`] endpoint.
+//!
+//! The [`PoolAccess`] is always stored in an account with a PDA address for
+//! which the pool's and the user's pubkeys are seeds. Endpoints of a pool with
+//! an access policy expect the caller's [`PoolAccess`] as the last remaining
+//! account, see [`check_pool_access`].
+
+use crate::prelude::*;
+
+/// A one-to-one account with relationship to a user and a pool.
+#[account]
+#[derive(Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct PoolAccess {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub pool: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub user: Pubkey,
+    /// Whether the user can swap and deposit liquidity. Users whose access
+    /// was revoked can still redeem their liquidity.
+    pub is_allowed: bool,
+}
+
+impl PoolAccess {
+    pub const PDA_PREFIX: &'static [u8; 11] = b"pool_access";
+
+    pub fn space() -> usize {
+        let discriminant = 8;
+        let pool = 32;
+        let user = 32;
+        let is_allowed = 1;
+
+        discriminant + pool + user + is_allowed
+    }
+}
+
+/// If the pool has no access policy, returns the remaining accounts as they
+/// are. Otherwise the last remaining account must be the user's
+/// [`PoolAccess`], and the rest of the remaining accounts is returned.
+///
+/// Redemptions are open also to users whose access was revoked, therefore
+/// with `is_redeem` we only check that the user has been granted the access
+/// at some point.
+pub fn check_pool_access<'a, 'info>(
+    pool: &Account<'info, Pool>,
+    user: Pubkey,
+    remaining_accounts: &'a [AccountInfo<'info>],
+    is_redeem: bool,
+) -> Result<&'a [AccountInfo<'info>]> {
+    if pool.access_policy.is_none() {
+        return Ok(remaining_accounts);
+    }
+
+    let (access, remaining_accounts) = remaining_accounts
+        .split_last()
+        .ok_or(AmmError::PoolAccessDenied)?;
+    // the user has never been granted the access
+    if access.owner != &crate::ID {
+        return Err(error!(AmmError::PoolAccessDenied));
+    }
+    // the account is owned by our program and of the correct type, hence it's
+    // been created by the put pool access endpoint
+    let access = Account::<PoolAccess>::try_from(access)?;
+    if access.pool != pool.key() || access.user != user {
+        return Err(error!(err::acc(
+            "Pool access must belong to the pool and the user"
+        )));
+    }
+
+    if !access.is_allowed && !is_redeem {
+        return Err(error!(AmmError::PoolAccessDenied));
+    }
+
+    Ok(remaining_accounts)
+}
@@ -0,0 +1,430 @@
+//! Model based test which applies long random sequences of deposits, swaps,
+//! redemptions and curve invariant updates to a [`Pool`] the same way the
+//! endpoints apply them, and checks after each step that the pool doesn't
+//! leak value to its users:
+//!
+//! - the value of an LP token never decreases, other than by the rounding of
+//!   LP tokens minted on deposits and for the program toll;
+//! - no more tokens leave the pool than entered it, where deposits and sold
+//!   tokens (swap fee included) enter, and redemptions and bought tokens leave;
+//! - the curve invariant never decreases on swaps.
+//!
+//! Operations which fail leave the pool as it was, like a failed transaction.
+//! Failing sequences are persisted to `proptest-regressions/models`.
+
+use crate::math::stable_curve_invariant_int::{self, Rounding, U512};
+use crate::prelude::*;
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+
+/// Amounts in the operations are relative to the reserves, so that they stay
+/// meaningful however the reserves evolve.
+const PERMILLION: u64 = 1_000_000;
+
+#[derive(Clone, Debug)]
+enum Op {
+    /// Max tokens to deposit per reserve, in permillion of the reserve.
+    Deposit(Vec<u64>),
+    /// Sells permillion of the sell reserve, swap fee included.
+    Swap {
+        sell: usize,
+        buy: usize,
+        share: u64,
+    },
+    /// Burns permillion of the LP supply.
+    Redeem {
+        share: u64,
+    },
+    UpdateCurveInvariant,
+}
+
+#[derive(Clone, Debug)]
+struct Setup {
+    /// Never [`Curve::Weighted`], see [`curve`].
+    curve: Curve,
+    swap_fee: Permillion,
+    /// First deposit, one amount per reserve.
+    initial_deposit: Vec<u64>,
+    ops: Vec<Op>,
+}
+
+#[derive(Clone, Debug)]
+struct Simulation {
+    pool: Pool,
+    lp_supply: TokenAmount,
+    config: ProgramConfig,
+    /// Upper bound on the LP tokens which the last operation minted in excess
+    /// of the value it added to the pool, see [`Simulation::mint_lp_tokens`].
+    excess_lp_tokens: u64,
+    /// Per reserve, tokens which entered the pool.
+    tokens_in: Vec<u128>,
+    /// Per reserve, tokens which left the pool.
+    tokens_out: Vec<u128>,
+}
+
+proptest! {
+    #[test]
+    fn pool_does_not_leak_value(setup in setup()) {
+        let mut sim = Simulation::new(&setup);
+        prop_assume!(sim.deposit(&setup.initial_deposit).is_ok());
+
+        for op in &setup.ops {
+            let before = sim.clone();
+            if sim.apply(op, setup.swap_fee).is_err() {
+                // the endpoint would revert the transaction
+                sim = before;
+                continue;
+            }
+
+            sim.check_token_flows()?;
+            sim.check_lp_token_value(&before)?;
+            if matches!(op, Op::Swap { .. }) {
+                sim.check_invariant_on_swap(&before)?;
+            }
+        }
+    }
+}
+
+impl Simulation {
+    fn new(setup: &Setup) -> Self {
+        let dimension = setup.initial_deposit.len();
+
+        let mut reserves = [Reserve::default(); 4];
+        for reserve in reserves.iter_mut().take(dimension) {
+            reserve.mint = Pubkey::new_unique();
+            reserve.vault = Pubkey::new_unique();
+        }
+
+        Self {
+            pool: Pool {
+                dimension: dimension as u64,
+                reserves,
+                curve: setup.curve,
+                swap_fee: setup.swap_fee,
+                ..Default::default()
+            },
+            lp_supply: TokenAmount::new(0),
+            config: ProgramConfig::default(),
+            excess_lp_tokens: 0,
+            tokens_in: vec![0; dimension],
+            tokens_out: vec![0; dimension],
+        }
+    }
+
+    fn apply(&mut self, op: &Op, swap_fee: Permillion) -> Result<()> {
+        self.excess_lp_tokens = 0;
+
+        match op {
+            Op::Deposit(shares) => {
+                let max_tokens: Vec<_> = self
+                    .pool
+                    .reserves()
+                    .iter()
+                    .zip(shares)
+                    .map(|(r, share)| share_of(r.tokens.amount, *share) + 1)
+                    .collect();
+
+                self.deposit(&max_tokens)
+            }
+            Op::Swap { sell, buy, share } => {
+                let sell_tokens =
+                    share_of(self.pool.reserves[*sell].tokens.amount, *share);
+
+                self.swap(*sell, TokenAmount::new(sell_tokens), *buy, swap_fee)
+            }
+            Op::Redeem { share } => {
+                let lp_tokens = share_of(self.lp_supply.amount, *share);
+
+                self.redeem(TokenAmount::new(lp_tokens))
+            }
+            Op::UpdateCurveInvariant => self.pool.update_curve_invariant(),
+        }
+    }
+
+    /// As in [`endpoints::deposit_liquidity`].
+    fn deposit(&mut self, max_tokens: &[u64]) -> Result<()> {
+        let max_tokens = self
+            .pool
+            .reserves()
+            .iter()
+            .zip(max_tokens)
+            .map(|(r, tokens)| (r.mint, TokenAmount::new(*tokens)))
+            .collect();
+
+        let first_reserve = self.pool.reserves[0].tokens;
+        let (lp_tokens_to_distribute, tokens_to_deposit) = self
+            .pool
+            .deposit_liquidity(max_tokens, self.lp_supply, &self.config)?;
+
+        self.mint_lp_tokens(lp_tokens_to_distribute, first_reserve);
+        record(
+            self.pool.reserves(),
+            &tokens_to_deposit,
+            &mut self.tokens_in,
+        );
+
+        Ok(())
+    }
+
+    /// As in [`endpoints::swap::handle`], the swap fee is taken from the sold
+    /// tokens and then re-added to the sell reserve, and the program toll is
+    /// minted in LP tokens.
+    fn swap(
+        &mut self,
+        sell: usize,
+        sell_tokens: TokenAmount,
+        buy: usize,
+        swap_fee: Permillion,
+    ) -> Result<()> {
+        let sell_mint = self.pool.reserves[sell].mint;
+        let buy_mint = self.pool.reserves[buy].mint;
+
+        let SwapResult {
+            bought,
+            toll_in_lp_tokens,
+            ..
+        } = self.pool.swap_with_fee(
+            sell_mint,
+            sell_tokens,
+            buy_mint,
+            swap_fee.into(),
+            self.lp_supply,
+            &self.config,
+            Slot::new(0),
+        )?;
+        if let Some(toll_in_lp_tokens) = toll_in_lp_tokens {
+            let first_reserve = self.pool.reserves[0].tokens;
+            self.mint_lp_tokens(toll_in_lp_tokens, first_reserve);
+        }
+
+        self.tokens_in[sell] += sell_tokens.amount as u128;
+        self.tokens_out[buy] += bought.amount as u128;
+
+        Ok(())
+    }
+
+    /// As in [`endpoints::redeem_liquidity`].
+    fn redeem(&mut self, lp_tokens: TokenAmount) -> Result<()> {
+        if lp_tokens.amount == 0 {
+            return Err(error!(AmmError::InvalidLpTokenAmount));
+        }
+
+        let min_tokens = self
+            .pool
+            .reserves()
+            .iter()
+            .map(|r| (r.mint, TokenAmount::new(0)))
+            .collect();
+
+        let tokens_to_redeem = self.pool.redeem_liquidity(
+            min_tokens,
+            lp_tokens,
+            self.lp_supply,
+            &self.config,
+        )?;
+
+        self.lp_supply.amount -= lp_tokens.amount;
+        record(
+            self.pool.reserves(),
+            &tokens_to_redeem,
+            &mut self.tokens_out,
+        );
+
+        Ok(())
+    }
+
+    /// The LP tokens are calculated by the first reserve's share of a deposit,
+    /// see [`Pool::deposit_tokens`]. The deposit into each reserve is ceiled,
+    /// hence the first reserve's share exceeds the others by up to a token,
+    /// i.e. up to `lp_supply / first_reserve` LP tokens. The program toll is
+    /// calculated as a deposit too.
+    fn mint_lp_tokens(
+        &mut self,
+        lp_tokens: TokenAmount,
+        first_reserve: TokenAmount,
+    ) {
+        if first_reserve.amount != 0 {
+            self.excess_lp_tokens +=
+                (self.lp_supply.amount + first_reserve.amount - 1)
+                    / first_reserve.amount;
+        }
+        self.lp_supply.amount += lp_tokens.amount;
+    }
+
+    fn check_token_flows(&self) -> std::result::Result<(), TestCaseError> {
+        for (index, reserve) in self.pool.reserves().iter().enumerate() {
+            let (tokens_in, tokens_out) =
+                (self.tokens_in[index], self.tokens_out[index]);
+
+            prop_assert!(tokens_out <= tokens_in);
+            prop_assert_eq!(
+                reserve.tokens.amount as u128,
+                tokens_in - tokens_out
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The value of an LP token is the curve's invariant divided by the LP
+    /// supply. For the constant product curve we compare the product of
+    /// reserves with the supply to the power of dimension instead. The LP
+    /// tokens minted in excess due to rounding are not counted.
+    fn check_lp_token_value(
+        &self,
+        before: &Self,
+    ) -> std::result::Result<(), TestCaseError> {
+        if before.lp_supply.amount == 0 || self.lp_supply.amount == 0 {
+            return Ok(());
+        }
+        let lp_supply = TokenAmount::new(
+            self.lp_supply.amount.saturating_sub(self.excess_lp_tokens),
+        );
+
+        if let Curve::Stable { .. } = self.pool.curve {
+            // the integer invariant is exact, we compare the upper bound of
+            // the current value to the lower bound of the previous one
+            if let (Some(invariant_before), Some(invariant_after)) = (
+                before.exact_invariant(Rounding::Down),
+                self.exact_invariant(Rounding::Up),
+            ) {
+                prop_assert!(
+                    U512::from(invariant_after)
+                        * U512::from(before.lp_supply.amount)
+                        >= U512::from(invariant_before)
+                            * U512::from(lp_supply.amount),
+                    "LP token value decreased from {:?} to {:?}",
+                    before,
+                    self
+                );
+            }
+        } else {
+            let value = |sim: &Self, supply: TokenAmount| {
+                let product =
+                    sim.pool.reserves().iter().fold(U512::one(), |acc, r| {
+                        acc * U512::from(r.tokens.amount)
+                    });
+                let supply_pow = U512::from(supply.amount)
+                    .pow(U512::from(self.pool.dimension));
+
+                product * supply_pow
+            };
+
+            // product_after / supply_after^n >=
+            // product_before / supply_before^n
+            prop_assert!(
+                value(self, before.lp_supply) >= value(before, lp_supply),
+                "LP token value decreased from {:?} to {:?}",
+                before,
+                self
+            );
+        }
+
+        Ok(())
+    }
+
+    fn check_invariant_on_swap(
+        &self,
+        before: &Self,
+    ) -> std::result::Result<(), TestCaseError> {
+        if let Curve::Stable { .. } = self.pool.curve {
+            if let (Some(invariant_before), Some(invariant_after)) = (
+                before.exact_invariant(Rounding::Down),
+                self.exact_invariant(Rounding::Up),
+            ) {
+                prop_assert!(invariant_after >= invariant_before);
+            }
+        } else {
+            let product = |sim: &Self| {
+                sim.pool.reserves().iter().fold(U512::one(), |acc, r| {
+                    acc * U512::from(r.tokens.amount)
+                })
+            };
+
+            prop_assert!(product(self) >= product(before));
+        }
+
+        Ok(())
+    }
+
+    /// [`None`] if the reserves are too imbalanced for the invariant to be
+    /// computed.
+    fn exact_invariant(&self, rounding: Rounding) -> Option<u128> {
+        let amplifier = match self.pool.curve {
+            Curve::Stable { amplifier, .. } => amplifier,
+            _ => return None,
+        };
+        let reserves: Vec<_> =
+            self.pool.reserves().iter().map(|r| r.tokens).collect();
+
+        stable_curve_invariant_int::compute(amplifier, &reserves, rounding).ok()
+    }
+}
+
+fn record(
+    reserves: &[Reserve],
+    tokens: &BTreeMap<Pubkey, TokenAmount>,
+    flow: &mut [u128],
+) {
+    for (index, reserve) in reserves.iter().enumerate() {
+        flow[index] += tokens[&reserve.mint].amount as u128;
+    }
+}
+
+fn share_of(amount: u64, share: u64) -> u64 {
+    (amount as u128 * share as u128 / PERMILLION as u128) as u64
+}
+
+fn setup() -> impl Strategy<Value = Setup> {
+    (
+        2..=consts::MAX_RESERVES,
+        curve(),
+        0..50_000u64,
+        1_000..10_000_000u64,
+    )
+        .prop_flat_map(|(dimension, curve, swap_fee, base)| {
+            (
+                Just(curve),
+                Just(Permillion {
+                    permillion: swap_fee,
+                }),
+                // roughly balanced, as stable pools are meant to be
+                prop::collection::vec(base / 2..base * 2, dimension),
+                prop::collection::vec(op(dimension), 1..32),
+            )
+        })
+        .prop_map(|(curve, swap_fee, initial_deposit, ops)| Setup {
+            curve,
+            swap_fee,
+            initial_deposit,
+            ops,
+        })
+}
+
+/// Weighted pools only admit the admin's deposits and no redemptions until
+/// the bootstrapping ends, which the simulation doesn't model. The checks
+/// treat any curve other than [`Curve::Stable`] as the constant product.
+fn curve() -> impl Strategy<Value = Curve> {
+    prop_oneof![
+        Just(Curve::ConstProd),
+        (1..200u64).prop_map(|amplifier| Curve::Stable {
+            amplifier,
+            invariant: SDecimal::default(),
+        }),
+    ]
+}
+
+fn op(dimension: usize) -> impl Strategy<Value = Op> {
+    prop_oneof![
+        prop::collection::vec(1..PERMILLION, dimension).prop_map(Op::Deposit),
+        (0..dimension, 1..dimension, 1..PERMILLION / 2).prop_map(
+            move |(sell, offset, share)| Op::Swap {
+                sell,
+                buy: (sell + offset) % dimension,
+                share,
+            }
+        ),
+        (1..=PERMILLION).prop_map(|share| Op::Redeem { share }),
+        Just(Op::UpdateCurveInvariant),
+    ]
+}
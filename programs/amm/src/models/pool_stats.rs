@@ -0,0 +1,362 @@
+//! Cumulative statistics of a [`Pool`], so that analytics don't have to parse
+//! every transaction's logs. The stats account is a PDA of the pool, created
+//! by anyone with [`crate::endpoints::create_pool_stats`]. Until it's created,
+//! the endpoints which would update it skip it.
+//!
+//! The counters are updated by [`crate::endpoints::swap`],
+//! [`crate::endpoints::swap_v2`], [`crate::endpoints::deposit_liquidity`]
+//! (and its variants) and [`crate::endpoints::redeem_liquidity`]. Swaps made
+//! by [`crate::endpoints::zap_in`] and [`crate::endpoints::execute_twamm`]
+//! count as any other swap, and the seed deposit of
+//! [`crate::endpoints::add_reserve`] as any other deposit. The counters are
+//! `u128` so that they don't overflow in the lifetime of a pool, and fail the
+//! transaction rather than wrap if they do.
+
+use crate::prelude::*;
+use std::collections::BTreeMap;
+use std::mem;
+
+#[derive(Default, Debug, Eq, PartialEq)]
+#[account]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct PoolStats {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub pool: Pubkey,
+    /// One entry per pool's reserve, matched by mint. Entries of reserves
+    /// which were removed from the pool are reused by new reserves.
+    pub reserves: [ReserveStats; consts::MAX_RESERVES],
+    /// LP tokens minted to the program toll wallet.
+    pub toll_lp_minted: u128,
+    pub swap_count: u64,
+    /// Zero if there hasn't been any swap yet.
+    pub last_swap_slot: Slot,
+    /// New counters take their space from the end of this array, so that
+    /// adding them doesn't require reallocating the stats accounts.
+    pub reserved: [u64; 8],
+}
+
+#[derive(
+    AnchorDeserialize,
+    AnchorSerialize,
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Default,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct ReserveStats {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub mint: Pubkey,
+    /// Tokens sold to the pool in swaps, swap fee included.
+    pub swap_volume_in: u128,
+    /// Tokens bought from the pool in swaps.
+    pub swap_volume_out: u128,
+    /// Swap fees paid in this mint, including the program toll and referral
+    /// parts.
+    pub swap_fees: u128,
+    /// Tokens deposited to the pool as liquidity.
+    pub deposited: u128,
+    /// Tokens redeemed from the pool for LP tokens.
+    pub redeemed: u128,
+}
+
+/// What a single swap adds to the stats.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SwapRecord {
+    pub sell_mint: Pubkey,
+    /// Swap fee included.
+    pub sold: TokenAmount,
+    pub swap_fee: TokenAmount,
+    pub buy_mint: Pubkey,
+    pub bought: TokenAmount,
+    pub toll_lp_minted: TokenAmount,
+    pub slot: Slot,
+}
+
+impl PoolStats {
+    pub const PDA_PREFIX: &'static [u8; 5] = b"stats";
+
+    pub fn space() -> usize {
+        let discriminant = 8;
+        let pool = 32;
+        let reserves = (32 + 5 * 16) * consts::MAX_RESERVES;
+        let toll_lp_minted = 16;
+        let swap_count = 8;
+        let last_swap_slot = mem::size_of::<Slot>();
+        let reserved = mem::size_of::<[u64; 8]>();
+
+        discriminant
+            + pool
+            + reserves
+            + toll_lp_minted
+            + swap_count
+            + last_swap_slot
+            + reserved
+    }
+
+    pub fn pda(pool: Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[Self::PDA_PREFIX, pool.as_ref()],
+            &crate::ID,
+        )
+        .0
+    }
+
+    /// Applies the update to the stats account, whose address must've been
+    /// already checked by the caller. If the account hasn't been created yet,
+    /// does nothing.
+    pub fn update_if_created(
+        account: &AccountInfo,
+        update: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        if account.owner != &crate::ID {
+            return Ok(());
+        }
+
+        let mut stats = {
+            let data = account.try_borrow_data()?;
+            Self::try_deserialize(&mut &data[..])?
+        };
+        update(&mut stats)?;
+
+        let mut data = account.try_borrow_mut_data()?;
+        stats.try_serialize(&mut data.as_mut())
+    }
+
+    pub fn record_swap(&mut self, pool: &Pool, swap: SwapRecord) -> Result<()> {
+        let sell = self.reserve_mut(pool, swap.sell_mint)?;
+        add(&mut sell.swap_volume_in, swap.sold)?;
+        add(&mut sell.swap_fees, swap.swap_fee)?;
+
+        let buy = self.reserve_mut(pool, swap.buy_mint)?;
+        add(&mut buy.swap_volume_out, swap.bought)?;
+
+        add(&mut self.toll_lp_minted, swap.toll_lp_minted)?;
+        self.swap_count = self
+            .swap_count
+            .checked_add(1)
+            .ok_or(AmmError::MathOverflow)?;
+        self.last_swap_slot = swap.slot;
+
+        Ok(())
+    }
+
+    pub fn record_deposit(
+        &mut self,
+        pool: &Pool,
+        deposited: &BTreeMap<Pubkey, TokenAmount>,
+    ) -> Result<()> {
+        for (mint, tokens) in deposited {
+            add(&mut self.reserve_mut(pool, *mint)?.deposited, *tokens)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn record_redeem(
+        &mut self,
+        pool: &Pool,
+        redeemed: &BTreeMap<Pubkey, TokenAmount>,
+    ) -> Result<()> {
+        for (mint, tokens) in redeemed {
+            add(&mut self.reserve_mut(pool, *mint)?.redeemed, *tokens)?;
+        }
+
+        Ok(())
+    }
+
+    /// Finds the entry of given reserve's mint. If there's none yet, takes
+    /// over an entry of a mint which isn't pool's reserve (anymore), and
+    /// resets its counters.
+    fn reserve_mut(
+        &mut self,
+        pool: &Pool,
+        mint: Pubkey,
+    ) -> Result<&mut ReserveStats> {
+        if let Some(index) = self.reserves.iter().position(|r| r.mint == mint) {
+            return Ok(&mut self.reserves[index]);
+        }
+
+        let unused = self
+            .reserves
+            .iter_mut()
+            .find(|r| pool.reserve_vault(r.mint).is_none())
+            .ok_or_else(|| {
+                err::acc(format!(
+                    "Mint '{}' is not part of this pool's stats",
+                    mint
+                ))
+            })?;
+        *unused = ReserveStats {
+            mint,
+            ..Default::default()
+        };
+
+        Ok(unused)
+    }
+}
+
+fn add(counter: &mut u128, tokens: TokenAmount) -> Result<()> {
+    *counter = counter
+        .checked_add(tokens.amount as u128)
+        .ok_or(AmmError::MathOverflow)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_with_mints(mints: &[Pubkey]) -> Pool {
+        let mut reserves = [Reserve::default(); consts::MAX_RESERVES];
+        for (reserve, mint) in reserves.iter_mut().zip(mints) {
+            reserve.mint = *mint;
+            reserve.vault = Pubkey::new_unique();
+        }
+
+        Pool {
+            dimension: mints.len() as u64,
+            reserves,
+            ..Default::default()
+        }
+    }
+
+    fn swap(sell_mint: Pubkey, buy_mint: Pubkey, slot: u64) -> SwapRecord {
+        SwapRecord {
+            sell_mint,
+            sold: TokenAmount::new(100),
+            swap_fee: TokenAmount::new(1),
+            buy_mint,
+            bought: TokenAmount::new(90),
+            toll_lp_minted: TokenAmount::new(2),
+            slot: Slot::new(slot),
+        }
+    }
+
+    #[test]
+    fn it_has_correct_space() {
+        let mut data = Vec::new();
+        PoolStats::default().try_serialize(&mut data).unwrap();
+
+        assert_eq!(data.len(), PoolStats::space());
+    }
+
+    #[test]
+    fn it_records_swaps() -> Result<()> {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = pool_with_mints(&[a, b]);
+        let mut stats = PoolStats::default();
+
+        stats.record_swap(&pool, swap(a, b, 10))?;
+        stats.record_swap(&pool, swap(b, a, 12))?;
+        stats.record_swap(&pool, swap(a, b, 15))?;
+
+        let stats_of =
+            |mint| *stats.reserves.iter().find(|r| r.mint == mint).unwrap();
+        assert_eq!(
+            stats_of(a),
+            ReserveStats {
+                mint: a,
+                swap_volume_in: 200,
+                swap_volume_out: 90,
+                swap_fees: 2,
+                deposited: 0,
+                redeemed: 0,
+            }
+        );
+        assert_eq!(
+            stats_of(b),
+            ReserveStats {
+                mint: b,
+                swap_volume_in: 100,
+                swap_volume_out: 180,
+                swap_fees: 1,
+                deposited: 0,
+                redeemed: 0,
+            }
+        );
+        assert_eq!(stats.toll_lp_minted, 6);
+        assert_eq!(stats.swap_count, 3);
+        assert_eq!(stats.last_swap_slot, Slot::new(15));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_records_deposits_and_redeems() -> Result<()> {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = pool_with_mints(&[a, b]);
+        let mut stats = PoolStats::default();
+
+        let tokens: BTreeMap<_, _> =
+            [(a, TokenAmount::new(10)), (b, TokenAmount::new(20))]
+                .into_iter()
+                .collect();
+        stats.record_deposit(&pool, &tokens)?;
+        stats.record_deposit(&pool, &tokens)?;
+        stats.record_redeem(&pool, &tokens)?;
+
+        let stats_of =
+            |mint| *stats.reserves.iter().find(|r| r.mint == mint).unwrap();
+        assert_eq!(stats_of(a).deposited, 20);
+        assert_eq!(stats_of(a).redeemed, 10);
+        assert_eq!(stats_of(b).deposited, 40);
+        assert_eq!(stats_of(b).redeemed, 20);
+        assert_eq!(stats.swap_count, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_reuses_entry_of_removed_reserve() -> Result<()> {
+        let mints: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut stats = PoolStats::default();
+        stats.record_swap(
+            &pool_with_mints(&mints),
+            swap(mints[0], mints[1], 1),
+        )?;
+        stats.record_swap(
+            &pool_with_mints(&mints),
+            swap(mints[2], mints[3], 2),
+        )?;
+
+        // the first reserve was replaced by a new one
+        let new_mint = Pubkey::new_unique();
+        let pool = pool_with_mints(&[new_mint, mints[1], mints[2], mints[3]]);
+        stats.record_swap(&pool, swap(new_mint, mints[1], 3))?;
+
+        assert_eq!(stats.reserves[0].mint, new_mint);
+        assert_eq!(stats.reserves[0].swap_volume_in, 100);
+        assert_eq!(stats.reserves[1].swap_volume_out, 180);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_on_overflow() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = pool_with_mints(&[a, b]);
+        let mut stats = PoolStats::default();
+        stats.record_swap(&pool, swap(a, b, 1)).unwrap();
+        stats.reserves[0].swap_volume_in = u128::MAX;
+
+        assert!(stats.record_swap(&pool, swap(a, b, 2)).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! Before the [`Pool`] account layout was versioned, every new property
+//! changed the size of the account. Pools created back then have the layout
+//! of [`PoolV1`], which is the same as the current layout but stops right after
+//! the swap fee.
+//!
+//! Such pools are recognized by their size, see
+//! [`Pool::try_deserialize_any_version`], and upgraded in place with
+//! [`crate::endpoints::migrate_pool`].
+
+use crate::prelude::*;
+use anchor_lang::error::ErrorCode;
+use anchor_lang::Discriminator;
+use std::mem;
+
+#[derive(
+    AnchorDeserialize, AnchorSerialize, Clone, Debug, Default, Eq, PartialEq,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct PoolV1 {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub admin: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub signer: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub mint: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub program_toll_wallet: Pubkey,
+    pub dimension: u64,
+    pub reserves: [Reserve; 4],
+    pub curve: Curve,
+    pub swap_fee: Permillion,
+}
+
+impl PoolV1 {
+    pub const VERSION: u8 = 1;
+
+    pub fn space() -> usize {
+        let discriminant = 8;
+        let initializer = 32;
+        let signer = 32;
+        let lp_token_program_fee_wallet = 32;
+        let mint = 32;
+        let dimension = 8;
+        let reserves = mem::size_of::<Reserve>() * 4;
+        let curve = mem::size_of::<Curve>();
+        let fee = mem::size_of::<Permillion>();
+
+        discriminant
+            + initializer
+            + signer
+            + lp_token_program_fee_wallet
+            + mint
+            + dimension
+            + reserves
+            + curve
+            + fee
+    }
+
+    /// The discriminator is the same as the current [`Pool`]'s, because the
+    /// account type hasn't changed, only its layout.
+    pub fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+        if buf.len() < 8 || buf[..8] != Pool::discriminator() {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl From<PoolV1> for Pool {
+    fn from(pool: PoolV1) -> Self {
+        let PoolV1 {
+            admin,
+            signer,
+            mint,
+            program_toll_wallet,
+            dimension,
+            reserves,
+            curve,
+            swap_fee,
+        } = pool;
+
+        Self {
+            admin,
+            signer,
+            mint,
+            program_toll_wallet,
+            dimension,
+            reserves,
+            curve,
+            swap_fee,
+            version: PoolV1::VERSION,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_has_space_of_pool_before_versioning() {
+        // size of the pool accounts as created by the program's version 2.0.1
+        assert_eq!(PoolV1::space(), 480);
+    }
+
+    #[test]
+    fn it_round_trips_v1_pool_with_stable_curve_to_v2() -> Result<()> {
+        let v1 = v1_pool();
+        let v1_data = v1_account_data(&v1);
+
+        let pool = Pool::try_deserialize_any_version(&mut v1_data.as_slice())?;
+        assert_eq!(pool.version, PoolV1::VERSION);
+        assert_eq!(pool.admin, v1.admin);
+        assert_eq!(pool.signer, v1.signer);
+        assert_eq!(pool.mint, v1.mint);
+        assert_eq!(pool.program_toll_wallet, v1.program_toll_wallet);
+        assert_eq!(pool.dimension, v1.dimension);
+        assert_eq!(pool.reserves, v1.reserves);
+        assert_eq!(pool.curve, v1.curve);
+        assert_eq!(pool.swap_fee, v1.swap_fee);
+        assert_eq!(pool.pending_swap_fee, None);
+        assert_eq!(pool.access_policy, None);
+
+        // the strict deserializer only accepts the current layout
+        assert!(Pool::try_deserialize(&mut v1_data.as_slice()).is_err());
+
+        // this is what the migration stores into the reallocated account
+        let pool = Pool {
+            version: Pool::VERSION,
+            ..pool
+        };
+        let mut v2_data = vec![0; Pool::space()];
+        pool.try_serialize(&mut v2_data.as_mut_slice())?;
+
+        let migrated = Pool::try_deserialize(&mut v2_data.as_slice())?;
+        assert_eq!(migrated, pool);
+        assert_eq!(
+            Pool::try_deserialize_any_version(&mut v2_data.as_slice())?,
+            pool
+        );
+
+        // the stable curve's invariant still matches the reserves
+        let mut recomputed = migrated.clone();
+        recomputed.update_curve_invariant()?;
+        assert_eq!(recomputed.curve, v1.curve);
+
+        // and the migrated pool keeps working
+        let mut migrated = migrated;
+        let bought = migrated.swap(
+            v1.reserves[0].mint,
+            TokenAmount::new(1_000),
+            v1.reserves[1].mint,
+            Slot::new(0),
+        )?;
+        assert!(bought.amount > 0);
+        let mut expected = Pool::from(v1);
+        let expected_bought = expected.swap(
+            expected.reserves[0].mint,
+            TokenAmount::new(1_000),
+            expected.reserves[1].mint,
+            Slot::new(0),
+        )?;
+        assert_eq!(bought, expected_bought);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_round_trips_v1_pool_with_three_reserves() -> Result<()> {
+        let mut v1 = v1_pool();
+        v1.dimension = 3;
+        v1.reserves[2] = Reserve {
+            tokens: TokenAmount::new(3_000_000),
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        };
+        let v1_data = v1_account_data(&v1);
+
+        let pool = Pool::try_deserialize_any_version(&mut v1_data.as_slice())?;
+        assert_eq!(pool.reserves(), &v1.reserves[..3]);
+
+        let mut v2_data = vec![0; Pool::space()];
+        pool.try_serialize(&mut v2_data.as_mut_slice())?;
+        assert_eq!(Pool::try_deserialize(&mut v2_data.as_slice())?, pool);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_fails_to_deserialize_v1_pool_with_wrong_discriminator() {
+        let mut data = v1_account_data(&v1_pool());
+        data[0] = data[0].wrapping_add(1);
+
+        assert!(
+            Pool::try_deserialize_any_version(&mut data.as_slice()).is_err()
+        );
+    }
+
+    fn v1_pool() -> PoolV1 {
+        let mut reserves = [Reserve::default(); 4];
+        reserves[0] = Reserve {
+            tokens: TokenAmount::new(1_000_000),
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        };
+        reserves[1] = Reserve {
+            tokens: TokenAmount::new(2_000_000),
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        };
+
+        let mut pool = Pool {
+            dimension: 2,
+            reserves,
+            curve: Curve::Stable {
+                amplifier: 10,
+                invariant: SDecimal::default(),
+            },
+            ..Default::default()
+        };
+        pool.update_curve_invariant().unwrap();
+
+        PoolV1 {
+            admin: Pubkey::new_unique(),
+            signer: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            program_toll_wallet: Pubkey::new_unique(),
+            dimension: pool.dimension,
+            reserves: pool.reserves,
+            curve: pool.curve,
+            swap_fee: Permillion { permillion: 3_000 },
+        }
+    }
+
+    /// Account data as the program's version 2.0.1 would store it.
+    fn v1_account_data(pool: &PoolV1) -> Vec<u8> {
+        let mut data = Pool::discriminator().to_vec();
+        pool.serialize(&mut data).unwrap();
+        // the mem size of the curve is larger than its borsh size
+        data.resize(PoolV1::space(), 0);
+
+        data
+    }
+}
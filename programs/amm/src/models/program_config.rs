@@ -0,0 +1,146 @@
+//! Program config holds tunables which the program's authority can change
+//! without a redeploy, see [`crate::endpoints::put_program_config`]. There's
+//! at most one config account, and until it's created, the endpoints use
+//! defaults given by [`crate::consts`].
+
+use crate::prelude::*;
+use anchor_lang::system_program;
+
+#[account]
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct ProgramConfig {
+    /// Pool's admin cannot set the swap fee higher than this value.
+    ///
+    /// Defaults to [`consts::MAX_SWAP_FEE`].
+    pub max_swap_fee: Permillion,
+    /// The program owner gets this share of the swap fee.
+    ///
+    /// Defaults to [`consts::PROGRAM_TOLL_SWAP_FEE_SHARE`].
+    pub program_toll_swap_fee_share: Permillion,
+    /// How the pools with stable curve recompute their invariant after a swap.
+    ///
+    /// Defaults to [`StableCurveInvariantMethod::Decimal`].
+    pub stable_curve_invariant: StableCurveInvariantMethod,
+    /// New tunables take their space from the end of this array, so that
+    /// adding them doesn't require reallocating the config account.
+    pub reserved: [u64; 7],
+}
+
+impl Default for ProgramConfig {
+    fn default() -> Self {
+        Self {
+            max_swap_fee: consts::MAX_SWAP_FEE,
+            program_toll_swap_fee_share: consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+            stable_curve_invariant: StableCurveInvariantMethod::default(),
+            reserved: [0; 7],
+        }
+    }
+}
+
+impl ProgramConfig {
+    pub const PDA_SEED: &'static [u8; 6] = b"config";
+
+    pub fn space() -> usize {
+        let discriminant = 8;
+        let max_swap_fee = 8;
+        let program_toll_swap_fee_share = 8;
+        let stable_curve_invariant = 1;
+        let reserved = 8 * 7;
+
+        discriminant
+            + max_swap_fee
+            + program_toll_swap_fee_share
+            + stable_curve_invariant
+            + reserved
+    }
+
+    /// Parses the config account, whose address must've been already checked
+    /// by the caller. If the account hasn't been created yet, returns the
+    /// defaults.
+    pub fn load(account: &AccountInfo) -> Result<Self> {
+        if account.owner == &system_program::ID {
+            Ok(Self::default())
+        } else if account.owner == &crate::ID {
+            let data = account.try_borrow_data()?;
+            Self::try_deserialize(&mut &data[..])
+        } else {
+            Err(error!(err::acc(
+                "Program config must be owned by the program"
+            )))
+        }
+    }
+
+    /// Checks that the tunables are within bounds which keep the pools usable.
+    pub fn validate(&self) -> Result<()> {
+        if self.max_swap_fee > consts::MAX_SWAP_FEE_UPPER_BOUND {
+            return Err(error!(err::arg(format!(
+                "Maximum swap fee can be at most {} permillion",
+                consts::MAX_SWAP_FEE_UPPER_BOUND.permillion
+            ))));
+        }
+
+        if self.program_toll_swap_fee_share
+            > consts::MAX_PROGRAM_TOLL_SWAP_FEE_SHARE
+        {
+            return Err(error!(err::arg(format!(
+                "Program toll share can be at most {} permillion",
+                consts::MAX_PROGRAM_TOLL_SWAP_FEE_SHARE.permillion
+            ))));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_has_valid_defaults() {
+        assert!(ProgramConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn it_validates_bounds() {
+        let config = ProgramConfig {
+            max_swap_fee: consts::MAX_SWAP_FEE_UPPER_BOUND,
+            program_toll_swap_fee_share:
+                consts::MAX_PROGRAM_TOLL_SWAP_FEE_SHARE,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        assert!(ProgramConfig {
+            max_swap_fee: Permillion {
+                permillion: consts::MAX_SWAP_FEE_UPPER_BOUND.permillion + 1,
+            },
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+
+        assert!(ProgramConfig {
+            program_toll_swap_fee_share: Permillion {
+                permillion: consts::MAX_PROGRAM_TOLL_SWAP_FEE_SHARE.permillion
+                    + 1,
+            },
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn it_has_correct_space() {
+        let mut data = Vec::new();
+        ProgramConfig::default().try_serialize(&mut data).unwrap();
+
+        assert_eq!(data.len(), ProgramConfig::space());
+    }
+}
@@ -5,7 +5,16 @@
 use crate::prelude::*;
 
 #[account]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct ProgramToll {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub authority: Pubkey,
 }
 
@@ -0,0 +1,40 @@
+//! Wallets and aggregators which route swaps to our pools can be registered as
+//! referrers. When the user passes a [`Referrer`] account to
+//! [`crate::endpoints::swap`], a share of the swap fee is minted as LP tokens
+//! to the referrer's LP wallet, the same way the program toll is paid.
+//!
+//! The [`Referrer`] is always stored in an account with a PDA address for which
+//! the referrer's authority pubkey is a seed.
+
+use crate::prelude::*;
+
+#[account]
+#[derive(Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct Referrer {
+    /// Owner of the LP wallets which receive the referral fees.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub authority: Pubkey,
+    /// What share of the swap fee is paid to the referrer. Can be at most
+    /// [`consts::MAX_REFERRER_SWAP_FEE_SHARE`].
+    pub share: Permillion,
+}
+
+impl Referrer {
+    pub const PDA_PREFIX: &'static [u8; 8] = b"referrer";
+
+    pub fn space() -> usize {
+        let discriminant = 8;
+        let authority = 32;
+        let share = 8;
+
+        discriminant + authority + share
+    }
+}
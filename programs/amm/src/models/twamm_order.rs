@@ -0,0 +1,378 @@
+//! A time-weighted average market maker (TWAMM) order sells a given amount of
+//! tokens against a [`Pool`] evenly over a period of slots. Instead of the user
+//! swapping many small amounts, a permissionless crank
+//! [`crate::endpoints::execute_twamm`] periodically trades all the sell tokens
+//! which became due since the last execution in a single swap.
+//!
+//! The order holds the tokens which are yet to be sold in the sell vault and
+//! the proceeds in the buy vault. Both vaults are owned by a PDA signer with
+//! the order's pubkey as a seed.
+//!
+//! Since anyone can run the crank, anyone can also move the pool's price right
+//! before an execution and back after it. The order's optional maximum price
+//! bounds how much such a sandwich can take from the order.
+
+use crate::prelude::*;
+use std::mem;
+
+#[account]
+#[derive(Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct TwammOrder {
+    /// The order trades against this pool.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub pool: Pubkey,
+    /// Can withdraw the proceeds and the unsold tokens.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub authority: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub sell_mint: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub buy_mint: Pubkey,
+    /// Token account owned by the order's signer, holds the tokens which
+    /// haven't been sold yet.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub sell_vault: Pubkey,
+    /// Token account owned by the order's signer, holds the proceeds until
+    /// they're withdrawn.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
+    pub buy_vault: Pubkey,
+    /// How many tokens the order sells in total, including the swap fee.
+    pub sell: TokenAmount,
+    /// How many tokens have been sold so far, including the swap fee.
+    pub sold: TokenAmount,
+    /// How many tokens have been bought so far.
+    pub bought: TokenAmount,
+    /// How many of the bought tokens haven't been withdrawn yet.
+    pub proceeds: TokenAmount,
+    /// The order flow starts accumulating after this slot.
+    pub start: Slot,
+    /// By this slot the whole `sell` amount is due.
+    pub end: Slot,
+    /// If set, an execution pays at most this many sell tokens per bought
+    /// token, swap fee included. An execution at a worse price fails and the
+    /// flow keeps accumulating.
+    pub max_price: Option<SDecimal>,
+}
+
+/// Result of one execution of the accumulated order flow.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct TwammExecution {
+    /// Tokens which flow from the order's sell vault to the pool, including
+    /// the swap fee.
+    pub sold: TokenAmount,
+    /// Part of the sold tokens which is the swap fee.
+    pub swap_fee: TokenAmount,
+    /// Tokens which flow from the pool to the order's buy vault.
+    pub bought: TokenAmount,
+}
+
+impl TwammOrder {
+    pub const SIGNER_PDA_PREFIX: &'static [u8; 12] = b"twamm_signer";
+
+    pub fn space() -> usize {
+        let discriminant = 8;
+        let pool = 32;
+        let authority = 32;
+        let sell_mint = 32;
+        let buy_mint = 32;
+        let sell_vault = 32;
+        let buy_vault = 32;
+        let sell = 8;
+        let sold = 8;
+        let bought = 8;
+        let proceeds = 8;
+        let start = 8;
+        let end = 8;
+        let max_price = 1 + mem::size_of::<SDecimal>();
+
+        discriminant
+            + pool
+            + authority
+            + sell_mint
+            + buy_mint
+            + sell_vault
+            + buy_vault
+            + sell
+            + sold
+            + bought
+            + proceeds
+            + start
+            + end
+            + max_price
+    }
+
+    /// How many tokens became due for sale since the last execution. The
+    /// order flow is linear in slots between `start` and `end`.
+    pub fn sell_due(&self, now: Slot) -> Result<TokenAmount> {
+        if now <= self.start {
+            return Ok(TokenAmount::new(0));
+        }
+
+        let duration = self.end.slot - self.start.slot;
+        let elapsed = now.min(self.end).slot - self.start.slot;
+        let due_in_total = (self.sell.amount as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(AmmError::MathOverflow)?
+            / duration as u128;
+
+        // can't underflow because the sold amount is always due
+        Ok(TokenAmount::new(due_in_total as u64 - self.sold.amount))
+    }
+
+    /// Trades the order flow accumulated since the last execution against the
+    /// pool. The pool's reserves and the order's state are updated.
+    ///
+    /// If the accumulated flow is so small that it would buy nothing, nothing
+    /// is traded and [`None`] is returned. The flow keeps accumulating until
+    /// the next execution.
+    ///
+    /// Fails if the execution would pay more than the order's maximum price.
+    pub fn execute(
+        &mut self,
+        pool: &mut Pool,
+        now: Slot,
+    ) -> Result<Option<TwammExecution>> {
+        let sell = self.sell_due(now)?;
+        if sell.amount == 0 {
+            return Ok(None);
+        }
+
+        let swap_fee = TokenAmount::new(
+            Decimal::from(sell)
+                .try_mul(Decimal::from(pool.swap_fee))?
+                .try_ceil()?,
+        );
+        let tokens_to_swap = TokenAmount::new(sell.amount - swap_fee.amount);
+        if tokens_to_swap.amount == 0 {
+            return Ok(None);
+        }
+
+        // the pool is only written to if the execution buys something
+        let mut pool_after = pool.clone();
+        let bought = pool_after.swap(
+            self.sell_mint,
+            tokens_to_swap,
+            self.buy_mint,
+            now,
+        )?;
+        if bought.amount == 0 {
+            return Ok(None);
+        }
+        // the swap fee stays in the reserve, same as on a regular swap
+        pool_after
+            .reserve_mut(self.sell_mint)
+            .ok_or(AmmError::InvariantViolation)?
+            .add_tokens(swap_fee)?;
+
+        if let Some(max_price) = self.max_price {
+            // `sell / bought > max_price` without dividing
+            let max_price = Decimal::from(max_price);
+            if Decimal::from(sell) > max_price.try_mul(Decimal::from(bought))? {
+                msg!(
+                    "Selling {} for {} is above the order's maximum price \
+                    of {} sell tokens per bought token",
+                    sell.amount,
+                    bought.amount,
+                    max_price
+                );
+                return Err(error!(AmmError::LimitPriceExceeded));
+            }
+        }
+
+        *pool = pool_after;
+
+        self.sold.amount += sell.amount;
+        self.bought = self
+            .bought
+            .checked_add(bought)
+            .ok_or(AmmError::MathOverflow)?;
+        self.proceeds = self
+            .proceeds
+            .checked_add(bought)
+            .ok_or(AmmError::MathOverflow)?;
+
+        Ok(Some(TwammExecution {
+            sold: sell,
+            swap_fee,
+            bought,
+        }))
+    }
+
+    /// Tokens which are still in the sell vault.
+    pub fn unsold(&self) -> TokenAmount {
+        TokenAmount::new(self.sell.amount - self.sold.amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::test_utils::set_clock;
+    use serial_test::serial;
+
+    #[test]
+    fn it_has_correct_space() {
+        let order = TwammOrder {
+            max_price: Some(SDecimal::default()),
+            ..Default::default()
+        };
+
+        assert_eq!(TwammOrder::space(), order.try_to_vec().unwrap().len() + 8);
+    }
+
+    #[test]
+    fn it_calculates_linear_sell_due() {
+        let mut order = order(1_000, 100, 200);
+
+        assert_eq!(order.sell_due(Slot::new(50)).unwrap().amount, 0);
+        assert_eq!(order.sell_due(Slot::new(100)).unwrap().amount, 0);
+        assert_eq!(order.sell_due(Slot::new(101)).unwrap().amount, 10);
+        assert_eq!(order.sell_due(Slot::new(150)).unwrap().amount, 500);
+        assert_eq!(order.sell_due(Slot::new(200)).unwrap().amount, 1_000);
+        assert_eq!(order.sell_due(Slot::new(1_000)).unwrap().amount, 1_000);
+
+        order.sold = TokenAmount::new(500);
+        assert_eq!(order.sell_due(Slot::new(150)).unwrap().amount, 0);
+        assert_eq!(order.sell_due(Slot::new(175)).unwrap().amount, 250);
+        assert_eq!(order.sell_due(Slot::new(201)).unwrap().amount, 500);
+    }
+
+    #[test]
+    fn it_accumulates_flow_which_buys_nothing() {
+        let mut pool = pool(1_000_000, 1_000_000);
+        let mut order = order(100, 0, 1_000);
+
+        // 1 token is due and it's taken entirely as the fee
+        assert_eq!(order.execute(&mut pool, Slot::new(10)).unwrap(), None);
+        assert_eq!(order.sold.amount, 0);
+
+        let execution =
+            order.execute(&mut pool, Slot::new(100)).unwrap().unwrap();
+        assert_eq!(execution.sold.amount, 10);
+        assert_eq!(execution.swap_fee.amount, 1);
+        assert_eq!(execution.bought.amount, 8);
+        assert_eq!(order.sold.amount, 10);
+        assert_eq!(order.proceeds.amount, 8);
+    }
+
+    #[test]
+    fn it_fails_execution_above_max_price() {
+        let mut pool = pool(1_000_000, 1_000_000);
+        let mut order = order(10_000, 0, 100);
+        // with the 1% fee and ~1% price impact the price is ~1.02
+        order.max_price =
+            Some(Decimal::from(Permillion::from_percent(101)).into());
+
+        // a sandwich moved the price before the execution
+        pool.reserves[0].tokens = TokenAmount::new(1_100_000);
+        pool.reserves[1].tokens = TokenAmount::new(909_091);
+        let pool_before = pool.clone();
+        assert!(order
+            .execute(&mut pool, Slot::new(100))
+            .unwrap_err()
+            .to_string()
+            .contains("LimitPriceExceeded"));
+        assert_eq!(pool, pool_before);
+        assert_eq!(order.sold.amount, 0);
+
+        // at the undisturbed price a higher limit is fine
+        let mut pool = self::pool(1_000_000, 1_000_000);
+        order.max_price =
+            Some(Decimal::from(Permillion::from_percent(103)).into());
+        let execution =
+            order.execute(&mut pool, Slot::new(100)).unwrap().unwrap();
+        assert_eq!(execution.sold.amount, 10_000);
+    }
+
+    #[test]
+    #[serial]
+    fn it_sells_everything_over_the_duration() {
+        // simulates a crank which is called every few slots with the slot
+        // provided by the clock stub
+        let mut pool = pool(1_000_000, 1_000_000);
+        let mut order = order(10_000, 10, 1_010);
+
+        let mut executions = 0;
+        for slot in (10..=1_100).step_by(7) {
+            set_clock(Slot::new(slot));
+            if order
+                .execute(&mut pool, Slot::current().unwrap())
+                .unwrap()
+                .is_some()
+            {
+                executions += 1;
+            }
+        }
+
+        assert!(executions > 100);
+        assert_eq!(order.sold.amount, 10_000);
+        assert_eq!(order.unsold().amount, 0);
+        assert_eq!(order.bought, order.proceeds);
+        assert_eq!(pool.reserves[0].tokens.amount, 1_010_000);
+        assert_eq!(
+            pool.reserves[1].tokens.amount,
+            1_000_000 - order.bought.amount
+        );
+        // selling in small chunks the price impact is about the same as with
+        // one swap of the same amount, ie. ~1%, plus the fee which is rounded
+        // up on each execution
+        assert!(order.bought.amount > 9_600);
+        assert!(order.bought.amount < 9_850);
+    }
+
+    fn order(sell: u64, start: u64, end: u64) -> TwammOrder {
+        TwammOrder {
+            sell_mint: Pubkey::new_from_array([1; 32]),
+            buy_mint: Pubkey::new_from_array([2; 32]),
+            sell: TokenAmount::new(sell),
+            start: Slot::new(start),
+            end: Slot::new(end),
+            ..Default::default()
+        }
+    }
+
+    fn pool(sell_reserve: u64, buy_reserve: u64) -> Pool {
+        Pool {
+            dimension: 2,
+            swap_fee: Permillion::from_percent(1),
+            reserves: [
+                Reserve {
+                    mint: Pubkey::new_from_array([1; 32]),
+                    vault: Pubkey::new_unique(),
+                    tokens: TokenAmount::new(sell_reserve),
+                },
+                Reserve {
+                    mint: Pubkey::new_from_array([2; 32]),
+                    vault: Pubkey::new_unique(),
+                    tokens: TokenAmount::new(buy_reserve),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Minimal interface of the Metaplex token metadata program, which wallets
+//! use to display a token's name, symbol and image. We only need to create and
+//! update metadata of pools' LP mints, and to read symbols of reserve mints,
+//! so we build those instructions ourselves instead of depending on the
+//! Metaplex crate.
+//!
+//! The layouts follow `mpl-token-metadata` v1, instructions
+//! `CreateMetadataAccountV2` and `UpdateMetadataAccountV2`.
+
+use crate::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+
+declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzJb6a8bt518x1s");
+
+/// Metadata PDAs are derived from this prefix, the program id and the mint.
+pub const PDA_PREFIX: &[u8; 8] = b"metadata";
+/// The token metadata program rejects longer names.
+pub const MAX_NAME_LEN: usize = 32;
+/// The token metadata program rejects longer symbols.
+pub const MAX_SYMBOL_LEN: usize = 10;
+/// The token metadata program rejects longer uris.
+pub const MAX_URI_LEN: usize = 200;
+
+const CREATE_METADATA_ACCOUNT_V2: u8 = 16;
+const UPDATE_METADATA_ACCOUNT_V2: u8 = 15;
+
+/// To be used as `Program<'info, TokenMetadata>` in accounts structs.
+#[derive(Clone)]
+pub struct TokenMetadata;
+
+impl anchor_lang::Id for TokenMetadata {
+    fn id() -> Pubkey {
+        ID
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct DataV2 {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub collection: Option<Collection>,
+    pub uses: Option<Uses>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Uses {
+    /// 0 is burn, 1 is multiple and 2 is single use
+    pub use_method: u8,
+    pub remaining: u64,
+    pub total: u64,
+}
+
+#[derive(AnchorSerialize)]
+struct CreateMetadataAccountArgsV2 {
+    data: DataV2,
+    is_mutable: bool,
+}
+
+#[derive(AnchorSerialize)]
+struct UpdateMetadataAccountArgsV2 {
+    data: Option<DataV2>,
+    update_authority: Option<Pubkey>,
+    primary_sale_happened: Option<bool>,
+    is_mutable: Option<bool>,
+}
+
+/// The beginning of the metadata account, which is all we need to read.
+#[derive(AnchorDeserialize)]
+struct MetadataPrefix {
+    _key: u8,
+    _update_authority: Pubkey,
+    mint: Pubkey,
+    _name: String,
+    symbol: String,
+}
+
+impl DataV2 {
+    /// LP tokens have no royalties, creators, collection nor uses.
+    pub fn new(name: String, symbol: String, uri: String) -> Self {
+        Self {
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.name.len() > MAX_NAME_LEN {
+            return Err(error!(err::arg(format!(
+                "Name can be at most {} bytes long",
+                MAX_NAME_LEN
+            ))));
+        }
+
+        if self.symbol.len() > MAX_SYMBOL_LEN {
+            return Err(error!(err::arg(format!(
+                "Symbol can be at most {} bytes long",
+                MAX_SYMBOL_LEN
+            ))));
+        }
+
+        if self.uri.len() > MAX_URI_LEN {
+            return Err(error!(err::arg(format!(
+                "Uri can be at most {} bytes long",
+                MAX_URI_LEN
+            ))));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn metadata_address(mint: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PDA_PREFIX, ID.as_ref(), mint.as_ref()], &ID)
+        .0
+}
+
+/// Reads the symbol of given mint from its metadata account. Returns [`None`]
+/// if the account isn't the mint's metadata, or if its symbol is empty.
+pub fn read_symbol(metadata: &AccountInfo, mint: Pubkey) -> Option<String> {
+    if metadata.owner != &ID || metadata.key() != metadata_address(mint) {
+        return None;
+    }
+
+    let data = metadata.try_borrow_data().ok()?;
+    let prefix = MetadataPrefix::deserialize(&mut &data[..]).ok()?;
+    if prefix.mint != mint {
+        return None;
+    }
+
+    // the metadata program pads the strings with null bytes
+    let symbol = prefix.symbol.trim_end_matches('\0').trim();
+    if symbol.is_empty() {
+        None
+    } else {
+        Some(symbol.to_string())
+    }
+}
+
+pub fn create_metadata_account_v2(
+    metadata: Pubkey,
+    mint: Pubkey,
+    mint_authority: Pubkey,
+    payer: Pubkey,
+    update_authority: Pubkey,
+    data: DataV2,
+) -> Instruction {
+    let args = CreateMetadataAccountArgsV2 {
+        data,
+        is_mutable: true,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(mint_authority, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(update_authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data: instruction_data(CREATE_METADATA_ACCOUNT_V2, &args),
+    }
+}
+
+pub fn update_metadata_account_v2(
+    metadata: Pubkey,
+    update_authority: Pubkey,
+    data: DataV2,
+) -> Instruction {
+    let args = UpdateMetadataAccountArgsV2 {
+        data: Some(data),
+        update_authority: None,
+        primary_sale_happened: None,
+        is_mutable: None,
+    };
+
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(update_authority, true),
+        ],
+        data: instruction_data(UPDATE_METADATA_ACCOUNT_V2, &args),
+    }
+}
+
+fn instruction_data(tag: u8, args: &impl AnchorSerialize) -> Vec<u8> {
+    let mut data = vec![tag];
+    // serialization into a vec cannot fail
+    args.serialize(&mut data).unwrap();
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_serializes_update_instruction() {
+        let ix = update_metadata_account_v2(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            DataV2::new("A".to_string(), "B".to_string(), "".to_string()),
+        );
+
+        let mut expected = vec![UPDATE_METADATA_ACCOUNT_V2];
+        expected.push(1); // some data
+        expected.extend_from_slice(&[1, 0, 0, 0, b'A']); // name
+        expected.extend_from_slice(&[1, 0, 0, 0, b'B']); // symbol
+        expected.extend_from_slice(&[0, 0, 0, 0]); // uri
+        expected.extend_from_slice(&[0, 0]); // seller fee
+        expected.extend_from_slice(&[0, 0, 0]); // no creators, collection, uses
+        expected.push(0); // update authority unchanged
+        expected.push(0); // primary sale happened unchanged
+        expected.push(0); // is mutable unchanged
+        assert_eq!(ix.data, expected);
+    }
+
+    #[test]
+    fn it_reads_padded_symbol() {
+        let mint = Pubkey::new_unique();
+        let key = metadata_address(mint);
+
+        let mut data = vec![4]; // metadata account key
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(mint.as_ref());
+        "Coin\0\0".to_string().serialize(&mut data).unwrap();
+        "USDC\0\0\0\0\0\0".to_string().serialize(&mut data).unwrap();
+
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &ID,
+            false,
+            0,
+        );
+        assert_eq!(read_symbol(&account, mint), Some("USDC".to_string()));
+        assert_eq!(read_symbol(&account, Pubkey::new_unique()), None);
+    }
+}
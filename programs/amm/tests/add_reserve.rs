@@ -0,0 +1,591 @@
+use ::amm::amm::add_reserve;
+use ::amm::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, MintExt, TokenAccountExt},
+    stub,
+};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_option::COption;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::default();
+    let pool_before = test.pool_copy();
+
+    // 1 token of the new mint is worth 2 tokens of the first reserve
+    test.add_reserve(ReservePrice {
+        reference_mint: pool_before.reserves[0].mint,
+        reference_tokens: TokenAmount::new(2),
+        tokens: TokenAmount::new(1),
+    })?;
+
+    let pool = test.pool_copy();
+    assert_eq!(pool.dimension, 3);
+    assert_eq!(
+        pool.reserves[2],
+        Reserve {
+            mint: test.new_mint(),
+            vault: test.vault.key,
+            tokens: TokenAmount::new(500),
+        }
+    );
+    assert_eq!(&pool.reserves[..2], pool_before.reserves());
+
+    // 10_000 / 2
+    assert_eq!(test.lp_supply(), 15_000);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn records_seed_deposit_in_pool_stats() -> Result<()> {
+    let mut test = Tester::default().pool_stats();
+    let price = ReservePrice {
+        reference_tokens: TokenAmount::new(2),
+        ..test.default_price()
+    };
+
+    // same seed as in `works`
+    test.add_reserve(price)?;
+
+    let stats =
+        PoolStats::try_deserialize(&mut test.pool_stats.data.as_slice())?;
+    assert_eq!(
+        stats.reserves[0],
+        ReserveStats {
+            mint: test.new_mint(),
+            deposited: 500,
+            ..Default::default()
+        }
+    );
+    assert_eq!(stats.swap_count, 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn works_with_stable_curve() -> Result<()> {
+    let mut test = Tester::with_curve(Curve::Stable {
+        amplifier: 10,
+        invariant: Default::default(),
+    });
+    let pool_before = test.pool_copy();
+
+    test.add_reserve(ReservePrice {
+        reference_mint: pool_before.reserves[1].mint,
+        reference_tokens: TokenAmount::new(1),
+        tokens: TokenAmount::new(1),
+    })?;
+
+    let pool = test.pool_copy();
+    assert_eq!(pool.dimension, 3);
+    // mean of the reserves as all tokens of a stable pool are worth the same
+    assert_eq!(pool.reserves[2].tokens, TokenAmount::new(1_500));
+    assert_ne!(pool.curve, pool_before.curve);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn adds_empty_reserve_if_pool_has_no_liquidity() -> Result<()> {
+    let mut test = Tester::default();
+    let mut pool = test.pool_copy();
+    pool.reserves[0].tokens = TokenAmount::new(0);
+    pool.reserves[1].tokens = TokenAmount::new(0);
+    test.pool = test.pool.clone().data(pool.clone());
+    test.lp_mint = test.lp_mint.clone().pack(
+        spl::mint::from_acc_info(&test.lp_mint.to_account_info()).supply(0),
+    );
+
+    test.add_reserve(ReservePrice::default())?;
+
+    let pool = test.pool_copy();
+    assert_eq!(pool.dimension, 3);
+    assert_eq!(pool.reserves[2].tokens, TokenAmount::new(0));
+    assert_eq!(test.lp_supply(), 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::default();
+    test.admin = AccountInfoWrapper::new().signer();
+
+    assert!(test
+        .add_reserve(test.default_price())
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_reference_mint_is_not_in_pool() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test
+        .add_reserve(ReservePrice {
+            reference_mint: Pubkey::new_unique(),
+            reference_tokens: TokenAmount::new(1),
+            tokens: TokenAmount::new(1),
+        })
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidTokenMints"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_price_makes_seed_dust() -> Result<()> {
+    let mut test = Tester::default();
+    let reference_mint = test.pool_copy().reserves[0].mint;
+
+    // the admin would get a third of the pool for a single token
+    assert!(test
+        .add_reserve(ReservePrice {
+            reference_mint,
+            reference_tokens: TokenAmount::new(u64::MAX),
+            tokens: TokenAmount::new(1),
+        })
+        .unwrap_err()
+        .to_string()
+        .contains("SeedValueOutOfBounds"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_stable_price_is_off_parity() -> Result<()> {
+    let mut test = Tester::with_curve(Curve::Stable {
+        amplifier: 10,
+        invariant: Default::default(),
+    });
+    let reference_mint = test.pool_copy().reserves[0].mint;
+
+    assert!(test
+        .add_reserve(ReservePrice {
+            reference_mint,
+            reference_tokens: TokenAmount::new(100),
+            tokens: TokenAmount::new(1),
+        })
+        .unwrap_err()
+        .to_string()
+        .contains("SeedValueOutOfBounds"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_mint_already_in_pool() -> Result<()> {
+    let mut test = Tester::default();
+    let existing_mint = test.pool_copy().reserves[0].mint;
+    test.vault = test.vault.clone().pack(
+        spl::token_account::from_acc_info(&test.vault.to_account_info())
+            .mint(existing_mint),
+    );
+    test.admin_wallet = test.admin_wallet.clone().pack(
+        spl::token_account::from_acc_info(&test.admin_wallet.to_account_info())
+            .mint(existing_mint),
+    );
+
+    assert!(test
+        .add_reserve(test.default_price())
+        .unwrap_err()
+        .to_string()
+        .contains("DuplicateReserve"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_has_max_reserves() -> Result<()> {
+    let mut test = Tester::default();
+    let mut pool = test.pool_copy();
+    for index in 2..consts::MAX_RESERVES {
+        pool.reserves[index] = Reserve {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            tokens: TokenAmount::new(1_000),
+        };
+    }
+    pool.dimension = consts::MAX_RESERVES as u64;
+    test.pool = test.pool.clone().data(pool);
+
+    assert!(test
+        .add_reserve(test.default_price())
+        .unwrap_err()
+        .to_string()
+        .contains("TooManyReserves"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_not_empty() -> Result<()> {
+    let mut test = Tester::default();
+    test.vault = test.vault.clone().pack(
+        spl::token_account::from_acc_info(&test.vault.to_account_info())
+            .amount(10),
+    );
+
+    assert!(test
+        .add_reserve(test.default_price())
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_has_delegate() -> Result<()> {
+    let mut test = Tester::default();
+    test.vault = test.vault.clone().pack({
+        let mut vault =
+            spl::token_account::from_acc_info(&test.vault.to_account_info());
+        vault.delegate = COption::Some(Pubkey::new_unique());
+
+        vault
+    });
+
+    assert!(test
+        .add_reserve(test.default_price())
+        .unwrap_err()
+        .to_string()
+        .contains("VaultHasDelegate"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_owner_is_not_pool_signer() -> Result<()> {
+    let mut test = Tester::default();
+    test.vault = test.vault.clone().pack({
+        let mut vault =
+            spl::token_account::from_acc_info(&test.vault.to_account_info());
+        vault.owner = Pubkey::new_unique();
+
+        vault
+    });
+
+    assert!(test
+        .add_reserve(test.default_price())
+        .unwrap_err()
+        .to_string()
+        .contains("VaultOwnerNotPoolSigner"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    pool_signer: AccountInfoWrapper,
+    lp_mint: AccountInfoWrapper,
+    admin_lp_wallet: AccountInfoWrapper,
+    vault: AccountInfoWrapper,
+    admin_wallet: AccountInfoWrapper,
+    token_program: AccountInfoWrapper,
+    pool_stats: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        Self::with_curve(Curve::ConstProd)
+    }
+}
+
+impl Tester {
+    fn with_curve(curve: Curve) -> Self {
+        let admin = AccountInfoWrapper::new().signer();
+        let pool = AccountInfoWrapper::new().owner(amm::ID).mutable();
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let lp_mint = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::mint::new(pool_signer.key).supply(10_000))
+            .owner(token::ID);
+        let admin_lp_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::token_account::new(admin.key).mint(lp_mint.key))
+            .owner(token::ID);
+        let new_mint = Pubkey::new_unique();
+        let vault = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::token_account::new(pool_signer.key).mint(new_mint))
+            .owner(token::ID);
+        let admin_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(
+                spl::token_account::new(admin.key)
+                    .mint(new_mint)
+                    .amount(1_000_000),
+            )
+            .owner(token::ID);
+        let token_program = AccountInfoWrapper::with_key(token::ID).program();
+        let pool_stats = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_stats",
+            &[PoolStats::PDA_PREFIX, pool.key.as_ref()],
+        )
+        .mutable()
+        .owner(system_program::ID);
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+
+        let mut pool_data = Pool {
+            admin: admin.key,
+            signer: pool_signer.key,
+            mint: lp_mint.key,
+            dimension: 2,
+            curve,
+            reserves: [
+                Reserve {
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                    tokens: TokenAmount::new(1_000),
+                },
+                Reserve {
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                    tokens: TokenAmount::new(2_000),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        };
+        pool_data.update_curve_invariant().unwrap();
+        let pool = pool.data(pool_data);
+
+        Self {
+            admin,
+            pool,
+            pool_signer,
+            lp_mint,
+            admin_lp_wallet,
+            vault,
+            admin_wallet,
+            token_program,
+            pool_stats,
+            program_config,
+        }
+    }
+
+    fn pool_stats(mut self) -> Self {
+        self.pool_stats = self.pool_stats.owner(amm::ID).data(PoolStats {
+            pool: self.pool.key,
+            ..Default::default()
+        });
+        self
+    }
+
+    fn default_price(&self) -> ReservePrice {
+        ReservePrice {
+            reference_mint: self.pool_copy().reserves[0].mint,
+            reference_tokens: TokenAmount::new(1),
+            tokens: TokenAmount::new(1),
+        }
+    }
+
+    fn new_mint(&self) -> Pubkey {
+        spl::token_account::from_acc_info(&self.vault.to_account_info()).mint
+    }
+
+    fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    fn lp_supply(&self) -> u64 {
+        spl::mint::from_acc_info(&self.lp_mint.to_account_info()).supply
+    }
+
+    fn add_reserve(&mut self, price: ReservePrice) -> Result<()> {
+        let pool = self.pool_copy();
+        let lp_supply = self.lp_supply();
+        let state = if lp_supply == 0 {
+            CpiValidatorState::Done
+        } else {
+            CpiValidatorState::TransferSeed {
+                admin: self.admin.key,
+                wallet: self.admin_wallet.key,
+                vault: self.vault.key,
+                tokens: pool.seed_tokens(price).unwrap_or_default(),
+                next_cpi: MintLpTokens {
+                    mint: self.lp_mint.key,
+                    destination: self.admin_lp_wallet.key,
+                    pool_signer: self.pool_signer.key,
+                    tokens: TokenAmount::new(lp_supply / pool.dimension),
+                },
+            }
+        };
+        let syscalls = self.set_syscalls(state);
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        add_reserve(ctx.build(&mut accounts), price)?;
+        accounts.exit(&amm::ID)?;
+
+        let CpiValidator(state) =
+            (*syscalls.validator().lock().unwrap()).clone();
+        assert_eq!(*state.lock().unwrap(), CpiValidatorState::Done);
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.lp_mint)
+            .acc(&mut self.admin_lp_wallet)
+            .acc(&mut self.vault)
+            .acc(&mut self.admin_wallet)
+            .acc(&mut self.token_program)
+            .acc(&mut self.pool_stats)
+            .acc(&mut self.program_config)
+    }
+
+    fn set_syscalls(
+        &self,
+        state: CpiValidatorState,
+    ) -> stub::Syscalls<CpiValidator> {
+        let validator = CpiValidator(Arc::new(Mutex::new(state)));
+        let syscalls = stub::Syscalls::new(validator);
+        syscalls.clone().set();
+
+        syscalls
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CpiValidator(Arc<Mutex<CpiValidatorState>>);
+
+#[derive(Debug, PartialEq, Eq)]
+enum CpiValidatorState {
+    TransferSeed {
+        admin: Pubkey,
+        wallet: Pubkey,
+        vault: Pubkey,
+        tokens: TokenAmount,
+        next_cpi: MintLpTokens,
+    },
+    MintLpTokens(MintLpTokens),
+    Done,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct MintLpTokens {
+    mint: Pubkey,
+    destination: Pubkey,
+    pool_signer: Pubkey,
+    tokens: TokenAmount,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut state = self.0.lock().unwrap();
+
+        match *state {
+            CpiValidatorState::TransferSeed {
+                admin,
+                wallet,
+                vault,
+                tokens,
+                ref next_cpi,
+            } => {
+                let expected_ix = token::spl_token::instruction::transfer(
+                    &token::ID,
+                    &wallet,
+                    &vault,
+                    &admin,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                let from_wallet = &accounts[0];
+                let to_vault = &accounts[1];
+                assert_eq!(from_wallet.key(), wallet);
+                assert_eq!(to_vault.key(), vault);
+
+                spl::token_account::transfer(
+                    &from_wallet,
+                    &to_vault,
+                    tokens.amount,
+                )
+                .expect("Source wallet does not have enough tokens");
+
+                *state = CpiValidatorState::MintLpTokens(next_cpi.clone());
+            }
+            CpiValidatorState::MintLpTokens(MintLpTokens {
+                mint,
+                destination,
+                pool_signer,
+                tokens,
+            }) => {
+                let expected_ix = token::spl_token::instruction::mint_to(
+                    &token::ID,
+                    &mint,
+                    &destination,
+                    &pool_signer,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                let wallet = &accounts[0];
+                let lp_mint = &accounts[1];
+                assert_eq!(wallet.key(), destination);
+                assert_eq!(lp_mint.key(), mint);
+
+                spl::mint::mint_to(wallet, lp_mint, tokens.amount)
+                    .expect("Cannot mint LP tokens");
+
+                *state = CpiValidatorState::Done;
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use ::amm::amm::cancel_swap_fee;
+use ::amm::prelude::*;
+use anchortest::builder::*;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::default();
+
+    test.cancel_swap_fee()?;
+
+    let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+    assert_eq!(pool.pending_swap_fee, None);
+    assert_eq!(pool.swap_fee, Permillion::from_percent(1));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_there_is_no_pending_fee() -> Result<()> {
+    let mut test = Tester::default();
+    test.cancel_swap_fee()?;
+
+    assert!(test
+        .cancel_swap_fee()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::default();
+    test.admin = AccountInfoWrapper::new().signer();
+
+    assert!(test
+        .cancel_swap_fee()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let admin = AccountInfoWrapper::new().signer();
+        let pool =
+            AccountInfoWrapper::new()
+                .mutable()
+                .owner(amm::ID)
+                .data(Pool {
+                    admin: admin.key,
+                    swap_fee: Permillion::from_percent(1),
+                    pending_swap_fee: Some(PendingSwapFee {
+                        fee: Permillion { permillion: 5_000 },
+                        effective_at: Slot::new(100),
+                    }),
+                    ..Default::default()
+                });
+
+        Self { admin, pool }
+    }
+}
+
+impl Tester {
+    fn cancel_swap_fee(&mut self) -> Result<()> {
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        cancel_swap_fee(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+    }
+}
@@ -0,0 +1,114 @@
+use ::amm::amm::check_pool_integrity;
+use ::amm::prelude::*;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, TokenAccountExt},
+};
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn works_if_vaults_match_reserves() -> Result<()> {
+    let mut test = Tester::new(&[100, 200, 300], &[100, 200, 300]);
+
+    test.check_pool_integrity()
+}
+
+#[test]
+#[serial]
+fn works_if_vault_has_surplus() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[100, 250]);
+
+    test.check_pool_integrity()
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_has_deficit() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[100, 199]);
+
+    assert!(test
+        .check_pool_integrity()
+        .unwrap_err()
+        .to_string()
+        .contains("InvariantViolation"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_duplicated() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[100, 200]);
+    test.vaults[1] = test.vaults[0].clone();
+
+    assert!(test
+        .check_pool_integrity()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    pool: AccountInfoWrapper,
+    vaults: Vec<AccountInfoWrapper>,
+}
+
+impl Tester {
+    fn new(reserve_amounts: &[u64], vault_amounts: &[u64]) -> Self {
+        let pool = AccountInfoWrapper::new().owner(amm::ID);
+
+        let mut reserves = [Reserve::default(); consts::MAX_RESERVES];
+        let vaults = reserve_amounts
+            .iter()
+            .zip(vault_amounts)
+            .enumerate()
+            .map(|(index, (reserve_amount, vault_amount))| {
+                let mint = Pubkey::new_unique();
+                let vault = AccountInfoWrapper::new()
+                    .pack(
+                        spl::token_account::new(Pubkey::new_unique())
+                            .mint(mint)
+                            .amount(*vault_amount),
+                    )
+                    .owner(token::ID);
+
+                reserves[index] = Reserve {
+                    mint,
+                    vault: vault.key,
+                    tokens: TokenAmount::new(*reserve_amount),
+                };
+
+                vault
+            })
+            .collect();
+
+        let pool = pool.data(Pool {
+            dimension: reserve_amounts.len() as u64,
+            reserves,
+            ..Default::default()
+        });
+
+        Self { pool, vaults }
+    }
+
+    fn check_pool_integrity(&mut self) -> Result<()> {
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        check_pool_integrity(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.pool)
+            .remaining_accounts(self.vaults.iter_mut())
+    }
+}
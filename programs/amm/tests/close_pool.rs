@@ -0,0 +1,297 @@
+use ::amm::amm::close_pool;
+use ::amm::prelude::*;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, MintExt, TokenAccountExt},
+    stub,
+};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::default();
+
+    test.close_pool()?;
+
+    assert!(Pool::try_deserialize(&mut test.pool.data.as_slice()).is_err());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn works_regardless_of_vaults_order() -> Result<()> {
+    let mut test = Tester::default();
+    test.vaults.reverse();
+
+    test.close_pool()?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::default();
+    test.admin = AccountInfoWrapper::new().mutable().signer();
+
+    assert!(test
+        .close_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_lp_mint_supply_is_not_zero() -> Result<()> {
+    let mut test = Tester::default();
+    test.lp_mint = test.lp_mint.clone().pack(
+        spl::mint::from_acc_info(&test.lp_mint.to_account_info()).supply(10),
+    );
+
+    assert!(test
+        .close_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_not_empty() -> Result<()> {
+    let mut test = Tester::default();
+    test.vaults[1] = test.vaults[1].clone().pack(
+        spl::token_account::from_acc_info(&test.vaults[1].to_account_info())
+            .amount(1),
+    );
+
+    assert!(test
+        .close_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_missing() -> Result<()> {
+    let mut test = Tester::default();
+    test.vaults.pop();
+
+    assert!(test
+        .close_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_duplicated() -> Result<()> {
+    let mut test = Tester::default();
+    test.vaults[1] = test.vaults[0].clone();
+
+    assert!(test
+        .close_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_not_pools_reserve() -> Result<()> {
+    let mut test = Tester::default();
+    test.vaults[1] = AccountInfoWrapper::new()
+        .mutable()
+        .pack(
+            spl::token_account::new(test.pool_signer.key)
+                .mint(Pubkey::new_unique()),
+        )
+        .owner(token::ID);
+
+    assert!(test
+        .close_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    pool_signer: AccountInfoWrapper,
+    lp_mint: AccountInfoWrapper,
+    token_program: AccountInfoWrapper,
+    vaults: Vec<AccountInfoWrapper>,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let admin = AccountInfoWrapper::new().mutable().signer();
+        let pool = AccountInfoWrapper::new().owner(amm::ID).mutable();
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let lp_mint = AccountInfoWrapper::new()
+            .pack(spl::mint::new(pool_signer.key))
+            .owner(token::ID);
+        let token_program = AccountInfoWrapper::with_key(token::ID).program();
+
+        let mut reserves = [Reserve::default(); consts::MAX_RESERVES];
+        let vaults: Vec<_> = (0..2)
+            .map(|index| {
+                let mint = Pubkey::new_unique();
+                let vault = AccountInfoWrapper::new()
+                    .mutable()
+                    .pack(spl::token_account::new(pool_signer.key).mint(mint))
+                    .owner(token::ID);
+
+                reserves[index] = Reserve {
+                    vault: vault.key,
+                    mint,
+                    tokens: TokenAmount::new(0),
+                };
+
+                vault
+            })
+            .collect();
+
+        let pool = pool.data(Pool {
+            admin: admin.key,
+            signer: pool_signer.key,
+            mint: lp_mint.key,
+            dimension: 2,
+            reserves,
+            ..Default::default()
+        });
+
+        Self {
+            admin,
+            pool,
+            pool_signer,
+            lp_mint,
+            token_program,
+            vaults,
+        }
+    }
+}
+
+impl Tester {
+    fn close_pool(&mut self) -> Result<()> {
+        let syscalls = self.set_syscalls(CpiValidatorState::CloseVaults {
+            admin: self.admin.key,
+            pool_signer: self.pool_signer.key,
+            vaults: self.vaults.iter().map(|v| v.key).collect(),
+        });
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        close_pool(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        let CpiValidator(state) =
+            (*syscalls.validator().lock().unwrap()).clone();
+        assert_eq!(*state.lock().unwrap(), CpiValidatorState::Done);
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.lp_mint)
+            .acc(&mut self.token_program)
+            .remaining_accounts(self.vaults.iter_mut())
+    }
+
+    fn set_syscalls(
+        &self,
+        state: CpiValidatorState,
+    ) -> stub::Syscalls<CpiValidator> {
+        let validator = CpiValidator(Arc::new(Mutex::new(state)));
+        let syscalls = stub::Syscalls::new(validator);
+        syscalls.slot(10);
+        syscalls.clone().set();
+
+        syscalls
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CpiValidator(Arc<Mutex<CpiValidatorState>>);
+
+#[derive(Debug, PartialEq, Eq)]
+enum CpiValidatorState {
+    CloseVaults {
+        admin: Pubkey,
+        pool_signer: Pubkey,
+        vaults: Vec<Pubkey>,
+    },
+    Done,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut state = self.0.lock().unwrap();
+
+        match *state {
+            CpiValidatorState::CloseVaults {
+                admin,
+                pool_signer,
+                ref mut vaults,
+            } => {
+                // vaults are closed in the order of the remaining accounts
+                let vault = vaults.remove(0);
+
+                let expected_ix = token::spl_token::instruction::close_account(
+                    &token::ID,
+                    &vault,
+                    &admin,
+                    &pool_signer,
+                    &[],
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                let closed_vault = &accounts[0];
+                assert_eq!(closed_vault.key(), vault);
+
+                if vaults.is_empty() {
+                    *state = CpiValidatorState::Done;
+                }
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
@@ -0,0 +1,513 @@
+//! Guards the compute units which the heaviest endpoints consume in their worst
+//! case against regressions.
+//!
+//! Each test measures an endpoint and compares the units with the baseline in
+//! [`BASELINE_FILE`]. The test fails if the endpoint consumes more than
+//! [`THRESHOLD_PERCENT`] over its baseline, or if the endpoint has no baseline.
+//! For a new test or after an intended change in consumption, run the tests
+//! with the `UPDATE_CU_BASELINE` env var set to record new baselines:
+//!
+//! ```bash
+//! UPDATE_CU_BASELINE=1 cargo test-bpf --test compute_units
+//! ```
+//!
+//! Natively run programs don't consume compute units, hence the tests are
+//! ignored unless compiled with the `test-bpf` feature, which `cargo test-bpf`
+//! enables.
+
+#[allow(dead_code)]
+mod e2e;
+
+use ::amm::prelude::{
+    PoolStats, ProgramConfig, Pubkey, StableCurveInvariantMethod,
+};
+use anchor_lang::{Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use e2e::{Bank, Result};
+use farming::consts::{MAX_HARVEST_MINTS, SNAPSHOTS_LEN};
+use farming::models::{
+    Farm, Farmer, Harvest, HarvestPeriod, Slot, Snapshot, Snapshots,
+    TokenAmount,
+};
+use serial_test::serial;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_program;
+use std::collections::BTreeMap;
+use std::{env, fs};
+
+/// Relative to the crate's root.
+const BASELINE_FILE: &str = "tests/compute_units.txt";
+const THRESHOLD_PERCENT: u32 = 5;
+const STAKE: u64 = 1_000_000;
+
+/// The more imbalanced a stable pool, the more iterations the invariant
+/// computation needs.
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(feature = "test-bpf"), ignore)]
+async fn swap_on_four_reserve_stable_pool() -> Result<()> {
+    let bank = Bank::start(Pubkey::new_unique()).await;
+    let units = swap_on_four_reserve_stable_pool_units(bank).await?;
+
+    assert_within_baseline("swap_on_four_reserve_stable_pool", units);
+
+    Ok(())
+}
+
+/// Same swap as in [`swap_on_four_reserve_stable_pool`], but the program
+/// config tells the pool to compute the invariant in integers. The difference
+/// of the two baselines is what [`StableCurveInvariantMethod::Integer`] saves.
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(feature = "test-bpf"), ignore)]
+async fn swap_on_four_reserve_stable_pool_with_integer_invariant() -> Result<()>
+{
+    let config = ProgramConfig {
+        stable_curve_invariant: StableCurveInvariantMethod::Integer,
+        ..Default::default()
+    };
+    let bank = Bank::start_with_accounts(
+        Pubkey::new_unique(),
+        vec![(
+            e2e::program_config_pda(),
+            e2e::program_account(
+                amm::ID,
+                e2e::serialize(&config, ProgramConfig::space()),
+            ),
+        )],
+    )
+    .await;
+    let units = swap_on_four_reserve_stable_pool_units(bank).await?;
+
+    assert_within_baseline(
+        "swap_on_four_reserve_stable_pool_with_integer_invariant",
+        units,
+    );
+
+    Ok(())
+}
+
+/// Zapping into a stable pool calculates a swap for each step of the bisection
+/// in [`amm::prelude::Pool::calculate_zap_in_swap`]. The zapped tokens are
+/// enough for the bisection to take all of its steps.
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(feature = "test-bpf"), ignore)]
+async fn zap_in_on_stable_pool() -> Result<()> {
+    let mut bank = Bank::start(Pubkey::new_unique()).await;
+    let reserves = [1_000_000_000_000, 2_000_000_000_000];
+    let StablePool {
+        user,
+        pool,
+        wallets,
+        lp_wallet,
+    } = create_stable_pool(&mut bank, &reserves).await?;
+
+    let units = bank
+        .compute_units(
+            &[Instruction {
+                program_id: amm::ID,
+                accounts: amm::accounts::ZapIn {
+                    user: user.pubkey(),
+                    discount: e2e::discount_pda(user.pubkey()),
+                    pool: pool.pool,
+                    pool_signer: pool.pool_signer,
+                    sell_wallet: wallets[0],
+                    buy_wallet: wallets[1],
+                    sell_vault: pool.vaults[0],
+                    buy_vault: pool.vaults[1],
+                    lp_mint: pool.lp_mint,
+                    lp_token_wallet: lp_wallet,
+                    program_toll_wallet: pool.program_toll_wallet,
+                    token_program: token::ID,
+                    program_config: e2e::program_config_pda(),
+                    pool_stats: PoolStats::pda(pool.pool),
+                }
+                .to_account_metas(None),
+                data: amm::instruction::ZapIn {
+                    tokens: TokenAmount::new(reserves[0] / 5),
+                    min_lp_tokens_out: TokenAmount::new(0),
+                }
+                .data(),
+            }],
+            &[&user],
+        )
+        .await?;
+
+    assert_within_baseline("zap_in_on_stable_pool", units);
+
+    Ok(())
+}
+
+/// Building a full snapshot history takes [`SNAPSHOTS_LEN`] transactions,
+/// therefore the farm and the farmer are stored into the bank before it
+/// starts.
+///
+/// The farm has a single harvest mint. The units grow with each harvest mint,
+/// which is why farmers who run out of compute units use
+/// `update_eligible_harvest_until` instead.
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(feature = "test-bpf"), ignore)]
+async fn update_eligible_harvest_over_full_snapshot_window() -> Result<()> {
+    let farm_pubkey = Pubkey::new_unique();
+    let farmer_pubkey = Pubkey::new_unique();
+    let last_snapshot_slot = SNAPSHOTS_LEN as u64;
+
+    let mut farm = Farm::default();
+    farm.harvests[0] = Harvest {
+        mint: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        ..Default::default()
+    };
+    farm.harvests[0].periods[0] = HarvestPeriod {
+        tps: TokenAmount::new(100),
+        starts_at: Slot::new(0),
+        ends_at: Slot::new(2 * last_snapshot_slot),
+    };
+    // snapshots started at slots 1 to 1000, the tip is at the latest one
+    let mut ring_buffer = [Snapshot::default(); SNAPSHOTS_LEN];
+    for (index, snapshot) in ring_buffer.iter_mut().enumerate() {
+        *snapshot = Snapshot {
+            staked: TokenAmount::new(STAKE),
+            started_at: Slot::new(index as u64 + 1),
+        };
+    }
+    farm.snapshots = Snapshots {
+        ring_buffer_tip: SNAPSHOTS_LEN as u64 - 1,
+        ring_buffer,
+    };
+    let farm_data =
+        [&Farm::discriminator()[..], bytemuck::bytes_of(&farm)].concat();
+
+    // hasn't had harvest calculated since the oldest snapshot
+    let farmer = Farmer {
+        authority: Pubkey::new_unique(),
+        farm: farm_pubkey,
+        staked: TokenAmount::new(STAKE),
+        calculate_next_harvest_from: Slot::new(1),
+        ..Default::default()
+    };
+
+    let mut bank = Bank::start_with_accounts(
+        Pubkey::new_unique(),
+        vec![
+            (farm_pubkey, e2e::program_account(farming::ID, farm_data)),
+            (
+                farmer_pubkey,
+                e2e::program_account(
+                    farming::ID,
+                    e2e::serialize(&farmer, Farmer::space()),
+                ),
+            ),
+        ],
+    )
+    .await;
+    bank.warp_slots(last_snapshot_slot + 10).await?;
+
+    let units = bank
+        .compute_units(
+            &[Instruction {
+                program_id: farming::ID,
+                accounts: farming::accounts::UpdateEligibleHarvest {
+                    farm: farm_pubkey,
+                    farmer: farmer_pubkey,
+                }
+                .to_account_metas(None),
+                data: farming::instruction::UpdateEligibleHarvest {}.data(),
+            }],
+            &[],
+        )
+        .await?;
+
+    assert_within_baseline(
+        "update_eligible_harvest_over_full_snapshot_window",
+        units,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(feature = "test-bpf"), ignore)]
+async fn claim_eligible_harvest_of_max_harvest_mints() -> Result<()> {
+    let mut bank = Bank::start(Pubkey::new_unique()).await;
+    let admin = bank.new_user().await?;
+    let user = bank.new_user().await?;
+
+    let stake_mint = bank.create_mint(None).await?;
+    let stake_wallet =
+        bank.create_wallet(stake_mint, user.pubkey(), STAKE).await?;
+    let farm = bank.create_farm(&admin, stake_mint).await?;
+    bank.farming_ix(
+        &admin,
+        farming::accounts::SetMinSnapshotWindow {
+            admin: admin.pubkey(),
+            farm: farm.farm,
+        },
+        farming::instruction::SetMinSnapshotWindow {
+            min_snapshot_window_slots: 1,
+        },
+    )
+    .await?;
+
+    let mut harvest_mints = Vec::with_capacity(MAX_HARVEST_MINTS);
+    for _ in 0..MAX_HARVEST_MINTS {
+        let harvest_mint = bank.create_mint(None).await?;
+        let harvest_wallet = bank
+            .create_wallet(harvest_mint, admin.pubkey(), 1_000_000)
+            .await?;
+        bank.add_harvest(&admin, &farm, harvest_mint).await?;
+        bank.farming_ix(
+            &admin,
+            farming::accounts::NewHarvestPeriod {
+                admin: admin.pubkey(),
+                farm: farm.farm,
+                harvest_wallet,
+                harvest_vault: e2e::harvest_vault(&farm, harvest_mint),
+                farm_signer_pda: farm.signer,
+                token_program: token::ID,
+                program_config: e2e::farming_program_config_pda(),
+            },
+            farming::instruction::NewHarvestPeriod {
+                harvest_mint,
+                // current slot
+                starts_at: Slot::new(0),
+                period_length_in_slots: 1_000,
+                tokens_per_slot: TokenAmount::new(100),
+            },
+        )
+        .await?;
+        harvest_mints.push(harvest_mint);
+    }
+
+    let farmer = start_farming(&mut bank, &user, &farm, stake_wallet).await?;
+    bank.warp_slots(10).await?;
+    bank.take_snapshot(&farm).await?;
+    bank.warp_slots(10).await?;
+    bank.process(
+        &[Instruction {
+            program_id: farming::ID,
+            accounts: farming::accounts::UpdateEligibleHarvest {
+                farm: farm.farm,
+                farmer,
+            }
+            .to_account_metas(None),
+            data: farming::instruction::UpdateEligibleHarvest {}.data(),
+        }],
+        &[],
+    )
+    .await?;
+
+    let mut accounts = farming::accounts::ClaimEligibleHarvest {
+        authority: user.pubkey(),
+        farmer,
+        farm_signer_pda: farm.signer,
+        token_program: token::ID,
+    }
+    .to_account_metas(None);
+    for harvest_mint in harvest_mints {
+        let wallet = bank
+            .create_token_account(harvest_mint, user.pubkey())
+            .await?;
+        accounts.push(AccountMeta::new(
+            e2e::harvest_vault(&farm, harvest_mint),
+            false,
+        ));
+        accounts.push(AccountMeta::new(wallet, false));
+    }
+
+    let units = bank
+        .compute_units(
+            &[Instruction {
+                program_id: farming::ID,
+                accounts,
+                data: farming::instruction::ClaimEligibleHarvest {}.data(),
+            }],
+            &[&user],
+        )
+        .await?;
+
+    assert_within_baseline(
+        "claim_eligible_harvest_of_max_harvest_mints",
+        units,
+    );
+
+    Ok(())
+}
+
+async fn start_farming(
+    bank: &mut Bank,
+    user: &Keypair,
+    farm: &e2e::FarmKeys,
+    stake_wallet: Pubkey,
+) -> Result<Pubkey> {
+    let farmer = e2e::farmer_pda(farm, user.pubkey());
+    bank.farming_ix(
+        user,
+        farming::accounts::CreateFarmer {
+            payer: user.pubkey(),
+            authority: user.pubkey(),
+            farmer,
+            farm: farm.farm,
+            system_program: system_program::ID,
+        },
+        farming::instruction::CreateFarmer {},
+    )
+    .await?;
+    bank.farming_ix(
+        user,
+        farming::accounts::StartFarming {
+            wallet_authority: user.pubkey(),
+            farmer,
+            stake_wallet,
+            farm: farm.farm,
+            stake_vault: farm.stake_vault,
+            token_program: token::ID,
+        },
+        farming::instruction::StartFarming {
+            stake: TokenAmount::new(STAKE),
+        },
+    )
+    .await?;
+
+    Ok(farmer)
+}
+
+/// Sells the smallest reserve for the largest one.
+async fn swap_on_four_reserve_stable_pool_units(mut bank: Bank) -> Result<u32> {
+    let reserves = [
+        1_000_000_000_000,
+        2_000_000_000_000,
+        500_000_000_000,
+        1_000_000_000_000,
+    ];
+    let StablePool {
+        user,
+        pool,
+        wallets,
+        ..
+    } = create_stable_pool(&mut bank, &reserves).await?;
+
+    bank.compute_units(
+        &[e2e::swap_ix(
+            user.pubkey(),
+            &pool,
+            (2, wallets[2]),
+            (1, wallets[1]),
+            reserves[2] / 5,
+            0,
+        )],
+        &[&user],
+    )
+    .await
+}
+
+struct StablePool {
+    user: Keypair,
+    pool: e2e::PoolKeys,
+    /// The user's wallets, ordered as the pool's mints. Each holds as many
+    /// tokens as its reserve.
+    wallets: Vec<Pubkey>,
+    /// The user's LP wallet which holds the whole LP supply.
+    lp_wallet: Pubkey,
+}
+
+/// The user makes the first deposit.
+async fn create_stable_pool(
+    bank: &mut Bank,
+    reserves: &[u64],
+) -> Result<StablePool> {
+    let admin = bank.new_user().await?;
+    let user = bank.new_user().await?;
+
+    let mut mints = Vec::with_capacity(reserves.len());
+    let mut wallets = Vec::with_capacity(reserves.len());
+    for tokens in reserves {
+        let mint = bank.create_mint(None).await?;
+        wallets
+            .push(bank.create_wallet(mint, user.pubkey(), 2 * tokens).await?);
+        mints.push(mint);
+    }
+
+    let pool = bank.create_pool(&admin, &mints, 100).await?;
+    let lp_wallet = bank.create_wallet(pool.lp_mint, user.pubkey(), 0).await?;
+    bank.deposit_liquidity(&user, &pool, lp_wallet, &wallets, reserves)
+        .await?;
+
+    Ok(StablePool {
+        user,
+        pool,
+        wallets,
+        lp_wallet,
+    })
+}
+
+/// Records the units if there's no baseline for the endpoint yet, or if the
+/// `UPDATE_CU_BASELINE` env var is set.
+fn assert_within_baseline(endpoint: &str, units: u32) {
+    let path = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), BASELINE_FILE);
+    let mut baseline = read_baseline(&path);
+
+    match baseline.get(endpoint) {
+        Some(&expected) if env::var_os("UPDATE_CU_BASELINE").is_none() => {
+            let max = expected + expected * THRESHOLD_PERCENT / 100;
+            assert!(
+                units <= max,
+                "'{}' consumed {} compute units, that's more than {}% over \
+                its baseline of {}",
+                endpoint,
+                units,
+                THRESHOLD_PERCENT,
+                expected
+            );
+        }
+        None if env::var_os("UPDATE_CU_BASELINE").is_none() => {
+            panic!(
+                "'{}' consumed {} compute units, but has no baseline in {}, \
+                run with UPDATE_CU_BASELINE set to record it",
+                endpoint, units, BASELINE_FILE
+            );
+        }
+        _ => {
+            baseline.insert(endpoint.to_string(), units);
+            write_baseline(&path, &baseline);
+        }
+    }
+}
+
+/// Each line is "endpoint = units", lines starting with '#' are comments.
+fn read_baseline(path: &str) -> BTreeMap<String, u32> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (endpoint, units) = line
+                .split_once('=')
+                .unwrap_or_else(|| panic!("Invalid baseline line '{}'", line));
+            let units = units
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid baseline line '{}'", line));
+
+            (endpoint.trim().to_string(), units)
+        })
+        .collect()
+}
+
+fn write_baseline(path: &str, baseline: &BTreeMap<String, u32>) {
+    let mut contents = String::from(
+        "# Compute units consumed by the worst case of endpoints, see \
+        tests/compute_units.rs\n",
+    );
+    for (endpoint, units) in baseline {
+        contents.push_str(&format!("{} = {}\n", endpoint, units));
+    }
+
+    fs::write(path, contents).expect("Cannot write baseline");
+}
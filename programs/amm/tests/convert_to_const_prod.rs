@@ -0,0 +1,137 @@
+use ::amm::amm::convert_to_const_prod;
+use ::amm::prelude::*;
+use anchortest::builder::*;
+use common::test_utils::set_clock;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+
+const END_SLOT: u64 = 1_000;
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test.convert_to_const_prod(END_SLOT).is_ok());
+
+    let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+    assert_eq!(pool.curve, Curve::ConstProd);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_is_bootstrapping() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test
+        .convert_to_const_prod(END_SLOT - 1)
+        .unwrap_err()
+        .to_string()
+        .contains("PoolBootstrapping"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_end_weights_are_not_equal() -> Result<()> {
+    let mut test = Tester::with_end_weight(Permillion::from_percent(60));
+
+    assert!(test
+        .convert_to_const_prod(END_SLOT)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_is_not_weighted() -> Result<()> {
+    let mut test = Tester::default();
+    test.pool.data = {
+        let mut pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+        pool.curve = Curve::ConstProd;
+        let mut data = Vec::new();
+        pool.try_serialize(&mut data)?;
+        data
+    };
+
+    assert!(test
+        .convert_to_const_prod(END_SLOT)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::default();
+    test.admin = AccountInfoWrapper::new().signer();
+
+    assert!(test
+        .convert_to_const_prod(END_SLOT)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        Self::with_end_weight(Permillion::from_percent(50))
+    }
+}
+
+impl Tester {
+    fn with_end_weight(end_weight: Permillion) -> Self {
+        let admin = AccountInfoWrapper::new().signer();
+        let pool =
+            AccountInfoWrapper::new()
+                .mutable()
+                .owner(amm::ID)
+                .data(Pool {
+                    admin: admin.key,
+                    curve: Curve::Weighted {
+                        start_weight: Permillion::from_percent(90),
+                        end_weight,
+                        start: Slot::new(END_SLOT / 2),
+                        end: Slot::new(END_SLOT),
+                    },
+                    ..Default::default()
+                });
+
+        Self { admin, pool }
+    }
+
+    fn convert_to_const_prod(&mut self, slot: u64) -> Result<()> {
+        set_clock(Slot::new(slot));
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        convert_to_const_prod(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+    }
+}
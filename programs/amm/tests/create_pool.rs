@@ -1,4 +1,4 @@
-use ::amm::amm::create_pool;
+use ::amm::amm::{create_pool, create_weighted_pool};
 use ::amm::prelude::*;
 use anchor_lang::solana_program::system_instruction;
 use anchor_lang::system_program;
@@ -26,6 +26,7 @@ fn with_two_reserves() -> Result<()> {
     assert!(test.create_pool(CONST_PROD_AMPLIFIER).is_ok());
 
     let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+    assert_eq!(pool.version, Pool::VERSION);
     assert_eq!(pool.dimension, 2);
     assert_eq!(pool.mint, test.lp_mint.key);
     assert_eq!(pool.program_toll_wallet, test.program_toll_wallet.key);
@@ -135,7 +136,7 @@ fn fails_if_more_than_four_reserves() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("TooManyReserves"));
 
     Ok(())
 }
@@ -155,7 +156,7 @@ fn fails_if_less_than_two_reserves() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("TooFewReserves"));
 
     let mut test = Tester::default();
     test.vaults = vec![];
@@ -163,7 +164,7 @@ fn fails_if_less_than_two_reserves() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("TooFewReserves"));
 
     Ok(())
 }
@@ -210,7 +211,7 @@ fn fails_on_duplicate_reserve_mint() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("DuplicateReserve"));
 
     Ok(())
 }
@@ -236,7 +237,7 @@ fn fails_if_vault_has_close_authority() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("VaultHasCloseAuthority"));
 
     Ok(())
 }
@@ -262,7 +263,7 @@ fn fails_if_vault_has_delegate() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("VaultHasDelegate"));
 
     Ok(())
 }
@@ -288,7 +289,7 @@ fn fails_if_vault_owner_is_not_pool_signer() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("VaultOwnerNotPoolSigner"));
 
     Ok(())
 }
@@ -314,7 +315,7 @@ fn fails_if_vault_is_frozen() -> Result<()> {
         .create_pool(CONST_PROD_AMPLIFIER)
         .unwrap_err()
         .to_string()
-        .contains("InvalidAccountInput"));
+        .contains("VaultFrozen"));
 
     Ok(())
 }
@@ -357,6 +358,92 @@ fn fails_if_vaults_empty_but_lp_mint_supply_is_not_zero() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn creates_weighted_pool() -> Result<()> {
+    let mut test = Tester::default();
+
+    test.create_weighted_pool(
+        Permillion::from_percent(90),
+        Permillion::from_percent(50),
+        Slot::new(100),
+        Slot::new(200),
+    )?;
+
+    let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+    assert_eq!(pool.dimension, 2);
+    assert_eq!(pool.admin, test.admin.key);
+    assert_eq!(pool.reserves[0].vault, test.vaults[0].key);
+    assert_eq!(pool.reserves[1].vault, test.vaults[1].key);
+    assert_eq!(
+        pool.curve,
+        Curve::Weighted {
+            start_weight: Permillion::from_percent(90),
+            end_weight: Permillion::from_percent(50),
+            start: Slot::new(100),
+            end: Slot::new(200),
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_to_create_weighted_pool_with_three_reserves() {
+    let mut test = Tester::default();
+    test.vaults.push(
+        AccountInfoWrapper::new()
+            .pack(spl::token_account::new(test.pool_signer.key))
+            .owner(token::ID),
+    );
+
+    let error = test
+        .create_weighted_pool(
+            Permillion::from_percent(90),
+            Permillion::from_percent(50),
+            Slot::new(100),
+            Slot::new(200),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidAccountInput"));
+}
+
+#[test]
+#[serial]
+fn fails_to_create_weighted_pool_with_extreme_weight() {
+    let mut test = Tester::default();
+
+    let error = test
+        .create_weighted_pool(
+            Permillion::from_percent(100),
+            Permillion::from_percent(50),
+            Slot::new(100),
+            Slot::new(200),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidArg"));
+}
+
+#[test]
+#[serial]
+fn fails_to_create_weighted_pool_if_start_is_not_before_end() {
+    let mut test = Tester::default();
+
+    let error = test
+        .create_weighted_pool(
+            Permillion::from_percent(90),
+            Permillion::from_percent(50),
+            Slot::new(200),
+            Slot::new(200),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidArg"));
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct Tester {
     admin: AccountInfoWrapper,
@@ -440,6 +527,30 @@ impl Tester {
         Ok(())
     }
 
+    fn create_weighted_pool(
+        &mut self,
+        start_weight: Permillion,
+        end_weight: Permillion,
+        start: Slot,
+        end: Slot,
+    ) -> Result<()> {
+        self.set_syscalls();
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        create_weighted_pool(
+            ctx.build(&mut accounts),
+            start_weight,
+            end_weight,
+            start,
+            end,
+        )?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
     fn context_wrapper(&mut self) -> ContextWrapper {
         ContextWrapper::new(amm::ID)
             .acc(&mut self.admin)
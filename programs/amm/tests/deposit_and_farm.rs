@@ -0,0 +1,82 @@
+#[allow(dead_code)]
+mod farm_lp;
+
+use ::amm::prelude::*;
+use anchortest::spl;
+use farm_lp::*;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use std::collections::BTreeMap;
+
+#[test]
+#[serial]
+fn deposits_and_stakes_all_minted_lp_tokens() -> Result<()> {
+    let mut tester = Tester::new();
+    let mints = tester.mints();
+
+    tester.deposit_and_farm(
+        BTreeMap::from([
+            (mints[0], TokenAmount::new(100)),
+            (mints[1], TokenAmount::new(100)),
+        ]),
+        TokenAmount::new(100),
+    )?;
+
+    let pool = tester.pool_copy();
+    assert_eq!(pool.reserves[0].tokens.amount, INITIAL_RESERVE + 100);
+    assert_eq!(pool.reserves[1].tokens.amount, INITIAL_RESERVE + 100);
+    // all LP tokens end up in the farm's stake vault
+    assert_eq!(tester.lp_token_wallet_amount(), 0);
+    assert_eq!(
+        spl::token_account::from_acc_info(
+            &tester.farm_stake_vault.to_account_info()
+        )
+        .amount,
+        INITIAL_LP_SUPPLY + 100
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_min_lp_tokens_out_is_not_reached() -> Result<()> {
+    let mut tester = Tester::new();
+    let mints = tester.mints();
+
+    let error = tester
+        .deposit_and_farm(
+            BTreeMap::from([
+                (mints[0], TokenAmount::new(100)),
+                (mints[1], TokenAmount::new(100)),
+            ]),
+            TokenAmount::new(101),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("LpTokensOutBelowMinimum"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_farming_program_is_wrong() -> Result<()> {
+    let mut tester = Tester::new();
+    let mints = tester.mints();
+    tester.farming = anchortest::builder::AccountInfoWrapper::new().program();
+
+    let error = tester
+        .deposit_and_farm(
+            BTreeMap::from([
+                (mints[0], TokenAmount::new(100)),
+                (mints[1], TokenAmount::new(100)),
+            ]),
+            TokenAmount::new(0),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidProgramId"));
+
+    Ok(())
+}
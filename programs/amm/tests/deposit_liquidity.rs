@@ -29,6 +29,34 @@ fn makes_initial_deposit_into_const_prod_with_two_reserves() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn records_deposit_in_pool_stats() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+    tester.create_pool_stats();
+
+    tester.deposit_liquidity(
+        reserves_to_max_amount_tokens(&reserves, 100),
+        &reserves,
+    )?;
+    tester.deposit_liquidity(
+        reserves_to_max_amount_tokens(&reserves, 50),
+        &reserves,
+    )?;
+
+    // the pool started empty, so its reserves are all the deposits
+    let pool = Pool::try_deserialize(&mut tester.pool.data.as_slice())?;
+    let stats = tester.pool_stats()?;
+    for (reserve, reserve_stats) in pool.reserves().iter().zip(stats.reserves) {
+        assert_eq!(reserve_stats.mint, reserve.mint);
+        assert_eq!(reserve_stats.deposited, reserve.tokens.amount as u128);
+        assert_eq!(reserve_stats.redeemed, 0);
+    }
+    assert_eq!(stats.swap_count, 0);
+
+    Ok(())
+}
+
 #[test]
 #[serial]
 fn makes_initial_deposit_into_const_prod_with_more_than_two_reserves(
@@ -150,7 +178,7 @@ fn fails_if_same_mint_passed_multiple_times() -> Result<()> {
         .deposit_liquidity(max_amount_tokens, &reserves)
         .unwrap_err()
         .to_string();
-    assert!(error.contains("InvalidArg"));
+    assert!(error.contains("DimensionMismatch"));
 
     Ok(())
 }
@@ -651,6 +679,118 @@ fn fails_if_no_lp_tokens_would_be_minted() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn deposit_v2_works_if_min_lp_tokens_out_is_reached() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+
+    tester.deposit_liquidity_v2(
+        reserves_to_max_amount_tokens(&reserves, 10),
+        TokenAmount::new(10),
+        &reserves,
+    )?;
+
+    let pool = Pool::try_deserialize(&mut tester.pool.data.as_slice())?;
+    assert_eq!(pool.reserves[0].tokens.amount, 10);
+    assert_eq!(pool.reserves[1].tokens.amount, 10);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn deposit_v2_fails_if_min_lp_tokens_out_is_not_reached() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+
+    let error = tester
+        .deposit_liquidity_v2(
+            reserves_to_max_amount_tokens(&reserves, 10),
+            TokenAmount::new(11),
+            &reserves,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("LpTokensOutBelowMinimum"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_user_is_not_admin_while_pool_is_bootstrapping() -> Result<()> {
+    let (mut tester, reserves) = Tester::new(
+        2,
+        Curve::Weighted {
+            start_weight: Permillion::from_percent(90),
+            end_weight: Permillion::from_percent(50),
+            start: Slot::new(10),
+            end: Slot::new(100),
+        },
+    );
+
+    let error = tester
+        .deposit_liquidity(
+            reserves_to_max_amount_tokens(&reserves, 10),
+            &reserves,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolBootstrapping"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn deposits_if_user_is_allowed_by_pool_access_policy() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+    tester.set_pool_access(true)?;
+
+    tester.deposit_liquidity(
+        reserves_to_max_amount_tokens(&reserves, 10),
+        &reserves,
+    )?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_access_is_revoked() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+    tester.set_pool_access(false)?;
+
+    let error = tester
+        .deposit_liquidity(
+            reserves_to_max_amount_tokens(&reserves, 10),
+            &reserves,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolAccessDenied"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_access_is_missing() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+    tester.set_pool_access(true)?;
+    tester.pool_access = None;
+
+    let error = tester
+        .deposit_liquidity(
+            reserves_to_max_amount_tokens(&reserves, 10),
+            &reserves,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolAccessDenied"));
+
+    Ok(())
+}
+
 // Creates input arg into the [`deposit_liquidity`] endpoint with all maxes
 // being the same.
 fn reserves_to_max_amount_tokens(
@@ -1,6 +1,7 @@
-use ::amm::amm::deposit_liquidity;
 use ::amm::amm::redeem_liquidity;
+use ::amm::amm::{deposit_liquidity, deposit_liquidity_v2};
 use ::amm::prelude::*;
+use anchor_lang::system_program;
 use anchor_spl::token;
 pub use anchor_spl::token::spl_token::state::{Account as TokenAccount, Mint};
 use anchortest::{
@@ -22,7 +23,10 @@ pub struct Tester {
     pub lp_mint: AccountInfoWrapper,
     pub lp_token_wallet: AccountInfoWrapper,
     pub token_program: AccountInfoWrapper,
+    pub pool_stats: AccountInfoWrapper,
+    pub program_config: AccountInfoWrapper,
     pub vaults_wallets: Vec<AccountInfoWrapper>,
+    pub pool_access: Option<AccountInfoWrapper>,
 }
 
 impl Tester {
@@ -61,6 +65,19 @@ impl Tester {
             .pack(spl::token_account::new(user.key).mint(lp_mint.key))
             .owner(token::ID);
         let token_program = AccountInfoWrapper::with_key(token::ID).program();
+        let pool_stats = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_stats",
+            &[PoolStats::PDA_PREFIX, pool.key.as_ref()],
+        )
+        .mutable()
+        .owner(system_program::ID);
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
         let mut reserves = [Reserve::default(); consts::MAX_RESERVES];
         let vaults_wallets: Vec<_> = (0..dimension)
             .map(|index| {
@@ -107,7 +124,10 @@ impl Tester {
                 lp_mint,
                 lp_token_wallet,
                 token_program,
+                pool_stats,
+                program_config,
                 vaults_wallets,
+                pool_access: None,
             },
             reserves,
         )
@@ -115,10 +135,75 @@ impl Tester {
 }
 
 impl Tester {
+    /// Makes the pool's stats account as if it was created, so that the
+    /// endpoints update it.
+    pub fn create_pool_stats(&mut self) {
+        self.pool_stats =
+            self.pool_stats.clone().owner(amm::ID).data(PoolStats {
+                pool: self.pool.key,
+                ..Default::default()
+            });
+    }
+
+    pub fn pool_stats(&self) -> Result<PoolStats> {
+        PoolStats::try_deserialize(&mut self.pool_stats.data.as_slice())
+    }
+
+    /// Sets an access policy on the pool and grants or revokes the access of
+    /// the user. The pool access is then passed as the last remaining account.
+    pub fn set_pool_access(&mut self, is_allowed: bool) -> Result<()> {
+        let mut pool = Pool::try_deserialize(&mut self.pool.data.as_slice())?;
+        pool.access_policy = Some(Pubkey::new_unique());
+        self.pool = AccountInfoWrapper::with_key(self.pool.key)
+            .owner(amm::ID)
+            .mutable()
+            .data(pool);
+
+        self.pool_access = Some(
+            AccountInfoWrapper::new()
+                .data(PoolAccess {
+                    pool: self.pool.key,
+                    user: self.user.key,
+                    is_allowed,
+                })
+                .owner(amm::ID),
+        );
+
+        Ok(())
+    }
+
     pub fn deposit_liquidity(
         &mut self,
         max_amount_tokens: BTreeMap<Pubkey, TokenAmount>,
         reserves: &[Reserve],
+    ) -> Result<stub::Syscalls<CpiValidator>> {
+        self.deposit_liquidity_with_min_lp_tokens_out(
+            max_amount_tokens,
+            None,
+            reserves,
+        )
+    }
+
+    pub fn deposit_liquidity_v2(
+        &mut self,
+        max_amount_tokens: BTreeMap<Pubkey, TokenAmount>,
+        min_lp_tokens_out: TokenAmount,
+        reserves: &[Reserve],
+    ) -> Result<stub::Syscalls<CpiValidator>> {
+        self.deposit_liquidity_with_min_lp_tokens_out(
+            max_amount_tokens,
+            Some(min_lp_tokens_out),
+            reserves,
+        )
+    }
+
+    /// If the minimum is provided, calls `deposit_liquidity_v2` instead of
+    /// `deposit_liquidity`.
+    fn deposit_liquidity_with_min_lp_tokens_out(
+        &mut self,
+        max_amount_tokens: BTreeMap<Pubkey, TokenAmount>,
+        min_lp_tokens_out: Option<TokenAmount>,
+        reserves: &[Reserve],
     ) -> Result<stub::Syscalls<CpiValidator>> {
         let mut pool = Pool::try_deserialize(&mut self.pool.data.as_slice())?;
         let lp_mint = Mint::unpack(&mut self.lp_mint.data.as_slice())?;
@@ -176,7 +261,15 @@ impl Tester {
             .map(|(mint, tokens)| TokenLimit { mint, tokens })
             .collect();
 
-        deposit_liquidity(ctx.build(&mut accounts), max_amount_tokens)?;
+        if let Some(min_lp_tokens_out) = min_lp_tokens_out {
+            deposit_liquidity_v2(
+                ctx.build(&mut accounts),
+                max_amount_tokens,
+                min_lp_tokens_out,
+            )?;
+        } else {
+            deposit_liquidity(ctx.build(&mut accounts), max_amount_tokens)?;
+        }
         accounts.exit(&amm::ID)?;
 
         let CpiValidator(state) =
@@ -276,7 +369,13 @@ impl Tester {
             .acc(&mut self.lp_mint)
             .acc(&mut self.lp_token_wallet)
             .acc(&mut self.token_program)
-            .remaining_accounts(self.vaults_wallets.iter_mut())
+            .acc(&mut self.pool_stats)
+            .acc(&mut self.program_config)
+            .remaining_accounts(
+                self.vaults_wallets
+                    .iter_mut()
+                    .chain(self.pool_access.iter_mut()),
+            )
     }
 
     fn set_syscalls(
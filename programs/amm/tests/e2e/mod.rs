@@ -0,0 +1,919 @@
+//! End-to-end tests run both programs on an in-process bank with the real SPL
+//! token program, and don't need a validator.
+//!
+//! With `cargo test` the programs run natively. With `cargo test-bpf` the
+//! `BPF_OUT_DIR` env var is set and the bank loads the compiled `amm.so` and
+//! `farming.so` instead.
+//!
+//! The program toll and the discount settings are singletons which only the
+//! program's upgrade authority can create, see
+//! [`amm::endpoints::create_program_toll`]. On the local bank the programs
+//! have no upgrade authority, therefore we store these accounts into the bank
+//! before it starts.
+//!
+//! Unlike the stub based tests, here errors are whatever the bank returns,
+//! hence the boxed [`Result`].
+//!
+//! Natively run programs don't consume compute units, therefore
+//! [`Bank::compute_units`] is only meaningful with `cargo test-bpf`, see
+//! [`is_bpf`].
+
+use ::amm::prelude::{
+    AccountDeserialize, AccountSerialize, Discount, DiscountSettings, Pool,
+    PoolStats, ProgramConfig, ProgramToll, Pubkey, TokenAmount, TokenLimit,
+};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{self, spl_token, Mint, TokenAccount};
+use farming::models::{Farm, Farmer, Harvest};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::sysvar::clock::Clock;
+use solana_sdk::transaction::Transaction;
+use solana_sdk::{system_instruction, system_program, sysvar};
+use std::{env, mem};
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Each new user starts with this many lamports.
+pub const USER_LAMPORTS: u64 = 10_000_000_000;
+
+/// The most compute units a transaction can request.
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+pub struct Bank {
+    ctx: ProgramTestContext,
+    /// Owns the toll wallets of all pools.
+    pub program_toll_authority: Pubkey,
+}
+
+/// Pubkeys of a pool and of the accounts it's created with.
+#[derive(Clone, Debug)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub pool_signer: Pubkey,
+    pub lp_mint: Pubkey,
+    pub program_toll_wallet: Pubkey,
+    /// Ordered as the mints the pool was created with.
+    pub mints: Vec<Pubkey>,
+    /// Ordered as the mints the pool was created with.
+    pub vaults: Vec<Pubkey>,
+}
+
+/// Pubkeys of a farm and of its PDAs.
+#[derive(Clone, Debug)]
+pub struct FarmKeys {
+    pub farm: Pubkey,
+    pub signer: Pubkey,
+    pub stake_vault: Pubkey,
+}
+
+impl Bank {
+    /// The discount settings authority is funded so that it can pay for new
+    /// discount accounts.
+    pub async fn start(discount_settings_authority: Pubkey) -> Self {
+        Self::start_with_accounts(discount_settings_authority, Vec::new()).await
+    }
+
+    /// Stores given accounts into the bank before it starts. Useful for
+    /// state which would take many transactions to build.
+    pub async fn start_with_accounts(
+        discount_settings_authority: Pubkey,
+        accounts: Vec<(Pubkey, Account)>,
+    ) -> Self {
+        let program_toll_authority = Pubkey::new_unique();
+
+        let mut program_test =
+            ProgramTest::new("amm", amm::ID, processor!(amm_entry));
+        program_test.add_program(
+            "farming",
+            farming::ID,
+            processor!(farming_entry),
+        );
+
+        program_test.add_account(
+            Pubkey::find_program_address(&[ProgramToll::PDA_SEED], &amm::ID).0,
+            program_account(
+                amm::ID,
+                serialize(
+                    &ProgramToll {
+                        authority: program_toll_authority,
+                    },
+                    ProgramToll::space(),
+                ),
+            ),
+        );
+        program_test.add_account(
+            Pubkey::find_program_address(
+                &[DiscountSettings::PDA_SEED],
+                &amm::ID,
+            )
+            .0,
+            program_account(
+                amm::ID,
+                serialize(
+                    &DiscountSettings {
+                        authority: discount_settings_authority,
+                    },
+                    DiscountSettings::space(),
+                ),
+            ),
+        );
+        program_test.add_account(
+            discount_settings_authority,
+            Account {
+                lamports: USER_LAMPORTS,
+                owner: system_program::ID,
+                ..Default::default()
+            },
+        );
+        for (pubkey, account) in accounts {
+            program_test.add_account(pubkey, account);
+        }
+
+        Self {
+            ctx: program_test.start_with_context().await,
+            program_toll_authority,
+        }
+    }
+
+    /// Signs the transaction with the bank's payer and given signers.
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<()> {
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await?;
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await?;
+
+        Ok(())
+    }
+
+    /// Finds the least compute budget with which the instructions succeed.
+    ///
+    /// Every attempt ends with an instruction which always fails, therefore
+    /// none of the attempts changes the bank. An attempt which fails on that
+    /// last instruction fits into its budget.
+    pub async fn compute_units(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<u32> {
+        // the budget is in (fails, fits]
+        let (mut fails, mut fits) = (0, MAX_COMPUTE_UNITS);
+        if !self
+            .fits_compute_budget(instructions, signers, fits)
+            .await?
+        {
+            return Err(format!(
+                "Instructions need more than {} compute units",
+                MAX_COMPUTE_UNITS
+            )
+            .into());
+        }
+
+        while fits - fails > 1 {
+            let budget = fails + (fits - fails) / 2;
+            if self
+                .fits_compute_budget(instructions, signers, budget)
+                .await?
+            {
+                fits = budget;
+            } else {
+                fails = budget;
+            }
+        }
+
+        Ok(fits)
+    }
+
+    async fn fits_compute_budget(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        budget: u32,
+    ) -> Result<bool> {
+        // has no lamports to transfer, also makes each attempt unique
+        let sentinel = Keypair::new();
+        let payer = self.ctx.payer.pubkey();
+
+        let mut attempt = Vec::with_capacity(instructions.len() + 2);
+        attempt.push(ComputeBudgetInstruction::request_units(budget, 0));
+        attempt.extend_from_slice(instructions);
+        attempt.push(system_instruction::transfer(
+            &sentinel.pubkey(),
+            &payer,
+            1,
+        ));
+        let sentinel_index = attempt.len() - 1;
+
+        let mut all_signers = signers.to_vec();
+        all_signers.push(&sentinel);
+
+        let err = match self.process(&attempt, &all_signers).await {
+            Ok(()) => return Err("Sentinel instruction succeeded".into()),
+            Err(e) => format!("{:?}", e),
+        };
+        if err.contains(&format!("InstructionError({},", sentinel_index)) {
+            Ok(true)
+        } else if err.contains("ComputationalBudgetExceeded") {
+            Ok(false)
+        } else {
+            Err(err.into())
+        }
+    }
+
+    pub async fn slot(&mut self) -> Result<u64> {
+        Ok(self.ctx.banks_client.get_sysvar::<Clock>().await?.slot)
+    }
+
+    pub async fn warp_slots(&mut self, slots: u64) -> Result<()> {
+        let slot = self.slot().await?;
+        self.ctx
+            .warp_to_slot(slot + slots)
+            .map_err(|e| format!("{:?}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn new_user(&mut self) -> Result<Keypair> {
+        let user = Keypair::new();
+        let payer = self.ctx.payer.pubkey();
+        self.process(
+            &[system_instruction::transfer(
+                &payer,
+                &user.pubkey(),
+                USER_LAMPORTS,
+            )],
+            &[],
+        )
+        .await?;
+
+        Ok(user)
+    }
+
+    /// Creates an account of given size owned by given program, the program
+    /// then initializes it.
+    pub async fn create_account(
+        &mut self,
+        account: &Keypair,
+        space: usize,
+        owner: &Pubkey,
+    ) -> Result<()> {
+        let rent = self.ctx.banks_client.get_rent().await?;
+        let payer = self.ctx.payer.pubkey();
+        self.process(
+            &[system_instruction::create_account(
+                &payer,
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                owner,
+            )],
+            &[account],
+        )
+        .await
+    }
+
+    /// The bank's payer is the mint authority unless given otherwise.
+    pub async fn create_mint(
+        &mut self,
+        authority: Option<Pubkey>,
+    ) -> Result<Pubkey> {
+        let mint = Keypair::new();
+        self.create_account(&mint, spl_token::state::Mint::LEN, &token::ID)
+            .await?;
+
+        let authority = authority.unwrap_or_else(|| self.ctx.payer.pubkey());
+        self.process(
+            &[spl_token::instruction::initialize_mint(
+                &token::ID,
+                &mint.pubkey(),
+                &authority,
+                None,
+                6,
+            )?],
+            &[],
+        )
+        .await?;
+
+        Ok(mint.pubkey())
+    }
+
+    pub async fn create_token_account(
+        &mut self,
+        mint: Pubkey,
+        owner: Pubkey,
+    ) -> Result<Pubkey> {
+        let account = Keypair::new();
+        self.create_account(
+            &account,
+            spl_token::state::Account::LEN,
+            &token::ID,
+        )
+        .await?;
+
+        self.process(
+            &[spl_token::instruction::initialize_account(
+                &token::ID,
+                &account.pubkey(),
+                &mint,
+                &owner,
+            )?],
+            &[],
+        )
+        .await?;
+
+        Ok(account.pubkey())
+    }
+
+    /// Works for mints created with the bank's payer as the authority.
+    pub async fn mint_to(
+        &mut self,
+        mint: Pubkey,
+        account: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        let payer = self.ctx.payer.pubkey();
+        self.process(
+            &[spl_token::instruction::mint_to(
+                &token::ID,
+                &mint,
+                &account,
+                &payer,
+                &[],
+                amount,
+            )?],
+            &[],
+        )
+        .await
+    }
+
+    /// Creates a token account of given mint for the owner and funds it.
+    pub async fn create_wallet(
+        &mut self,
+        mint: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+    ) -> Result<Pubkey> {
+        let wallet = self.create_token_account(mint, owner).await?;
+        if amount > 0 {
+            self.mint_to(mint, wallet, amount).await?;
+        }
+
+        Ok(wallet)
+    }
+
+    pub async fn account<T: AccountDeserialize>(
+        &mut self,
+        pubkey: Pubkey,
+    ) -> Result<T> {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(pubkey)
+            .await?
+            .ok_or_else(|| format!("Account '{}' doesn't exist", pubkey))?;
+
+        let data = T::try_deserialize(&mut account.data.as_slice())
+            .map_err(|e| e.to_string())?;
+
+        Ok(data)
+    }
+
+    pub async fn token_balance(&mut self, account: Pubkey) -> Result<u64> {
+        Ok(self.account::<TokenAccount>(account).await?.amount)
+    }
+
+    pub async fn mint_supply(&mut self, mint: Pubkey) -> Result<u64> {
+        Ok(self.account::<Mint>(mint).await?.supply)
+    }
+
+    pub async fn pool(&mut self, pool: &PoolKeys) -> Result<Pool> {
+        self.account(pool.pool).await
+    }
+
+    /// Creates a pool with empty vaults for given mints. The amplifier of zero
+    /// creates a constant product pool.
+    pub async fn create_pool(
+        &mut self,
+        admin: &Keypair,
+        mints: &[Pubkey],
+        amplifier: u64,
+    ) -> Result<PoolKeys> {
+        let pool = Keypair::new();
+        let (pool_signer, _) = Pubkey::find_program_address(
+            &[Pool::SIGNER_PDA_PREFIX, pool.pubkey().as_ref()],
+            &amm::ID,
+        );
+
+        let lp_mint = self.create_mint(Some(pool_signer)).await?;
+        let program_toll_wallet = self
+            .create_token_account(lp_mint, self.program_toll_authority)
+            .await?;
+        let mut vaults = Vec::with_capacity(mints.len());
+        for mint in mints {
+            vaults.push(self.create_token_account(*mint, pool_signer).await?);
+        }
+
+        let mut accounts = amm::accounts::CreatePool {
+            admin: admin.pubkey(),
+            pool: pool.pubkey(),
+            pool_signer,
+            program_toll: program_toll_pda(),
+            program_toll_wallet,
+            lp_mint,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(
+            vaults
+                .iter()
+                .map(|vault| AccountMeta::new_readonly(*vault, false)),
+        );
+
+        self.process(
+            &[Instruction {
+                program_id: amm::ID,
+                accounts,
+                data: amm::instruction::CreatePool { amplifier }.data(),
+            }],
+            &[admin, &pool],
+        )
+        .await?;
+
+        Ok(PoolKeys {
+            pool: pool.pubkey(),
+            pool_signer,
+            lp_mint,
+            program_toll_wallet,
+            mints: mints.to_vec(),
+            vaults,
+        })
+    }
+
+    pub async fn create_pool_stats(
+        &mut self,
+        payer: &Keypair,
+        pool: &PoolKeys,
+    ) -> Result<()> {
+        self.process(
+            &[Instruction {
+                program_id: amm::ID,
+                accounts: amm::accounts::CreatePoolStats {
+                    payer: payer.pubkey(),
+                    pool: pool.pool,
+                    pool_stats: PoolStats::pda(pool.pool),
+                    system_program: system_program::ID,
+                }
+                .to_account_metas(None),
+                data: amm::instruction::CreatePoolStats {}.data(),
+            }],
+            &[payer],
+        )
+        .await
+    }
+
+    pub async fn set_pool_swap_fee(
+        &mut self,
+        admin: &Keypair,
+        pool: &PoolKeys,
+        fee: amm::prelude::Permillion,
+    ) -> Result<()> {
+        self.process(
+            &[Instruction {
+                program_id: amm::ID,
+                accounts: amm::accounts::SetPoolSwapFee {
+                    admin: admin.pubkey(),
+                    pool: pool.pool,
+                    program_config: program_config_pda(),
+                }
+                .to_account_metas(None),
+                data: amm::instruction::SetPoolSwapFee { fee }.data(),
+            }],
+            &[admin],
+        )
+        .await
+    }
+
+    /// Wallets are ordered as the pool's mints.
+    pub async fn deposit_liquidity(
+        &mut self,
+        user: &Keypair,
+        pool: &PoolKeys,
+        lp_token_wallet: Pubkey,
+        wallets: &[Pubkey],
+        max_tokens: &[u64],
+    ) -> Result<()> {
+        let mut accounts = amm::accounts::DepositLiquidity {
+            user: user.pubkey(),
+            pool: pool.pool,
+            pool_signer_pda: pool.pool_signer,
+            lp_mint: pool.lp_mint,
+            lp_token_wallet,
+            token_program: token::ID,
+            pool_stats: PoolStats::pda(pool.pool),
+            program_config: program_config_pda(),
+        }
+        .to_account_metas(None);
+        accounts.extend(vaults_and_wallets(pool, wallets));
+
+        let max_amount_tokens = pool
+            .mints
+            .iter()
+            .zip(max_tokens)
+            .map(|(mint, tokens)| TokenLimit {
+                mint: *mint,
+                tokens: TokenAmount::new(*tokens),
+            })
+            .collect();
+
+        self.process(
+            &[Instruction {
+                program_id: amm::ID,
+                accounts,
+                data: amm::instruction::DepositLiquidity { max_amount_tokens }
+                    .data(),
+            }],
+            &[user],
+        )
+        .await
+    }
+
+    /// Wallets are ordered as the pool's mints. The user accepts any amount
+    /// of tokens in return.
+    pub async fn redeem_liquidity(
+        &mut self,
+        user: &Keypair,
+        pool: &PoolKeys,
+        lp_token_wallet: Pubkey,
+        wallets: &[Pubkey],
+        lp_tokens_to_burn: u64,
+    ) -> Result<()> {
+        let mut accounts = amm::accounts::RedeemLiquidity {
+            user: user.pubkey(),
+            pool: pool.pool,
+            pool_signer: pool.pool_signer,
+            lp_mint: pool.lp_mint,
+            lp_token_wallet,
+            token_program: token::ID,
+            pool_stats: PoolStats::pda(pool.pool),
+            program_config: program_config_pda(),
+        }
+        .to_account_metas(None);
+        accounts.extend(vaults_and_wallets(pool, wallets));
+
+        let min_amount_tokens = pool
+            .mints
+            .iter()
+            .map(|mint| TokenLimit {
+                mint: *mint,
+                tokens: TokenAmount::new(0),
+            })
+            .collect();
+
+        self.process(
+            &[Instruction {
+                program_id: amm::ID,
+                accounts,
+                data: amm::instruction::RedeemLiquidity {
+                    lp_tokens_to_burn: TokenAmount::new(lp_tokens_to_burn),
+                    min_amount_tokens,
+                }
+                .data(),
+            }],
+            &[user],
+        )
+        .await
+    }
+
+    /// Sells tokens of the pool's `sell` mint for its `buy` mint.
+    pub async fn swap(
+        &mut self,
+        user: &Keypair,
+        pool: &PoolKeys,
+        sell: (usize, Pubkey),
+        buy: (usize, Pubkey),
+        sell_tokens: u64,
+        min_buy: u64,
+    ) -> Result<()> {
+        self.process(
+            &[swap_ix(
+                user.pubkey(),
+                pool,
+                sell,
+                buy,
+                sell_tokens,
+                min_buy,
+            )],
+            &[user],
+        )
+        .await
+    }
+
+    /// The discount settings authority pays for the new discount account.
+    pub async fn put_discount(
+        &mut self,
+        authority: &Keypair,
+        user: Pubkey,
+        discount_amount: amm::prelude::Permillion,
+        valid_until: u64,
+    ) -> Result<()> {
+        let mut accounts = amm::accounts::PutDiscount {
+            authority: authority.pubkey(),
+            discount: discount_pda(user),
+            discount_settings: Pubkey::find_program_address(
+                &[DiscountSettings::PDA_SEED],
+                &amm::ID,
+            )
+            .0,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        // rent for the new discount account is paid by the authority
+        accounts[0].is_writable = true;
+
+        self.process(
+            &[Instruction {
+                program_id: amm::ID,
+                accounts,
+                data: amm::instruction::PutDiscount {
+                    user,
+                    discount_amount,
+                    valid_until: amm::prelude::Slot::new(valid_until),
+                }
+                .data(),
+            }],
+            &[authority],
+        )
+        .await
+    }
+
+    pub async fn create_farm(
+        &mut self,
+        admin: &Keypair,
+        stake_mint: Pubkey,
+    ) -> Result<FarmKeys> {
+        let farm = Keypair::new();
+        self.create_account(&farm, 8 + mem::size_of::<Farm>(), &farming::ID)
+            .await?;
+
+        let keys = FarmKeys {
+            farm: farm.pubkey(),
+            signer: Pubkey::find_program_address(
+                &[Farm::SIGNER_PDA_PREFIX, farm.pubkey().as_ref()],
+                &farming::ID,
+            )
+            .0,
+            stake_vault: Pubkey::find_program_address(
+                &[Farm::STAKE_VAULT_PREFIX, farm.pubkey().as_ref()],
+                &farming::ID,
+            )
+            .0,
+        };
+
+        self.farming_ix(
+            admin,
+            farming::accounts::CreateFarm {
+                admin: admin.pubkey(),
+                farm: keys.farm,
+                farm_signer_pda: keys.signer,
+                stake_mint,
+                stake_vault: keys.stake_vault,
+                token_program: token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            farming::instruction::CreateFarm {},
+        )
+        .await?;
+
+        Ok(keys)
+    }
+
+    pub async fn add_harvest(
+        &mut self,
+        admin: &Keypair,
+        farm: &FarmKeys,
+        harvest_mint: Pubkey,
+    ) -> Result<()> {
+        self.farming_ix(
+            admin,
+            farming::accounts::AddHarvest {
+                admin: admin.pubkey(),
+                farm: farm.farm,
+                farm_signer_pda: farm.signer,
+                harvest_mint,
+                harvest_vault: harvest_vault(farm, harvest_mint),
+                token_program: token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            farming::instruction::AddHarvest {},
+        )
+        .await
+    }
+
+    pub async fn take_snapshot(&mut self, farm: &FarmKeys) -> Result<()> {
+        self.process(
+            &[Instruction {
+                program_id: farming::ID,
+                accounts: farming::accounts::TakeSnapshot {
+                    farm: farm.farm,
+                    stake_vault: farm.stake_vault,
+                    program_config: farming_program_config_pda(),
+                }
+                .to_account_metas(None),
+                data: farming::instruction::TakeSnapshot {}.data(),
+            }],
+            &[],
+        )
+        .await
+    }
+
+    /// Sends a single farming instruction signed by given signer.
+    pub async fn farming_ix(
+        &mut self,
+        signer: &Keypair,
+        accounts: impl ToAccountMetas,
+        data: impl InstructionData,
+    ) -> Result<()> {
+        self.process(
+            &[Instruction {
+                program_id: farming::ID,
+                accounts: accounts.to_account_metas(None),
+                data: data.data(),
+            }],
+            &[signer],
+        )
+        .await
+    }
+}
+
+/// Whether the bank loads the programs compiled to BPF.
+pub fn is_bpf() -> bool {
+    env::var_os("BPF_OUT_DIR").is_some()
+}
+
+pub fn swap_ix(
+    user: Pubkey,
+    pool: &PoolKeys,
+    (sell, sell_wallet): (usize, Pubkey),
+    (buy, buy_wallet): (usize, Pubkey),
+    sell_tokens: u64,
+    min_buy: u64,
+) -> Instruction {
+    Instruction {
+        program_id: amm::ID,
+        accounts: amm::accounts::Swap {
+            user,
+            discount: discount_pda(user),
+            pool: pool.pool,
+            pool_signer: pool.pool_signer,
+            sell_wallet,
+            buy_wallet,
+            sell_vault: pool.vaults[sell],
+            buy_vault: pool.vaults[buy],
+            lp_mint: pool.lp_mint,
+            program_toll_wallet: pool.program_toll_wallet,
+            token_program: token::ID,
+            program_config: program_config_pda(),
+            pool_stats: PoolStats::pda(pool.pool),
+        }
+        .to_account_metas(None),
+        data: amm::instruction::Swap {
+            sell: TokenAmount::new(sell_tokens),
+            min_buy: TokenAmount::new(min_buy),
+        }
+        .data(),
+    }
+}
+
+pub fn program_toll_pda() -> Pubkey {
+    Pubkey::find_program_address(&[ProgramToll::PDA_SEED], &amm::ID).0
+}
+
+pub fn program_config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[ProgramConfig::PDA_SEED], &amm::ID).0
+}
+
+pub fn discount_pda(user: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[Discount::PDA_PREFIX, user.as_ref()],
+        &amm::ID,
+    )
+    .0
+}
+
+pub fn farming_program_config_pda() -> Pubkey {
+    Pubkey::find_program_address(
+        &[farming::models::ProgramConfig::PDA_SEED],
+        &farming::ID,
+    )
+    .0
+}
+
+pub fn farmer_pda(farm: &FarmKeys, authority: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            Farmer::ACCOUNT_PREFIX,
+            farm.farm.as_ref(),
+            authority.as_ref(),
+        ],
+        &farming::ID,
+    )
+    .0
+}
+
+pub fn harvest_vault(farm: &FarmKeys, harvest_mint: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            Harvest::VAULT_PREFIX,
+            farm.farm.as_ref(),
+            harvest_mint.as_ref(),
+        ],
+        &farming::ID,
+    )
+    .0
+}
+
+pub fn whitelist_compounding(farm: &FarmKeys) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            Farm::WHITELIST_PDA_PREFIX,
+            farm.farm.as_ref(),
+            farm.farm.as_ref(),
+        ],
+        &farming::ID,
+    )
+    .0
+}
+
+fn vaults_and_wallets<'a>(
+    pool: &'a PoolKeys,
+    wallets: &'a [Pubkey],
+) -> impl Iterator<Item = AccountMeta> + 'a {
+    pool.vaults.iter().zip(wallets).flat_map(|(vault, wallet)| {
+        [
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*wallet, false),
+        ]
+    })
+}
+
+/// An account owned by given program with given data, as if the program had
+/// initialized it.
+pub fn program_account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Serializes the data with its discriminator and pads it to the space.
+pub fn serialize(data: &impl AccountSerialize, space: usize) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(space);
+    data.try_serialize(&mut buf).unwrap();
+    buf.resize(space, 0);
+
+    buf
+}
+
+// Anchor ties the lifetime of the accounts slice to the lifetime of the
+// account infos, which the processor macro doesn't. The leaked slices are
+// freed when the test process exits.
+fn amm_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    amm::entry(program_id, accounts, data)
+}
+
+fn farming_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    farming::entry(program_id, accounts, data)
+}
@@ -0,0 +1,274 @@
+#[allow(dead_code)]
+mod e2e;
+
+use ::amm::prelude::Pubkey;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use e2e::{Bank, Result};
+use farming::models::{Farmer, Slot, TokenAmount};
+use pretty_assertions::assert_eq;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::Signer;
+use solana_sdk::system_program;
+
+const REWARD_TPS: u64 = 100;
+const LP_TPS: u64 = 10;
+const HARVEST_PERIOD_SLOTS: u64 = 1_000;
+const STAKE: u64 = 500_000;
+
+/// The farm's stake mint is the LP mint of a pool. The farm harvests a reward
+/// mint and the LP mint itself, so that the LP harvest can be compounded.
+#[tokio::test]
+async fn farms_lp_tokens_claims_harvest_and_compounds() -> Result<()> {
+    let mut bank = Bank::start(Pubkey::new_unique()).await;
+
+    let admin = bank.new_user().await?;
+    let user = bank.new_user().await?;
+
+    let mint_a = bank.create_mint(None).await?;
+    let mint_b = bank.create_mint(None).await?;
+    let pool = bank.create_pool(&admin, &[mint_a, mint_b], 0).await?;
+    let admin_lp_wallet = bank
+        .create_token_account(pool.lp_mint, admin.pubkey())
+        .await?;
+    let user_lp_wallet = bank
+        .create_token_account(pool.lp_mint, user.pubkey())
+        .await?;
+    for (owner, lp_wallet) in
+        [(&admin, admin_lp_wallet), (&user, user_lp_wallet)]
+    {
+        let wallets = [
+            bank.create_wallet(mint_a, owner.pubkey(), STAKE).await?,
+            bank.create_wallet(mint_b, owner.pubkey(), STAKE).await?,
+        ];
+        bank.deposit_liquidity(owner, &pool, lp_wallet, &wallets, &[STAKE; 2])
+            .await?;
+    }
+    assert_eq!(bank.token_balance(user_lp_wallet).await?, STAKE);
+
+    //
+    // farm creation and harvest periods
+    //
+    let farm = bank.create_farm(&admin, pool.lp_mint).await?;
+
+    let reward_mint = bank.create_mint(None).await?;
+    let admin_reward_wallet = bank
+        .create_wallet(reward_mint, admin.pubkey(), 1_000_000)
+        .await?;
+    let reward_vault = e2e::harvest_vault(&farm, reward_mint);
+    let lp_vault = e2e::harvest_vault(&farm, pool.lp_mint);
+
+    bank.add_harvest(&admin, &farm, reward_mint).await?;
+    bank.add_harvest(&admin, &farm, pool.lp_mint).await?;
+    bank.farming_ix(
+        &admin,
+        farming::accounts::SetMinSnapshotWindow {
+            admin: admin.pubkey(),
+            farm: farm.farm,
+        },
+        farming::instruction::SetMinSnapshotWindow {
+            min_snapshot_window_slots: 10,
+        },
+    )
+    .await?;
+    for (harvest_mint, harvest_wallet, tps) in [
+        (reward_mint, admin_reward_wallet, REWARD_TPS),
+        (pool.lp_mint, admin_lp_wallet, LP_TPS),
+    ] {
+        bank.farming_ix(
+            &admin,
+            farming::accounts::NewHarvestPeriod {
+                admin: admin.pubkey(),
+                farm: farm.farm,
+                harvest_wallet,
+                harvest_vault: e2e::harvest_vault(&farm, harvest_mint),
+                farm_signer_pda: farm.signer,
+                token_program: token::ID,
+                program_config: e2e::farming_program_config_pda(),
+            },
+            farming::instruction::NewHarvestPeriod {
+                harvest_mint,
+                // current slot
+                starts_at: Slot::new(0),
+                period_length_in_slots: HARVEST_PERIOD_SLOTS,
+                tokens_per_slot: TokenAmount::new(tps),
+            },
+        )
+        .await?;
+    }
+    assert_eq!(
+        bank.token_balance(reward_vault).await?,
+        REWARD_TPS * HARVEST_PERIOD_SLOTS
+    );
+    assert_eq!(
+        bank.token_balance(lp_vault).await?,
+        LP_TPS * HARVEST_PERIOD_SLOTS
+    );
+
+    //
+    // start farming and snapshots
+    //
+    let farmer = e2e::farmer_pda(&farm, user.pubkey());
+    bank.farming_ix(
+        &user,
+        farming::accounts::CreateFarmer {
+            payer: user.pubkey(),
+            authority: user.pubkey(),
+            farmer,
+            farm: farm.farm,
+            system_program: system_program::ID,
+        },
+        farming::instruction::CreateFarmer {},
+    )
+    .await?;
+    bank.farming_ix(
+        &user,
+        farming::accounts::StartFarming {
+            wallet_authority: user.pubkey(),
+            farmer,
+            stake_wallet: user_lp_wallet,
+            farm: farm.farm,
+            stake_vault: farm.stake_vault,
+            token_program: token::ID,
+        },
+        farming::instruction::StartFarming {
+            stake: TokenAmount::new(STAKE),
+        },
+    )
+    .await?;
+    assert_eq!(bank.token_balance(user_lp_wallet).await?, 0);
+    assert_eq!(bank.token_balance(farm.stake_vault).await?, STAKE);
+
+    // staked tokens are vested until the next snapshot
+    bank.warp_slots(20).await?;
+    let snapshot_slot = bank.slot().await?;
+    bank.take_snapshot(&farm).await?;
+
+    //
+    // claim
+    //
+    bank.warp_slots(100).await?;
+    let claim_slot = bank.slot().await?;
+    let user_reward_wallet = bank
+        .create_token_account(reward_mint, user.pubkey())
+        .await?;
+    let mut claim = farming::accounts::ClaimEligibleHarvest {
+        authority: user.pubkey(),
+        farmer,
+        farm_signer_pda: farm.signer,
+        token_program: token::ID,
+    }
+    .to_account_metas(None);
+    claim.push(AccountMeta::new(reward_vault, false));
+    claim.push(AccountMeta::new(user_reward_wallet, false));
+    bank.process(
+        &[
+            Instruction {
+                program_id: farming::ID,
+                accounts: farming::accounts::UpdateEligibleHarvest {
+                    farm: farm.farm,
+                    farmer,
+                }
+                .to_account_metas(None),
+                data: farming::instruction::UpdateEligibleHarvest {}.data(),
+            },
+            Instruction {
+                program_id: farming::ID,
+                accounts: claim,
+                data: farming::instruction::ClaimEligibleHarvest {}.data(),
+            },
+        ],
+        &[&user],
+    )
+    .await?;
+
+    // the user is the only farmer, and therefore gets all the harvest since
+    // the snapshot, inclusive of both slots
+    let claimed = REWARD_TPS * (claim_slot - snapshot_slot + 1);
+    assert_eq!(bank.token_balance(user_reward_wallet).await?, claimed);
+    assert_eq!(
+        bank.token_balance(reward_vault).await?,
+        REWARD_TPS * HARVEST_PERIOD_SLOTS - claimed
+    );
+
+    //
+    // compound
+    //
+    bank.farming_ix(
+        &admin,
+        farming::accounts::WhitelistFarmForCompouding {
+            admin: admin.pubkey(),
+            source_farm: farm.farm,
+            target_farm: farm.farm,
+            whitelist_compounding: e2e::whitelist_compounding(&farm),
+            system_program: system_program::ID,
+        },
+        farming::instruction::WhitelistFarmForCompounding {},
+    )
+    .await?;
+
+    bank.warp_slots(10).await?;
+    let compound_slot = bank.slot().await?;
+    bank.farming_ix(
+        &user,
+        farming::accounts::CompoundSameFarm {
+            farm: farm.farm,
+            farm_signer_pda: farm.signer,
+            whitelist_compounding: e2e::whitelist_compounding(&farm),
+            stake_vault: farm.stake_vault,
+            harvest_vault: lp_vault,
+            farmer,
+            token_program: token::ID,
+        },
+        farming::instruction::CompoundSameFarm {},
+    )
+    .await?;
+
+    // LP harvest hasn't been claimed, all of it is compounded
+    let compounded = LP_TPS * (compound_slot - snapshot_slot + 1);
+    let farmer_account: Farmer = bank.account(farmer).await?;
+    assert_eq!(farmer_account.staked, TokenAmount::new(STAKE));
+    // compounded tokens are vested until the next snapshot
+    assert_eq!(farmer_account.vested, TokenAmount::new(compounded));
+    assert_eq!(
+        bank.token_balance(farm.stake_vault).await?,
+        STAKE + compounded
+    );
+    assert_eq!(
+        bank.token_balance(lp_vault).await?,
+        LP_TPS * HARVEST_PERIOD_SLOTS - compounded
+    );
+
+    //
+    // stop farming
+    //
+    bank.warp_slots(20).await?;
+    bank.take_snapshot(&farm).await?;
+    bank.farming_ix(
+        &user,
+        farming::accounts::StopFarming {
+            authority: user.pubkey(),
+            farmer,
+            stake_wallet: user_lp_wallet,
+            farm: farm.farm,
+            farm_signer_pda: farm.signer,
+            stake_vault: farm.stake_vault,
+            token_program: token::ID,
+        },
+        farming::instruction::StopFarming {
+            unstake_max: TokenAmount::new(u64::MAX),
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        bank.token_balance(user_lp_wallet).await?,
+        STAKE + compounded
+    );
+    assert_eq!(bank.token_balance(farm.stake_vault).await?, 0);
+    let farmer_account: Farmer = bank.account(farmer).await?;
+    assert_eq!(farmer_account.staked, TokenAmount::new(0));
+    assert_eq!(farmer_account.vested, TokenAmount::new(0));
+
+    Ok(())
+}
@@ -0,0 +1,332 @@
+#[allow(dead_code)]
+mod e2e;
+
+use ::amm::endpoints::calculate_toll_in_lp_tokens;
+use ::amm::prelude::*;
+use e2e::{Bank, PoolKeys, Result};
+use pretty_assertions::assert_eq;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::BTreeMap;
+
+#[tokio::test]
+async fn creates_pool_deposits_swaps_with_discount_and_redeems() -> Result<()> {
+    let discount_authority = Keypair::new();
+    let mut bank = Bank::start(discount_authority.pubkey()).await;
+
+    let admin = bank.new_user().await?;
+    let user = bank.new_user().await?;
+    let mint_a = bank.create_mint(None).await?;
+    let mint_b = bank.create_mint(None).await?;
+
+    let pool = bank.create_pool(&admin, &[mint_a, mint_b], 0).await?;
+    bank.set_pool_swap_fee(&admin, &pool, Permillion::from_percent(1))
+        .await?;
+
+    let wallet_a = bank.create_wallet(mint_a, user.pubkey(), 5_000_000).await?;
+    let wallet_b = bank.create_wallet(mint_b, user.pubkey(), 5_000_000).await?;
+    let lp_wallet = bank.create_wallet(pool.lp_mint, user.pubkey(), 0).await?;
+    let wallets = [wallet_a, wallet_b];
+
+    //
+    // deposit
+    //
+    let mut model = bank.pool(&pool).await?;
+    let DepositResult {
+        lp_tokens_to_distribute,
+        tokens_to_deposit,
+    } = model.deposit_tokens(
+        limits(&pool, &[1_000_000, 2_000_000]),
+        TokenAmount::new(0),
+    )?;
+    let lp_tokens = lp_tokens_to_distribute.unwrap().amount;
+
+    bank.deposit_liquidity(
+        &user,
+        &pool,
+        lp_wallet,
+        &wallets,
+        &[1_000_000, 2_000_000],
+    )
+    .await?;
+
+    assert_eq!(bank.token_balance(lp_wallet).await?, lp_tokens);
+    assert_eq!(bank.mint_supply(pool.lp_mint).await?, lp_tokens);
+    assert_eq!(
+        bank.token_balance(wallet_a).await?,
+        5_000_000 - tokens_to_deposit[&mint_a].amount
+    );
+    assert_eq!(
+        bank.token_balance(wallet_b).await?,
+        5_000_000 - tokens_to_deposit[&mint_b].amount
+    );
+    assert_reserves_match_vaults(&mut bank, &pool, &model).await?;
+
+    //
+    // swap with discount
+    //
+    let slot = bank.slot().await?;
+    bank.put_discount(
+        &discount_authority,
+        user.pubkey(),
+        Permillion::from_percent(50),
+        slot + 1_000,
+    )
+    .await?;
+    let discount: Discount =
+        bank.account(e2e::discount_pda(user.pubkey())).await?;
+    assert_eq!(discount.amount, Permillion::from_percent(50));
+
+    let sell = TokenAmount::new(10_000);
+    // 1% fee with 50% discount, ie. 0.5% of the sell amount
+    let swap_fee = TokenAmount::new(50);
+    let lp_supply = TokenAmount::new(bank.mint_supply(pool.lp_mint).await?);
+    let bought = model.swap(
+        mint_a,
+        TokenAmount::new(sell.amount - swap_fee.amount),
+        mint_b,
+        Slot::new(slot),
+    )?;
+    model.reserve_mut(mint_a).unwrap().add_tokens(swap_fee)?;
+    let toll = calculate_toll_in_lp_tokens(
+        &model,
+        swap_fee,
+        consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+        mint_a,
+        lp_supply,
+    )?
+    .unwrap();
+
+    let wallet_a_before = bank.token_balance(wallet_a).await?;
+    let wallet_b_before = bank.token_balance(wallet_b).await?;
+    bank.swap(
+        &user,
+        &pool,
+        (0, wallet_a),
+        (1, wallet_b),
+        sell.amount,
+        bought.amount,
+    )
+    .await?;
+
+    assert_eq!(
+        bank.token_balance(wallet_a).await?,
+        wallet_a_before - sell.amount
+    );
+    assert_eq!(
+        bank.token_balance(wallet_b).await?,
+        wallet_b_before + bought.amount
+    );
+    assert_eq!(
+        bank.token_balance(pool.program_toll_wallet).await?,
+        toll.amount
+    );
+    assert_eq!(
+        bank.mint_supply(pool.lp_mint).await?,
+        lp_supply.amount + toll.amount
+    );
+    assert_reserves_match_vaults(&mut bank, &pool, &model).await?;
+
+    //
+    // redeem
+    //
+    let lp_supply = TokenAmount::new(bank.mint_supply(pool.lp_mint).await?);
+    let tokens_to_redeem = model.redeem_tokens(
+        limits(&pool, &[0, 0]),
+        TokenAmount::new(lp_tokens),
+        lp_supply,
+    )?;
+
+    let wallet_a_before = bank.token_balance(wallet_a).await?;
+    let wallet_b_before = bank.token_balance(wallet_b).await?;
+    bank.redeem_liquidity(&user, &pool, lp_wallet, &wallets, lp_tokens)
+        .await?;
+
+    assert_eq!(bank.token_balance(lp_wallet).await?, 0);
+    assert_eq!(
+        bank.token_balance(wallet_a).await?,
+        wallet_a_before + tokens_to_redeem[&mint_a].amount
+    );
+    assert_eq!(
+        bank.token_balance(wallet_b).await?,
+        wallet_b_before + tokens_to_redeem[&mint_b].amount
+    );
+    // only the toll's liquidity is left in the pool
+    assert_eq!(bank.mint_supply(pool.lp_mint).await?, toll.amount);
+    assert_reserves_match_vaults(&mut bank, &pool, &model).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fails_swap_below_min_buy_and_leaves_pool_untouched() -> Result<()> {
+    let mut bank = Bank::start(Keypair::new().pubkey()).await;
+
+    let admin = bank.new_user().await?;
+    let user = bank.new_user().await?;
+    let mint_a = bank.create_mint(None).await?;
+    let mint_b = bank.create_mint(None).await?;
+
+    let pool = bank.create_pool(&admin, &[mint_a, mint_b], 10).await?;
+    let wallet_a = bank.create_wallet(mint_a, user.pubkey(), 1_000_000).await?;
+    let wallet_b = bank.create_wallet(mint_b, user.pubkey(), 1_000_000).await?;
+    let lp_wallet = bank.create_wallet(pool.lp_mint, user.pubkey(), 0).await?;
+
+    bank.deposit_liquidity(
+        &user,
+        &pool,
+        lp_wallet,
+        &[wallet_a, wallet_b],
+        &[500_000, 500_000],
+    )
+    .await?;
+    let pool_before = bank.pool(&pool).await?;
+
+    // a balanced stable pool cannot give more than it receives
+    assert!(bank
+        .swap(&user, &pool, (0, wallet_a), (1, wallet_b), 1_000, 1_001)
+        .await
+        .is_err());
+
+    assert_eq!(bank.pool(&pool).await?, pool_before);
+    assert_eq!(bank.token_balance(wallet_a).await?, 500_000);
+    assert_eq!(bank.token_balance(wallet_b).await?, 500_000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn tracks_pool_stats_once_created() -> Result<()> {
+    let mut bank = Bank::start(Keypair::new().pubkey()).await;
+
+    let admin = bank.new_user().await?;
+    let user = bank.new_user().await?;
+    let mint_a = bank.create_mint(None).await?;
+    let mint_b = bank.create_mint(None).await?;
+
+    let pool = bank.create_pool(&admin, &[mint_a, mint_b], 0).await?;
+    bank.set_pool_swap_fee(&admin, &pool, Permillion::from_percent(1))
+        .await?;
+    let wallet_a = bank.create_wallet(mint_a, user.pubkey(), 5_000_000).await?;
+    let wallet_b = bank.create_wallet(mint_b, user.pubkey(), 5_000_000).await?;
+    let lp_wallet = bank.create_wallet(pool.lp_mint, user.pubkey(), 0).await?;
+    let wallets = [wallet_a, wallet_b];
+
+    // not tracked, because the stats don't exist yet
+    bank.deposit_liquidity(
+        &user,
+        &pool,
+        lp_wallet,
+        &wallets,
+        &[1_000_000, 1_000_000],
+    )
+    .await?;
+
+    bank.create_pool_stats(&user, &pool).await?;
+    let stats: PoolStats = bank.account(PoolStats::pda(pool.pool)).await?;
+    assert_eq!(stats.pool, pool.pool);
+    assert_eq!(stats.reserves[0].mint, mint_a);
+    assert_eq!(stats.reserves[1].mint, mint_b);
+    assert_eq!(stats.reserves[0].deposited, 0);
+
+    let mut model = bank.pool(&pool).await?;
+    let lp_supply = TokenAmount::new(bank.mint_supply(pool.lp_mint).await?);
+    let deposited = model
+        .deposit_tokens(limits(&pool, &[1_000, 1_000]), lp_supply)?
+        .tokens_to_deposit;
+    bank.deposit_liquidity(&user, &pool, lp_wallet, &wallets, &[1_000, 1_000])
+        .await?;
+
+    let sell = TokenAmount::new(10_000);
+    // 1% fee
+    let swap_fee = TokenAmount::new(100);
+    let lp_supply = TokenAmount::new(bank.mint_supply(pool.lp_mint).await?);
+    let bought = model.swap(
+        mint_a,
+        TokenAmount::new(sell.amount - swap_fee.amount),
+        mint_b,
+        Slot::new(bank.slot().await?),
+    )?;
+    model.reserve_mut(mint_a).unwrap().add_tokens(swap_fee)?;
+    let toll = calculate_toll_in_lp_tokens(
+        &model,
+        swap_fee,
+        consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+        mint_a,
+        lp_supply,
+    )?
+    .unwrap();
+    bank.swap(
+        &user,
+        &pool,
+        (0, wallet_a),
+        (1, wallet_b),
+        sell.amount,
+        bought.amount,
+    )
+    .await?;
+    let swap_slot = bank.slot().await?;
+
+    let lp_supply = TokenAmount::new(bank.mint_supply(pool.lp_mint).await?);
+    let redeemed = model.redeem_tokens(
+        limits(&pool, &[0, 0]),
+        TokenAmount::new(1_000),
+        lp_supply,
+    )?;
+    bank.redeem_liquidity(&user, &pool, lp_wallet, &wallets, 1_000)
+        .await?;
+
+    let stats: PoolStats = bank.account(PoolStats::pda(pool.pool)).await?;
+    assert_eq!(
+        stats.reserves[0],
+        ReserveStats {
+            mint: mint_a,
+            swap_volume_in: sell.amount as u128,
+            swap_volume_out: 0,
+            swap_fees: swap_fee.amount as u128,
+            deposited: deposited[&mint_a].amount as u128,
+            redeemed: redeemed[&mint_a].amount as u128,
+        }
+    );
+    assert_eq!(
+        stats.reserves[1],
+        ReserveStats {
+            mint: mint_b,
+            swap_volume_in: 0,
+            swap_volume_out: bought.amount as u128,
+            swap_fees: 0,
+            deposited: deposited[&mint_b].amount as u128,
+            redeemed: redeemed[&mint_b].amount as u128,
+        }
+    );
+    assert_eq!(stats.toll_lp_minted, toll.amount as u128);
+    assert_eq!(stats.swap_count, 1);
+    assert_eq!(stats.last_swap_slot, Slot::new(swap_slot));
+
+    Ok(())
+}
+
+fn limits(pool: &PoolKeys, tokens: &[u64]) -> BTreeMap<Pubkey, TokenAmount> {
+    pool.mints
+        .iter()
+        .zip(tokens)
+        .map(|(mint, tokens)| (*mint, TokenAmount::new(*tokens)))
+        .collect()
+}
+
+async fn assert_reserves_match_vaults(
+    bank: &mut Bank,
+    pool: &PoolKeys,
+    model: &Pool,
+) -> Result<()> {
+    let on_chain = bank.pool(pool).await?;
+    assert_eq!(on_chain.reserves, model.reserves);
+
+    for reserve in on_chain.reserves() {
+        assert_eq!(
+            bank.token_balance(reserve.vault).await?,
+            reserve.tokens.amount
+        );
+    }
+
+    Ok(())
+}
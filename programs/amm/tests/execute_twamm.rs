@@ -0,0 +1,223 @@
+#[allow(dead_code)]
+mod twamm;
+
+use ::amm::prelude::*;
+use anchortest::spl;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use twamm::*;
+
+#[test]
+#[serial]
+fn executes_order_flow_accumulated_so_far() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+
+    test.execute_twamm(150)?;
+
+    // 500 tokens are due out of which 5 is the fee
+    let order = test.order_copy();
+    assert_eq!(order.sold.amount, 500);
+    assert_eq!(order.bought.amount, 494);
+    assert_eq!(order.proceeds.amount, 494);
+    let pool = test.pool_copy();
+    assert_eq!(pool.reserves[0].tokens.amount, 1_000_500);
+    assert_eq!(pool.reserves[1].tokens.amount, 999_506);
+    assert_eq!(Tester::amount(&test.order_sell_vault), 500);
+    assert_eq!(Tester::amount(&test.order_buy_vault), 494);
+    assert!(Tester::amount(&test.program_toll_wallet) > 0);
+
+    // executing again in the same slot does nothing
+    test.execute_twamm(150)?;
+    assert_eq!(test.order_copy(), order);
+
+    // after the end, the rest of the order is executed
+    test.execute_twamm(1_000)?;
+
+    let order = test.order_copy();
+    assert_eq!(order.sold.amount, 1_000);
+    assert_eq!(order.bought.amount, 988);
+    assert_eq!(order.unsold().amount, 0);
+    let pool = test.pool_copy();
+    assert_eq!(pool.reserves[0].tokens.amount, 1_001_000);
+    assert_eq!(pool.reserves[1].tokens.amount, 999_012);
+    assert_eq!(Tester::amount(&test.order_sell_vault), 0);
+    assert_eq!(Tester::amount(&test.order_buy_vault), 988);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn executes_order_and_records_pool_stats() -> Result<()> {
+    let mut test = Tester::new(pool()).pool_stats();
+    let sell_mint = test.pool_copy().reserves[0].mint;
+    let buy_mint = test.pool_copy().reserves[1].mint;
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+
+    // same execution as in `executes_order_flow_accumulated_so_far`
+    test.execute_twamm(150)?;
+
+    let stats =
+        PoolStats::try_deserialize(&mut test.pool_stats.data.as_slice())?;
+    assert_eq!(
+        stats.reserves[0],
+        ReserveStats {
+            mint: sell_mint,
+            swap_volume_in: 500,
+            swap_fees: 5,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        stats.reserves[1],
+        ReserveStats {
+            mint: buy_mint,
+            swap_volume_out: 494,
+            ..Default::default()
+        }
+    );
+    assert_eq!(stats.swap_count, 1);
+    assert_eq!(stats.last_swap_slot, Slot::new(150));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn does_nothing_if_flow_is_too_small() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 1_000)?;
+    let pool_before = test.pool_copy();
+
+    // 1 token is due which is entirely taken by the fee
+    test.execute_twamm(101)?;
+
+    assert_eq!(test.order_copy().sold.amount, 0);
+    assert_eq!(test.pool_copy(), pool_before);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn is_simulated_with_crank_every_few_slots() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(100_000), 1_000)?;
+
+    for slot in (100..=1_200).step_by(13) {
+        test.execute_twamm(slot)?;
+    }
+
+    let order = test.order_copy();
+    assert_eq!(order.sold.amount, 100_000);
+    assert_eq!(Tester::amount(&test.order_buy_vault), order.bought.amount);
+    let pool = test.pool_copy();
+    assert_eq!(pool.reserves[0].tokens.amount, 1_100_000);
+    assert_eq!(
+        pool.reserves[1].tokens.amount,
+        1_000_000 - order.bought.amount
+    );
+    assert_eq!(
+        Tester::amount(&test.pool_buy_vault),
+        pool.reserves[1].tokens.amount
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_vault_does_not_match_reserve() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+    test.pool_buy_vault = test.order_buy_vault.clone();
+
+    let error = test.execute_twamm(150).unwrap_err().to_string();
+    assert!(error.contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_order_is_of_different_pool() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+    let order = TwammOrder {
+        pool: Pubkey::new_unique(),
+        ..test.order_copy()
+    };
+    test.order = test.order.clone().data(order);
+
+    let error = test.execute_twamm(150).unwrap_err().to_string();
+    assert!(error.contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn executes_order_within_max_price() -> Result<()> {
+    let mut test = Tester::new(pool());
+    // 500 tokens are sold for 494, i.e. ~1.012 sell tokens per bought token
+    let max_price = Decimal::from(Permillion::from_percent(102));
+    test.place_twamm_order_with_max_price(
+        100,
+        TokenAmount::new(1_000),
+        100,
+        Some(max_price.into()),
+    )?;
+
+    test.execute_twamm(150)?;
+
+    assert_eq!(test.order_copy().sold.amount, 500);
+    assert_eq!(test.order_copy().bought.amount, 494);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_execution_is_above_max_price() -> Result<()> {
+    let mut test = Tester::new(pool());
+    let max_price = Decimal::from(Permillion::from_percent(101));
+    test.place_twamm_order_with_max_price(
+        100,
+        TokenAmount::new(1_000),
+        100,
+        Some(max_price.into()),
+    )?;
+    let pool_before = test.pool_copy();
+
+    let error = test.execute_twamm(150).unwrap_err().to_string();
+    assert!(error.contains("LimitPriceExceeded"));
+    assert_eq!(test.pool_copy(), pool_before);
+    assert_eq!(test.order_copy().sold.amount, 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_has_no_liquidity() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+    test.lp_mint = test.lp_mint.clone().pack(
+        spl::mint::from_acc_info(&test.lp_mint.to_account_info()).supply(0),
+    );
+
+    let error = test.execute_twamm(150).unwrap_err().to_string();
+    assert!(error.contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+fn pool() -> Pool {
+    Pool {
+        dimension: 2,
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(1_000_000, 1_000_000),
+        ..Default::default()
+    }
+}
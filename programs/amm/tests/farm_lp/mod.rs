@@ -0,0 +1,543 @@
+use ::amm::amm::{deposit_and_farm, unfarm_and_redeem};
+use ::amm::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, *},
+    stub,
+};
+use pretty_assertions::assert_eq;
+use solana_sdk::instruction::Instruction;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+pub const INITIAL_RESERVE: u64 = 1_000;
+pub const INITIAL_LP_SUPPLY: u64 = 1_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tester {
+    pub user: AccountInfoWrapper,
+    pub pool: AccountInfoWrapper,
+    pub pool_signer: AccountInfoWrapper,
+    pub lp_mint: AccountInfoWrapper,
+    pub lp_token_wallet: AccountInfoWrapper,
+    pub token_program: AccountInfoWrapper,
+    pub pool_stats: AccountInfoWrapper,
+    pub program_config: AccountInfoWrapper,
+    pub farmer: AccountInfoWrapper,
+    pub farm: AccountInfoWrapper,
+    pub farm_signer_pda: AccountInfoWrapper,
+    pub farm_stake_vault: AccountInfoWrapper,
+    pub farming: AccountInfoWrapper,
+    pub vaults_wallets: Vec<AccountInfoWrapper>,
+    /// How many LP tokens the stubbed farming program has staked for the
+    /// user. Unstaking transfers at most this many.
+    pub staked: u64,
+}
+
+impl Tester {
+    /// Constant product pool with two reserves of [`INITIAL_RESERVE`] tokens
+    /// each and [`INITIAL_LP_SUPPLY`] LP tokens in circulation.
+    pub fn new() -> Self {
+        let user = AccountInfoWrapper::new().signer();
+        let pool = AccountInfoWrapper::new().owner(amm::ID).mutable();
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let lp_mint = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::mint::new(pool_signer.key).supply(INITIAL_LP_SUPPLY))
+            .owner(token::ID);
+        let lp_token_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::token_account::new(user.key).mint(lp_mint.key))
+            .owner(token::ID);
+        let token_program = AccountInfoWrapper::with_key(token::ID).program();
+        let pool_stats = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_stats",
+            &[PoolStats::PDA_PREFIX, pool.key.as_ref()],
+        )
+        .mutable()
+        .owner(system_program::ID);
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+
+        let farm = AccountInfoWrapper::new().owner(farming::ID);
+        let farmer = AccountInfoWrapper::new().mutable().owner(farming::ID);
+        let farm_signer_pda = AccountInfoWrapper::new();
+        let farm_stake_vault = AccountInfoWrapper::new()
+            .mutable()
+            .pack(
+                spl::token_account::new(farm_signer_pda.key)
+                    .mint(lp_mint.key)
+                    .amount(INITIAL_LP_SUPPLY),
+            )
+            .owner(token::ID);
+        let farming = AccountInfoWrapper::with_key(farming::ID).program();
+
+        let mut reserves = [Reserve::default(); consts::MAX_RESERVES];
+        let vaults_wallets = (0..2)
+            .flat_map(|index| {
+                let mint = Pubkey::new_unique();
+                let vault = AccountInfoWrapper::new()
+                    .mutable()
+                    .pack(
+                        spl::token_account::new(pool_signer.key)
+                            .mint(mint)
+                            .amount(INITIAL_RESERVE),
+                    )
+                    .owner(token::ID);
+                reserves[index] = Reserve {
+                    vault: vault.key,
+                    mint,
+                    tokens: TokenAmount::new(INITIAL_RESERVE),
+                };
+                let wallet = AccountInfoWrapper::new()
+                    .mutable()
+                    .pack(
+                        spl::token_account::new(user.key)
+                            .mint(mint)
+                            .amount(1_000_000),
+                    )
+                    .owner(token::ID);
+
+                vec![vault, wallet].into_iter()
+            })
+            .collect();
+
+        let pool = pool.data(Pool {
+            signer: pool_signer.key,
+            mint: lp_mint.key,
+            dimension: 2,
+            reserves,
+            ..Default::default()
+        });
+
+        Self {
+            user,
+            pool,
+            pool_signer,
+            lp_mint,
+            lp_token_wallet,
+            token_program,
+            pool_stats,
+            program_config,
+            farmer,
+            farm,
+            farm_signer_pda,
+            farm_stake_vault,
+            farming,
+            vaults_wallets,
+            staked: 0,
+        }
+    }
+
+    pub fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    pub fn lp_token_wallet_amount(&self) -> u64 {
+        spl::token_account::from_acc_info(
+            &self.lp_token_wallet.to_account_info(),
+        )
+        .amount
+    }
+
+    pub fn lp_supply(&self) -> u64 {
+        spl::mint::from_acc_info(&self.lp_mint.to_account_info()).supply
+    }
+
+    pub fn mints(&self) -> Vec<Pubkey> {
+        self.pool_copy().reserves().iter().map(|r| r.mint).collect()
+    }
+
+    pub fn deposit_and_farm(
+        &mut self,
+        max_amount_tokens: BTreeMap<Pubkey, TokenAmount>,
+        min_lp_tokens_out: TokenAmount,
+    ) -> Result<()> {
+        let expected_cpis = self
+            .expected_deposit_cpis(max_amount_tokens.clone())
+            .unwrap_or_default();
+        let expected_cpis = Arc::new(Mutex::new(expected_cpis));
+        let syscalls = stub::Syscalls::new(CpiValidator {
+            expected_cpis: Arc::clone(&expected_cpis),
+            unstake: 0,
+        });
+        syscalls.set();
+
+        let mut ctx = self.deposit_context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        deposit_and_farm(
+            ctx.build(&mut accounts),
+            into_limits(max_amount_tokens),
+            min_lp_tokens_out,
+        )?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*expected_cpis.lock().unwrap(), vec![]);
+
+        Ok(())
+    }
+
+    pub fn unfarm_and_redeem(
+        &mut self,
+        unstake_max: TokenAmount,
+        min_amount_tokens: BTreeMap<Pubkey, TokenAmount>,
+    ) -> Result<()> {
+        let unstake = unstake_max.amount.min(self.staked);
+        let expected_cpis = self.expected_redeem_cpis(
+            unstake_max,
+            TokenAmount::new(unstake),
+            min_amount_tokens.clone(),
+        );
+        let expected_cpis = Arc::new(Mutex::new(expected_cpis));
+        let syscalls = stub::Syscalls::new(CpiValidator {
+            expected_cpis: Arc::clone(&expected_cpis),
+            unstake,
+        });
+        syscalls.set();
+
+        let mut ctx = self.redeem_context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        unfarm_and_redeem(
+            ctx.build(&mut accounts),
+            unstake_max,
+            into_limits(min_amount_tokens),
+        )?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*expected_cpis.lock().unwrap(), vec![]);
+
+        Ok(())
+    }
+
+    /// Replicates the endpoint's logic to find which CPIs to expect.
+    fn expected_deposit_cpis(
+        &self,
+        max_amount_tokens: BTreeMap<Pubkey, TokenAmount>,
+    ) -> Result<Vec<ExpectedCpi>> {
+        let mut pool = self.pool_copy();
+        let DepositResult {
+            lp_tokens_to_distribute,
+            tokens_to_deposit,
+        } = pool.deposit_tokens(
+            max_amount_tokens,
+            TokenAmount::new(self.lp_supply()),
+        )?;
+        let lp_tokens = lp_tokens_to_distribute.unwrap_or_default();
+
+        let mut cpis: Vec<_> = self
+            .vaults_wallets
+            .chunks(2)
+            .map(|vault_wallet| {
+                let vault = &vault_wallet[0];
+                let wallet = &vault_wallet[1];
+                let mint =
+                    spl::token_account::from_acc_info(&vault.to_account_info())
+                        .mint;
+                ExpectedCpi::Transfer {
+                    authority: self.user.key,
+                    from: wallet.key,
+                    to: vault.key,
+                    tokens: tokens_to_deposit[&mint],
+                }
+            })
+            .collect();
+        cpis.push(ExpectedCpi::MintTo {
+            signer: self.pool_signer.key,
+            mint: self.lp_mint.key,
+            destination: self.lp_token_wallet.key,
+            tokens: lp_tokens,
+        });
+        cpis.push(ExpectedCpi::StartFarming {
+            ix: Instruction {
+                program_id: farming::ID,
+                accounts: farming::accounts::StartFarming {
+                    wallet_authority: self.user.key,
+                    farmer: self.farmer.key,
+                    stake_wallet: self.lp_token_wallet.key,
+                    farm: self.farm.key,
+                    stake_vault: self.farm_stake_vault.key,
+                    token_program: token::ID,
+                }
+                .to_account_metas(None),
+                data: farming::instruction::StartFarming { stake: lp_tokens }
+                    .data(),
+            },
+            stake_wallet: self.lp_token_wallet.key,
+            stake_vault: self.farm_stake_vault.key,
+            stake: lp_tokens,
+        });
+
+        Ok(cpis)
+    }
+
+    /// Replicates the endpoint's logic to find which CPIs to expect.
+    fn expected_redeem_cpis(
+        &self,
+        unstake_max: TokenAmount,
+        unstake: TokenAmount,
+        min_amount_tokens: BTreeMap<Pubkey, TokenAmount>,
+    ) -> Vec<ExpectedCpi> {
+        let mut cpis = vec![ExpectedCpi::StopFarming {
+            ix: Instruction {
+                program_id: farming::ID,
+                accounts: farming::accounts::StopFarming {
+                    authority: self.user.key,
+                    farmer: self.farmer.key,
+                    stake_wallet: self.lp_token_wallet.key,
+                    farm: self.farm.key,
+                    farm_signer_pda: self.farm_signer_pda.key,
+                    stake_vault: self.farm_stake_vault.key,
+                    token_program: token::ID,
+                }
+                .to_account_metas(None),
+                data: farming::instruction::StopFarming { unstake_max }.data(),
+            },
+            stake_wallet: self.lp_token_wallet.key,
+            stake_vault: self.farm_stake_vault.key,
+        }];
+
+        // if the redemption fails, only the unstaking is expected
+        let mut pool = self.pool_copy();
+        if let Ok(tokens_to_redeem) = pool.redeem_tokens(
+            min_amount_tokens,
+            unstake,
+            TokenAmount::new(self.lp_supply()),
+        ) {
+            cpis.extend(self.vaults_wallets.chunks(2).map(|vault_wallet| {
+                let vault = &vault_wallet[0];
+                let wallet = &vault_wallet[1];
+                let mint =
+                    spl::token_account::from_acc_info(&vault.to_account_info())
+                        .mint;
+                ExpectedCpi::Transfer {
+                    authority: self.pool_signer.key,
+                    from: vault.key,
+                    to: wallet.key,
+                    tokens: tokens_to_redeem[&mint],
+                }
+            }));
+            cpis.push(ExpectedCpi::Burn {
+                authority: self.user.key,
+                mint: self.lp_mint.key,
+                source: self.lp_token_wallet.key,
+                tokens: unstake,
+            });
+        }
+
+        cpis
+    }
+
+    fn deposit_context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.user)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.lp_mint)
+            .acc(&mut self.lp_token_wallet)
+            .acc(&mut self.token_program)
+            .acc(&mut self.pool_stats)
+            .acc(&mut self.program_config)
+            .acc(&mut self.farmer)
+            .acc(&mut self.farm)
+            .acc(&mut self.farm_stake_vault)
+            .acc(&mut self.farming)
+            .remaining_accounts(self.vaults_wallets.iter_mut())
+    }
+
+    fn redeem_context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.user)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.lp_mint)
+            .acc(&mut self.lp_token_wallet)
+            .acc(&mut self.token_program)
+            .acc(&mut self.pool_stats)
+            .acc(&mut self.program_config)
+            .acc(&mut self.farmer)
+            .acc(&mut self.farm)
+            .acc(&mut self.farm_signer_pda)
+            .acc(&mut self.farm_stake_vault)
+            .acc(&mut self.farming)
+            .remaining_accounts(self.vaults_wallets.iter_mut())
+    }
+}
+
+fn into_limits(tokens: BTreeMap<Pubkey, TokenAmount>) -> Vec<TokenLimit> {
+    tokens
+        .into_iter()
+        .map(|(mint, tokens)| TokenLimit { mint, tokens })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct CpiValidator {
+    expected_cpis: Arc<Mutex<Vec<ExpectedCpi>>>,
+    /// How many LP tokens the stubbed `stop_farming` moves from the stake
+    /// vault to the stake wallet.
+    unstake: u64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ExpectedCpi {
+    Transfer {
+        authority: Pubkey,
+        from: Pubkey,
+        to: Pubkey,
+        tokens: TokenAmount,
+    },
+    MintTo {
+        signer: Pubkey,
+        mint: Pubkey,
+        destination: Pubkey,
+        tokens: TokenAmount,
+    },
+    Burn {
+        authority: Pubkey,
+        mint: Pubkey,
+        source: Pubkey,
+        tokens: TokenAmount,
+    },
+    /// The farming program transfers `stake` into its vault.
+    StartFarming {
+        ix: Instruction,
+        stake_wallet: Pubkey,
+        stake_vault: Pubkey,
+        stake: TokenAmount,
+    },
+    /// The farming program transfers the unstaked tokens out of its vault.
+    StopFarming {
+        ix: Instruction,
+        stake_wallet: Pubkey,
+        stake_vault: Pubkey,
+    },
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut expected_cpis = self.expected_cpis.lock().unwrap();
+        if expected_cpis.is_empty() {
+            panic!("No more instructions expected, got {:#?}", ix);
+        }
+        let find =
+            |key: Pubkey| accounts.iter().find(|acc| acc.key() == key).unwrap();
+
+        match expected_cpis.remove(0) {
+            ExpectedCpi::Transfer {
+                authority,
+                from,
+                to,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::transfer(
+                    &token::ID,
+                    &from,
+                    &to,
+                    &authority,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                spl::token_account::transfer(
+                    find(from),
+                    find(to),
+                    tokens.amount,
+                )
+                .expect("Source does not have enough tokens");
+            }
+            ExpectedCpi::MintTo {
+                signer,
+                mint,
+                destination,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::mint_to(
+                    &token::ID,
+                    &mint,
+                    &destination,
+                    &signer,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                spl::mint::mint_to(
+                    find(destination),
+                    find(mint),
+                    tokens.amount,
+                )
+                .expect("Cannot mint LP tokens");
+            }
+            ExpectedCpi::Burn {
+                authority,
+                mint,
+                source,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::burn(
+                    &token::ID,
+                    &source,
+                    &mint,
+                    &authority,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                spl::mint::burn_from(find(source), find(mint), tokens.amount)
+                    .expect("Cannot burn LP tokens");
+            }
+            ExpectedCpi::StartFarming {
+                ix: expected_ix,
+                stake_wallet,
+                stake_vault,
+                stake,
+            } => {
+                assert_eq!(&expected_ix, ix);
+
+                spl::token_account::transfer(
+                    find(stake_wallet),
+                    find(stake_vault),
+                    stake.amount,
+                )
+                .expect("Stake wallet does not have enough tokens");
+            }
+            ExpectedCpi::StopFarming {
+                ix: expected_ix,
+                stake_wallet,
+                stake_vault,
+            } => {
+                assert_eq!(&expected_ix, ix);
+
+                spl::token_account::transfer(
+                    find(stake_vault),
+                    find(stake_wallet),
+                    self.unstake,
+                )
+                .expect("Stake vault does not have enough tokens");
+            }
+        }
+    }
+}
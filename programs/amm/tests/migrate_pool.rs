@@ -0,0 +1,105 @@
+//! The successful migration reallocates the pool account, which relies on the
+//! runtime's account serialization and cannot be stubbed here. The layout
+//! round trip is unit tested in the [`amm::models::pool_v1`] module.
+
+use ::amm::amm::migrate_pool;
+use ::amm::prelude::*;
+use anchor_lang::system_program;
+use anchortest::builder::*;
+use common::test_utils::set_clock;
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn fails_if_pool_is_already_migrated() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test
+        .migrate_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_is_not_owned_by_amm() -> Result<()> {
+    let mut test = Tester::default();
+    test.pool = test.pool.clone().owner(Pubkey::new_unique());
+
+    assert!(test
+        .migrate_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_account_is_not_pool() -> Result<()> {
+    let mut test = Tester::default();
+    test.pool = test.pool.clone().data(Discount::default());
+
+    assert!(test
+        .migrate_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("AccountDiscriminatorMismatch"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    payer: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    system_program: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let payer = AccountInfoWrapper::new().mutable().signer();
+        let pool =
+            AccountInfoWrapper::new()
+                .mutable()
+                .owner(amm::ID)
+                .data(Pool {
+                    version: Pool::VERSION,
+                    ..Default::default()
+                });
+        let system_program =
+            AccountInfoWrapper::with_key(system_program::ID).program();
+
+        Self {
+            payer,
+            pool,
+            system_program,
+        }
+    }
+}
+
+impl Tester {
+    fn migrate_pool(&mut self) -> Result<()> {
+        // the migration reads the rent, but doesn't depend on the clock
+        set_clock(Slot::new(0));
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        migrate_pool(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.payer)
+            .acc(&mut self.pool)
+            .acc(&mut self.system_program)
+    }
+}
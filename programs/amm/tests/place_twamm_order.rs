@@ -0,0 +1,125 @@
+#[allow(dead_code)]
+mod twamm;
+
+use ::amm::prelude::*;
+use anchor_spl::token;
+use anchortest::{builder::*, spl};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use twamm::*;
+
+#[test]
+#[serial]
+fn places_order() -> Result<()> {
+    let mut test = Tester::new(pool());
+    let sell_wallet_before = Tester::amount(&test.sell_wallet);
+
+    test.place_twamm_order(100, TokenAmount::new(1_000), 50)?;
+
+    let pool = test.pool_copy();
+    assert_eq!(
+        test.order_copy(),
+        TwammOrder {
+            pool: test.pool.key,
+            authority: test.user.key,
+            sell_mint: pool.reserves[0].mint,
+            buy_mint: pool.reserves[1].mint,
+            sell_vault: test.order_sell_vault.key,
+            buy_vault: test.order_buy_vault.key,
+            sell: TokenAmount::new(1_000),
+            start: Slot::new(100),
+            end: Slot::new(150),
+            ..Default::default()
+        }
+    );
+    assert_eq!(Tester::amount(&test.order_sell_vault), 1_000);
+    assert_eq!(
+        sell_wallet_before - Tester::amount(&test.sell_wallet),
+        1_000
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_sell_is_zero() {
+    let mut test = Tester::new(pool());
+
+    let error = test
+        .place_twamm_order(100, TokenAmount::new(0), 50)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("ZeroTokenAmount"));
+}
+
+#[test]
+#[serial]
+fn fails_if_duration_is_zero() {
+    let mut test = Tester::new(pool());
+
+    let error = test
+        .place_twamm_order(100, TokenAmount::new(1_000), 0)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidArg"));
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_not_owned_by_order_signer() {
+    let mut test = Tester::new(pool());
+    let buy_mint = test.pool_copy().reserves[1].mint;
+    test.order_buy_vault = AccountInfoWrapper::new()
+        .pack(spl::token_account::new(test.user.key).mint(buy_mint))
+        .owner(token::ID);
+
+    let error = test
+        .place_twamm_order(100, TokenAmount::new(1_000), 50)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidAccountInput"));
+}
+
+#[test]
+#[serial]
+fn fails_if_buy_mint_is_sell_mint() {
+    let mut test = Tester::new(pool());
+    let sell_mint = test.pool_copy().reserves[0].mint;
+    test.order_buy_vault = AccountInfoWrapper::new()
+        .pack(spl::token_account::new(test.order_signer.key).mint(sell_mint))
+        .owner(token::ID);
+
+    let error = test
+        .place_twamm_order(100, TokenAmount::new(1_000), 50)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidAccountInput"));
+}
+
+#[test]
+#[serial]
+fn fails_if_buy_mint_is_not_in_pool() {
+    let mut test = Tester::new(pool());
+    test.order_buy_vault = AccountInfoWrapper::new()
+        .pack(
+            spl::token_account::new(test.order_signer.key)
+                .mint(Pubkey::new_unique()),
+        )
+        .owner(token::ID);
+
+    let error = test
+        .place_twamm_order(100, TokenAmount::new(1_000), 50)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidAccountInput"));
+}
+
+fn pool() -> Pool {
+    Pool {
+        dimension: 2,
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(1_000_000, 1_000_000),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,163 @@
+use ::amm::amm::propose_swap_fee;
+use ::amm::prelude::*;
+use anchor_lang::system_program;
+use anchortest::builder::*;
+use common::test_utils::set_clock;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+
+const CURRENT_SLOT: u64 = 1_000;
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::default();
+
+    let fee = Permillion { permillion: 5_000 };
+    let effective_at =
+        Slot::new(CURRENT_SLOT + consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS);
+    test.propose_swap_fee(fee, effective_at)?;
+
+    let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+    assert_eq!(
+        pool.pending_swap_fee,
+        Some(PendingSwapFee { fee, effective_at })
+    );
+    // the fee doesn't change until a swap after the effective slot
+    assert_eq!(pool.swap_fee, Permillion::default());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn overwrites_pending_proposal() -> Result<()> {
+    let mut test = Tester::default();
+
+    let effective_at =
+        Slot::new(CURRENT_SLOT + consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS);
+    test.propose_swap_fee(consts::MAX_SWAP_FEE, effective_at)?;
+
+    let fee = Permillion { permillion: 1_000 };
+    let effective_at = Slot::new(effective_at.slot + 1);
+    test.propose_swap_fee(fee, effective_at)?;
+
+    let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+    assert_eq!(
+        pool.pending_swap_fee,
+        Some(PendingSwapFee { fee, effective_at })
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_effective_too_soon() -> Result<()> {
+    let mut test = Tester::default();
+
+    let effective_at =
+        Slot::new(CURRENT_SLOT + consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS - 1);
+    assert!(test
+        .propose_swap_fee(consts::MAX_SWAP_FEE, effective_at)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidArg"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_fee_more_than_max() -> Result<()> {
+    let mut test = Tester::default();
+
+    let fee = Permillion {
+        permillion: consts::MAX_SWAP_FEE.permillion + 1,
+    };
+    let effective_at =
+        Slot::new(CURRENT_SLOT + consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS);
+    assert!(test
+        .propose_swap_fee(fee, effective_at)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidArg"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::default();
+    test.admin = AccountInfoWrapper::new().signer();
+
+    let effective_at =
+        Slot::new(CURRENT_SLOT + consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS);
+    assert!(test
+        .propose_swap_fee(consts::MAX_SWAP_FEE, effective_at)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let admin = AccountInfoWrapper::new().signer();
+        let pool =
+            AccountInfoWrapper::new()
+                .mutable()
+                .owner(amm::ID)
+                .data(Pool {
+                    admin: admin.key,
+                    ..Default::default()
+                });
+
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+
+        Self {
+            admin,
+            pool,
+            program_config,
+        }
+    }
+}
+
+impl Tester {
+    fn propose_swap_fee(
+        &mut self,
+        fee: Permillion,
+        effective_at: Slot,
+    ) -> Result<()> {
+        set_clock(Slot::new(CURRENT_SLOT));
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        propose_swap_fee(ctx.build(&mut accounts), fee, effective_at)?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+            .acc(&mut self.program_config)
+    }
+}
@@ -0,0 +1,262 @@
+use ::amm::amm::put_pool_access;
+use ::amm::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
+use anchortest::{builder::*, stub};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn creates_new_pool_access() -> Result<()> {
+    let user = Pubkey::new_unique();
+
+    let mut test = Tester::new(user);
+    let og_state = test.clone();
+
+    assert!(test.put_pool_access_create(true).is_ok());
+
+    let pool_access =
+        PoolAccess::try_deserialize(&mut test.pool_access.data.as_slice())?;
+    assert_eq!(
+        pool_access,
+        PoolAccess {
+            pool: test.pool.key,
+            user,
+            is_allowed: true,
+        }
+    );
+
+    // no other changes should have happened
+    test.pool_access = og_state.pool_access.clone();
+    assert_eq!(test, og_state);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn revokes_existing_pool_access() -> Result<()> {
+    let user = Pubkey::new_unique();
+
+    let mut test = Tester::new(user);
+    let og_state = test.clone();
+
+    assert!(test.put_pool_access_create(true).is_ok());
+    // the stub of the system_program::create_account doesn't change the
+    // owner of the account, so we need to change it manually
+    test.pool_access.owner = amm::ID;
+
+    test.authority.is_writable = false;
+    assert!(test.put_pool_access_update(false).is_ok());
+
+    let pool_access =
+        PoolAccess::try_deserialize(&mut test.pool_access.data.as_slice())?;
+    assert_eq!(
+        pool_access,
+        PoolAccess {
+            pool: test.pool.key,
+            user,
+            is_allowed: false,
+        }
+    );
+
+    // no other changes should have happened
+    test.pool_access = og_state.pool_access.clone();
+    test.authority.is_writable = og_state.authority.is_writable;
+    assert_eq!(test, og_state);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_authority_is_not_pools_access_policy() -> Result<()> {
+    let user = Pubkey::new_unique();
+
+    let mut test = Tester::new(user);
+    test.authority = AccountInfoWrapper::new().mutable().signer();
+    assert!(test
+        .put_pool_access_create(true)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_has_no_access_policy() -> Result<()> {
+    let user = Pubkey::new_unique();
+
+    let mut test = Tester::new(user);
+    test.pool = AccountInfoWrapper::with_key(test.pool.key)
+        .data(Pool::default())
+        .owner(amm::ID);
+    assert!(test
+        .put_pool_access_create(true)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_authority_is_not_mutable_on_creation() -> Result<()> {
+    let user = Pubkey::new_unique();
+
+    let mut test = Tester::new(user);
+    test.authority.is_writable = false;
+    assert!(test
+        .put_pool_access_create(true)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    user: Pubkey,
+    authority: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    pool_access: AccountInfoWrapper,
+    system_program: AccountInfoWrapper,
+}
+
+impl Tester {
+    fn new(user: Pubkey) -> Self {
+        let authority = AccountInfoWrapper::new().mutable().signer();
+        let pool = AccountInfoWrapper::new()
+            .data(Pool {
+                access_policy: Some(authority.key),
+                ..Default::default()
+            })
+            .owner(amm::ID);
+        let pool_access = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_access",
+            &[PoolAccess::PDA_PREFIX, pool.key.as_ref(), user.as_ref()],
+        )
+        .owner(system_program::ID)
+        .mutable()
+        .size(PoolAccess::space());
+        let system_program =
+            AccountInfoWrapper::with_key(system_program::ID).program();
+
+        Self {
+            user,
+            authority,
+            pool,
+            pool_access,
+            system_program,
+        }
+    }
+}
+
+impl Tester {
+    /// Does not expect a call to [`system_program::create_account`]
+    fn put_pool_access_update(&mut self, is_allowed: bool) -> Result<()> {
+        self.put_pool_access(CpiValidatorState::Done, is_allowed)
+    }
+
+    /// Will expect a call to [`system_program::create_account`]
+    fn put_pool_access_create(&mut self, is_allowed: bool) -> Result<()> {
+        self.put_pool_access(
+            CpiValidatorState::CreatePoolAccess {
+                payer: self.authority.key,
+                pool_access: self.pool_access.key,
+            },
+            is_allowed,
+        )
+    }
+
+    fn put_pool_access(
+        &mut self,
+        state: CpiValidatorState,
+        is_allowed: bool,
+    ) -> Result<()> {
+        let user = self.user;
+
+        let state = self.set_syscalls(state);
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        put_pool_access(ctx.build(&mut accounts), user, is_allowed)?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*state.lock().unwrap(), CpiValidatorState::Done);
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.authority)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_access)
+            .acc(&mut self.system_program)
+            .ix_data(self.user.as_ref().to_vec())
+    }
+
+    fn set_syscalls(
+        &self,
+        state: CpiValidatorState,
+    ) -> Arc<Mutex<CpiValidatorState>> {
+        let state = Arc::new(Mutex::new(state));
+
+        let syscalls = stub::Syscalls::new(CpiValidator(Arc::clone(&state)));
+        syscalls.set();
+
+        state
+    }
+}
+
+struct CpiValidator(Arc<Mutex<CpiValidatorState>>);
+#[derive(Debug, Eq, PartialEq)]
+enum CpiValidatorState {
+    CreatePoolAccess { payer: Pubkey, pool_access: Pubkey },
+    Done,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut state = self.0.lock().unwrap();
+        match *state {
+            CpiValidatorState::CreatePoolAccess { payer, pool_access } => {
+                let rent = Rent::default().minimum_balance(PoolAccess::space());
+                let expected_ix = system_instruction::create_account(
+                    &payer,
+                    &pool_access,
+                    rent,
+                    PoolAccess::space() as u64,
+                    &amm::ID,
+                );
+                assert_eq!(&expected_ix, ix);
+
+                let pool_access = accounts
+                    .iter()
+                    .find(|acc| acc.key() == pool_access)
+                    .unwrap();
+                let mut lamports = pool_access.lamports.borrow_mut();
+                **lamports = rent;
+
+                *state = CpiValidatorState::Done;
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
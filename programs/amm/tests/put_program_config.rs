@@ -0,0 +1,322 @@
+use ::amm::amm::put_program_config;
+use ::amm::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
+use anchortest::{builder::*, stub};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn creates_new_config() -> Result<()> {
+    let mut test = Tester::default();
+    let og_state = test.clone();
+
+    let max_swap_fee = Permillion::from_percent(2);
+    let toll_share = Permillion::from_percent(20);
+    assert!(test
+        .put_program_config_create(
+            max_swap_fee,
+            toll_share,
+            StableCurveInvariantMethod::Integer,
+        )
+        .is_ok());
+
+    let config = ProgramConfig::try_deserialize(
+        &mut test.program_config.data.as_slice(),
+    )?;
+    assert_eq!(
+        config,
+        ProgramConfig {
+            max_swap_fee,
+            program_toll_swap_fee_share: toll_share,
+            stable_curve_invariant: StableCurveInvariantMethod::Integer,
+            ..Default::default()
+        }
+    );
+
+    // no other changes should have happened
+    test.program_config = og_state.program_config.clone();
+    assert_eq!(test, og_state);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn updates_existing_config() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test
+        .put_program_config_create(
+            Permillion::from_percent(2),
+            Permillion::from_percent(20),
+            StableCurveInvariantMethod::Integer,
+        )
+        .is_ok());
+    // the stub of the system_program::create_account doesn't change the
+    // owner of the account, so we need to change it manually
+    test.program_config.owner = amm::ID;
+
+    assert!(test
+        .put_program_config_update(
+            consts::MAX_SWAP_FEE,
+            consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+        )
+        .is_ok());
+
+    let config = ProgramConfig::try_deserialize(
+        &mut test.program_config.data.as_slice(),
+    )?;
+    assert_eq!(config, ProgramConfig::default());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_out_of_bounds() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test
+        .put_program_config_update(
+            Permillion {
+                permillion: consts::MAX_SWAP_FEE_UPPER_BOUND.permillion + 1,
+            },
+            consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidArg"));
+
+    assert!(test
+        .put_program_config_update(
+            consts::MAX_SWAP_FEE,
+            Permillion {
+                permillion: consts::MAX_PROGRAM_TOLL_SWAP_FEE_SHARE.permillion
+                    + 1,
+            },
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidArg"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_program_data_does_not_match() -> Result<()> {
+    let mut test = Tester::default();
+    test.amm = AccountInfoWrapper::with_key(amm::ID)
+        .program_with_data_addr(Pubkey::new_unique());
+
+    assert!(test
+        .put_program_config_update(
+            consts::MAX_SWAP_FEE,
+            consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_program_authority_does_not_match() -> Result<()> {
+    let mut test = Tester::default();
+    test.amm_metadata =
+        AccountInfoWrapper::new().program_data(Pubkey::new_unique());
+
+    assert!(test
+        .put_program_config_update(
+            consts::MAX_SWAP_FEE,
+            consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pda_does_not_match() -> Result<()> {
+    let mut test = Tester::default();
+    test.program_config =
+        AccountInfoWrapper::pda(amm::ID, "program_config", &[b"wrong_seed"])
+            .size(ProgramConfig::space())
+            .mutable()
+            .owner(system_program::ID);
+
+    assert!(test
+        .put_program_config_update(
+            consts::MAX_SWAP_FEE,
+            consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("ConstraintSeeds"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    program_authority: AccountInfoWrapper,
+    amm: AccountInfoWrapper,
+    amm_metadata: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
+    system_program: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let program_authority = AccountInfoWrapper::new().mutable().signer();
+        let amm_metadata =
+            AccountInfoWrapper::new().program_data(program_authority.key);
+        let amm = AccountInfoWrapper::with_key(amm::ID)
+            .program_with_data_addr(amm_metadata.key);
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .size(ProgramConfig::space())
+        .mutable()
+        .owner(system_program::ID);
+        let system_program =
+            AccountInfoWrapper::with_key(system_program::ID).program();
+
+        Self {
+            program_authority,
+            amm,
+            amm_metadata,
+            program_config,
+            system_program,
+        }
+    }
+}
+
+impl Tester {
+    /// Does not expect a call to [`system_program::create_account`]
+    fn put_program_config_update(
+        &mut self,
+        max_swap_fee: Permillion,
+        toll_share: Permillion,
+    ) -> Result<()> {
+        self.put_program_config(
+            CpiValidatorState::Done,
+            max_swap_fee,
+            toll_share,
+            StableCurveInvariantMethod::default(),
+        )
+    }
+
+    /// Will expect a call to [`system_program::create_account`]
+    fn put_program_config_create(
+        &mut self,
+        max_swap_fee: Permillion,
+        toll_share: Permillion,
+        stable_curve_invariant: StableCurveInvariantMethod,
+    ) -> Result<()> {
+        self.put_program_config(
+            CpiValidatorState::CreateProgramConfig {
+                payer: self.program_authority.key,
+                program_config: self.program_config.key,
+            },
+            max_swap_fee,
+            toll_share,
+            stable_curve_invariant,
+        )
+    }
+
+    fn put_program_config(
+        &mut self,
+        state: CpiValidatorState,
+        max_swap_fee: Permillion,
+        toll_share: Permillion,
+        stable_curve_invariant: StableCurveInvariantMethod,
+    ) -> Result<()> {
+        let state = Arc::new(Mutex::new(state));
+        stub::Syscalls::new(CpiValidator(Arc::clone(&state))).set();
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        put_program_config(
+            ctx.build(&mut accounts),
+            max_swap_fee,
+            toll_share,
+            stable_curve_invariant,
+        )?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*state.lock().unwrap(), CpiValidatorState::Done);
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.program_authority)
+            .acc(&mut self.amm)
+            .acc(&mut self.amm_metadata)
+            .acc(&mut self.program_config)
+            .acc(&mut self.system_program)
+    }
+}
+
+struct CpiValidator(Arc<Mutex<CpiValidatorState>>);
+#[derive(Debug, Eq, PartialEq)]
+enum CpiValidatorState {
+    CreateProgramConfig {
+        payer: Pubkey,
+        program_config: Pubkey,
+    },
+    Done,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut state = self.0.lock().unwrap();
+        match *state {
+            CpiValidatorState::CreateProgramConfig {
+                payer,
+                program_config,
+            } => {
+                let rent =
+                    Rent::default().minimum_balance(ProgramConfig::space());
+                let expected_ix = system_instruction::create_account(
+                    &payer,
+                    &program_config,
+                    rent,
+                    ProgramConfig::space() as u64,
+                    &amm::ID,
+                );
+                assert_eq!(&expected_ix, ix);
+
+                let program_config = accounts
+                    .iter()
+                    .find(|acc| acc.key() == program_config)
+                    .unwrap();
+                let mut lamports = program_config.lamports.borrow_mut();
+                **lamports = rent;
+
+                *state = CpiValidatorState::Done;
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
@@ -39,6 +39,40 @@ fn redeems_liquidity_from_const_prod_with_two_reserves() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn records_redemption_in_pool_stats() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+    tester.create_pool_stats();
+
+    tester.deposit_liquidity(
+        reserves
+            .iter()
+            .map(|r| (r.mint, TokenAmount::new(10)))
+            .collect(),
+        &reserves,
+    )?;
+    tester.redeem_liquidity(
+        reserves
+            .iter()
+            .map(|r| (r.mint, TokenAmount::new(0)))
+            .collect(),
+        TokenAmount::new(4),
+        &reserves,
+    )?;
+
+    // the pool started empty, so what's left is the deposit minus redemption
+    let pool = Pool::try_deserialize(&mut tester.pool.data.as_slice())?;
+    let stats = tester.pool_stats()?;
+    for (reserve, reserve_stats) in pool.reserves().iter().zip(stats.reserves) {
+        assert_eq!(reserve_stats.mint, reserve.mint);
+        assert_eq!(reserve_stats.deposited, 10);
+        assert_eq!(reserve_stats.redeemed, 10 - reserve.tokens.amount as u128);
+    }
+
+    Ok(())
+}
+
 #[test]
 #[serial]
 fn redeems_liquidity_from_const_prod_with_more_than_two_reserves() -> Result<()>
@@ -316,7 +350,7 @@ fn fails_if_same_mint_passed_multiple_times() -> Result<()> {
         .unwrap_err()
         .to_string();
 
-    assert!(error.contains("InvalidArg"));
+    assert!(error.contains("DimensionMismatch"));
 
     Ok(())
 }
@@ -384,7 +418,7 @@ fn fails_if_redemption_amounts_below_min_amount_tokens() -> Result<()> {
         .unwrap_err()
         .to_string();
 
-    assert!(error.contains("InvalidArg"));
+    assert!(error.contains("RedeemBelowMinimum"));
 
     Ok(())
 }
@@ -801,3 +835,126 @@ fn prints_lp_token_supply_in_redeem_and_deposit_liquidity() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[serial]
+fn fails_while_pool_is_bootstrapping() -> Result<()> {
+    let (mut tester, reserves) = Tester::new(
+        2,
+        Curve::Weighted {
+            start_weight: Permillion::from_percent(90),
+            end_weight: Permillion::from_percent(50),
+            start: Slot::new(10),
+            end: Slot::new(100),
+        },
+    );
+
+    let error = tester
+        .redeem_liquidity(
+            reserves
+                .iter()
+                .map(|r| (r.mint, TokenAmount::new(5)))
+                .collect(),
+            TokenAmount::new(5),
+            &reserves,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolBootstrapping"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_while_pool_is_bootstrapping_even_if_user_is_admin() -> Result<()> {
+    let (mut tester, reserves) = Tester::new(
+        2,
+        Curve::Weighted {
+            start_weight: Permillion::from_percent(90),
+            end_weight: Permillion::from_percent(50),
+            start: Slot::new(10),
+            end: Slot::new(100),
+        },
+    );
+    let pool = Pool {
+        admin: tester.user.key,
+        ..Pool::try_deserialize(&mut tester.pool.data.as_slice())?
+    };
+    tester.pool = tester.pool.clone().data(pool);
+
+    let error = tester
+        .redeem_liquidity(
+            reserves
+                .iter()
+                .map(|r| (r.mint, TokenAmount::new(5)))
+                .collect(),
+            TokenAmount::new(5),
+            &reserves,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolBootstrapping"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn redeems_even_if_pool_access_is_revoked() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+    tester.set_pool_access(true)?;
+
+    tester.deposit_liquidity(
+        reserves
+            .iter()
+            .map(|r| (r.mint, TokenAmount::new(10)))
+            .collect(),
+        &reserves,
+    )?;
+
+    tester.set_pool_access(false)?;
+
+    tester.redeem_liquidity(
+        reserves
+            .iter()
+            .map(|r| (r.mint, TokenAmount::new(5)))
+            .collect(),
+        TokenAmount::new(5),
+        &reserves,
+    )?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_to_redeem_if_pool_access_was_never_granted() -> Result<()> {
+    let (mut tester, reserves) = Tester::new_const_prod(2);
+
+    tester.deposit_liquidity(
+        reserves
+            .iter()
+            .map(|r| (r.mint, TokenAmount::new(10)))
+            .collect(),
+        &reserves,
+    )?;
+
+    tester.set_pool_access(true)?;
+    tester.pool_access = None;
+
+    let error = tester
+        .redeem_liquidity(
+            reserves
+                .iter()
+                .map(|r| (r.mint, TokenAmount::new(5)))
+                .collect(),
+            TokenAmount::new(5),
+            &reserves,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolAccessDenied"));
+
+    Ok(())
+}
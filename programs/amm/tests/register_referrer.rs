@@ -0,0 +1,197 @@
+use ::amm::amm::register_referrer;
+use ::amm::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
+use anchortest::{builder::*, stub};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::default();
+
+    test.register_referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE)?;
+
+    let referrer =
+        Referrer::try_deserialize(&mut test.referrer.data.as_slice())?;
+    assert_eq!(
+        referrer,
+        Referrer {
+            authority: test.referrer_authority.key,
+            share: consts::MAX_REFERRER_SWAP_FEE_SHARE,
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_share_is_more_than_max() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test
+        .register_referrer(Permillion {
+            permillion: consts::MAX_REFERRER_SWAP_FEE_SHARE.permillion + 1,
+        })
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidArg"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_program_toll_authority() -> Result<()> {
+    let mut test = Tester::default();
+    test.program_toll_authority = AccountInfoWrapper::new().mutable().signer();
+
+    assert!(test
+        .register_referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pda_does_not_match() -> Result<()> {
+    let mut test = Tester::default();
+    test.referrer =
+        AccountInfoWrapper::pda(amm::ID, "referrer", &[b"wrong_seed"])
+            .size(Referrer::space())
+            .mutable()
+            .owner(amm::ID);
+
+    assert!(test
+        .register_referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE)
+        .unwrap_err()
+        .to_string()
+        .contains("ConstraintSeeds"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    program_toll_authority: AccountInfoWrapper,
+    program_toll: AccountInfoWrapper,
+    referrer_authority: AccountInfoWrapper,
+    referrer: AccountInfoWrapper,
+    system_program: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let program_toll_authority =
+            AccountInfoWrapper::new().mutable().signer();
+        let program_toll = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_toll",
+            &[ProgramToll::PDA_SEED],
+        )
+        .owner(amm::ID)
+        .data(ProgramToll {
+            authority: program_toll_authority.key,
+        });
+        let referrer_authority = AccountInfoWrapper::new();
+        let referrer = AccountInfoWrapper::pda(
+            amm::ID,
+            "referrer",
+            &[Referrer::PDA_PREFIX, referrer_authority.key.as_ref()],
+        )
+        .size(Referrer::space())
+        .mutable()
+        .owner(amm::ID);
+        let system_program =
+            AccountInfoWrapper::with_key(system_program::ID).program();
+
+        Self {
+            program_toll_authority,
+            program_toll,
+            referrer_authority,
+            referrer,
+            system_program,
+        }
+    }
+}
+
+impl Tester {
+    fn register_referrer(&mut self, share: Permillion) -> Result<()> {
+        self.set_syscalls();
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        register_referrer(ctx.build(&mut accounts), share)?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.program_toll_authority)
+            .acc(&mut self.program_toll)
+            .acc(&mut self.referrer_authority)
+            .acc(&mut self.referrer)
+            .acc(&mut self.system_program)
+    }
+
+    fn set_syscalls(&self) {
+        stub::Syscalls::new(CpiValidator(CpiValidatorState::CreateReferrer {
+            program_toll_authority: self.program_toll_authority.key,
+            referrer: self.referrer.key,
+        }))
+        .set();
+    }
+}
+
+struct CpiValidator(CpiValidatorState);
+enum CpiValidatorState {
+    CreateReferrer {
+        program_toll_authority: Pubkey,
+        referrer: Pubkey,
+    },
+    Done,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        match self.0 {
+            CpiValidatorState::CreateReferrer {
+                program_toll_authority,
+                referrer,
+            } => {
+                let rent = Rent::default().minimum_balance(Referrer::space());
+                let expected_ix = system_instruction::create_account(
+                    &program_toll_authority,
+                    &referrer,
+                    rent,
+                    Referrer::space() as u64,
+                    &amm::ID,
+                );
+                assert_eq!(&expected_ix, ix);
+
+                let referrer =
+                    accounts.iter().find(|acc| acc.key() == referrer).unwrap();
+                let mut lamports = referrer.lamports.borrow_mut();
+                **lamports = rent;
+
+                self.0 = CpiValidatorState::Done;
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use ::amm::amm::remove_reserve;
+use ::amm::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, TokenAccountExt},
+    stub,
+};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::default();
+    let pool_before = test.pool_copy();
+
+    test.remove_reserve()?;
+
+    let pool = test.pool_copy();
+    assert_eq!(pool.dimension, 2);
+    assert_eq!(pool.reserves[0], pool_before.reserves[1]);
+    assert_eq!(pool.reserves[1], pool_before.reserves[2]);
+    assert_eq!(pool.reserves[2], Reserve::default());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::default();
+    test.admin = AccountInfoWrapper::new().mutable().signer();
+
+    assert!(test
+        .remove_reserve()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_not_empty() -> Result<()> {
+    let mut test = Tester::default();
+    test.vault = test.vault.clone().pack(
+        spl::token_account::from_acc_info(&test.vault.to_account_info())
+            .amount(1),
+    );
+
+    assert!(test
+        .remove_reserve()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_reserve_balance_is_not_zero() -> Result<()> {
+    let mut test = Tester::default();
+    let mut pool = test.pool_copy();
+    pool.reserves[0].tokens = TokenAmount::new(1);
+    test.pool = test.pool.clone().data(pool);
+
+    assert!(test
+        .remove_reserve()
+        .unwrap_err()
+        .to_string()
+        .contains("ReserveNotEmpty"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_only_two_reserves_would_be_left() -> Result<()> {
+    let mut test = Tester::default();
+    let mut pool = test.pool_copy();
+    pool.reserves[2] = Reserve::default();
+    pool.dimension = 2;
+    test.pool = test.pool.clone().data(pool);
+
+    assert!(test
+        .remove_reserve()
+        .unwrap_err()
+        .to_string()
+        .contains("TooFewReserves"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_not_reserves_vault() -> Result<()> {
+    let mut test = Tester::default();
+    let mint = test.pool_copy().reserves[0].mint;
+    test.vault = AccountInfoWrapper::new()
+        .mutable()
+        .pack(spl::token_account::new(test.pool_signer.key).mint(mint))
+        .owner(token::ID);
+
+    assert!(test
+        .remove_reserve()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    pool_signer: AccountInfoWrapper,
+    vault: AccountInfoWrapper,
+    token_program: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let admin = AccountInfoWrapper::new().mutable().signer();
+        let pool = AccountInfoWrapper::new().owner(amm::ID).mutable();
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let mint = Pubkey::new_unique();
+        let vault = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::token_account::new(pool_signer.key).mint(mint))
+            .owner(token::ID);
+        let token_program = AccountInfoWrapper::with_key(token::ID).program();
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+
+        let pool = pool.data(Pool {
+            admin: admin.key,
+            signer: pool_signer.key,
+            dimension: 3,
+            reserves: [
+                Reserve {
+                    mint,
+                    vault: vault.key,
+                    tokens: TokenAmount::new(0),
+                },
+                Reserve {
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                    tokens: TokenAmount::new(0),
+                },
+                Reserve {
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                    tokens: TokenAmount::new(0),
+                },
+                Reserve::default(),
+            ],
+            ..Default::default()
+        });
+
+        Self {
+            admin,
+            pool,
+            pool_signer,
+            vault,
+            token_program,
+            program_config,
+        }
+    }
+}
+
+impl Tester {
+    fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    fn remove_reserve(&mut self) -> Result<()> {
+        let syscalls = self.set_syscalls(CpiValidatorState::CloseVault {
+            admin: self.admin.key,
+            pool_signer: self.pool_signer.key,
+            vault: self.vault.key,
+        });
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        remove_reserve(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        let CpiValidator(state) =
+            (*syscalls.validator().lock().unwrap()).clone();
+        assert_eq!(*state.lock().unwrap(), CpiValidatorState::Done);
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.vault)
+            .acc(&mut self.token_program)
+            .acc(&mut self.program_config)
+    }
+
+    fn set_syscalls(
+        &self,
+        state: CpiValidatorState,
+    ) -> stub::Syscalls<CpiValidator> {
+        let validator = CpiValidator(Arc::new(Mutex::new(state)));
+        let syscalls = stub::Syscalls::new(validator);
+        syscalls.clone().set();
+
+        syscalls
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CpiValidator(Arc<Mutex<CpiValidatorState>>);
+
+#[derive(Debug, PartialEq, Eq)]
+enum CpiValidatorState {
+    CloseVault {
+        admin: Pubkey,
+        pool_signer: Pubkey,
+        vault: Pubkey,
+    },
+    Done,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        _accounts: &[AccountInfo],
+    ) {
+        let mut state = self.0.lock().unwrap();
+
+        match *state {
+            CpiValidatorState::CloseVault {
+                admin,
+                pool_signer,
+                vault,
+            } => {
+                let expected_ix = token::spl_token::instruction::close_account(
+                    &token::ID,
+                    &vault,
+                    &admin,
+                    &pool_signer,
+                    &[],
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                *state = CpiValidatorState::Done;
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
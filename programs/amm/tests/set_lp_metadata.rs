@@ -0,0 +1,435 @@
+use ::amm::amm::set_lp_metadata;
+use ::amm::prelude::*;
+use ::amm::token_metadata::{self, DataV2};
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use anchortest::{builder::*, spl, stub};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn creates_metadata_with_name_from_reserve_mints() -> Result<()> {
+    let mut test = Tester::default();
+    test.reserve_metadata =
+        test.reserve_metadata_with_symbols(&["USDC", "USDT"]);
+
+    test.set_lp_metadata_create(
+        None,
+        None,
+        "https://example.com/lp.json",
+        DataV2::new(
+            "AMM LP USDC-USDT".to_string(),
+            "USDC-USDT".to_string(),
+            "https://example.com/lp.json".to_string(),
+        ),
+    )?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn creates_metadata_with_short_mints_if_reserve_metadata_missing() -> Result<()>
+{
+    let mut test = Tester::default();
+    let short_mints: Vec<_> = test
+        .reserve_mints
+        .iter()
+        .map(|mint| mint.to_string()[..4].to_string())
+        .collect();
+
+    test.set_lp_metadata_create(
+        None,
+        None,
+        "",
+        DataV2::new(
+            format!("AMM LP {}", short_mints.join("-")),
+            short_mints.join("-"),
+            "".to_string(),
+        ),
+    )?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn creates_metadata_with_default_symbol_if_derived_is_too_long() -> Result<()> {
+    let mut test = Tester::default();
+    test.reserve_metadata =
+        test.reserve_metadata_with_symbols(&["wstETH", "stSOL"]);
+
+    test.set_lp_metadata_create(
+        None,
+        None,
+        "",
+        DataV2::new(
+            "AMM LP wstETH-stSOL".to_string(),
+            set_lp_metadata::DEFAULT_LP_SYMBOL.to_string(),
+            "".to_string(),
+        ),
+    )?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn creates_metadata_with_overrides() -> Result<()> {
+    let mut test = Tester::default();
+    // not needed when both name and symbol are given
+    test.reserve_metadata.clear();
+
+    test.set_lp_metadata_create(
+        Some("Stable LP"),
+        Some("sLP"),
+        "",
+        DataV2::new("Stable LP".to_string(), "sLP".to_string(), "".to_string()),
+    )?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn updates_existing_metadata() -> Result<()> {
+    let mut test = Tester::default();
+    test.lp_metadata.owner = token_metadata::ID;
+    test.reserve_metadata =
+        test.reserve_metadata_with_symbols(&["USDC", "USDT"]);
+
+    test.set_lp_metadata(
+        CpiValidatorState::UpdateMetadata {
+            metadata: test.lp_metadata.key,
+            update_authority: test.pool_signer.key,
+            data: DataV2::new(
+                "AMM LP USDC-USDT".to_string(),
+                "sLP".to_string(),
+                "".to_string(),
+            ),
+        },
+        None,
+        Some("sLP"),
+        "",
+    )?;
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::default();
+    test.admin = AccountInfoWrapper::new().mutable().signer();
+
+    assert!(test
+        .set_lp_metadata(CpiValidatorState::Done, None, None, "")
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_lp_metadata_is_not_pda_of_lp_mint() -> Result<()> {
+    let mut test = Tester::default();
+    test.lp_metadata = AccountInfoWrapper::new()
+        .mutable()
+        .owner(system_program::ID);
+
+    assert!(test
+        .set_lp_metadata(CpiValidatorState::Done, None, None, "")
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_reserve_metadata_is_missing_one() -> Result<()> {
+    let mut test = Tester::default();
+    test.reserve_metadata.pop();
+
+    assert!(test
+        .set_lp_metadata(CpiValidatorState::Done, None, None, "")
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_name_is_too_long() -> Result<()> {
+    let mut test = Tester::default();
+
+    assert!(test
+        .set_lp_metadata(
+            CpiValidatorState::Done,
+            Some(&"A".repeat(token_metadata::MAX_NAME_LEN + 1)),
+            None,
+            "",
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidArg"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    pool_signer: AccountInfoWrapper,
+    lp_mint: AccountInfoWrapper,
+    lp_metadata: AccountInfoWrapper,
+    token_metadata_program: AccountInfoWrapper,
+    system_program: AccountInfoWrapper,
+    rent: AccountInfoWrapper,
+    reserve_mints: Vec<Pubkey>,
+    reserve_metadata: Vec<AccountInfoWrapper>,
+}
+
+impl Default for Tester {
+    fn default() -> Self {
+        let admin = AccountInfoWrapper::new().mutable().signer();
+        let pool = AccountInfoWrapper::new().owner(amm::ID);
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let lp_mint = AccountInfoWrapper::new()
+            .pack(spl::mint::new(pool_signer.key))
+            .owner(token::ID);
+        let lp_metadata = AccountInfoWrapper::with_key(
+            token_metadata::metadata_address(lp_mint.key),
+        )
+        .mutable()
+        .owner(system_program::ID);
+        let token_metadata_program =
+            AccountInfoWrapper::with_key(token_metadata::ID).program();
+        let system_program =
+            AccountInfoWrapper::with_key(system_program::ID).program();
+        let rent = AccountInfoWrapper::with_key(sysvar::rent::ID);
+
+        let mut reserves = [Reserve::default(); consts::MAX_RESERVES];
+        let reserve_mints: Vec<_> = (0..2)
+            .map(|index| {
+                let mint = Pubkey::new_unique();
+                reserves[index] = Reserve {
+                    vault: Pubkey::new_unique(),
+                    mint,
+                    tokens: TokenAmount::new(0),
+                };
+
+                mint
+            })
+            .collect();
+        // reserve mints without metadata
+        let reserve_metadata = reserve_mints
+            .iter()
+            .map(|mint| {
+                AccountInfoWrapper::with_key(token_metadata::metadata_address(
+                    *mint,
+                ))
+                .owner(system_program::ID)
+            })
+            .collect();
+
+        let pool = pool.data(Pool {
+            admin: admin.key,
+            signer: pool_signer.key,
+            mint: lp_mint.key,
+            dimension: 2,
+            reserves,
+            ..Default::default()
+        });
+
+        Self {
+            admin,
+            pool,
+            pool_signer,
+            lp_mint,
+            lp_metadata,
+            token_metadata_program,
+            system_program,
+            rent,
+            reserve_mints,
+            reserve_metadata,
+        }
+    }
+}
+
+impl Tester {
+    /// Metadata accounts of the reserve mints as the metadata program stores
+    /// them, with the strings padded by null bytes.
+    fn reserve_metadata_with_symbols(
+        &self,
+        symbols: &[&str],
+    ) -> Vec<AccountInfoWrapper> {
+        self.reserve_mints
+            .iter()
+            .zip(symbols)
+            .map(|(mint, symbol)| {
+                let mut data = vec![4]; // metadata account key
+                data.extend_from_slice(Pubkey::new_unique().as_ref());
+                data.extend_from_slice(mint.as_ref());
+                padded("Some Coin", token_metadata::MAX_NAME_LEN)
+                    .serialize(&mut data)
+                    .unwrap();
+                padded(symbol, token_metadata::MAX_SYMBOL_LEN)
+                    .serialize(&mut data)
+                    .unwrap();
+
+                let mut metadata = AccountInfoWrapper::with_key(
+                    token_metadata::metadata_address(*mint),
+                )
+                .owner(token_metadata::ID);
+                metadata.data = data;
+                metadata
+            })
+            .collect()
+    }
+
+    /// Will expect a call to create the metadata account with given data.
+    fn set_lp_metadata_create(
+        &mut self,
+        name: Option<&str>,
+        symbol: Option<&str>,
+        uri: &str,
+        expected_data: DataV2,
+    ) -> Result<()> {
+        self.set_lp_metadata(
+            CpiValidatorState::CreateMetadata {
+                metadata: self.lp_metadata.key,
+                mint: self.lp_mint.key,
+                pool_signer: self.pool_signer.key,
+                payer: self.admin.key,
+                data: expected_data,
+            },
+            name,
+            symbol,
+            uri,
+        )
+    }
+
+    fn set_lp_metadata(
+        &mut self,
+        state: CpiValidatorState,
+        name: Option<&str>,
+        symbol: Option<&str>,
+        uri: &str,
+    ) -> Result<()> {
+        let state = Arc::new(Mutex::new(state));
+        stub::Syscalls::new(CpiValidator(Arc::clone(&state))).set();
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        set_lp_metadata(
+            ctx.build(&mut accounts),
+            name.map(ToString::to_string),
+            symbol.map(ToString::to_string),
+            uri.to_string(),
+        )?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*state.lock().unwrap(), CpiValidatorState::Done);
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.lp_mint)
+            .acc(&mut self.lp_metadata)
+            .acc(&mut self.token_metadata_program)
+            .acc(&mut self.system_program)
+            .acc(&mut self.rent)
+            .remaining_accounts(self.reserve_metadata.iter_mut())
+    }
+}
+
+fn padded(s: &str, len: usize) -> String {
+    format!("{}{}", s, "\0".repeat(len - s.len()))
+}
+
+struct CpiValidator(Arc<Mutex<CpiValidatorState>>);
+#[derive(Debug, Eq, PartialEq)]
+enum CpiValidatorState {
+    CreateMetadata {
+        metadata: Pubkey,
+        mint: Pubkey,
+        pool_signer: Pubkey,
+        payer: Pubkey,
+        data: DataV2,
+    },
+    UpdateMetadata {
+        metadata: Pubkey,
+        update_authority: Pubkey,
+        data: DataV2,
+    },
+    Done,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        _accounts: &[AccountInfo],
+    ) {
+        let mut state = self.0.lock().unwrap();
+        match &*state {
+            CpiValidatorState::CreateMetadata {
+                metadata,
+                mint,
+                pool_signer,
+                payer,
+                data,
+            } => {
+                let expected_ix = token_metadata::create_metadata_account_v2(
+                    *metadata,
+                    *mint,
+                    *pool_signer,
+                    *payer,
+                    *pool_signer,
+                    data.clone(),
+                );
+                assert_eq!(&expected_ix, ix);
+
+                *state = CpiValidatorState::Done;
+            }
+            CpiValidatorState::UpdateMetadata {
+                metadata,
+                update_authority,
+                data,
+            } => {
+                let expected_ix = token_metadata::update_metadata_account_v2(
+                    *metadata,
+                    *update_authority,
+                    data.clone(),
+                );
+                assert_eq!(&expected_ix, ix);
+
+                *state = CpiValidatorState::Done;
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
@@ -1,19 +1,56 @@
 use ::amm::amm::set_pool_swap_fee;
 use ::amm::prelude::*;
+use anchor_lang::system_program;
 use anchortest::builder::*;
+use common::test_utils::set_clock;
 use pretty_assertions::assert_eq;
 use serial_test::serial;
 
+const CURRENT_SLOT: u64 = 1_000;
+
 #[test]
 #[serial]
 fn works() -> Result<()> {
     let mut test = Tester::default();
 
+    // the pool has no liquidity yet, hence the fee applies right away
     let fee = Permillion { permillion: 5_000 };
     assert!(test.set_pool_swap_fee(fee).is_ok());
 
     let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
     assert_eq!(pool.swap_fee, fee);
+    assert_eq!(pool.pending_swap_fee, None);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn schedules_fee_change_if_pool_has_liquidity() -> Result<()> {
+    let mut test = Tester::default();
+    let mut pool = test.pool_copy();
+    pool.dimension = 2;
+    pool.reserves[0].tokens = TokenAmount::new(1_000);
+    pool.reserves[1].tokens = TokenAmount::new(1_000);
+    test.pool = test.pool.clone().data(pool);
+
+    let fee = Permillion { permillion: 5_000 };
+    test.set_pool_swap_fee(fee)?;
+
+    let mut pool = test.pool_copy();
+    let effective_at =
+        Slot::new(CURRENT_SLOT + consts::MIN_SWAP_FEE_CHANGE_DELAY_SLOTS);
+    assert_eq!(pool.swap_fee, Permillion::default());
+    assert_eq!(
+        pool.pending_swap_fee,
+        Some(PendingSwapFee { fee, effective_at })
+    );
+
+    // the fee doesn't apply before the delay
+    assert!(!pool.apply_pending_swap_fee(Slot::new(effective_at.slot - 1)));
+    assert_eq!(pool.swap_fee, Permillion::default());
+    assert!(pool.apply_pending_swap_fee(effective_at));
+    assert_eq!(pool.swap_fee, fee);
 
     Ok(())
 }
@@ -48,10 +85,55 @@ fn fails_if_discount_more_than_1_percent() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn max_swap_fee_is_read_from_program_config() -> Result<()> {
+    let mut test = Tester::default();
+    let max_swap_fee = Permillion::from_percent(5);
+    test.program_config =
+        test.program_config.owner(amm::ID).data(ProgramConfig {
+            max_swap_fee,
+            ..Default::default()
+        });
+
+    assert!(test.set_pool_swap_fee(max_swap_fee).is_ok());
+    let pool = Pool::try_deserialize(&mut test.pool.data.as_slice())?;
+    assert_eq!(pool.swap_fee, max_swap_fee);
+
+    let fee = Permillion {
+        permillion: max_swap_fee.permillion + 1,
+    };
+    assert!(test
+        .set_pool_swap_fee(fee)
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidArg"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_program_config_pda_does_not_match() -> Result<()> {
+    let mut test = Tester::default();
+    test.program_config =
+        AccountInfoWrapper::pda(amm::ID, "program_config", &[b"wrong_seed"])
+            .owner(system_program::ID);
+
+    assert!(test
+        .set_pool_swap_fee(consts::MAX_SWAP_FEE)
+        .unwrap_err()
+        .to_string()
+        .contains("ConstraintSeeds"));
+
+    Ok(())
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct Tester {
     admin: AccountInfoWrapper,
     pool: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
 }
 
 impl Default for Tester {
@@ -66,12 +148,29 @@ impl Default for Tester {
                     ..Default::default()
                 });
 
-        Self { admin, pool }
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+
+        Self {
+            admin,
+            pool,
+            program_config,
+        }
     }
 }
 
 impl Tester {
+    fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
     fn set_pool_swap_fee(&mut self, fee: Permillion) -> Result<()> {
+        set_clock(Slot::new(CURRENT_SLOT));
+
         let mut ctx = self.context_wrapper();
         let mut accounts = ctx.accounts()?;
 
@@ -85,5 +184,6 @@ impl Tester {
         ContextWrapper::new(amm::ID)
             .acc(&mut self.admin)
             .acc(&mut self.pool)
+            .acc(&mut self.program_config)
     }
 }
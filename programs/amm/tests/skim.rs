@@ -0,0 +1,280 @@
+use ::amm::amm::skim;
+use ::amm::prelude::*;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, TokenAccountExt},
+    stub,
+};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::new(100, 150);
+    let pool_before = test.pool_copy();
+
+    test.skim(TokenAmount::new(50))?;
+
+    // pool's state doesn't change
+    assert_eq!(test.pool_copy(), pool_before);
+    assert_eq!(test.vault_amount(), 100);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_there_is_no_surplus() -> Result<()> {
+    let mut test = Tester::new(100, 100);
+
+    assert!(test
+        .skim(TokenAmount::new(0))
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_there_is_deficit() -> Result<()> {
+    let mut test = Tester::new(100, 90);
+
+    assert!(test
+        .skim(TokenAmount::new(0))
+        .unwrap_err()
+        .to_string()
+        .contains("InvariantViolation"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_toll_wallet_is_not_owned_by_toll_authority() -> Result<()> {
+    let mut test = Tester::new(100, 150);
+    let mint =
+        spl::token_account::from_acc_info(&test.vault.to_account_info()).mint;
+    test.program_toll_wallet = AccountInfoWrapper::new()
+        .mutable()
+        .pack(spl::token_account::new(Pubkey::new_unique()).mint(mint))
+        .owner(token::ID);
+
+    assert!(test
+        .skim(TokenAmount::new(50))
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_toll_wallet_is_of_different_mint() -> Result<()> {
+    let mut test = Tester::new(100, 150);
+    test.program_toll_wallet = test.program_toll_wallet.clone().pack(
+        spl::token_account::from_acc_info(
+            &test.program_toll_wallet.to_account_info(),
+        )
+        .mint(Pubkey::new_unique()),
+    );
+
+    assert!(test
+        .skim(TokenAmount::new(50))
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    pool: AccountInfoWrapper,
+    pool_signer: AccountInfoWrapper,
+    vault: AccountInfoWrapper,
+    program_toll: AccountInfoWrapper,
+    program_toll_wallet: AccountInfoWrapper,
+    token_program: AccountInfoWrapper,
+}
+
+impl Tester {
+    fn new(reserve_amount: u64, vault_amount: u64) -> Self {
+        let pool = AccountInfoWrapper::new().owner(amm::ID);
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let mint = Pubkey::new_unique();
+        let vault = AccountInfoWrapper::new()
+            .mutable()
+            .pack(
+                spl::token_account::new(pool_signer.key)
+                    .mint(mint)
+                    .amount(vault_amount),
+            )
+            .owner(token::ID);
+        let program_toll_authority = Pubkey::new_unique();
+        let program_toll = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_toll",
+            &[ProgramToll::PDA_SEED],
+        )
+        .data(ProgramToll {
+            authority: program_toll_authority,
+        })
+        .owner(amm::ID);
+        let program_toll_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::token_account::new(program_toll_authority).mint(mint))
+            .owner(token::ID);
+        let token_program = AccountInfoWrapper::with_key(token::ID).program();
+
+        let pool = pool.data(Pool {
+            signer: pool_signer.key,
+            dimension: 2,
+            reserves: [
+                Reserve {
+                    mint,
+                    vault: vault.key,
+                    tokens: TokenAmount::new(reserve_amount),
+                },
+                Reserve {
+                    mint: Pubkey::new_unique(),
+                    vault: Pubkey::new_unique(),
+                    tokens: TokenAmount::new(reserve_amount),
+                },
+                Reserve::default(),
+                Reserve::default(),
+            ],
+            ..Default::default()
+        });
+
+        Self {
+            pool,
+            pool_signer,
+            vault,
+            program_toll,
+            program_toll_wallet,
+            token_program,
+        }
+    }
+
+    fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    fn vault_amount(&self) -> u64 {
+        spl::token_account::from_acc_info(&self.vault.to_account_info()).amount
+    }
+
+    fn skim(&mut self, expected_surplus: TokenAmount) -> Result<()> {
+        let syscalls = self.set_syscalls(CpiValidatorState::TransferSurplus {
+            pool_signer: self.pool_signer.key,
+            vault: self.vault.key,
+            wallet: self.program_toll_wallet.key,
+            tokens: expected_surplus,
+        });
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        skim(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        let CpiValidator(state) =
+            (*syscalls.validator().lock().unwrap()).clone();
+        assert_eq!(*state.lock().unwrap(), CpiValidatorState::Done);
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.vault)
+            .acc(&mut self.program_toll)
+            .acc(&mut self.program_toll_wallet)
+            .acc(&mut self.token_program)
+    }
+
+    fn set_syscalls(
+        &self,
+        state: CpiValidatorState,
+    ) -> stub::Syscalls<CpiValidator> {
+        let validator = CpiValidator(Arc::new(Mutex::new(state)));
+        let syscalls = stub::Syscalls::new(validator);
+        syscalls.clone().set();
+
+        syscalls
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CpiValidator(Arc<Mutex<CpiValidatorState>>);
+
+#[derive(Debug, PartialEq, Eq)]
+enum CpiValidatorState {
+    TransferSurplus {
+        pool_signer: Pubkey,
+        vault: Pubkey,
+        wallet: Pubkey,
+        tokens: TokenAmount,
+    },
+    Done,
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut state = self.0.lock().unwrap();
+
+        match *state {
+            CpiValidatorState::TransferSurplus {
+                pool_signer,
+                vault,
+                wallet,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::transfer(
+                    &token::ID,
+                    &vault,
+                    &wallet,
+                    &pool_signer,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                let from_vault = &accounts[0];
+                let to_wallet = &accounts[1];
+                assert_eq!(from_vault.key(), vault);
+                assert_eq!(to_wallet.key(), wallet);
+
+                spl::token_account::transfer(
+                    &from_vault,
+                    &to_wallet,
+                    tokens.amount,
+                )
+                .expect("Source vault does not have enough tokens");
+
+                *state = CpiValidatorState::Done;
+            }
+            CpiValidatorState::Done => {
+                panic!("No more instructions expected, got {:#?}", ix);
+            }
+        }
+    }
+}
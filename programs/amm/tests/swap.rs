@@ -1,5 +1,8 @@
-use ::amm::amm::swap;
-use ::amm::endpoints::{calculate_swap_fee, calculate_toll_in_lp_tokens};
+use ::amm::amm::{swap, swap_v2};
+use ::amm::endpoints::{
+    calculate_referral_in_lp_tokens, calculate_swap_fee,
+    calculate_toll_in_lp_tokens,
+};
 use ::amm::prelude::*;
 use anchor_lang::system_program;
 use anchor_spl::token;
@@ -59,6 +62,99 @@ fn swaps_const_prod_two_reserves_no_discount() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn swaps_with_program_toll_share_from_program_config() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool_before.clone()).program_config(
+        ProgramConfig {
+            program_toll_swap_fee_share: Permillion::from_percent(10),
+            ..Default::default()
+        },
+    );
+
+    let supply_before = test.lp_supply();
+
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
+
+    // same swap as in `swaps_const_prod_two_reserves_no_discount`, but the
+    // program toll is a smaller share of the swap fee
+    let supply_after = test.lp_supply();
+    assert!(supply_after > supply_before);
+    assert!(supply_after < supply_before + 50);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swaps_and_records_pool_stats() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+    let sell_mint = pool_before.reserves[0].mint;
+    let buy_mint = pool_before.reserves[1].mint;
+
+    let mut test = Tester::no_discount(pool_before)
+        .slot(Slot::new(100))
+        .pool_stats();
+
+    // same swap as in `swaps_const_prod_two_reserves_no_discount`
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        sell_mint,
+        buy_mint,
+    )?;
+
+    let stats =
+        PoolStats::try_deserialize(&mut test.pool_stats.data.as_slice())?;
+    assert_eq!(
+        stats.reserves[0],
+        ReserveStats {
+            mint: sell_mint,
+            swap_volume_in: 10_000,
+            swap_fees: 900,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        stats.reserves[1],
+        ReserveStats {
+            mint: buy_mint,
+            swap_volume_out: 6_254,
+            ..Default::default()
+        }
+    );
+    assert_eq!(stats.toll_lp_minted, 50);
+    assert_eq!(stats.swap_count, 1);
+    assert_eq!(stats.last_swap_slot, Slot::new(100));
+
+    Ok(())
+}
+
 #[test]
 #[serial]
 fn swaps_stable_curve_three_reserves_no_discount() -> Result<()> {
@@ -226,7 +322,7 @@ fn fails_if_sell_amount_is_zero() -> Result<()> {
         )
         .unwrap_err()
         .to_string();
-    assert!(error.contains("InvalidArg"));
+    assert!(error.contains("ZeroTokenAmount"));
 
     Ok(())
 }
@@ -632,168 +728,875 @@ fn prints_lp_token_supply() -> Result<()> {
     Ok(())
 }
 
-#[derive(Clone, Debug, PartialEq)]
-struct Tester {
-    time: Slot,
-    user: AccountInfoWrapper,
-    discount: AccountInfoWrapper,
-    pool: AccountInfoWrapper,
-    pool_signer: AccountInfoWrapper,
-    sell_wallet: AccountInfoWrapper,
-    buy_wallet: AccountInfoWrapper,
-    sell_vault: AccountInfoWrapper,
-    buy_vault: AccountInfoWrapper,
-    lp_mint: AccountInfoWrapper,
-    program_toll_wallet: AccountInfoWrapper,
-    token_program: AccountInfoWrapper,
-}
+#[test]
+#[serial]
+fn applies_pending_swap_fee_once_effective() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        pending_swap_fee: Some(PendingSwapFee {
+            fee: Permillion::from_percent(1),
+            effective_at: Slot::new(100),
+        }),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
 
-impl Tester {
-    fn with_discount(pool_data: Pool, user_discount: Discount) -> Self {
-        Self::new(pool_data, Some(user_discount))
-    }
+    let mut test =
+        Tester::no_discount(pool_before.clone()).slot(Slot::new(100));
 
-    fn no_discount(pool_data: Pool) -> Self {
-        Self::new(pool_data, None)
-    }
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_000),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
 
-    fn slot(mut self, slot: Slot) -> Self {
-        self.time = slot;
-        self
-    }
+    let pool_after = test.pool_copy();
+    assert_eq!(pool_after.swap_fee, Permillion::from_percent(1));
+    assert_eq!(pool_after.pending_swap_fee, None);
+    // 20_000 + 10_000
+    assert_eq!(pool_after.reserves[0].tokens.amount, 30_000);
+    // floor(20_000 - (400_000_000 / 29_900))
+    assert_eq!(pool_after.reserves[1].tokens.amount, 13_378);
 
-    // Since the order of the reserves does not matter (that's unit tested),
-    // we make a convention for parametrizing the tests:
-    // The first reserve is always base (sell);
-    // The second is always quote (buy).
-    fn new(mut pool_data: Pool, user_discount: Option<Discount>) -> Self {
-        pool_data.update_curve_invariant().ok();
+    Ok(())
+}
 
-        let user = AccountInfoWrapper::new().signer();
-        let discount = AccountInfoWrapper::pda(
-            amm::ID,
-            "discount",
-            &[Discount::PDA_PREFIX, user.key.as_ref()],
-        );
-        let discount = if let Some(d) = user_discount {
-            discount.owner(amm::ID).data(d)
-        } else {
-            discount.owner(system_program::ID)
-        };
-        let pool = AccountInfoWrapper::new()
-            .owner(amm::ID)
-            .mutable()
-            .data(pool_data.clone());
-        let pool_signer = AccountInfoWrapper::pda(
-            amm::ID,
-            "pool_signer",
-            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
-        );
-        let lp_mint = AccountInfoWrapper::with_key(pool_data.mint)
-            .mutable()
-            .pack(spl::mint::new(pool_signer.key).supply(10_000))
-            .owner(token::ID);
-        let program_toll_wallet =
-            AccountInfoWrapper::with_key(pool_data.program_toll_wallet)
-                .mutable()
-                .pack(
-                    spl::token_account::new(Pubkey::new_unique())
-                        .mint(lp_mint.key),
-                )
-                .owner(token::ID);
-        let Reserve {
-            mint: sell_mint,
-            vault: sell_vault,
-            tokens: sell_tokens,
-        } = pool_data.reserves()[0];
-        let sell_vault = AccountInfoWrapper::with_key(sell_vault)
-            .mutable()
-            .pack(
-                spl::token_account::new(pool_signer.key)
-                    .mint(sell_mint)
-                    .amount(sell_tokens.amount),
-            )
-            .owner(token::ID);
-        let sell_wallet = AccountInfoWrapper::new()
-            .mutable()
-            .pack(
-                spl::token_account::new(user.key)
-                    .mint(sell_mint)
-                    .amount(u64::MAX / 2),
-            )
-            .owner(token::ID);
-        let Reserve {
-            mint: buy_mint,
-            vault: buy_vault,
-            tokens: buy_tokens,
-        } = pool_data.reserves()[1];
-        let buy_vault = AccountInfoWrapper::with_key(buy_vault)
-            .mutable()
-            .pack(
-                spl::token_account::new(pool_signer.key)
-                    .mint(buy_mint)
-                    .amount(buy_tokens.amount),
-            )
-            .owner(token::ID);
-        let buy_wallet = AccountInfoWrapper::new()
-            .mutable()
-            .pack(
-                spl::token_account::new(user.key)
-                    .mint(buy_mint)
-                    .amount(u64::MAX / 2),
-            )
-            .owner(token::ID);
-        let token_program =
-            AccountInfoWrapper::with_key(anchor_spl::token::ID).program();
+#[test]
+#[serial]
+fn does_not_apply_pending_swap_fee_before_effective() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        pending_swap_fee: Some(PendingSwapFee {
+            fee: Permillion::from_percent(1),
+            effective_at: Slot::new(100),
+        }),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
 
-        Self {
-            time: Slot::new(0),
-            user,
-            discount,
-            pool,
-            pool_signer,
-            sell_wallet,
-            buy_wallet,
-            sell_vault,
-            buy_vault,
-            lp_mint,
-            program_toll_wallet,
-            token_program,
-        }
-    }
+    let mut test = Tester::no_discount(pool_before.clone()).slot(Slot::new(99));
 
-    fn pool_copy(&self) -> Pool {
-        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
-    }
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
 
-    fn lp_supply(&mut self) -> u64 {
-        spl::mint::from_acc_info(&self.lp_mint.to_account_info()).supply
-    }
+    let pool_after = test.pool_copy();
+    assert_eq!(pool_after.swap_fee, Permillion::from_percent(9));
+    assert_eq!(pool_after.pending_swap_fee, pool_before.pending_swap_fee);
+    assert_eq!(pool_after.reserves[1].tokens.amount, 13_746);
 
-    fn swap(
-        &mut self,
-        sell: TokenAmount,
-        min_buy: TokenAmount,
-        sell_mint: Pubkey,
-        buy_mint: Pubkey,
-    ) -> Result<stub::Syscalls<CpiValidator>> {
-        // we set it to done initially just so that we can set the slot, will
-        // overwrite it later
-        self.set_syscalls(CpiValidatorState::Done);
+    Ok(())
+}
 
-        let mut pool = self.pool_copy();
-        let fee = calculate_swap_fee(
-            sell,
-            pool.swap_fee,
-            &self.discount.to_account_info(),
-        )
-        .unwrap_or_default();
-        let receive_tokens = pool
-            .swap(
-                sell_mint,
-                TokenAmount::new(sell.amount - fee.amount),
-                buy_mint,
-            )
+#[test]
+#[serial]
+fn swap_v2_works_within_limits() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test =
+        Tester::no_discount(pool_before.clone()).slot(Slot::new(100));
+
+    // 10_000 / 6_254 ~ 1.599
+    test.swap_v2(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+        Some(Slot::new(100)),
+        Some(Decimal::from_permillion(1_600_000).into()),
+    )?;
+
+    let pool_after = test.pool_copy();
+    assert_eq!(pool_after.reserves[0].tokens.amount, 30_000);
+    assert_eq!(pool_after.reserves[1].tokens.amount, 13_746);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swap_v2_works_without_limits() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool_before.clone());
+
+    test.swap_v2(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+        None,
+        None,
+    )?;
+
+    let pool_after = test.pool_copy();
+    assert_eq!(pool_after.reserves[1].tokens.amount, 13_746);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swap_v2_fails_if_deadline_exceeded() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone()).slot(Slot::new(101));
+
+    let error = test
+        .swap_v2(
+            TokenAmount::new(10_000),
+            TokenAmount::new(6_254),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+            Some(Slot::new(100)),
+            None,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("DeadlineExceeded"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swap_v2_fails_if_limit_price_exceeded() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone());
+
+    // 10_000 / 6_254 ~ 1.599
+    let error = test
+        .swap_v2(
+            TokenAmount::new(10_000),
+            TokenAmount::new(6_254),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+            None,
+            Some(Decimal::from_permillion(1_590_000).into()),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("LimitPriceExceeded"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swap_v2_fails_if_slippage_exceeded() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone());
+
+    let error = test
+        .swap_v2(
+            TokenAmount::new(10_000),
+            TokenAmount::new(20_000),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+            None,
+            Some(Decimal::from(2u64).into()),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("SlippageExceeded"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swaps_with_referrer_no_discount() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool_before.clone())
+        .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE);
+
+    let supply_before = test.lp_supply();
+
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
+
+    let pool = test.pool_copy();
+    // referral is paid in LP tokens, reserves are the same as without it
+    assert_eq!(pool.reserves[0].tokens.amount, 30_000);
+    assert_eq!(pool.reserves[1].tokens.amount, 13_746);
+
+    // fee is 900, referrer gets 1/6 of it which is worth 150 sell tokens,
+    // 25 = floor(10_000 * (150 / 2) / 30_000)
+    assert_eq!(test.referrer_lp_tokens(), 25);
+    // toll + referral
+    assert_eq!(supply_before + 50 + 25, test.lp_supply());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn calculates_referral_from_supply_after_toll() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool_before.clone())
+        .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE);
+    test.lp_mint = test.lp_mint.clone().pack(
+        spl::mint::from_acc_info(&test.lp_mint.to_account_info())
+            .supply(1_000_000),
+    );
+
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
+
+    // toll is 1/3 of the fee of 900, ie. worth 300 sell tokens,
+    // 5_000 = floor(1_000_000 * (300 / 2) / 30_000)
+    //
+    // referral is 1/6 of the fee, ie. worth 150 sell tokens, and it's
+    // calculated from the supply which includes the toll,
+    // 2_512 = floor(1_005_000 * (150 / 2) / 30_000)
+    //
+    // from the stale supply before the toll it would be 2_500
+    assert_eq!(test.referrer_lp_tokens(), 2_512);
+    assert_eq!(test.lp_supply(), 1_000_000 + 5_000 + 2_512);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swaps_with_referrer_and_discount() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::with_discount(
+        pool_before.clone(),
+        Discount {
+            amount: Permillion::from_percent(50),
+            valid_until: Slot::new(500),
+        },
+    )
+    .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE);
+
+    let supply_before = test.lp_supply();
+
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_463),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
+
+    let pool = test.pool_copy();
+    assert_eq!(pool.reserves[0].tokens.amount, 30_000);
+    assert_eq!(pool.reserves[1].tokens.amount, 13_537);
+
+    // the referral is a share of the discounted fee of 450, ie. worth 75 sell
+    // tokens, 12 = floor(10_000 * round(75 / 2) / 30_000)
+    assert_eq!(test.referrer_lp_tokens(), 12);
+    // toll + referral
+    assert_eq!(supply_before + 25 + 12, test.lp_supply());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn skips_referral_if_fee_would_not_allow_for_it() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::with_discount(
+        pool_before.clone(),
+        Discount {
+            amount: Permillion::from_percent(100),
+            valid_until: Slot::new(500),
+        },
+    )
+    .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE);
+
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_666),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
+
+    assert_eq!(test.referrer_lp_tokens(), 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_referrer_lp_wallet_is_not_owned_by_referrer() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone())
+        .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE);
+    let lp_mint = test.lp_mint.key;
+    let (_, referrer_lp_wallet) = test.referrer.as_mut().unwrap();
+    *referrer_lp_wallet = referrer_lp_wallet
+        .clone()
+        .pack(spl::token_account::new(Pubkey::new_unique()).mint(lp_mint));
+
+    let error = test
+        .swap(
+            TokenAmount::new(10_000),
+            TokenAmount::new(6_254),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_referrer_lp_wallet_mint_mismatches_pool_mint() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone())
+        .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE);
+    let (referrer, referrer_lp_wallet) = test.referrer.as_mut().unwrap();
+    let referrer_authority =
+        Referrer::try_deserialize(&mut referrer.data.as_slice())?.authority;
+    *referrer_lp_wallet = referrer_lp_wallet.clone().pack(
+        spl::token_account::new(referrer_authority).mint(Pubkey::new_unique()),
+    );
+
+    let error = test
+        .swap(
+            TokenAmount::new(10_000),
+            TokenAmount::new(6_254),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_referrer_account_is_not_owned_by_program() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone())
+        .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE);
+    let (referrer, _) = test.referrer.as_mut().unwrap();
+    *referrer = referrer.clone().owner(system_program::ID);
+
+    assert!(test
+        .swap(
+            TokenAmount::new(10_000),
+            TokenAmount::new(6_254),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+        )
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn swaps_with_referrer_and_pool_access() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        access_policy: Some(Pubkey::new_unique()),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool_before.clone())
+        .referrer(consts::MAX_REFERRER_SWAP_FEE_SHARE)
+        .pool_access(true);
+
+    test.swap(
+        TokenAmount::new(10_000),
+        TokenAmount::new(6_254),
+        pool_before.reserves[0].mint,
+        pool_before.reserves[1].mint,
+    )?;
+
+    assert_eq!(test.referrer_lp_tokens(), 25);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_access_is_revoked() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        access_policy: Some(Pubkey::new_unique()),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone()).pool_access(false);
+
+    let error = test
+        .swap(
+            TokenAmount::new(10_000),
+            TokenAmount::new(6_254),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolAccessDenied"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_access_is_missing() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(9),
+        reserves: create_two_reserves(
+            TokenAmount::new(20_000),
+            TokenAmount::new(20_000),
+        ),
+        access_policy: Some(Pubkey::new_unique()),
+        ..Default::default()
+    };
+
+    let mut test = Tester::no_discount(pool.clone());
+
+    let error = test
+        .swap(
+            TokenAmount::new(10_000),
+            TokenAmount::new(6_254),
+            pool.reserves[0].mint,
+            pool.reserves[1].mint,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("PoolAccessDenied"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    time: Slot,
+    user: AccountInfoWrapper,
+    discount: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    pool_signer: AccountInfoWrapper,
+    sell_wallet: AccountInfoWrapper,
+    buy_wallet: AccountInfoWrapper,
+    sell_vault: AccountInfoWrapper,
+    buy_vault: AccountInfoWrapper,
+    lp_mint: AccountInfoWrapper,
+    program_toll_wallet: AccountInfoWrapper,
+    token_program: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
+    pool_stats: AccountInfoWrapper,
+    /// Referrer account and referrer's LP wallet
+    referrer: Option<(AccountInfoWrapper, AccountInfoWrapper)>,
+    pool_access: Option<AccountInfoWrapper>,
+}
+
+impl Tester {
+    fn with_discount(pool_data: Pool, user_discount: Discount) -> Self {
+        Self::new(pool_data, Some(user_discount))
+    }
+
+    fn no_discount(pool_data: Pool) -> Self {
+        Self::new(pool_data, None)
+    }
+
+    fn slot(mut self, slot: Slot) -> Self {
+        self.time = slot;
+        self
+    }
+
+    fn referrer(mut self, share: Permillion) -> Self {
+        let authority = Pubkey::new_unique();
+        let referrer = AccountInfoWrapper::pda(
+            amm::ID,
+            "referrer",
+            &[Referrer::PDA_PREFIX, authority.as_ref()],
+        )
+        .owner(amm::ID)
+        .data(Referrer { authority, share });
+        let referrer_lp_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::token_account::new(authority).mint(self.lp_mint.key))
+            .owner(token::ID);
+
+        self.referrer = Some((referrer, referrer_lp_wallet));
+        self
+    }
+
+    fn program_config(mut self, config: ProgramConfig) -> Self {
+        self.program_config = self.program_config.owner(amm::ID).data(config);
+        self
+    }
+
+    fn pool_stats(mut self) -> Self {
+        self.pool_stats = self.pool_stats.owner(amm::ID).data(PoolStats {
+            pool: self.pool.key,
+            ..Default::default()
+        });
+        self
+    }
+
+    fn pool_access(mut self, is_allowed: bool) -> Self {
+        let pool_access = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_access",
+            &[
+                PoolAccess::PDA_PREFIX,
+                self.pool.key.as_ref(),
+                self.user.key.as_ref(),
+            ],
+        )
+        .owner(amm::ID)
+        .data(PoolAccess {
+            pool: self.pool.key,
+            user: self.user.key,
+            is_allowed,
+        });
+
+        self.pool_access = Some(pool_access);
+        self
+    }
+
+    fn referrer_lp_tokens(&self) -> u64 {
+        let (_, referrer_lp_wallet) = self.referrer.as_ref().unwrap();
+        spl::token_account::from_acc_info(&referrer_lp_wallet.to_account_info())
+            .amount
+    }
+
+    // Since the order of the reserves does not matter (that's unit tested),
+    // we make a convention for parametrizing the tests:
+    // The first reserve is always base (sell);
+    // The second is always quote (buy).
+    fn new(mut pool_data: Pool, user_discount: Option<Discount>) -> Self {
+        pool_data.update_curve_invariant().ok();
+
+        let user = AccountInfoWrapper::new().signer();
+        let discount = AccountInfoWrapper::pda(
+            amm::ID,
+            "discount",
+            &[Discount::PDA_PREFIX, user.key.as_ref()],
+        );
+        let discount = if let Some(d) = user_discount {
+            discount.owner(amm::ID).data(d)
+        } else {
+            discount.owner(system_program::ID)
+        };
+        let pool = AccountInfoWrapper::new()
+            .owner(amm::ID)
+            .mutable()
+            .data(pool_data.clone());
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let lp_mint = AccountInfoWrapper::with_key(pool_data.mint)
+            .mutable()
+            .pack(spl::mint::new(pool_signer.key).supply(10_000))
+            .owner(token::ID);
+        let program_toll_wallet =
+            AccountInfoWrapper::with_key(pool_data.program_toll_wallet)
+                .mutable()
+                .pack(
+                    spl::token_account::new(Pubkey::new_unique())
+                        .mint(lp_mint.key),
+                )
+                .owner(token::ID);
+        let Reserve {
+            mint: sell_mint,
+            vault: sell_vault,
+            tokens: sell_tokens,
+        } = pool_data.reserves()[0];
+        let sell_vault = AccountInfoWrapper::with_key(sell_vault)
+            .mutable()
+            .pack(
+                spl::token_account::new(pool_signer.key)
+                    .mint(sell_mint)
+                    .amount(sell_tokens.amount),
+            )
+            .owner(token::ID);
+        let sell_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(
+                spl::token_account::new(user.key)
+                    .mint(sell_mint)
+                    .amount(u64::MAX / 2),
+            )
+            .owner(token::ID);
+        let Reserve {
+            mint: buy_mint,
+            vault: buy_vault,
+            tokens: buy_tokens,
+        } = pool_data.reserves()[1];
+        let buy_vault = AccountInfoWrapper::with_key(buy_vault)
+            .mutable()
+            .pack(
+                spl::token_account::new(pool_signer.key)
+                    .mint(buy_mint)
+                    .amount(buy_tokens.amount),
+            )
+            .owner(token::ID);
+        let buy_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(
+                spl::token_account::new(user.key)
+                    .mint(buy_mint)
+                    .amount(u64::MAX / 2),
+            )
+            .owner(token::ID);
+        let token_program =
+            AccountInfoWrapper::with_key(anchor_spl::token::ID).program();
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+        let pool_stats = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_stats",
+            &[PoolStats::PDA_PREFIX, pool.key.as_ref()],
+        )
+        .mutable()
+        .owner(system_program::ID);
+
+        Self {
+            time: Slot::new(0),
+            user,
+            discount,
+            pool,
+            pool_signer,
+            sell_wallet,
+            buy_wallet,
+            sell_vault,
+            buy_vault,
+            lp_mint,
+            program_toll_wallet,
+            token_program,
+            program_config,
+            pool_stats,
+            referrer: None,
+            pool_access: None,
+        }
+    }
+
+    fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    fn lp_supply(&mut self) -> u64 {
+        spl::mint::from_acc_info(&self.lp_mint.to_account_info()).supply
+    }
+
+    fn swap(
+        &mut self,
+        sell: TokenAmount,
+        min_buy: TokenAmount,
+        sell_mint: Pubkey,
+        buy_mint: Pubkey,
+    ) -> Result<stub::Syscalls<CpiValidator>> {
+        self.swap_with_limits(sell, min_buy, sell_mint, buy_mint, None)
+    }
+
+    fn swap_v2(
+        &mut self,
+        sell: TokenAmount,
+        min_buy: TokenAmount,
+        sell_mint: Pubkey,
+        buy_mint: Pubkey,
+        valid_until: Option<Slot>,
+        max_price: Option<SDecimal>,
+    ) -> Result<stub::Syscalls<CpiValidator>> {
+        self.swap_with_limits(
+            sell,
+            min_buy,
+            sell_mint,
+            buy_mint,
+            Some((valid_until, max_price)),
+        )
+    }
+
+    /// If limits are provided, calls `swap_v2` instead of `swap`.
+    fn swap_with_limits(
+        &mut self,
+        sell: TokenAmount,
+        min_buy: TokenAmount,
+        sell_mint: Pubkey,
+        buy_mint: Pubkey,
+        limits: Option<(Option<Slot>, Option<SDecimal>)>,
+    ) -> Result<stub::Syscalls<CpiValidator>> {
+        // we set it to done initially just so that we can set the slot, will
+        // overwrite it later
+        self.set_syscalls(CpiValidatorState::Done);
+
+        let mut pool = self.pool_copy();
+        pool.apply_pending_swap_fee(self.time);
+        let fee = calculate_swap_fee(
+            sell,
+            pool.swap_fee,
+            &self.discount.to_account_info(),
+        )
+        .unwrap_or_default();
+        let receive_tokens = pool
+            .swap(
+                sell_mint,
+                TokenAmount::new(sell.amount - fee.amount),
+                buy_mint,
+                self.time,
+            )
             .unwrap_or_default();
         pool.reserve_mut(sell_mint).unwrap().add_tokens(fee)?;
         let supply =
@@ -801,17 +1604,46 @@ impl Tester {
         let mint_toll = calculate_toll_in_lp_tokens(
             &pool,
             fee,
+            ProgramConfig::load(&self.program_config.to_account_info())?
+                .program_toll_swap_fee_share,
             pool.reserves[0].mint,
             supply.into(),
         )
         .ok()
         .flatten()
-        .map(|tokens| MintToll {
+        .map(|tokens| MintLpTokens {
             tokens,
             signer: self.pool_signer.key,
             destination: self.program_toll_wallet.key,
             mint: self.lp_mint.key,
         });
+        // the referral is calculated from the supply after the toll
+        let supply_after_toll = supply
+            + mint_toll
+                .as_ref()
+                .map(|mint| mint.tokens.amount)
+                .unwrap_or(0);
+        let mint_referral =
+            self.referrer.as_ref().and_then(|(referrer, wallet)| {
+                let referrer =
+                    Referrer::try_deserialize(&mut referrer.data.as_slice())
+                        .ok()?;
+                calculate_referral_in_lp_tokens(
+                    &pool,
+                    fee,
+                    referrer.share,
+                    pool.reserves[0].mint,
+                    supply_after_toll.into(),
+                )
+                .ok()
+                .flatten()
+                .map(|tokens| MintLpTokens {
+                    tokens,
+                    signer: self.pool_signer.key,
+                    destination: wallet.key,
+                    mint: self.lp_mint.key,
+                })
+            });
         let state = CpiValidatorState::TransferSoldTokens {
             user: self.user.key,
             vault: self.sell_vault.key,
@@ -822,7 +1654,7 @@ impl Tester {
                 vault: self.buy_vault.key,
                 wallet: self.buy_wallet.key,
                 tokens: receive_tokens,
-                next_cpi: mint_toll,
+                next_cpis: mint_toll.into_iter().chain(mint_referral).collect(),
             },
         };
         let syscalls = self.set_syscalls(state);
@@ -830,7 +1662,17 @@ impl Tester {
         let mut ctx = self.context_wrapper();
         let mut accounts = ctx.accounts()?;
 
-        swap(ctx.build(&mut accounts), sell, min_buy)?;
+        if let Some((valid_until, max_price)) = limits {
+            swap_v2(
+                ctx.build(&mut accounts),
+                sell,
+                min_buy,
+                valid_until,
+                max_price,
+            )?;
+        } else {
+            swap(ctx.build(&mut accounts), sell, min_buy)?;
+        }
         accounts.exit(&amm::ID)?;
 
         let CpiValidator(state) =
@@ -853,6 +1695,14 @@ impl Tester {
             .acc(&mut self.lp_mint)
             .acc(&mut self.program_toll_wallet)
             .acc(&mut self.token_program)
+            .acc(&mut self.program_config)
+            .acc(&mut self.pool_stats)
+            .remaining_accounts(
+                self.referrer
+                    .iter_mut()
+                    .flat_map(|(referrer, wallet)| [referrer, wallet])
+                    .chain(self.pool_access.iter_mut()),
+            )
     }
 
     fn set_syscalls(
@@ -880,7 +1730,8 @@ enum CpiValidatorState {
         next_cpi: TransferBoughtTokens,
     },
     TransferBoughtTokens(TransferBoughtTokens),
-    MintToll(MintToll),
+    /// Toll and referral, in this order
+    MintLpTokens(Vec<MintLpTokens>),
     Done,
 }
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -889,10 +1740,10 @@ struct TransferBoughtTokens {
     vault: Pubkey,
     wallet: Pubkey,
     tokens: TokenAmount,
-    next_cpi: Option<MintToll>,
+    next_cpis: Vec<MintLpTokens>,
 }
 #[derive(Debug, Eq, PartialEq, Clone)]
-struct MintToll {
+struct MintLpTokens {
     signer: Pubkey,
     mint: Pubkey,
     destination: Pubkey,
@@ -945,7 +1796,7 @@ impl stub::ValidateCpis for CpiValidator {
                 vault,
                 wallet,
                 tokens,
-                ref next_cpi,
+                ref next_cpis,
             }) => {
                 let expected_ix = token::spl_token::instruction::transfer(
                     &token::ID,
@@ -970,18 +1821,20 @@ impl stub::ValidateCpis for CpiValidator {
                 )
                 .expect("Source vault does not have enough tokens");
 
-                *state = if let Some(next_cpi) = next_cpi {
-                    CpiValidatorState::MintToll(next_cpi.clone())
-                } else {
+                *state = if next_cpis.is_empty() {
                     CpiValidatorState::Done
+                } else {
+                    CpiValidatorState::MintLpTokens(next_cpis.clone())
                 };
             }
-            CpiValidatorState::MintToll(MintToll {
-                mint,
-                destination,
-                signer,
-                tokens,
-            }) => {
+            CpiValidatorState::MintLpTokens(ref mut mints) => {
+                let MintLpTokens {
+                    mint,
+                    destination,
+                    signer,
+                    tokens,
+                } = mints.remove(0);
+
                 let expected_ix = token::spl_token::instruction::mint_to(
                     &token::ID,
                     &mint,
@@ -1001,7 +1854,9 @@ impl stub::ValidateCpis for CpiValidator {
                 spl::mint::mint_to(wallet, lp_mint, tokens.amount)
                     .expect("Cannot mint LP tokens");
 
-                *state = CpiValidatorState::Done;
+                if mints.is_empty() {
+                    *state = CpiValidatorState::Done;
+                }
             }
             CpiValidatorState::Done => {
                 panic!("No more instructions expected, got {:#?}", ix);
@@ -0,0 +1,204 @@
+use ::amm::amm::sync_pool;
+use ::amm::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, TokenAccountExt},
+};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+
+#[test]
+#[serial]
+fn works() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[150, 190]);
+
+    test.sync_pool()?;
+
+    let pool = test.pool_copy();
+    assert_eq!(pool.reserves[0].tokens, TokenAmount::new(150));
+    assert_eq!(pool.reserves[1].tokens, TokenAmount::new(190));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn updates_stable_curve_invariant() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[300, 200]);
+    let mut pool = test.pool_copy();
+    pool.curve = Curve::Stable {
+        amplifier: 10,
+        invariant: Default::default(),
+    };
+    pool.update_curve_invariant()?;
+    test.pool = test.pool.clone().data(pool.clone());
+
+    test.sync_pool()?;
+
+    assert_ne!(test.pool_copy().curve, pool.curve);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn updates_stable_curve_invariant_with_configured_method() -> Result<()> {
+    let mut test =
+        Tester::new(&[100, 200], &[300, 200]).program_config(ProgramConfig {
+            stable_curve_invariant: StableCurveInvariantMethod::Integer,
+            ..Default::default()
+        });
+    let mut pool = test.pool_copy();
+    pool.curve = Curve::Stable {
+        amplifier: 10,
+        invariant: Default::default(),
+    };
+    test.pool = test.pool.clone().data(pool.clone());
+
+    test.sync_pool()?;
+
+    pool.reserves[0].tokens = TokenAmount::new(300);
+    pool.update_curve_invariant_with(StableCurveInvariantMethod::Integer)?;
+    assert_eq!(test.pool_copy().curve, pool.curve);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_admin() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[100, 200]);
+    test.admin = AccountInfoWrapper::new().signer();
+
+    assert!(test
+        .sync_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_missing() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[100, 200]);
+    test.vaults.pop();
+
+    assert!(test
+        .sync_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_vault_is_not_reserves_vault() -> Result<()> {
+    let mut test = Tester::new(&[100, 200], &[100, 200]);
+    let mint =
+        spl::token_account::from_acc_info(&test.vaults[0].to_account_info())
+            .mint;
+    test.vaults[0] = AccountInfoWrapper::new()
+        .pack(spl::token_account::new(Pubkey::new_unique()).mint(mint))
+        .owner(token::ID);
+
+    assert!(test
+        .sync_pool()
+        .unwrap_err()
+        .to_string()
+        .contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    admin: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
+    vaults: Vec<AccountInfoWrapper>,
+}
+
+impl Tester {
+    fn new(reserve_amounts: &[u64], vault_amounts: &[u64]) -> Self {
+        let admin = AccountInfoWrapper::new().signer();
+        let pool = AccountInfoWrapper::new().owner(amm::ID).mutable();
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+
+        let mut reserves = [Reserve::default(); consts::MAX_RESERVES];
+        let vaults = reserve_amounts
+            .iter()
+            .zip(vault_amounts)
+            .enumerate()
+            .map(|(index, (reserve_amount, vault_amount))| {
+                let mint = Pubkey::new_unique();
+                let vault = AccountInfoWrapper::new()
+                    .pack(
+                        spl::token_account::new(Pubkey::new_unique())
+                            .mint(mint)
+                            .amount(*vault_amount),
+                    )
+                    .owner(token::ID);
+
+                reserves[index] = Reserve {
+                    mint,
+                    vault: vault.key,
+                    tokens: TokenAmount::new(*reserve_amount),
+                };
+
+                vault
+            })
+            .collect();
+
+        let pool = pool.data(Pool {
+            admin: admin.key,
+            dimension: reserve_amounts.len() as u64,
+            reserves,
+            ..Default::default()
+        });
+
+        Self {
+            admin,
+            pool,
+            program_config,
+            vaults,
+        }
+    }
+
+    fn program_config(mut self, config: ProgramConfig) -> Self {
+        self.program_config = self.program_config.owner(amm::ID).data(config);
+        self
+    }
+
+    fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    fn sync_pool(&mut self) -> Result<()> {
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        sync_pool(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        Ok(())
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.admin)
+            .acc(&mut self.pool)
+            .acc(&mut self.program_config)
+            .remaining_accounts(self.vaults.iter_mut())
+    }
+}
@@ -0,0 +1,469 @@
+use ::amm::amm::{execute_twamm, place_twamm_order, withdraw_twamm_proceeds};
+use ::amm::endpoints::calculate_toll_in_lp_tokens;
+use ::amm::prelude::*;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, *},
+    stub,
+};
+use pretty_assertions::assert_eq;
+use solana_sdk::instruction::Instruction;
+use std::sync::{Arc, Mutex};
+
+pub const LP_SUPPLY: u64 = 1_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tester {
+    pub user: AccountInfoWrapper,
+    pub pool: AccountInfoWrapper,
+    pub pool_signer: AccountInfoWrapper,
+    pub order: AccountInfoWrapper,
+    pub order_signer: AccountInfoWrapper,
+    pub sell_wallet: AccountInfoWrapper,
+    pub buy_wallet: AccountInfoWrapper,
+    pub order_sell_vault: AccountInfoWrapper,
+    pub order_buy_vault: AccountInfoWrapper,
+    pub pool_sell_vault: AccountInfoWrapper,
+    pub pool_buy_vault: AccountInfoWrapper,
+    pub lp_mint: AccountInfoWrapper,
+    pub program_toll_wallet: AccountInfoWrapper,
+    pub token_program: AccountInfoWrapper,
+    pub program_config: AccountInfoWrapper,
+    pub pool_stats: AccountInfoWrapper,
+    pub system_program: AccountInfoWrapper,
+}
+
+impl Tester {
+    /// The first reserve of the pool is what the order sells, the second one
+    /// is what it buys. The order account is not initialized yet.
+    pub fn new(mut pool_data: Pool) -> Self {
+        pool_data.mint = Pubkey::new_unique();
+        pool_data.program_toll_wallet = Pubkey::new_unique();
+        pool_data.update_curve_invariant().ok();
+        let sell_reserve = pool_data.reserves[0];
+        let buy_reserve = pool_data.reserves[1];
+
+        let user = AccountInfoWrapper::new().mutable().signer();
+        let pool = AccountInfoWrapper::new()
+            .owner(amm::ID)
+            .mutable()
+            .data(pool_data.clone());
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let order = AccountInfoWrapper::new()
+            .signer()
+            .owner(amm::ID)
+            .mutable()
+            .size(TwammOrder::space());
+        let order_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "order_signer",
+            &[TwammOrder::SIGNER_PDA_PREFIX, order.key.as_ref()],
+        );
+        let token_account = |owner: Pubkey, mint: Pubkey, amount: u64| {
+            AccountInfoWrapper::new()
+                .mutable()
+                .pack(spl::token_account::new(owner).mint(mint).amount(amount))
+                .owner(token::ID)
+        };
+        let sell_wallet = token_account(user.key, sell_reserve.mint, 1_000_000);
+        let buy_wallet = token_account(user.key, buy_reserve.mint, 0);
+        let order_sell_vault =
+            token_account(order_signer.key, sell_reserve.mint, 0);
+        let order_buy_vault =
+            token_account(order_signer.key, buy_reserve.mint, 0);
+        let pool_sell_vault = AccountInfoWrapper::with_key(sell_reserve.vault)
+            .mutable()
+            .pack(
+                spl::token_account::new(pool_signer.key)
+                    .mint(sell_reserve.mint)
+                    .amount(sell_reserve.tokens.amount),
+            )
+            .owner(token::ID);
+        let pool_buy_vault = AccountInfoWrapper::with_key(buy_reserve.vault)
+            .mutable()
+            .pack(
+                spl::token_account::new(pool_signer.key)
+                    .mint(buy_reserve.mint)
+                    .amount(buy_reserve.tokens.amount),
+            )
+            .owner(token::ID);
+        let lp_mint = AccountInfoWrapper::with_key(pool_data.mint)
+            .mutable()
+            .pack(spl::mint::new(pool_signer.key).supply(LP_SUPPLY))
+            .owner(token::ID);
+        let program_toll_wallet =
+            AccountInfoWrapper::with_key(pool_data.program_toll_wallet)
+                .mutable()
+                .pack(
+                    spl::token_account::new(Pubkey::new_unique())
+                        .mint(lp_mint.key),
+                )
+                .owner(token::ID);
+        let token_program = AccountInfoWrapper::with_key(token::ID).program();
+        let system_program =
+            AccountInfoWrapper::with_key(system_program::ID).program();
+
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+        let pool_stats = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_stats",
+            &[PoolStats::PDA_PREFIX, pool.key.as_ref()],
+        )
+        .mutable()
+        .owner(system_program::ID);
+
+        Self {
+            user,
+            pool,
+            pool_signer,
+            order,
+            order_signer,
+            sell_wallet,
+            buy_wallet,
+            order_sell_vault,
+            order_buy_vault,
+            pool_sell_vault,
+            pool_buy_vault,
+            lp_mint,
+            program_toll_wallet,
+            token_program,
+            program_config,
+            pool_stats,
+            system_program,
+        }
+    }
+
+    pub fn pool_stats(mut self) -> Self {
+        self.pool_stats = self.pool_stats.owner(amm::ID).data(PoolStats {
+            pool: self.pool.key,
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    pub fn order_copy(&self) -> TwammOrder {
+        TwammOrder::try_deserialize(&mut self.order.data.as_slice()).unwrap()
+    }
+
+    pub fn amount(wallet: &AccountInfoWrapper) -> u64 {
+        spl::token_account::from_acc_info(&wallet.to_account_info()).amount
+    }
+
+    pub fn place_twamm_order(
+        &mut self,
+        slot: u64,
+        sell: TokenAmount,
+        duration: u64,
+    ) -> Result<()> {
+        self.place_twamm_order_with_max_price(slot, sell, duration, None)
+    }
+
+    pub fn place_twamm_order_with_max_price(
+        &mut self,
+        slot: u64,
+        sell: TokenAmount,
+        duration: u64,
+        max_price: Option<SDecimal>,
+    ) -> Result<()> {
+        let rent = Rent::default().minimum_balance(TwammOrder::space());
+        let expected_cpis = self.set_syscalls(
+            slot,
+            vec![
+                ExpectedCpi::CreateAccount {
+                    ix: system_instruction::create_account(
+                        &self.user.key,
+                        &self.order.key,
+                        rent,
+                        TwammOrder::space() as u64,
+                        &amm::ID,
+                    ),
+                    rent,
+                },
+                ExpectedCpi::Transfer {
+                    authority: self.user.key,
+                    from: self.sell_wallet.key,
+                    to: self.order_sell_vault.key,
+                    tokens: sell,
+                },
+            ],
+        );
+
+        let mut ctx = ContextWrapper::new(amm::ID)
+            .acc(&mut self.user)
+            .acc(&mut self.pool)
+            .acc(&mut self.order)
+            .acc(&mut self.order_signer)
+            .acc(&mut self.sell_wallet)
+            .acc(&mut self.order_sell_vault)
+            .acc(&mut self.order_buy_vault)
+            .acc(&mut self.token_program)
+            .acc(&mut self.system_program);
+        let mut accounts = ctx.accounts()?;
+
+        place_twamm_order(ctx.build(&mut accounts), sell, duration, max_price)?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*expected_cpis.lock().unwrap(), vec![]);
+
+        Ok(())
+    }
+
+    pub fn execute_twamm(&mut self, slot: u64) -> Result<()> {
+        let expected_cpis =
+            self.expected_execute_cpis(slot).unwrap_or_default();
+        let expected_cpis = self.set_syscalls(slot, expected_cpis);
+
+        let mut ctx = ContextWrapper::new(amm::ID)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.order)
+            .acc(&mut self.order_signer)
+            .acc(&mut self.order_sell_vault)
+            .acc(&mut self.order_buy_vault)
+            .acc(&mut self.pool_sell_vault)
+            .acc(&mut self.pool_buy_vault)
+            .acc(&mut self.lp_mint)
+            .acc(&mut self.program_toll_wallet)
+            .acc(&mut self.token_program)
+            .acc(&mut self.program_config)
+            .acc(&mut self.pool_stats);
+        let mut accounts = ctx.accounts()?;
+
+        execute_twamm(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*expected_cpis.lock().unwrap(), vec![]);
+
+        Ok(())
+    }
+
+    pub fn withdraw_twamm_proceeds(&mut self, slot: u64) -> Result<()> {
+        let order = self.order_copy();
+        let mut expected_cpis = vec![];
+        if order.proceeds.amount > 0 {
+            expected_cpis.push(ExpectedCpi::Transfer {
+                authority: self.order_signer.key,
+                from: self.order_buy_vault.key,
+                to: self.buy_wallet.key,
+                tokens: order.proceeds,
+            });
+        }
+        if slot >= order.end.slot && order.unsold().amount > 0 {
+            expected_cpis.push(ExpectedCpi::Transfer {
+                authority: self.order_signer.key,
+                from: self.order_sell_vault.key,
+                to: self.sell_wallet.key,
+                tokens: order.unsold(),
+            });
+        }
+        let expected_cpis = self.set_syscalls(slot, expected_cpis);
+
+        let mut ctx = ContextWrapper::new(amm::ID)
+            .acc(&mut self.user)
+            .acc(&mut self.order)
+            .acc(&mut self.order_signer)
+            .acc(&mut self.order_sell_vault)
+            .acc(&mut self.order_buy_vault)
+            .acc(&mut self.sell_wallet)
+            .acc(&mut self.buy_wallet)
+            .acc(&mut self.token_program);
+        let mut accounts = ctx.accounts()?;
+
+        withdraw_twamm_proceeds(ctx.build(&mut accounts))?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*expected_cpis.lock().unwrap(), vec![]);
+
+        Ok(())
+    }
+
+    /// Replicates the endpoint's logic to find which CPIs to expect.
+    fn expected_execute_cpis(&self, slot: u64) -> Result<Vec<ExpectedCpi>> {
+        let mut pool = self.pool_copy();
+        let mut order = self.order_copy();
+        pool.apply_pending_swap_fee(Slot::new(slot));
+
+        let execution = match order.execute(&mut pool, Slot::new(slot))? {
+            Some(execution) => execution,
+            None => return Ok(vec![]),
+        };
+
+        let mut cpis = vec![
+            ExpectedCpi::Transfer {
+                authority: self.order_signer.key,
+                from: self.order_sell_vault.key,
+                to: self.pool_sell_vault.key,
+                tokens: execution.sold,
+            },
+            ExpectedCpi::Transfer {
+                authority: self.pool_signer.key,
+                from: self.pool_buy_vault.key,
+                to: self.order_buy_vault.key,
+                tokens: execution.bought,
+            },
+        ];
+        let supply =
+            spl::mint::from_acc_info(&self.lp_mint.to_account_info()).supply;
+        if let Some(toll) = calculate_toll_in_lp_tokens(
+            &pool,
+            execution.swap_fee,
+            consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+            order.sell_mint,
+            supply.into(),
+        )? {
+            cpis.push(ExpectedCpi::MintTo {
+                signer: self.pool_signer.key,
+                mint: self.lp_mint.key,
+                destination: self.program_toll_wallet.key,
+                tokens: toll,
+            });
+        }
+
+        Ok(cpis)
+    }
+
+    fn set_syscalls(
+        &self,
+        slot: u64,
+        expected_cpis: Vec<ExpectedCpi>,
+    ) -> Arc<Mutex<Vec<ExpectedCpi>>> {
+        let expected_cpis = Arc::new(Mutex::new(expected_cpis));
+        let syscalls =
+            stub::Syscalls::new(CpiValidator(Arc::clone(&expected_cpis)));
+        syscalls.slot(slot);
+        syscalls.set();
+
+        expected_cpis
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CpiValidator(Arc<Mutex<Vec<ExpectedCpi>>>);
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ExpectedCpi {
+    CreateAccount {
+        ix: Instruction,
+        rent: u64,
+    },
+    Transfer {
+        authority: Pubkey,
+        from: Pubkey,
+        to: Pubkey,
+        tokens: TokenAmount,
+    },
+    MintTo {
+        signer: Pubkey,
+        mint: Pubkey,
+        destination: Pubkey,
+        tokens: TokenAmount,
+    },
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut expected_cpis = self.0.lock().unwrap();
+        if expected_cpis.is_empty() {
+            panic!("No more instructions expected, got {:#?}", ix);
+        }
+        let find =
+            |key: Pubkey| accounts.iter().find(|acc| acc.key() == key).unwrap();
+
+        match expected_cpis.remove(0) {
+            ExpectedCpi::CreateAccount {
+                ix: expected_ix,
+                rent,
+            } => {
+                assert_eq!(&expected_ix, ix);
+
+                let account = find(expected_ix.accounts[1].pubkey);
+                **account.lamports.borrow_mut() = rent;
+            }
+            ExpectedCpi::Transfer {
+                authority,
+                from,
+                to,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::transfer(
+                    &token::ID,
+                    &from,
+                    &to,
+                    &authority,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                spl::token_account::transfer(
+                    find(from),
+                    find(to),
+                    tokens.amount,
+                )
+                .expect("Source does not have enough tokens");
+            }
+            ExpectedCpi::MintTo {
+                signer,
+                mint,
+                destination,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::mint_to(
+                    &token::ID,
+                    &mint,
+                    &destination,
+                    &signer,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                spl::mint::mint_to(
+                    find(destination),
+                    find(mint),
+                    tokens.amount,
+                )
+                .expect("Cannot mint LP tokens");
+            }
+        }
+    }
+}
+
+pub fn create_two_reserves(sell: u64, buy: u64) -> [Reserve; 4] {
+    [
+        Reserve {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            tokens: TokenAmount::new(sell),
+        },
+        Reserve {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            tokens: TokenAmount::new(buy),
+        },
+        Reserve::default(),
+        Reserve::default(),
+    ]
+}
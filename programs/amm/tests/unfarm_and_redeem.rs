@@ -0,0 +1,85 @@
+#[allow(dead_code)]
+mod farm_lp;
+
+use ::amm::prelude::*;
+use anchortest::spl;
+use farm_lp::*;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use std::collections::BTreeMap;
+
+#[test]
+#[serial]
+fn unstakes_and_redeems_lp_tokens() -> Result<()> {
+    let mut tester = Tester::new();
+    tester.staked = 100;
+    let mints = tester.mints();
+
+    tester.unfarm_and_redeem(
+        TokenAmount::new(100),
+        BTreeMap::from([
+            (mints[0], TokenAmount::new(100)),
+            (mints[1], TokenAmount::new(100)),
+        ]),
+    )?;
+
+    let pool = tester.pool_copy();
+    assert_eq!(pool.reserves[0].tokens.amount, INITIAL_RESERVE - 100);
+    assert_eq!(pool.reserves[1].tokens.amount, INITIAL_RESERVE - 100);
+    assert_eq!(tester.lp_token_wallet_amount(), 0);
+    assert_eq!(tester.lp_supply(), INITIAL_LP_SUPPLY - 100);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn redeems_only_what_was_unstaked() -> Result<()> {
+    let mut tester = Tester::new();
+    tester.staked = 50;
+    let mints = tester.mints();
+    // the user already has some LP tokens in the wallet which must not be
+    // redeemed
+    tester.lp_token_wallet = tester.lp_token_wallet.clone().pack(
+        spl::token_account::new(tester.user.key)
+            .mint(tester.lp_mint.key)
+            .amount(30),
+    );
+
+    tester.unfarm_and_redeem(
+        TokenAmount::new(100),
+        BTreeMap::from([
+            (mints[0], TokenAmount::new(50)),
+            (mints[1], TokenAmount::new(50)),
+        ]),
+    )?;
+
+    let pool = tester.pool_copy();
+    assert_eq!(pool.reserves[0].tokens.amount, INITIAL_RESERVE - 50);
+    assert_eq!(pool.reserves[1].tokens.amount, INITIAL_RESERVE - 50);
+    assert_eq!(tester.lp_token_wallet_amount(), 30);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_redeemed_tokens_are_below_minimum() -> Result<()> {
+    let mut tester = Tester::new();
+    tester.staked = 100;
+    let mints = tester.mints();
+
+    let error = tester
+        .unfarm_and_redeem(
+            TokenAmount::new(100),
+            BTreeMap::from([
+                (mints[0], TokenAmount::new(101)),
+                (mints[1], TokenAmount::new(100)),
+            ]),
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("RedeemBelowMinimum"));
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+#[allow(dead_code)]
+mod twamm;
+
+use ::amm::prelude::*;
+use anchortest::builder::*;
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use twamm::*;
+
+#[test]
+#[serial]
+fn withdraws_proceeds_while_order_runs() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+    test.execute_twamm(150)?;
+    let sell_wallet_before = Tester::amount(&test.sell_wallet);
+
+    test.withdraw_twamm_proceeds(150)?;
+
+    let order = test.order_copy();
+    assert_eq!(order.proceeds.amount, 0);
+    assert_eq!(order.bought.amount, 494);
+    // unsold tokens stay in the order until it ends
+    assert_eq!(order.unsold().amount, 500);
+    assert_eq!(Tester::amount(&test.buy_wallet), 494);
+    assert_eq!(Tester::amount(&test.order_buy_vault), 0);
+    assert_eq!(Tester::amount(&test.sell_wallet), sell_wallet_before);
+
+    // the rest of the order is still executed
+    test.execute_twamm(200)?;
+    test.withdraw_twamm_proceeds(200)?;
+    assert_eq!(Tester::amount(&test.buy_wallet), 988);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn withdraws_unsold_tokens_after_end() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+    test.execute_twamm(150)?;
+    let sell_wallet_before = Tester::amount(&test.sell_wallet);
+
+    test.withdraw_twamm_proceeds(200)?;
+
+    let order = test.order_copy();
+    assert_eq!(order.sell.amount, 500);
+    assert_eq!(order.unsold().amount, 0);
+    assert_eq!(Tester::amount(&test.buy_wallet), 494);
+    assert_eq!(Tester::amount(&test.sell_wallet) - sell_wallet_before, 500);
+
+    // nothing is left to execute
+    let pool_before = test.pool_copy();
+    test.execute_twamm(300)?;
+    assert_eq!(test.pool_copy(), pool_before);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_signer_is_not_order_authority() -> Result<()> {
+    let mut test = Tester::new(pool());
+    test.place_twamm_order(100, TokenAmount::new(1_000), 100)?;
+    test.execute_twamm(150)?;
+    test.user = AccountInfoWrapper::new().signer();
+
+    let error = test.withdraw_twamm_proceeds(150).unwrap_err().to_string();
+    assert!(error.contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+fn pool() -> Pool {
+    Pool {
+        dimension: 2,
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(1_000_000, 1_000_000),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,601 @@
+use ::amm::amm::zap_in;
+use ::amm::endpoints::{
+    calculate_discounted_swap_fee_share, calculate_swap_fee,
+    calculate_toll_in_lp_tokens,
+};
+use ::amm::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use anchortest::{
+    builder::*,
+    spl::{self, *},
+    stub,
+};
+use pretty_assertions::assert_eq;
+use serial_test::serial;
+use solana_sdk::instruction::Instruction;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[serial]
+fn zaps_into_const_prod() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(
+            TokenAmount::new(10_000),
+            TokenAmount::new(10_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::new(pool_before.clone());
+    let sell_wallet_before = test.sell_wallet_amount();
+
+    test.zap_in(TokenAmount::new(1_000), TokenAmount::new(485))?;
+
+    let pool_after = test.pool_copy();
+    // 490 tokens are swapped, out of which 5 is the fee, for 462 buy tokens,
+    // then 509 sell tokens and 462 buy tokens are deposited
+    assert_eq!(pool_after.reserves[0].tokens.amount, 10_999);
+    assert_eq!(pool_after.reserves[1].tokens.amount, 10_000);
+    // 1 sell token is left over in the user's wallet
+    assert_eq!(sell_wallet_before - test.sell_wallet_amount(), 999);
+    // floor(10_000 * 509 / 10_490)
+    assert_eq!(test.lp_token_wallet_amount(), 485);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn zaps_into_stable_curve() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(
+            TokenAmount::new(10_000_000),
+            TokenAmount::new(9_000_000),
+        ),
+        curve: Curve::Stable {
+            amplifier: 10,
+            invariant: Default::default(),
+        },
+        ..Default::default()
+    };
+
+    let mut test = Tester::new(pool_before.clone());
+    let sell_wallet_before = test.sell_wallet_amount();
+
+    test.zap_in(TokenAmount::new(100_000), TokenAmount::new(1))?;
+
+    // less than 0.1% of the zapped tokens are left over
+    assert!(sell_wallet_before - test.sell_wallet_amount() > 99_900);
+    assert!(test.lp_token_wallet_amount() > 0);
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn zaps_in_and_records_pool_stats() -> Result<()> {
+    let pool_before = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(
+            TokenAmount::new(10_000),
+            TokenAmount::new(10_000),
+        ),
+        ..Default::default()
+    };
+    let sell_mint = pool_before.reserves[0].mint;
+    let buy_mint = pool_before.reserves[1].mint;
+
+    let mut test = Tester::new(pool_before).pool_stats();
+
+    // same zap as in `zaps_into_const_prod`
+    test.zap_in(TokenAmount::new(1_000), TokenAmount::new(485))?;
+
+    let stats =
+        PoolStats::try_deserialize(&mut test.pool_stats.data.as_slice())?;
+    assert_eq!(
+        stats.reserves[0],
+        ReserveStats {
+            mint: sell_mint,
+            swap_volume_in: 490,
+            swap_fees: 5,
+            deposited: 509,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        stats.reserves[1],
+        ReserveStats {
+            mint: buy_mint,
+            swap_volume_out: 462,
+            deposited: 462,
+            ..Default::default()
+        }
+    );
+    assert_eq!(stats.swap_count, 1);
+    assert_eq!(stats.last_swap_slot, Slot::new(10));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_min_lp_tokens_out_is_not_reached() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(
+            TokenAmount::new(10_000),
+            TokenAmount::new(10_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::new(pool);
+
+    let error = test
+        .zap_in(TokenAmount::new(1_000), TokenAmount::new(486))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("LpTokensOutBelowMinimum"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_zapped_amount_is_zero() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(
+            TokenAmount::new(10_000),
+            TokenAmount::new(10_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::new(pool);
+
+    let error = test
+        .zap_in(TokenAmount::new(0), TokenAmount::new(0))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("ZeroTokenAmount"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_pool_has_more_than_two_reserves() -> Result<()> {
+    let mut reserves =
+        create_two_reserves(TokenAmount::new(10_000), TokenAmount::new(10_000));
+    reserves[2] = Reserve {
+        mint: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        tokens: TokenAmount::new(10_000),
+    };
+    let pool = Pool {
+        dimension: 3,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(1),
+        reserves,
+        ..Default::default()
+    };
+
+    let mut test = Tester::new(pool);
+
+    let error = test
+        .zap_in(TokenAmount::new(1_000), TokenAmount::new(0))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidArg"));
+
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn fails_if_lp_token_wallet_mint_mismatches_pool_mint() -> Result<()> {
+    let pool = Pool {
+        dimension: 2,
+        program_toll_wallet: Pubkey::new_unique(),
+        swap_fee: Permillion::from_percent(1),
+        reserves: create_two_reserves(
+            TokenAmount::new(10_000),
+            TokenAmount::new(10_000),
+        ),
+        ..Default::default()
+    };
+
+    let mut test = Tester::new(pool);
+    test.lp_token_wallet = AccountInfoWrapper::new()
+        .mutable()
+        .pack(spl::token_account::new(test.user.key).mint(Pubkey::new_unique()))
+        .owner(token::ID);
+
+    let error = test
+        .zap_in(TokenAmount::new(1_000), TokenAmount::new(0))
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("InvalidAccountInput"));
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tester {
+    user: AccountInfoWrapper,
+    discount: AccountInfoWrapper,
+    pool: AccountInfoWrapper,
+    pool_signer: AccountInfoWrapper,
+    sell_wallet: AccountInfoWrapper,
+    buy_wallet: AccountInfoWrapper,
+    sell_vault: AccountInfoWrapper,
+    buy_vault: AccountInfoWrapper,
+    lp_mint: AccountInfoWrapper,
+    lp_token_wallet: AccountInfoWrapper,
+    program_toll_wallet: AccountInfoWrapper,
+    token_program: AccountInfoWrapper,
+    program_config: AccountInfoWrapper,
+    pool_stats: AccountInfoWrapper,
+}
+
+impl Tester {
+    // The first reserve is always the zapped (sell) one.
+    fn new(mut pool_data: Pool) -> Self {
+        pool_data.mint = Pubkey::new_unique();
+        pool_data.update_curve_invariant().ok();
+
+        let user = AccountInfoWrapper::new().signer();
+        let discount = AccountInfoWrapper::pda(
+            amm::ID,
+            "discount",
+            &[Discount::PDA_PREFIX, user.key.as_ref()],
+        )
+        .owner(system_program::ID);
+        let pool = AccountInfoWrapper::new()
+            .owner(amm::ID)
+            .mutable()
+            .data(pool_data.clone());
+        let pool_signer = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_signer",
+            &[Pool::SIGNER_PDA_PREFIX, pool.key.as_ref()],
+        );
+        let lp_mint = AccountInfoWrapper::with_key(pool_data.mint)
+            .mutable()
+            .pack(spl::mint::new(pool_signer.key).supply(10_000))
+            .owner(token::ID);
+        let lp_token_wallet = AccountInfoWrapper::new()
+            .mutable()
+            .pack(spl::token_account::new(user.key).mint(lp_mint.key))
+            .owner(token::ID);
+        let program_toll_wallet =
+            AccountInfoWrapper::with_key(pool_data.program_toll_wallet)
+                .mutable()
+                .pack(
+                    spl::token_account::new(Pubkey::new_unique())
+                        .mint(lp_mint.key),
+                )
+                .owner(token::ID);
+        let wallet_and_vault = |reserve: Reserve| {
+            let vault = AccountInfoWrapper::with_key(reserve.vault)
+                .mutable()
+                .pack(
+                    spl::token_account::new(pool_signer.key)
+                        .mint(reserve.mint)
+                        .amount(reserve.tokens.amount),
+                )
+                .owner(token::ID);
+            let wallet = AccountInfoWrapper::new()
+                .mutable()
+                .pack(
+                    spl::token_account::new(user.key)
+                        .mint(reserve.mint)
+                        .amount(u64::MAX / 2),
+                )
+                .owner(token::ID);
+
+            (wallet, vault)
+        };
+        let (sell_wallet, sell_vault) =
+            wallet_and_vault(pool_data.reserves()[0]);
+        let (buy_wallet, buy_vault) = wallet_and_vault(pool_data.reserves()[1]);
+        let token_program = AccountInfoWrapper::with_key(token::ID).program();
+        let program_config = AccountInfoWrapper::pda(
+            amm::ID,
+            "program_config",
+            &[ProgramConfig::PDA_SEED],
+        )
+        .owner(system_program::ID);
+        let pool_stats = AccountInfoWrapper::pda(
+            amm::ID,
+            "pool_stats",
+            &[PoolStats::PDA_PREFIX, pool.key.as_ref()],
+        )
+        .mutable()
+        .owner(system_program::ID);
+
+        Self {
+            user,
+            discount,
+            pool,
+            pool_signer,
+            sell_wallet,
+            buy_wallet,
+            sell_vault,
+            buy_vault,
+            lp_mint,
+            lp_token_wallet,
+            program_toll_wallet,
+            token_program,
+            program_config,
+            pool_stats,
+        }
+    }
+
+    fn pool_stats(mut self) -> Self {
+        self.pool_stats = self.pool_stats.owner(amm::ID).data(PoolStats {
+            pool: self.pool.key,
+            ..Default::default()
+        });
+        self
+    }
+
+    fn pool_copy(&self) -> Pool {
+        Pool::try_deserialize(&mut self.pool.data.as_slice()).unwrap()
+    }
+
+    fn sell_wallet_amount(&self) -> u64 {
+        spl::token_account::from_acc_info(&self.sell_wallet.to_account_info())
+            .amount
+    }
+
+    fn lp_token_wallet_amount(&self) -> u64 {
+        spl::token_account::from_acc_info(
+            &self.lp_token_wallet.to_account_info(),
+        )
+        .amount
+    }
+
+    fn zap_in(
+        &mut self,
+        tokens: TokenAmount,
+        min_lp_tokens_out: TokenAmount,
+    ) -> Result<()> {
+        let expected_cpis = self.expected_cpis(tokens).unwrap_or_default();
+        let expected_cpis = Arc::new(Mutex::new(expected_cpis));
+        let syscalls =
+            stub::Syscalls::new(CpiValidator(Arc::clone(&expected_cpis)));
+        syscalls.slot(10);
+        syscalls.set();
+
+        let mut ctx = self.context_wrapper();
+        let mut accounts = ctx.accounts()?;
+
+        zap_in(ctx.build(&mut accounts), tokens, min_lp_tokens_out)?;
+        accounts.exit(&amm::ID)?;
+
+        assert_eq!(*expected_cpis.lock().unwrap(), vec![]);
+
+        Ok(())
+    }
+
+    /// Replicates the endpoint's logic to find which CPIs to expect.
+    fn expected_cpis(&self, tokens: TokenAmount) -> Result<Vec<ExpectedCpi>> {
+        let mut pool = self.pool_copy();
+        let sell_mint = pool.reserves[0].mint;
+        let discount = self.discount.to_account_info();
+
+        let fee_share =
+            calculate_discounted_swap_fee_share(pool.swap_fee, &discount)?;
+        let (buy_mint, tokens_to_sell) = pool.calculate_zap_in_swap(
+            sell_mint,
+            tokens,
+            fee_share,
+            Slot::new(10),
+        )?;
+        let fee = calculate_swap_fee(tokens_to_sell, pool.swap_fee, &discount)?;
+        let bought = pool.swap(
+            sell_mint,
+            TokenAmount::new(tokens_to_sell.amount - fee.amount),
+            buy_mint,
+            Slot::new(10),
+        )?;
+        pool.reserve_mut(sell_mint).unwrap().add_tokens(fee)?;
+
+        let supply =
+            spl::mint::from_acc_info(&self.lp_mint.to_account_info()).supply;
+        let toll = calculate_toll_in_lp_tokens(
+            &pool,
+            fee,
+            consts::PROGRAM_TOLL_SWAP_FEE_SHARE,
+            sell_mint,
+            supply.into(),
+        )?;
+        let supply_after_toll = supply + toll.unwrap_or_default().amount;
+
+        let DepositResult {
+            lp_tokens_to_distribute,
+            tokens_to_deposit,
+        } = pool.deposit_tokens(
+            BTreeMap::from([
+                (
+                    sell_mint,
+                    TokenAmount::new(tokens.amount - tokens_to_sell.amount),
+                ),
+                (buy_mint, bought),
+            ]),
+            supply_after_toll.into(),
+        )?;
+
+        let mut cpis = vec![ExpectedCpi::Transfer {
+            authority: self.user.key,
+            from: self.sell_wallet.key,
+            to: self.sell_vault.key,
+            tokens: TokenAmount::new(
+                tokens_to_sell.amount + tokens_to_deposit[&sell_mint].amount,
+            ),
+        }];
+        let buy_dust = bought.amount - tokens_to_deposit[&buy_mint].amount;
+        if buy_dust > 0 {
+            cpis.push(ExpectedCpi::Transfer {
+                authority: self.pool_signer.key,
+                from: self.buy_vault.key,
+                to: self.buy_wallet.key,
+                tokens: TokenAmount::new(buy_dust),
+            });
+        }
+        if let Some(toll) = toll {
+            cpis.push(ExpectedCpi::MintTo {
+                signer: self.pool_signer.key,
+                mint: self.lp_mint.key,
+                destination: self.program_toll_wallet.key,
+                tokens: toll,
+            });
+        }
+        if let Some(lp_tokens) = lp_tokens_to_distribute {
+            cpis.push(ExpectedCpi::MintTo {
+                signer: self.pool_signer.key,
+                mint: self.lp_mint.key,
+                destination: self.lp_token_wallet.key,
+                tokens: lp_tokens,
+            });
+        }
+
+        Ok(cpis)
+    }
+
+    fn context_wrapper(&mut self) -> ContextWrapper {
+        ContextWrapper::new(amm::ID)
+            .acc(&mut self.user)
+            .acc(&mut self.discount)
+            .acc(&mut self.pool)
+            .acc(&mut self.pool_signer)
+            .acc(&mut self.sell_wallet)
+            .acc(&mut self.buy_wallet)
+            .acc(&mut self.sell_vault)
+            .acc(&mut self.buy_vault)
+            .acc(&mut self.lp_mint)
+            .acc(&mut self.lp_token_wallet)
+            .acc(&mut self.program_toll_wallet)
+            .acc(&mut self.token_program)
+            .acc(&mut self.program_config)
+            .acc(&mut self.pool_stats)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CpiValidator(Arc<Mutex<Vec<ExpectedCpi>>>);
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum ExpectedCpi {
+    Transfer {
+        authority: Pubkey,
+        from: Pubkey,
+        to: Pubkey,
+        tokens: TokenAmount,
+    },
+    MintTo {
+        signer: Pubkey,
+        mint: Pubkey,
+        destination: Pubkey,
+        tokens: TokenAmount,
+    },
+}
+
+impl stub::ValidateCpis for CpiValidator {
+    fn validate_next_instruction(
+        &mut self,
+        ix: &Instruction,
+        accounts: &[AccountInfo],
+    ) {
+        let mut expected_cpis = self.0.lock().unwrap();
+        if expected_cpis.is_empty() {
+            panic!("No more instructions expected, got {:#?}", ix);
+        }
+        let find =
+            |key: Pubkey| accounts.iter().find(|acc| acc.key() == key).unwrap();
+
+        match expected_cpis.remove(0) {
+            ExpectedCpi::Transfer {
+                authority,
+                from,
+                to,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::transfer(
+                    &token::ID,
+                    &from,
+                    &to,
+                    &authority,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                spl::token_account::transfer(
+                    find(from),
+                    find(to),
+                    tokens.amount,
+                )
+                .expect("Source does not have enough tokens");
+            }
+            ExpectedCpi::MintTo {
+                signer,
+                mint,
+                destination,
+                tokens,
+            } => {
+                let expected_ix = token::spl_token::instruction::mint_to(
+                    &token::ID,
+                    &mint,
+                    &destination,
+                    &signer,
+                    &[],
+                    tokens.amount,
+                )
+                .unwrap();
+                assert_eq!(&expected_ix, ix);
+
+                spl::mint::mint_to(
+                    find(destination),
+                    find(mint),
+                    tokens.amount,
+                )
+                .expect("Cannot mint LP tokens");
+            }
+        }
+    }
+}
+
+fn create_two_reserves(sell: TokenAmount, buy: TokenAmount) -> [Reserve; 4] {
+    [
+        Reserve {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            tokens: sell,
+        },
+        Reserve {
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            tokens: buy,
+        },
+        Reserve::default(),
+        Reserve::default(),
+    ]
+}
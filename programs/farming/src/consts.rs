@@ -24,9 +24,15 @@ pub const HARVEST_PERIODS_LEN: usize = 10;
 pub const SNAPSHOTS_LEN: usize = 1000;
 
 /// Automation must wait at least this many slots before it can take a new
-/// snapshot.
+/// snapshot, unless the farm or the [`crate::models::ProgramConfig`] says
+/// otherwise.
 ///
 /// There are ~2 slots per second. The
 /// [`crate::endpoints::take_snapshot`] endpoint is available for a
 /// single [`crate::models::Farm`] at most this often.
 pub const MIN_SNAPSHOT_WINDOW_SLOTS: u64 = 2 * 3600;
+
+/// The [`crate::models::ProgramConfig`] can set the default minimum snapshot
+/// window at most to this value, ie. the snapshot history is at most
+/// ~1000 days.
+pub const MAX_SNAPSHOT_WINDOW_SLOTS: u64 = 2 * 3600 * 24;
@@ -8,6 +8,7 @@ pub mod create_farm;
 pub mod create_farmer;
 pub mod dewhitelist_farm_for_compounding;
 pub mod new_harvest_period;
+pub mod put_program_config;
 pub mod remove_harvest;
 pub mod set_farm_owner;
 pub mod set_min_snapshot_window;
@@ -27,6 +28,7 @@ pub use create_farm::*;
 pub use create_farmer::*;
 pub use dewhitelist_farm_for_compounding::*;
 pub use new_harvest_period::*;
+pub use put_program_config::*;
 pub use remove_harvest::*;
 pub use set_farm_owner::*;
 pub use set_min_snapshot_window::*;
@@ -56,9 +56,8 @@ pub fn handle(ctx: Context<Airdrop>, airdrop: TokenAmount) -> Result<()> {
         .ok_or_else(|| {
             err::acc("Farmer cannot harvest given harvest wallet's mint")
         })?;
-    harvest.amount = harvest
-        .amount
-        .checked_add(airdrop.amount)
+    *harvest = harvest
+        .checked_add(airdrop)
         .ok_or(FarmingError::MathOverflow)?;
 
     // from authority's wallet to farm's vault
@@ -46,6 +46,13 @@ pub struct NewHarvestPeriod<'info> {
     )]
     pub farm_signer_pda: AccountInfo<'info>,
     pub token_program: Program<'info, Token>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
 }
 
 pub fn handle(
@@ -90,11 +97,13 @@ pub fn handle(
             - 1,
     );
 
+    let config = ProgramConfig::load(&accounts.program_config)?;
     let scheduled_launch = farm.new_harvest_period(
         current_slot,
         harvest_mint,
         (starts_at, ends_at),
         tps,
+        &config,
     )?;
 
     // if we're overwriting a scheduled launch, then there have been tokens
@@ -0,0 +1,112 @@
+//! Either creates the singleton [`ProgramConfig`] - if it doesn't exist yet -
+//! or overwrites the existing one. Until the config is created, the program
+//! uses the defaults from [`crate::consts`].
+
+use crate::prelude::*;
+use anchor_lang::system_program;
+
+/// Only the program authority can change the config.
+#[cfg(not(feature = "dev"))]
+#[derive(Accounts)]
+pub struct PutProgramConfig<'info> {
+    #[account(mut)]
+    pub program_authority: Signer<'info>,
+    #[account(
+        constraint = farming.programdata_address()? ==
+            Some(farming_metadata.key())
+            @ err::acc("Farming program metadata account mismatch"),
+    )]
+    pub farming: Program<'info, crate::program::Farming>,
+    #[account(
+        constraint = farming_metadata.upgrade_authority_address ==
+            Some(program_authority.key())
+            @ err::acc("Signer isn't program's authority"),
+    )]
+    pub farming_metadata: Account<'info, ProgramData>,
+    /// CHECK: we create the config account if it does not exist yet in the
+    /// [`handle`] fn
+    #[account(
+        mut,
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Due to the way the anchor loads programs on localnet (so that we can use any
+/// pubkey and don't have to sign the program deploy), the programs on localnet
+/// don't have the same structure in terms of having a data account as with
+/// normal deployment.
+///
+/// That's why for localnet we compile the program under a dev feature and
+/// remove the checks which are in the production program.
+#[cfg(feature = "dev")]
+#[derive(Accounts)]
+pub struct PutProgramConfig<'info> {
+    #[account(mut)]
+    pub program_authority: Signer<'info>,
+    /// CHECK: we create the config account if it does not exist yet in the
+    /// [`handle`] fn
+    #[account(
+        mut,
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle(
+    ctx: Context<PutProgramConfig>,
+    min_snapshot_window_slots: u64,
+    allow_oldest_period_overwrite: bool,
+) -> Result<()> {
+    let accounts = ctx.accounts;
+
+    let config = ProgramConfig {
+        min_snapshot_window_slots,
+        allow_oldest_period_overwrite,
+        ..Default::default()
+    };
+    config.validate()?;
+
+    let should_be_created =
+        accounts.program_config.owner == &system_program::ID;
+    if should_be_created {
+        // The following logic is basically what `#[account(init)]` does.
+        let pda_seeds = &[
+            ProgramConfig::PDA_SEED.as_ref(),
+            &[*ctx.bumps.get("program_config").unwrap()],
+        ];
+        let rent = Rent::get()?.minimum_balance(ProgramConfig::space());
+        system_program::create_account(
+            accounts
+                .as_create_program_config_account_context()
+                .with_signer(&[&pda_seeds[..]]),
+            rent,
+            ProgramConfig::space() as u64,
+            ctx.program_id,
+        )?;
+    }
+
+    // overwrites the data in the config account
+    let mut config_data = accounts.program_config.try_borrow_mut_data()?;
+    config.try_serialize(&mut config_data.as_mut())?;
+
+    Ok(())
+}
+
+impl<'info> PutProgramConfig<'info> {
+    fn as_create_program_config_account_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::CreateAccount<'info>>
+    {
+        let cpi_accounts = system_program::CreateAccount {
+            from: self.program_authority.to_account_info(),
+            to: self.program_config.to_account_info(),
+        };
+        let cpi_program = self.system_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
@@ -11,6 +11,13 @@ pub struct TakeSnapshot<'info> {
     pub farm: AccountLoader<'info, Farm>,
     /// The link to the farm is checked in the [`handle`] function.
     pub stake_vault: Account<'info, TokenAccount>,
+    /// CHECK: The program config might not be created, in which case we use
+    /// the defaults, see [`ProgramConfig::load`].
+    #[account(
+        seeds = [ProgramConfig::PDA_SEED],
+        bump,
+    )]
+    pub program_config: AccountInfo<'info>,
 }
 
 pub fn handle(ctx: Context<TakeSnapshot>) -> Result<()> {
@@ -26,9 +33,11 @@ pub fn handle(ctx: Context<TakeSnapshot>) -> Result<()> {
         )));
     }
 
+    let config = ProgramConfig::load(&accounts.program_config)?;
     farm.take_snapshot(
         Slot::current()?,
         TokenAmount::new(accounts.stake_vault.amount),
+        &config,
     )?;
 
     Ok(())
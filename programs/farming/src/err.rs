@@ -39,13 +39,9 @@ pub enum FarmingError {
 }
 
 pub fn acc(msg: impl Display) -> FarmingError {
-    msg!("[InvalidAccountInput] {}", msg);
-
-    FarmingError::InvalidAccountInput
+    common::err::acc(FarmingError::InvalidAccountInput, msg)
 }
 
 pub fn arg(msg: impl Display) -> FarmingError {
-    msg!("[InvalidArg] {}", msg);
-
-    FarmingError::InvalidArg
+    common::err::arg(FarmingError::InvalidArg, msg)
 }
@@ -6,6 +6,9 @@ pub mod err;
 pub mod models;
 pub mod prelude;
 
+#[cfg(feature = "serde")]
+pub use common::serde_helpers;
+
 use crate::prelude::*;
 use endpoints::*;
 
@@ -19,6 +22,21 @@ declare_id!("DFarMhaRkdYqhK5jZsexMftaJuWHrY7VzAfkXx5ZmxqZ");
 pub mod farming {
     use super::*;
 
+    /// # Important
+    /// This endpoint requires different accounts based on whether the program
+    /// is compiled with the "dev" feature.
+    pub fn put_program_config(
+        ctx: Context<PutProgramConfig>,
+        min_snapshot_window_slots: u64,
+        allow_oldest_period_overwrite: bool,
+    ) -> Result<()> {
+        endpoints::put_program_config::handle(
+            ctx,
+            min_snapshot_window_slots,
+            allow_oldest_period_overwrite,
+        )
+    }
+
     pub fn create_farm(ctx: Context<CreateFarm>) -> Result<()> {
         endpoints::create_farm::handle(ctx)
     }
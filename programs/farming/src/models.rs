@@ -1,59 +1,27 @@
 pub mod farm;
 pub mod farmer;
+pub mod program_config;
 #[cfg(test)]
 mod tests;
 
+pub use common::{Slot, TokenAmount};
 pub use farm::*;
 pub use farmer::*;
+pub use program_config::*;
 
-use crate::prelude::*;
+// Anchor's IDL parser reads only this crate's sources, hence it wouldn't find
+// the layouts of the shared types re-exported above. The parser doesn't
+// evaluate `cfg` attributes, so it reads these copies, which are never
+// compiled. They must match the definitions in the `common` crate.
 
-#[derive(
-    AnchorDeserialize,
-    AnchorSerialize,
-    Clone,
-    Copy,
-    Debug,
-    Default,
-    Eq,
-    PartialEq,
-    Ord,
-    PartialOrd,
-)]
+#[cfg(any())]
+#[derive(AnchorDeserialize, AnchorSerialize)]
 pub struct TokenAmount {
     pub amount: u64,
 }
 
-#[derive(
-    AnchorDeserialize,
-    AnchorSerialize,
-    Clone,
-    Copy,
-    Debug,
-    Default,
-    Eq,
-    PartialEq,
-    Ord,
-    PartialOrd,
-)]
+#[cfg(any())]
+#[derive(AnchorDeserialize, AnchorSerialize)]
 pub struct Slot {
     pub slot: u64,
 }
-
-impl TokenAmount {
-    pub fn new(amount: u64) -> Self {
-        Self { amount }
-    }
-}
-
-impl Slot {
-    pub fn new(slot: u64) -> Self {
-        Self { slot }
-    }
-
-    pub fn current() -> Result<Self> {
-        Ok(Self {
-            slot: Clock::get()?.slot,
-        })
-    }
-}
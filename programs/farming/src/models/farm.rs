@@ -11,8 +11,17 @@ use std::ops::RangeInclusive;
 /// with the program, and they become eligible for harvest.
 #[derive(Default)]
 #[account(zero_copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Farm {
     /// Can change settings on this farm.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub admin: Pubkey,
     /// The mint of tokens which are staked by farmers. Also referred to as
     /// `S`.
@@ -21,10 +30,18 @@ pub struct Farm {
     /// serves as a natural boundary between the two features: _(1)_ depositing
     /// liquidity and swapping; _(2)_ farming with which this document is
     /// concerned
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub stake_mint: Pubkey,
     /// Staked tokens are stored in this program's vault (token account.)
     ///
     /// This is derivable from the farm's pubkey as a seed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub stake_vault: Pubkey,
     /// List of different harvest mints with harvest periods, each with its own
     /// configuration of how many tokens are released per slot.
@@ -43,7 +60,7 @@ pub struct Farm {
     /// that the ring_buffer in total has a minimum amount of time elapsed.
     /// When a Farm is initiated, min_snapshot_window_slots is defaulted to
     /// zero. When zero, the endpoint take_snapshots will set this constraint
-    /// to the default value [`consts::MIN_SNAPSHOT_WINDOW_SLOTS`].
+    /// to the default value given by the [`ProgramConfig`].
     /// This field is configurable via the endpoint set_min_snapshot_window
     /// which can be called by the admin.
     pub min_snapshot_window_slots: u64,
@@ -56,14 +73,27 @@ pub struct Farm {
 /// this way we also safe some account space.
 #[derive(Debug, Eq, PartialEq, Default)]
 #[zero_copy]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Harvest {
     /// The mint of tokens which are distributed to farmers. This can be the
     /// same mint as `S`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub mint: Pubkey,
     /// Admin deposits the reward tokens which are harvested by farmer into
     /// this vault.
     ///
     /// This is derivable from the farm's pubkey and harvest mint's pubkey.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub vault: Pubkey,
     /// The harvest is distributed using a configurable _tokens per slot_
     /// (`ρ`.) This value represents how many tokens should be divided
@@ -85,8 +115,8 @@ pub struct Harvest {
     /// having `ρ = 0`.
     ///
     /// Changes to `ρ` must be kept until no snapshots refer that much back in
-    /// time. This limits the update frequency. For `dev` feature, this
-    /// limitation is removed.
+    /// time. This limits the update frequency, unless the [`ProgramConfig`]
+    /// allows overwriting the oldest period.
     ///
     /// # Note
     /// This len must match [`consts::HARVEST_PERIODS_LEN`].
@@ -95,6 +125,11 @@ pub struct Harvest {
 
 #[derive(Debug, Default, Eq, PartialEq)]
 #[zero_copy]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct HarvestPeriod {
     pub tps: TokenAmount,
     pub starts_at: Slot,
@@ -103,6 +138,11 @@ pub struct HarvestPeriod {
 
 #[derive(Eq, PartialEq)]
 #[zero_copy]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Snapshots {
     /// What's the last snapshot index to consider valid. When the buffer tip
     /// reaches [`consts::SNAPSHOTS_LEN`], it is set to 0 again and now the
@@ -123,12 +163,18 @@ pub struct Snapshots {
     ///
     /// # Note
     /// Len must match [`consts::SNAPSHOTS_LEN`].
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::array"))]
     pub ring_buffer: [Snapshot; 1000],
 }
 
 /// Defines a snapshot window.
 #[derive(Debug, Default, Eq, PartialEq)]
 #[zero_copy]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Snapshot {
     pub staked: TokenAmount,
     pub started_at: Slot,
@@ -144,6 +190,11 @@ pub struct Snapshot {
 /// anchor constraints when calling
 /// [`crate::endpoints::dewhitelist_farm_for_compounding`].
 #[account]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct WhitelistCompounding {}
 
 impl Default for Snapshots {
@@ -212,6 +263,7 @@ impl Farm {
         harvest_mint: Pubkey,
         period: (Slot, Slot),
         tps: TokenAmount,
+        config: &ProgramConfig,
     ) -> Result<Option<HarvestPeriod>> {
         let oldest_snapshot = self.oldest_snapshot();
 
@@ -272,17 +324,12 @@ impl Farm {
         // already initialized as a fixed number length array of default
         // elements so unwrap `.last()` is safe
         let oldest_period = harvest.periods.last().unwrap();
-        let is_oldest_period_initialized = if cfg!(any(feature = "prod", test))
-        {
-            // if the oldest period is within the current snapshot history,
-            // we are unable to update its value, the admin already passed the
-            // allowed max number of possible configuration updates
-            oldest_period.ends_at.slot != 0
-        } else {
-            // always enable updates in dev
-            false
-        };
-        if is_oldest_period_initialized
+        // if the oldest period is within the current snapshot history, we are
+        // unable to update its value, the admin already passed the allowed max
+        // number of possible configuration updates
+        let is_oldest_period_initialized = oldest_period.ends_at.slot != 0;
+        if !config.allow_oldest_period_overwrite
+            && is_oldest_period_initialized
             && oldest_period.ends_at.slot >= oldest_snapshot.started_at.slot
         {
             msg!("Oldest period is still within the ring buffer history");
@@ -423,13 +470,14 @@ impl Farm {
         &mut self,
         clock: Slot,
         stake_vault: TokenAmount,
+        config: &ProgramConfig,
     ) -> Result<()> {
         // When the farm is initialised, farm.min_snapshot_window_slots is set
         // to zero If the admin does not change this value the program
-        // defaults the minimum snapshot window slots to the default
-        // value
+        // defaults the minimum snapshot window slots to the program's
+        // default value
         let min_snapshot_window_slots = if self.min_snapshot_window_slots == 0 {
-            consts::MIN_SNAPSHOT_WINDOW_SLOTS
+            config.min_snapshot_window_slots
         } else {
             self.min_snapshot_window_slots
         };
@@ -640,9 +688,21 @@ mod tests {
     fn it_returns_first_snapshot_after_some_slot() -> Result<()> {
         let mut farm = Farm::default();
         farm.min_snapshot_window_slots = 1;
-        farm.take_snapshot(Slot::new(2), TokenAmount::new(1))?;
-        farm.take_snapshot(Slot::new(4), TokenAmount::new(2))?;
-        farm.take_snapshot(Slot::new(6), TokenAmount::new(3))?;
+        farm.take_snapshot(
+            Slot::new(2),
+            TokenAmount::new(1),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(4),
+            TokenAmount::new(2),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(6),
+            TokenAmount::new(3),
+            &ProgramConfig::default(),
+        )?;
 
         assert_eq!(farm.first_snapshot_after(Slot::new(10)), None);
 
@@ -685,7 +745,11 @@ mod tests {
         let mut farm = Farm::default();
         farm.min_snapshot_window_slots = 1;
         for i in 5..consts::SNAPSHOTS_LEN * 2 {
-            farm.take_snapshot(Slot::new(i as u64 * 2), TokenAmount::new(1))?;
+            farm.take_snapshot(
+                Slot::new(i as u64 * 2),
+                TokenAmount::new(1),
+                &ProgramConfig::default(),
+            )?;
         }
 
         assert_eq!(
@@ -822,6 +886,7 @@ mod tests {
         let _result = farm.take_snapshot(
             Slot::new(current_slot),
             TokenAmount::new(stake_vault_amount),
+            &ProgramConfig::default(),
         );
 
         // After take_snapshot is called the tip should
@@ -891,6 +956,7 @@ mod tests {
                 harvest_mint,
                 (Slot::new(30), Slot::new(25)),
                 TokenAmount::new(20),
+                &ProgramConfig::default(),
             )
             .is_err());
 
@@ -909,18 +975,21 @@ mod tests {
             harvest_mint,
             (Slot::new(5), Slot::new(25)),
             TokenAmount::new(20),
+            &ProgramConfig::default(),
         )?;
         farm.new_harvest_period(
             Slot::new(10),
             harvest_mint,
             (Slot::new(30), Slot::new(50)),
             TokenAmount::new(20),
+            &ProgramConfig::default(),
         )?;
         farm.new_harvest_period(
             Slot::new(10),
             harvest_mint,
             (Slot::new(40), Slot::new(50)),
             TokenAmount::new(20),
+            &ProgramConfig::default(),
         )?;
 
         assert_eq!(
@@ -955,12 +1024,14 @@ mod tests {
             harvest_mint,
             (Slot::new(5), Slot::new(25)),
             TokenAmount::new(20),
+            &ProgramConfig::default(),
         )?;
         farm.new_harvest_period(
             Slot::new(10),
             harvest_mint,
             (Slot::new(30), Slot::new(50)),
             TokenAmount::new(20),
+            &ProgramConfig::default(),
         )?;
         assert!(farm
             .new_harvest_period(
@@ -968,6 +1039,7 @@ mod tests {
                 harvest_mint,
                 (Slot::new(20), Slot::new(50)),
                 TokenAmount::new(20),
+                &ProgramConfig::default(),
             )
             .is_err());
 
@@ -996,6 +1068,7 @@ mod tests {
             harvest_mint,
             (Slot::new(15), Slot::new(25)),
             TokenAmount::new(20),
+            &ProgramConfig::default(),
         )?;
         assert_eq!(
             farm.harvests[0].periods[0],
@@ -1039,6 +1112,7 @@ mod tests {
             harvest_mint,
             (Slot::new(160), Slot::new(165)),
             TokenAmount::new(10),
+            &ProgramConfig::default(),
         );
         assert!(output.is_err());
 
@@ -1051,6 +1125,7 @@ mod tests {
             harvest_mint,
             (Slot::new(160), Slot::new(165)),
             TokenAmount::new(10),
+            &ProgramConfig::default(),
         );
         assert!(output.is_ok());
         assert_eq!(
@@ -1071,6 +1146,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_can_add_new_period_if_config_allows_overwrite() {
+        let mut farm = Farm::default();
+
+        let harvest_mint = farm.harvests[0].mint;
+        farm.harvests[0].periods =
+            [10, 9, 8, 7, 6, 5, 4, 3, 2, 1].map(|u| HarvestPeriod {
+                starts_at: Slot::new(u * 10),
+                ends_at: Slot::new(u * 10 + 5),
+                tps: TokenAmount::new(100 * u),
+            });
+
+        let config = ProgramConfig {
+            allow_oldest_period_overwrite: true,
+            ..Default::default()
+        };
+        let output = farm.new_harvest_period(
+            Slot::new(150),
+            harvest_mint,
+            (Slot::new(160), Slot::new(165)),
+            TokenAmount::new(10),
+            &config,
+        );
+        assert!(output.is_ok());
+        assert_eq!(farm.harvests[0].periods[0].starts_at, Slot::new(160));
+        assert_eq!(farm.harvests[0].periods[9].starts_at, Slot::new(20));
+    }
+
+    #[test]
+    fn it_takes_snapshot_with_window_from_config() {
+        let mut farm = Farm::default();
+        let config = ProgramConfig {
+            min_snapshot_window_slots: 10,
+            ..Default::default()
+        };
+
+        assert!(farm
+            .take_snapshot(Slot::new(9), TokenAmount::new(1), &config)
+            .is_err());
+        assert!(farm
+            .take_snapshot(Slot::new(10), TokenAmount::new(1), &config)
+            .is_ok());
+
+        // the farm's own window takes precedence
+        farm.min_snapshot_window_slots = 1;
+        assert!(farm
+            .take_snapshot(Slot::new(11), TokenAmount::new(1), &config)
+            .is_ok());
+    }
+
     #[test]
     fn it_gets_window_snapshots_eligible_to_harvest() -> Result<()> {
         // This test asserts that the associated function
@@ -1401,9 +1526,21 @@ mod tests {
             ..Default::default()
         };
 
-        farm.take_snapshot(Slot::new(4), TokenAmount::new(1))?;
-        farm.take_snapshot(Slot::new(7), TokenAmount::new(1))?;
-        farm.take_snapshot(Slot::new(10), TokenAmount::new(1))?;
+        farm.take_snapshot(
+            Slot::new(4),
+            TokenAmount::new(1),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(7),
+            TokenAmount::new(1),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(10),
+            TokenAmount::new(1),
+            &ProgramConfig::default(),
+        )?;
 
         let calculate_next_harvest_from = Slot { slot: 0 };
 
@@ -1430,9 +1567,21 @@ mod tests {
             ..Default::default()
         };
 
-        farm.take_snapshot(Slot::new(4), TokenAmount::new(1))?;
-        farm.take_snapshot(Slot::new(7), TokenAmount::new(1))?;
-        farm.take_snapshot(Slot::new(10), TokenAmount::new(1))?;
+        farm.take_snapshot(
+            Slot::new(4),
+            TokenAmount::new(1),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(7),
+            TokenAmount::new(1),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(10),
+            TokenAmount::new(1),
+            &ProgramConfig::default(),
+        )?;
 
         let calculate_next_harvest_from = Slot { slot: 4 };
 
@@ -12,10 +12,23 @@ use std::{cmp, mem};
 /// related to the farmer's stake.
 #[account]
 #[derive(Default, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct Farmer {
     /// This signer can claim harvest, start/stop farming.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub authority: Pubkey,
     /// What farm is this farmer associated with.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub farm: Pubkey,
     /// How many tokens are currently _earning_ harvest. Upon stake, tokens are
     /// firstly added to the `vested` amount and only in the next
@@ -77,7 +90,16 @@ pub struct Farmer {
     Eq,
     PartialEq,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct AvailableHarvest {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helpers::pubkey")
+    )]
     pub mint: Pubkey,
     pub tokens: TokenAmount,
 }
@@ -104,10 +126,9 @@ impl Farmer {
         tokens: TokenAmount,
     ) -> Result<()> {
         self.vested_at = current_slot;
-        self.vested.amount = self
+        self.vested = self
             .vested
-            .amount
-            .checked_add(tokens.amount)
+            .checked_add(tokens)
             .ok_or(FarmingError::MathOverflow)?;
 
         Ok(())
@@ -115,10 +136,8 @@ impl Farmer {
 
     pub fn total_deposited(&self) -> Result<TokenAmount> {
         self.staked
-            .amount
-            .checked_add(self.vested.amount)
+            .checked_add(self.vested)
             .ok_or_else(|| error!(FarmingError::MathOverflow))
-            .map(TokenAmount::new)
     }
 
     pub fn unstake(&mut self, max: TokenAmount) -> Result<TokenAmount> {
@@ -169,10 +188,9 @@ impl Farmer {
                 self.calculate_next_harvest_from = snapshot.started_at;
 
                 // mark funds which are beyond vesting period as staked
-                self.staked.amount = self
+                self.staked = self
                     .staked
-                    .amount
-                    .checked_add(self.vested.amount)
+                    .checked_add(self.vested)
                     .ok_or(FarmingError::MathOverflow)?;
                 self.vested = TokenAmount { amount: 0 };
             }
@@ -816,26 +834,37 @@ mod tests {
         farm.min_snapshot_window_slots = 1;
         farm.add_harvest(harvest_mint, Pubkey::new_unique())?;
 
-        farm.take_snapshot(Slot::new(1), TokenAmount::new(100))?;
+        farm.take_snapshot(
+            Slot::new(1),
+            TokenAmount::new(100),
+            &ProgramConfig::default(),
+        )?;
         farm.new_harvest_period(
             Slot::new(1),
             harvest_mint,
             (Slot::new(1), Slot::new(3)),
             TokenAmount::new(1),
+            &ProgramConfig::default(),
         )?;
         farm.new_harvest_period(
             Slot::new(11),
             harvest_mint,
             (Slot::new(11), Slot::new(13)),
             TokenAmount::new(10),
+            &ProgramConfig::default(),
         )?;
         farm.new_harvest_period(
             Slot::new(31),
             harvest_mint,
             (Slot::new(31), Slot::new(33)),
             TokenAmount::new(100),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(50),
+            TokenAmount::new(100),
+            &ProgramConfig::default(),
         )?;
-        farm.take_snapshot(Slot::new(50), TokenAmount::new(100))?;
 
         let mut farmer = Farmer {
             staked: TokenAmount::new(100),
@@ -893,8 +922,16 @@ mod tests {
         let mut farm = Farm::default();
         farm.min_snapshot_window_slots = 1;
         farm.add_harvest(Pubkey::new_unique(), Pubkey::new_unique())?;
-        farm.take_snapshot(Slot::new(1), TokenAmount::new(100))?;
-        farm.take_snapshot(Slot::new(10), TokenAmount::new(100))?;
+        farm.take_snapshot(
+            Slot::new(1),
+            TokenAmount::new(100),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(10),
+            TokenAmount::new(100),
+            &ProgramConfig::default(),
+        )?;
 
         let mut farmer = Farmer {
             staked: TokenAmount::new(100),
@@ -0,0 +1,122 @@
+//! Program config holds tunables which the program's authority can change
+//! without a redeploy, see [`crate::endpoints::put_program_config`]. There's
+//! at most one config account, and until it's created, the endpoints use the
+//! defaults given by [`crate::consts`].
+
+use crate::prelude::*;
+use anchor_lang::system_program;
+
+#[account]
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct ProgramConfig {
+    /// Minimum window between snapshots of farms which haven't set their own
+    /// [`Farm::min_snapshot_window_slots`].
+    ///
+    /// Defaults to [`consts::MIN_SNAPSHOT_WINDOW_SLOTS`].
+    pub min_snapshot_window_slots: u64,
+    /// If enabled, the admin can add a new harvest period even if the oldest
+    /// period is still within the snapshot history. The oldest period is then
+    /// dropped, which changes the harvest of farmers who haven't claimed it
+    /// yet. Useful on devnet.
+    ///
+    /// Defaults to false.
+    pub allow_oldest_period_overwrite: bool,
+    /// New tunables take their space from the end of this array, so that
+    /// adding them doesn't require reallocating the config account.
+    pub reserved: [u64; 8],
+}
+
+impl Default for ProgramConfig {
+    fn default() -> Self {
+        Self {
+            min_snapshot_window_slots: consts::MIN_SNAPSHOT_WINDOW_SLOTS,
+            allow_oldest_period_overwrite: false,
+            reserved: [0; 8],
+        }
+    }
+}
+
+impl ProgramConfig {
+    pub const PDA_SEED: &'static [u8; 6] = b"config";
+
+    pub fn space() -> usize {
+        let discriminant = 8;
+        let min_snapshot_window_slots = 8;
+        let allow_oldest_period_overwrite = 1;
+        let reserved = 8 * 8;
+
+        discriminant
+            + min_snapshot_window_slots
+            + allow_oldest_period_overwrite
+            + reserved
+    }
+
+    /// Parses the config account, whose address must've been already checked
+    /// by the caller. If the account hasn't been created yet, returns the
+    /// defaults.
+    pub fn load(account: &AccountInfo) -> Result<Self> {
+        if account.owner == &system_program::ID {
+            Ok(Self::default())
+        } else if account.owner == &crate::ID {
+            let data = account.try_borrow_data()?;
+            Self::try_deserialize(&mut &data[..])
+        } else {
+            Err(error!(err::acc(
+                "Program config must be owned by the program"
+            )))
+        }
+    }
+
+    /// Checks that the tunables are within bounds which keep the farms usable.
+    pub fn validate(&self) -> Result<()> {
+        if self.min_snapshot_window_slots == 0
+            || self.min_snapshot_window_slots
+                > consts::MAX_SNAPSHOT_WINDOW_SLOTS
+        {
+            return Err(error!(err::arg(format!(
+                "Minimum snapshot window must be between 1 and {} slots",
+                consts::MAX_SNAPSHOT_WINDOW_SLOTS
+            ))));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_has_valid_defaults() {
+        assert!(ProgramConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn it_validates_bounds() {
+        let config = |min_snapshot_window_slots| ProgramConfig {
+            min_snapshot_window_slots,
+            ..Default::default()
+        };
+
+        assert!(config(1).validate().is_ok());
+        assert!(config(consts::MAX_SNAPSHOT_WINDOW_SLOTS).validate().is_ok());
+        assert!(config(0).validate().is_err());
+        assert!(config(consts::MAX_SNAPSHOT_WINDOW_SLOTS + 1)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn it_has_correct_space() {
+        let mut data = Vec::new();
+        ProgramConfig::default().try_serialize(&mut data).unwrap();
+
+        assert_eq!(data.len(), ProgramConfig::space());
+    }
+}
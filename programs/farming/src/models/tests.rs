@@ -18,8 +18,13 @@ fn it_updates_eligible_harvest_for_multiple_farmers() -> Result<()> {
         harvest,
         (Slot::new(1), Slot::new(u64::MAX)),
         TokenAmount::new(tps),
+        &ProgramConfig::default(),
+    )?;
+    farm.take_snapshot(
+        Slot::new(1),
+        TokenAmount::new(0),
+        &ProgramConfig::default(),
     )?;
-    farm.take_snapshot(Slot::new(1), TokenAmount::new(0))?;
 
     let mut farmer1 = Farmer {
         calculate_next_harvest_from: Slot::new(1),
@@ -41,15 +46,27 @@ fn it_updates_eligible_harvest_for_multiple_farmers() -> Result<()> {
     let mut farmers = vec![farmer1, farmer2, farmer3];
 
     // start earning harvest from slot 4
-    farm.take_snapshot(Slot::new(4), TokenAmount::new(total_staked))?;
-    farm.take_snapshot(Slot::new(8), TokenAmount::new(total_staked))?;
+    farm.take_snapshot(
+        Slot::new(4),
+        TokenAmount::new(total_staked),
+        &ProgramConfig::default(),
+    )?;
+    farm.take_snapshot(
+        Slot::new(8),
+        TokenAmount::new(total_staked),
+        &ProgramConfig::default(),
+    )?;
 
     farmers.iter_mut().for_each(|f| {
         f.check_vested_period_and_update_harvest(&farm, Slot::new(8))
             .unwrap()
     });
 
-    farm.take_snapshot(Slot::new(12), TokenAmount::new(total_staked))?;
+    farm.take_snapshot(
+        Slot::new(12),
+        TokenAmount::new(total_staked),
+        &ProgramConfig::default(),
+    )?;
 
     // last slot to earn harvest for is 14
     farmers.iter_mut().for_each(|f| {
@@ -72,3 +89,51 @@ fn it_updates_eligible_harvest_for_multiple_farmers() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn it_serializes_farmer() {
+    let farmer = Farmer {
+        authority: Pubkey::new_unique(),
+        staked: TokenAmount::new(10),
+        vested_at: Slot::new(5),
+        ..Default::default()
+    };
+
+    let value = serde_json::to_value(&farmer).unwrap();
+
+    assert_eq!(value["authority"], farmer.authority.to_string());
+    assert_eq!(value["farm"], Pubkey::default().to_string());
+    assert_eq!(value["staked"], 10);
+    assert_eq!(value["vested_at"], 5);
+    assert_eq!(serde_json::from_value::<Farmer>(value).unwrap(), farmer);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn it_serializes_farm_with_full_snapshot_history() {
+    let mut farm = Farm {
+        stake_mint: Pubkey::new_unique(),
+        ..Default::default()
+    };
+    let mut ring_buffer = farm.snapshots.ring_buffer;
+    ring_buffer[consts::SNAPSHOTS_LEN - 1] = Snapshot {
+        staked: TokenAmount::new(1),
+        started_at: Slot::new(2),
+    };
+    farm.snapshots.ring_buffer = ring_buffer;
+
+    let value = serde_json::to_value(&farm).unwrap();
+
+    assert_eq!(value["stake_mint"], farm.stake_mint.to_string());
+    let snapshots = value["snapshots"]["ring_buffer"].as_array().unwrap();
+    assert_eq!(snapshots.len(), consts::SNAPSHOTS_LEN);
+    assert_eq!(
+        snapshots[consts::SNAPSHOTS_LEN - 1],
+        serde_json::json!({ "staked": 1, "started_at": 2 })
+    );
+
+    let decoded: Farm = serde_json::from_value(value).unwrap();
+    assert_eq!(decoded.stake_mint, farm.stake_mint);
+    assert!(decoded.snapshots == farm.snapshots);
+}
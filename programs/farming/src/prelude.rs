@@ -8,14 +8,7 @@ pub use decimal::{Decimal, TryAdd, TryDiv, TryMul, TryRound};
 pub mod utils {
     use super::*;
 
-    /// Sets global clock to given slot. During testing, make sure to run
-    /// serial test if there are clock dependent tests
-    pub fn set_clock(slot: Slot) {
-        assert!(slot.slot < 256);
-        solana_sdk::program_stubs::set_syscall_stubs(Box::new(
-            SyscallStubs::new(slot),
-        ));
-    }
+    pub use common::test_utils::set_clock;
 
     pub fn generate_snapshots(
         snapshots_raw_vec: &mut Vec<(u64, u64)>,
@@ -56,34 +49,54 @@ pub mod utils {
         farm.min_snapshot_window_slots = 1;
         farm.add_harvest(harvest_mint, Pubkey::new_unique())?;
 
-        farm.take_snapshot(Slot::new(1), TokenAmount::new(100))?;
+        farm.take_snapshot(
+            Slot::new(1),
+            TokenAmount::new(100),
+            &ProgramConfig::default(),
+        )?;
         farm.new_harvest_period(
             Slot::new(1),
             harvest_mint,
             (Slot::new(1), Slot::new(3)),
             TokenAmount::new(1),
+            &ProgramConfig::default(),
         )?;
 
-        farm.take_snapshot(Slot::new(7), TokenAmount::new(100))?;
+        farm.take_snapshot(
+            Slot::new(7),
+            TokenAmount::new(100),
+            &ProgramConfig::default(),
+        )?;
         farm.new_harvest_period(
             Slot::new(5),
             harvest_mint,
             (Slot::new(5), Slot::new(9)),
             TokenAmount::new(10),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(10),
+            TokenAmount::new(200),
+            &ProgramConfig::default(),
         )?;
-        farm.take_snapshot(Slot::new(10), TokenAmount::new(200))?;
         farm.new_harvest_period(
             Slot::new(10),
             harvest_mint,
             (Slot::new(10), Slot::new(14)),
             TokenAmount::new(20),
+            &ProgramConfig::default(),
+        )?;
+        farm.take_snapshot(
+            Slot::new(15),
+            TokenAmount::new(400),
+            &ProgramConfig::default(),
         )?;
-        farm.take_snapshot(Slot::new(15), TokenAmount::new(400))?;
         farm.new_harvest_period(
             Slot::new(15),
             harvest_mint,
             (Slot::new(15), Slot::new(30)),
             TokenAmount::new(30),
+            &ProgramConfig::default(),
         )?;
 
         Ok((harvest_mint, farm))
@@ -166,29 +179,4 @@ pub mod utils {
 
         snapshots_vec
     }
-
-    struct SyscallStubs {
-        clock: Slot,
-    }
-
-    impl SyscallStubs {
-        fn new(clock: Slot) -> Self {
-            Self { clock }
-        }
-    }
-
-    impl solana_sdk::program_stubs::SyscallStubs for SyscallStubs {
-        fn sol_log(&self, message: &str) {
-            println!("[LOG] {}", message);
-        }
-
-        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
-            unsafe {
-                // TODO: Not sure how this works really, but it seems to
-                // convert the value to `Clock::get()?.slot`
-                *var_addr = self.clock.slot as u8;
-            }
-            0
-        }
-    }
 }
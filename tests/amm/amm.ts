@@ -25,6 +25,14 @@ export async function createProgramToll(
   return programToll;
 }
 
+export function programConfigAddress(): PublicKey {
+  const [programConfig, _bumpSeed] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    amm.programId
+  );
+  return programConfig;
+}
+
 export function programTollAddress(): PublicKey {
   const [programToll, _bumpSeed] = PublicKey.findProgramAddressSync(
     [Buffer.from("toll")],
@@ -13,7 +13,12 @@ import {
   SystemProgram,
 } from "@solana/web3.js";
 import { airdrop, amm, payer, provider } from "../helpers";
-import { createProgramToll, discountAddress, programTollAddress } from "./amm";
+import {
+  createProgramToll,
+  discountAddress,
+  programConfigAddress,
+  programTollAddress,
+} from "./amm";
 import { BN } from "@project-serum/anchor";
 
 export interface DepositLiquidityArgs {
@@ -122,6 +127,13 @@ export class Pool {
     )[0];
   }
 
+  public static statsFrom(publicKey: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("stats"), publicKey.toBytes()],
+      amm.programId
+    )[0];
+  }
+
   public signer(): PublicKey {
     return Pool.signerFrom(this.id.publicKey);
   }
@@ -232,6 +244,8 @@ export class Pool {
         lpMint,
         lpTokenWallet,
         tokenProgram: TOKEN_PROGRAM_ID,
+        poolStats: Pool.statsFrom(pool),
+        programConfig: programConfigAddress(),
       })
       .remainingAccounts(vaultsAndWallets)
       .signers([user])
@@ -333,6 +347,8 @@ export class Pool {
         lpMint,
         lpTokenWallet,
         tokenProgram: TOKEN_PROGRAM_ID,
+        poolStats: Pool.statsFrom(pool),
+        programConfig: programConfigAddress(),
       })
       .remainingAccounts(vaultsAndWallets)
       .signers([user])
@@ -366,6 +382,8 @@ export class Pool {
         poolSigner: this.signerPda(),
         programTollWallet: pool.programTollWallet,
         lpMint: pool.mint,
+        programConfig: programConfigAddress(),
+        poolStats: Pool.statsFrom(this.id.publicKey),
       })
       .signers([user])
       .rpc();
@@ -376,7 +394,11 @@ export class Pool {
       .setPoolSwapFee({
         permillion: new BN(permillion),
       })
-      .accounts({ admin: this.admin.publicKey, pool: this.id.publicKey })
+      .accounts({
+        admin: this.admin.publicKey,
+        pool: this.id.publicKey,
+        programConfig: programConfigAddress(),
+      })
       .signers([this.admin])
       .rpc();
   }
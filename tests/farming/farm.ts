@@ -189,6 +189,14 @@ export class Farm {
     return pda;
   }
 
+  public static programConfig(): PublicKey {
+    const [pda, _bumpSeed] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      farming.programId
+    );
+    return pda;
+  }
+
   public static async signerFrom(
     publicKey: PublicKey
   ): Promise<[PublicKey, number]> {
@@ -308,6 +316,7 @@ export class Farm {
       .accounts({
         farm,
         stakeVault,
+        programConfig: Farm.programConfig(),
       })
       .rpc();
   }
@@ -491,6 +500,7 @@ export class Farm {
         harvestVault,
         harvestWallet,
         farmSignerPda,
+        programConfig: Farm.programConfig(),
       })
       .signers(signers)
       .rpc();